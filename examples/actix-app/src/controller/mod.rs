@@ -1,4 +1,5 @@
 pub(crate) mod auth;
 pub(crate) mod file;
+pub(crate) mod passage;
 pub(crate) mod stats;
 pub(crate) mod user;
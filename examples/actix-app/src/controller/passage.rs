@@ -0,0 +1,33 @@
+use zino::{prelude::*, Request, Response, Result};
+use zino_model::{Passage, Resource};
+
+pub async fn answer(mut req: Request) -> Result {
+    let body = req.parse_body::<Map>().await?;
+    let Some(question) = body.get_str("question").filter(|s| !s.is_empty()) else {
+        reject!(req, "question", "it should be nonempty");
+    };
+
+    let user_session = req.get_data::<UserSession<Uuid>>();
+    let data = Passage::answer(question, &body, user_session.as_ref())
+        .await
+        .extract(&req)?;
+    let mut res = Response::default().context(&req);
+    res.set_json_data(data);
+    Ok(res.into())
+}
+
+pub async fn index(req: Request) -> Result {
+    let Some(user_session) = req.get_data::<UserSession<Uuid>>() else {
+        reject!(req, unauthorized, "the user session should be initialized");
+    };
+
+    let resource_id = req.parse_param::<Uuid>("id")?;
+    let resource = Resource::try_get_model(&resource_id).await.extract(&req)?;
+    let num_passages = Passage::index_resource(&resource, &user_session)
+        .await
+        .extract(&req)?;
+
+    let mut res = Response::default().context(&req);
+    res.set_json_data(Map::from_entry("num_passages", num_passages));
+    Ok(res.into())
+}
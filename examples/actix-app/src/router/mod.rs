@@ -1,5 +1,5 @@
 use crate::{
-    controller::{auth, file, stats, user},
+    controller::{auth, file, passage, stats, user},
     middleware,
     model::Tag,
 };
@@ -11,6 +11,7 @@ pub fn routes() -> Vec<RouterConfigure> {
     vec![
         auth_router as RouterConfigure,
         file_router as RouterConfigure,
+        passage_router as RouterConfigure,
         user_router as RouterConfigure,
         tag_router as RouterConfigure,
     ]
@@ -43,6 +44,15 @@ fn file_router(cfg: &mut ServiceConfig) {
     );
 }
 
+fn passage_router(cfg: &mut ServiceConfig) {
+    cfg.service(
+        scope("/passage")
+            .route("/answer", post().to(passage::answer))
+            .route("/resource/{id}/index", post().to(passage::index))
+            .wrap(middleware::UserSessionInitializer),
+    );
+}
+
 fn user_router(cfg: &mut ServiceConfig) {
     cfg.route("/user/new", post().to(user::new))
         .route("/user/{id}/delete", post().to(User::soft_delete))
@@ -44,6 +44,7 @@ use opendal::{
     ErrorKind::Unsupported,
     Operator,
 };
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
 use toml::Table;
 
 /// Global storage accessor built on the top of [`opendal`](https://crates.io/crates/opendal).
@@ -582,10 +583,27 @@ impl GlobalAccessor {
     }
 
     /// Gets the operator for the specific service.
+    /// It returns `None` if the accessors have been closed.
     #[inline]
     pub fn get(name: &str) -> Option<&'static Operator> {
+        if ACCESSORS_CLOSED.load(Relaxed) {
+            return None;
+        }
         SHARED_STORAGE_ACCESSORS.find(name)
     }
+
+    /// Returns an iterator over the shared operators with their service names.
+    #[inline]
+    pub fn iter() -> impl Iterator<Item = (&'static str, &'static Operator)> {
+        SHARED_STORAGE_ACCESSORS.iter()
+    }
+
+    /// Closes the shared operators so that no more storage operations
+    /// can be started via [`GlobalAccessor::get`].
+    #[inline]
+    pub fn close_all() {
+        ACCESSORS_CLOSED.store(true, Relaxed);
+    }
 }
 
 /// Shared storage accessors.
@@ -602,3 +620,6 @@ static SHARED_STORAGE_ACCESSORS: LazyLock<StaticRecord<Operator>> = LazyLock::ne
     }
     operators
 });
+
+/// A flag to indicate whether the shared storage accessors have been closed.
+static ACCESSORS_CLOSED: AtomicBool = AtomicBool::new(false);
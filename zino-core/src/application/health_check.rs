@@ -0,0 +1,429 @@
+use super::plugin;
+use crate::{
+    datetime::DateTime,
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    state::State,
+    BoxFuture, LazyLock, Map,
+};
+use futures::future::{self, Either};
+use futures_timer::Delay;
+use parking_lot::{Mutex, RwLock};
+use std::{
+    future::Future,
+    pin::pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// A custom health check for a component of the application.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use zino_core::{application::HealthCheck, error::Error, BoxFuture, Map};
+///
+/// struct RedisHealth;
+///
+/// impl HealthCheck for RedisHealth {
+///     fn name(&self) -> &'static str {
+///         "redis"
+///     }
+///
+///     fn check(&self) -> BoxFuture<'_, Result<Map, Error>> {
+///         Box::pin(async {
+///             let mut details = Map::new();
+///             details.upsert("pong", Redis::ping().await?);
+///             Ok(details)
+///         })
+///     }
+/// }
+/// ```
+pub trait HealthCheck: Send + Sync {
+    /// Returns the component name.
+    fn name(&self) -> &'static str;
+
+    /// Returns `true` if the application can not be ready without the component.
+    #[inline]
+    fn is_critical(&self) -> bool {
+        true
+    }
+
+    /// Checks the health of the component and returns the details.
+    fn check(&self) -> BoxFuture<'_, Result<Map, Error>>;
+}
+
+/// Health status of a component.
+#[derive(Debug, Clone)]
+pub struct ComponentHealth {
+    /// Component name.
+    name: &'static str,
+    /// Component category.
+    category: &'static str,
+    /// A flag to indicate whether the component is critical.
+    critical: bool,
+    /// Error message.
+    error: Option<String>,
+    /// Details.
+    details: Map,
+}
+
+impl ComponentHealth {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(name: &'static str, category: &'static str) -> Self {
+        Self {
+            name,
+            category,
+            critical: true,
+            error: None,
+            details: Map::new(),
+        }
+    }
+
+    /// Sets the critical flag.
+    #[inline]
+    pub fn set_critical(&mut self, critical: bool) {
+        self.critical = critical;
+    }
+
+    /// Sets the error message.
+    #[inline]
+    pub fn set_error(&mut self, error: impl ToString) {
+        self.error = Some(error.to_string());
+    }
+
+    /// Sets the details.
+    #[inline]
+    pub fn set_details(&mut self, details: Map) {
+        self.details = details;
+    }
+
+    /// Returns `true` if the component is healthy.
+    #[inline]
+    pub fn is_up(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Returns `true` if the component is critical.
+    #[inline]
+    pub fn is_critical(&self) -> bool {
+        self.critical
+    }
+
+    /// Returns the component name.
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the component category.
+    #[inline]
+    pub fn category(&self) -> &'static str {
+        self.category
+    }
+
+    /// Consumes the component health and returns as a json object.
+    pub fn into_map(self) -> Map {
+        let mut map = Map::new();
+        map.upsert("name", self.name);
+        map.upsert("category", self.category);
+        map.upsert("status", if self.error.is_none() { "up" } else { "down" });
+        map.upsert("critical", self.critical);
+        if let Some(error) = self.error {
+            map.upsert("error", error);
+        }
+        if !self.details.is_empty() {
+            map.upsert("details", self.details);
+        }
+        map
+    }
+}
+
+/// A health report aggregating the status of components.
+#[derive(Debug, Clone, Default)]
+pub struct HealthReport {
+    /// Components.
+    components: Vec<ComponentHealth>,
+    /// The time when the components were checked.
+    checked_at: DateTime,
+}
+
+impl HealthReport {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks the liveness of the application.
+    /// It does not check any component so that it is cheap enough for frequent probes.
+    #[inline]
+    pub fn liveness() -> Self {
+        Self::new()
+    }
+
+    /// Checks the readiness of the application by aggregating the availability
+    /// of connection pools, storage accessors, data sources, plugins and custom checks.
+    ///
+    /// The components are checked concurrently, and each check is bounded by
+    /// the `check-timeout` in the `[health]` config. The report is cached for
+    /// the `cache-ttl` so that frequent probes will not overload the components.
+    pub async fn readiness() -> Self {
+        if super::shutdown_hook::is_shutting_down() {
+            let mut report = Self::new();
+            let mut component = ComponentHealth::new(super::APP_NMAE.as_ref(), "application");
+            component.set_error("the application is shutting down");
+            report.add_component(component);
+            return report;
+        }
+
+        let cache_ttl = SHARED_HEALTH_CONFIG.cache_ttl;
+        if let Some((checked_at, report)) = SHARED_READINESS_REPORT.lock().as_ref() {
+            if checked_at.elapsed() < cache_ttl {
+                return report.clone();
+            }
+        }
+
+        let timeout = SHARED_HEALTH_CONFIG.check_timeout;
+        let mut checks: Vec<BoxFuture<'static, ComponentHealth>> = Vec::new();
+
+        #[cfg(feature = "orm")]
+        for cp in crate::orm::GlobalPool::iter() {
+            use crate::orm::PoolManager;
+
+            let mut component = ComponentHealth::new(cp.name(), "database");
+            let mut details = Map::new();
+            details.upsert("database", cp.database());
+            details.upsert("missed_count", cp.missed_count());
+            component.set_details(details);
+            let check = async move {
+                if cp.check_availability().await {
+                    Ok(Map::new())
+                } else {
+                    Err("the connection pool is unavailable".to_owned())
+                }
+            };
+            checks.push(Box::pin(check_component(component, check, timeout)));
+        }
+
+        #[cfg(feature = "accessor")]
+        for (name, operator) in crate::accessor::GlobalAccessor::iter() {
+            let mut component = ComponentHealth::new(name, "accessor");
+            let mut details = Map::new();
+            details.upsert("scheme", operator.info().scheme().into_static());
+            component.set_details(details);
+            let check = async move {
+                operator
+                    .check()
+                    .await
+                    .map(|_| Map::new())
+                    .map_err(|err| err.to_string())
+            };
+            checks.push(Box::pin(check_component(component, check, timeout)));
+        }
+
+        #[cfg(feature = "connector")]
+        for (name, data_source) in crate::connector::GlobalConnector::iter() {
+            let mut component = ComponentHealth::new(name, "connector");
+            let mut details = Map::new();
+            details.upsert("protocol", data_source.protocol());
+            details.upsert("source_type", data_source.source_type());
+            component.set_critical(false);
+            component.set_details(details);
+            let check = async move {
+                data_source
+                    .check_availability()
+                    .await
+                    .map(|_| Map::new())
+                    .map_err(|err| err.message().to_owned())
+            };
+            checks.push(Box::pin(check_component(component, check, timeout)));
+        }
+
+        let mut report = Self::new();
+        report.components = future::join_all(checks).await;
+
+        for (name, result) in plugin::load_results() {
+            let mut component = ComponentHealth::new(name, "plugin");
+            if let Err(err) = result {
+                component.set_error(err);
+            }
+            report.add_component(component);
+        }
+
+        let custom_checks = SHARED_HEALTH_CHECKS.read().clone();
+        let custom_checks = custom_checks.into_iter().map(|check| {
+            let mut component = ComponentHealth::new(check.name(), "custom");
+            component.set_critical(check.is_critical());
+            let check = async move { check.check().await.map_err(|err| err.message().to_owned()) };
+            check_component(component, check, timeout)
+        });
+        report
+            .components
+            .extend(future::join_all(custom_checks).await);
+
+        *SHARED_READINESS_REPORT.lock() = Some((Instant::now(), report.clone()));
+        report
+    }
+
+    /// Registers a custom health check shared by the readiness reports.
+    #[inline]
+    pub fn register(check: impl HealthCheck + 'static) {
+        SHARED_HEALTH_CHECKS.write().push(Arc::new(check));
+    }
+
+    /// Adds the health status of a component.
+    #[inline]
+    pub fn add_component(&mut self, component: ComponentHealth) {
+        self.components.push(component);
+    }
+
+    /// Returns `true` if all the critical components are healthy.
+    #[inline]
+    pub fn is_up(&self) -> bool {
+        self.components
+            .iter()
+            .all(|component| component.is_up() || !component.is_critical())
+    }
+
+    /// Returns a reference to the components.
+    #[inline]
+    pub fn components(&self) -> &[ComponentHealth] {
+        &self.components
+    }
+
+    /// Consumes the report and returns as a json object.
+    pub fn into_map(self) -> Map {
+        let mut map = Map::new();
+        map.upsert("status", if self.is_up() { "up" } else { "down" });
+        map.upsert("app_name", *super::APP_NMAE);
+        map.upsert("app_version", *super::APP_VERSION);
+        map.upsert("checked_at", self.checked_at);
+        if !self.components.is_empty() {
+            let components = self
+                .components
+                .into_iter()
+                .map(|component| component.into_map())
+                .collect::<Vec<_>>();
+            map.upsert("components", components);
+        }
+        map
+    }
+}
+
+/// Shared custom health checks.
+static SHARED_HEALTH_CHECKS: LazyLock<RwLock<Vec<Arc<dyn HealthCheck>>>> =
+    LazyLock::new(|| RwLock::new(Vec::new()));
+
+/// Checks a component with a timeout and returns its health status.
+async fn check_component<F>(
+    mut component: ComponentHealth,
+    check: F,
+    timeout: Duration,
+) -> ComponentHealth
+where
+    F: Future<Output = Result<Map, String>>,
+{
+    match future::select(pin!(check), Delay::new(timeout)).await {
+        Either::Left((Ok(details), _)) => {
+            if !details.is_empty() {
+                component.set_details(details);
+            }
+        }
+        Either::Left((Err(err), _)) => component.set_error(err),
+        Either::Right(_) => {
+            let timeout = timeout.as_millis();
+            component.set_error(format!("the check has timed out after {timeout}ms"));
+        }
+    }
+    component
+}
+
+/// Options for the health checks.
+#[derive(Debug, Clone, Copy)]
+struct HealthConfig {
+    /// Timeout of checking a component.
+    check_timeout: Duration,
+    /// Time-to-live of the cached readiness report.
+    cache_ttl: Duration,
+}
+
+/// Shared options for the health checks.
+static SHARED_HEALTH_CONFIG: LazyLock<HealthConfig> = LazyLock::new(|| {
+    let config = State::shared().get_config("health");
+    let check_timeout = config
+        .and_then(|config| config.get_duration("check-timeout"))
+        .unwrap_or(Duration::from_secs(3));
+    let cache_ttl = config
+        .and_then(|config| config.get_duration("cache-ttl"))
+        .unwrap_or(Duration::from_secs(1));
+    HealthConfig {
+        check_timeout,
+        cache_ttl,
+    }
+});
+
+/// Shared readiness report with the time when it was checked.
+static SHARED_READINESS_REPORT: LazyLock<Mutex<Option<(Instant, HealthReport)>>> =
+    LazyLock::new(|| Mutex::new(None));
+
+#[cfg(test)]
+mod tests {
+    use super::{ComponentHealth, HealthReport};
+    use crate::{extension::JsonObjectExt, Map};
+    use futures::executor::block_on;
+    use futures_timer::Delay;
+    use std::time::Duration;
+
+    #[test]
+    fn it_checks_components_with_timeout() {
+        let timeout = Duration::from_millis(50);
+        let component = ComponentHealth::new("fast", "custom");
+        let check = async { Ok(Map::from_entry("pong", true)) };
+        let component = block_on(super::check_component(component, check, timeout));
+        assert!(component.is_up());
+        assert_eq!(component.into_map().get_str("status"), Some("up"));
+
+        let component = ComponentHealth::new("slow", "custom");
+        let check = async {
+            Delay::new(Duration::from_secs(5)).await;
+            Ok(Map::new())
+        };
+        let component = block_on(super::check_component(component, check, timeout));
+        assert!(!component.is_up());
+
+        let component = ComponentHealth::new("failed", "custom");
+        let check = async { Err("connection refused".to_owned()) };
+        let component = block_on(super::check_component(component, check, timeout));
+        assert_eq!(
+            component.into_map().get_str("error"),
+            Some("connection refused")
+        );
+    }
+
+    #[test]
+    fn it_aggregates_critical_components() {
+        let mut report = HealthReport::new();
+        assert!(report.is_up());
+
+        let mut component = ComponentHealth::new("cache", "custom");
+        component.set_critical(false);
+        component.set_error("the cache is unavailable");
+        report.add_component(component);
+        assert!(report.is_up());
+
+        let mut component = ComponentHealth::new("main", "database");
+        component.set_error("the connection pool is unavailable");
+        report.add_component(component);
+        assert!(!report.is_up());
+        assert_eq!(report.into_map().get_str("status"), Some("down"));
+    }
+
+    #[test]
+    fn it_caches_readiness_reports() {
+        let report = block_on(HealthReport::readiness());
+        let cached_report = block_on(HealthReport::readiness());
+        assert_eq!(report.checked_at, cached_report.checked_at);
+    }
+}
@@ -44,7 +44,7 @@ use crate::{
     schedule::{AsyncJobScheduler, AsyncScheduler, Scheduler},
     state::{Env, State},
     trace::TraceContext,
-    LazyLock, Map,
+    BoxFuture, LazyLock, Map,
 };
 use reqwest::Response;
 use serde::de::DeserializeOwned;
@@ -52,9 +52,11 @@ use std::{env, fs, path::PathBuf, thread};
 use toml::value::Table;
 use utoipa::openapi::{OpenApi, OpenApiBuilder};
 
+mod health_check;
 mod plugin;
 mod secret_key;
 mod server_tag;
+mod shutdown_hook;
 mod static_record;
 mod tracing_subscriber;
 
@@ -68,8 +70,10 @@ pub(crate) mod http_client;
 
 pub(crate) use secret_key::SECRET_KEY;
 
+pub use health_check::{ComponentHealth, HealthCheck, HealthReport};
 pub use plugin::Plugin;
 pub use server_tag::ServerTag;
+pub use shutdown_hook::ShutdownHook;
 pub use static_record::StaticRecord;
 
 /// Application interfaces.
@@ -175,6 +179,29 @@ pub trait Application {
         self
     }
 
+    /// Adds a custom health check reported by the readiness probe.
+    #[inline]
+    fn add_health_check<C: HealthCheck + 'static>(self, check: C) -> Self
+    where
+        Self: Sized,
+    {
+        HealthReport::register(check);
+        self
+    }
+
+    /// Registers a hook to be run when the application shuts down.
+    /// The hooks are run in the reverse order of registration
+    /// before closing the connection pools and storage accessors.
+    #[inline]
+    fn on_shutdown<F>(self, name: &'static str, hook: F) -> Self
+    where
+        Self: Sized,
+        F: FnOnce() -> BoxFuture<'static, Result<(), Error>> + Send + 'static,
+    {
+        shutdown_hook::register(name, Box::new(hook));
+        self
+    }
+
     /// Gets the [OpenAPI](https://spec.openapis.org/oas/latest.html) document.
     #[inline]
     fn openapi() -> OpenApi {
@@ -291,11 +318,36 @@ pub trait Application {
         crate::orm::GlobalPool::connect_all().await;
     }
 
-    /// Handles the graceful shutdown.
+    /// Marks the application as shutting down so that the readiness probe fails
+    /// and the schedulers stop ticking.
+    #[inline]
+    fn start_shutdown() {
+        shutdown_hook::start();
+    }
+
+    /// Returns `true` if the application is shutting down.
     #[inline]
+    fn is_shutting_down() -> bool {
+        shutdown_hook::is_shutting_down()
+    }
+
+    /// Handles the graceful shutdown.
+    ///
+    /// It should be called after the in-flight requests have been drained.
+    /// The registered shutdown hooks are run before closing the connection pools,
+    /// data source connectors and storage accessors.
     async fn shutdown() {
+        shutdown_hook::start();
+        shutdown_hook::run_all().await;
+
         #[cfg(feature = "orm")]
         crate::orm::GlobalPool::close_all().await;
+
+        #[cfg(feature = "connector")]
+        crate::connector::GlobalConnector::close_all().await;
+
+        #[cfg(feature = "accessor")]
+        crate::accessor::GlobalAccessor::close_all();
     }
 
     /// Makes an HTTP request to the provided resource.
@@ -1,9 +1,11 @@
+use super::ShutdownHook;
 use crate::{
     error::Error,
     extension::TomlTableExt,
     state::{Env, State},
-    BoxFuture,
+    BoxFuture, LazyLock,
 };
+use parking_lot::RwLock;
 use smallvec::SmallVec;
 use toml::value::Table;
 
@@ -13,6 +15,8 @@ pub struct Plugin {
     name: &'static str,
     /// Plugin loader.
     loader: Option<BoxFuture<'static, Result<(), Error>>>,
+    /// Shutdown hook.
+    shutdown_hook: Option<ShutdownHook>,
     /// Running environments.
     environments: SmallVec<[Env; 2]>,
     /// Dependencies.
@@ -26,6 +30,7 @@ impl Plugin {
         Self {
             name,
             loader: None,
+            shutdown_hook: None,
             environments: SmallVec::new(),
             dependencies: SmallVec::new(),
         }
@@ -37,6 +42,16 @@ impl Plugin {
         self.loader = Some(loader);
     }
 
+    /// Sets an asynchronous hook to be run when the application shuts down.
+    /// It only takes effect if the plugin has been loaded successfully.
+    #[inline]
+    pub fn set_shutdown_hook<F>(&mut self, hook: F)
+    where
+        F: FnOnce() -> BoxFuture<'static, Result<(), Error>> + Send + 'static,
+    {
+        self.shutdown_hook = Some(Box::new(hook));
+    }
+
     /// Enables the running environment [`Env::Dev`].
     #[inline]
     pub fn enable_dev(&mut self) {
@@ -102,13 +117,48 @@ impl Plugin {
         }
     }
 
-    /// Loads the plugin.
+    /// Records the load error of the plugin, which will be reported by the health checks.
     #[inline]
+    pub fn record_load_error(&self, err: &Error) {
+        record_load_result(self.name, Err(err.message().to_owned()));
+    }
+
+    /// Loads the plugin.
     pub async fn load(self) -> Result<(), Error> {
-        if let Some(loader) = self.loader {
+        let result = if let Some(loader) = self.loader {
             loader.await
         } else {
             Ok(())
-        }
+        };
+        let load_result = match &result {
+            Ok(_) => {
+                if let Some(hook) = self.shutdown_hook {
+                    super::shutdown_hook::register(self.name, hook);
+                }
+                Ok(())
+            }
+            Err(err) => Err(err.message().to_owned()),
+        };
+        record_load_result(self.name, load_result);
+        result
+    }
+}
+
+/// Records the load result of a plugin.
+fn record_load_result(name: &'static str, result: Result<(), String>) {
+    let mut results = PLUGIN_LOAD_RESULTS.write();
+    if let Some(entry) = results.iter_mut().find(|entry| entry.0 == name) {
+        entry.1 = result;
+    } else {
+        results.push((name, result));
     }
 }
+
+/// Returns the load results of the plugins.
+pub(super) fn load_results() -> Vec<(&'static str, Result<(), String>)> {
+    PLUGIN_LOAD_RESULTS.read().clone()
+}
+
+/// Load results of the plugins.
+static PLUGIN_LOAD_RESULTS: LazyLock<RwLock<Vec<(&'static str, Result<(), String>)>>> =
+    LazyLock::new(|| RwLock::new(Vec::new()));
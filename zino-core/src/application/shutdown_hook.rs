@@ -0,0 +1,76 @@
+use crate::{error::Error, BoxFuture, LazyLock};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+/// An asynchronous hook to be run when the application shuts down.
+/// The future is only created when the hook is run.
+pub type ShutdownHook = Box<dyn FnOnce() -> BoxFuture<'static, Result<(), Error>> + Send>;
+
+/// Registers a shutdown hook with a name.
+pub(super) fn register(name: &'static str, hook: ShutdownHook) {
+    SHUTDOWN_HOOKS.lock().push((name, hook));
+}
+
+/// Runs the registered shutdown hooks in the reverse order of registration.
+pub(super) async fn run_all() {
+    let hooks = std::mem::take(&mut *SHUTDOWN_HOOKS.lock());
+    for (name, hook) in hooks.into_iter().rev() {
+        match hook().await {
+            Ok(()) => tracing::info!("shutdown hook `{name}` has been finished"),
+            Err(err) => tracing::error!("fail to run the shutdown hook `{name}`: {err}"),
+        }
+    }
+}
+
+/// Marks the application as shutting down.
+#[inline]
+pub(super) fn start() {
+    SHUTTING_DOWN.store(true, Relaxed);
+}
+
+/// Returns `true` if the application is shutting down.
+#[inline]
+pub(super) fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Relaxed)
+}
+
+/// Registered shutdown hooks.
+static SHUTDOWN_HOOKS: LazyLock<Mutex<Vec<(&'static str, ShutdownHook)>>> =
+    LazyLock::new(|| Mutex::new(Vec::new()));
+
+/// A flag to indicate whether the application is shutting down.
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::Error, warn};
+    use futures::executor::block_on;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    #[test]
+    fn it_runs_shutdown_hooks_in_reverse_order() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        for name in ["database", "cache", "queue"] {
+            let records = records.clone();
+            let hook = move || -> crate::BoxFuture<'static, Result<(), Error>> {
+                Box::pin(async move {
+                    records.lock().push(name);
+                    if name == "cache" {
+                        Err(warn!("fail to flush the cache"))
+                    } else {
+                        Ok(())
+                    }
+                })
+            };
+            super::register(name, Box::new(hook));
+        }
+        assert!(records.lock().is_empty());
+
+        block_on(super::run_all());
+        assert_eq!(*records.lock(), ["queue", "cache", "database"]);
+
+        block_on(super::run_all());
+        assert_eq!(records.lock().len(), 3);
+    }
+}
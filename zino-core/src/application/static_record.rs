@@ -18,6 +18,12 @@ impl<T> StaticRecord<T> {
         self.inner.push((key, value));
     }
 
+    /// Returns an iterator over the entries.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &T)> {
+        self.inner.iter().map(|(key, value)| (*key, value))
+    }
+
     /// Searches for the key and returns its value.
     #[inline]
     pub fn find(&self, key: &str) -> Option<&T> {
@@ -137,6 +137,19 @@ impl JwtClaims<()> {
     }
 }
 
+/// Verifies the JWT token in the value of an `authorization` header by the shared key,
+/// and returns the claims if it is valid.
+pub(crate) fn verify_authorization(authorization: &str) -> Option<JwtClaims> {
+    let token = authorization
+        .strip_prefix("Bearer ")
+        .unwrap_or(authorization);
+    let options = default_verification_options();
+    JwtClaims::shared_key()
+        .verify_token::<Map>(token, Some(options))
+        .ok()
+        .map(JwtClaims)
+}
+
 /// Returns the default time tolerance.
 #[inline]
 pub(crate) fn default_time_tolerance() -> Duration {
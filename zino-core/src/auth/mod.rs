@@ -22,7 +22,9 @@ pub use user_session::UserSession;
 mod jwt_claims;
 
 #[cfg(feature = "jwt")]
-pub(crate) use jwt_claims::{default_time_tolerance, default_verification_options};
+pub(crate) use jwt_claims::{
+    default_time_tolerance, default_verification_options, verify_authorization,
+};
 
 #[cfg(feature = "jwt")]
 pub use jwt_claims::{JwtClaims, JwtHmacKey};
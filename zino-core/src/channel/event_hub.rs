@@ -0,0 +1,221 @@
+use super::{CloudEvent, Subscription};
+use crate::{extension::TomlTableExt, state::State, LazyLock};
+use futures::{
+    channel::mpsc::{self, Receiver, Sender},
+    Stream,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// Global access to the shared event hub.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlobalEventHub;
+
+impl GlobalEventHub {
+    /// Publishes a cloud event to the subscribers whose subscription matches it,
+    /// and returns the number of subscribers the event has been delivered to.
+    ///
+    /// A subscriber whose buffer is full is lagging behind, and it will be disconnected
+    /// instead of blocking the publisher or buffering the events without bound.
+    pub fn publish(event: CloudEvent) -> usize {
+        let mut hub = SHARED_EVENT_HUB.inner.lock();
+        let capacity = SHARED_EVENT_HUB.capacity;
+        if capacity > 0 {
+            if hub.history.len() >= capacity {
+                hub.history.pop_front();
+            }
+            hub.history.push_back(event.clone());
+        }
+
+        let mut num_deliveries = 0;
+        hub.subscribers.retain_mut(|(subscription, sender)| {
+            if sender.is_closed() {
+                false
+            } else if subscription.matches(&event) {
+                match sender.try_send(event.clone()) {
+                    Ok(()) => {
+                        num_deliveries += 1;
+                        true
+                    }
+                    Err(err) => {
+                        if err.is_full() {
+                            tracing::warn!(
+                                event_id = event.id(),
+                                "disconnect a lagging subscriber of the event hub"
+                            );
+                        }
+                        false
+                    }
+                }
+            } else {
+                true
+            }
+        });
+        num_deliveries
+    }
+
+    /// Subscribes to the cloud events matching the subscription.
+    ///
+    /// If the `last_event_id` is provided, the retained events published after it
+    /// will be replayed first. If the ID is no longer retained, all the retained events
+    /// matching the subscription will be replayed.
+    pub fn subscribe(subscription: Subscription, last_event_id: Option<&str>) -> EventSubscriber {
+        let (sender, receiver) = mpsc::channel(SHARED_EVENT_HUB.buffer_size);
+        let mut hub = SHARED_EVENT_HUB.inner.lock();
+        let replay = if let Some(last_event_id) = last_event_id {
+            let history = &hub.history;
+            let start = history
+                .iter()
+                .rposition(|event| event.id() == last_event_id)
+                .map(|index| index + 1)
+                .unwrap_or_default();
+            history
+                .range(start..)
+                .filter(|event| subscription.matches(event))
+                .cloned()
+                .collect()
+        } else {
+            VecDeque::new()
+        };
+        hub.subscribers.push((subscription, sender));
+        EventSubscriber { replay, receiver }
+    }
+
+    /// Returns the number of active subscribers.
+    #[inline]
+    pub fn subscriber_count() -> usize {
+        let hub = SHARED_EVENT_HUB.inner.lock();
+        hub.subscribers
+            .iter()
+            .filter(|(_, sender)| !sender.is_closed())
+            .count()
+    }
+
+    /// Returns the interval for sending heartbeats to the subscribers.
+    #[inline]
+    pub fn heartbeat_interval() -> Duration {
+        SHARED_EVENT_HUB.heartbeat_interval
+    }
+}
+
+/// A stream of cloud events for a subscription.
+#[derive(Debug)]
+pub struct EventSubscriber {
+    /// Events to be replayed.
+    replay: VecDeque<CloudEvent>,
+    /// Receiver of the live events.
+    receiver: Receiver<CloudEvent>,
+}
+
+impl Stream for EventSubscriber {
+    type Item = CloudEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(event) = self.replay.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+/// Subscribers and retained events.
+#[derive(Debug, Default)]
+struct EventHubInner {
+    /// Subscribers.
+    subscribers: Vec<(Subscription, Sender<CloudEvent>)>,
+    /// Retained events for replay.
+    history: VecDeque<CloudEvent>,
+}
+
+/// An event hub with topic routing.
+#[derive(Debug)]
+struct EventHub {
+    /// Inner state.
+    inner: Mutex<EventHubInner>,
+    /// Maximum number of retained events.
+    capacity: usize,
+    /// Buffer size of the live events for each subscriber.
+    buffer_size: usize,
+    /// Heartbeat interval.
+    heartbeat_interval: Duration,
+}
+
+/// Shared event hub.
+static SHARED_EVENT_HUB: LazyLock<EventHub> = LazyLock::new(|| {
+    let mut capacity = 1000;
+    let mut buffer_size = 256;
+    let mut heartbeat_interval = Duration::from_secs(15);
+    if let Some(config) = State::shared().get_config("channel") {
+        if let Some(value) = config.get_usize("replay-capacity") {
+            capacity = value;
+        }
+        if let Some(value) = config.get_usize("buffer-size") {
+            buffer_size = value;
+        }
+        if let Some(interval) = config.get_duration("heartbeat-interval") {
+            heartbeat_interval = interval;
+        }
+    }
+    EventHub {
+        inner: Mutex::new(EventHubInner::default()),
+        capacity,
+        buffer_size,
+        heartbeat_interval,
+    }
+});
+
+#[cfg(test)]
+mod tests {
+    use super::GlobalEventHub;
+    use crate::channel::{CloudEvent, Subscription};
+    use futures::{executor::block_on, StreamExt};
+
+    #[test]
+    fn it_fans_out_cloud_events() {
+        let created = Subscription::new(None, Some("fan-out.created".to_owned()));
+        let all = Subscription::new(None, Some("fan-out.*".to_owned()));
+        let mut created_subscriber = GlobalEventHub::subscribe(created, None);
+        let mut all_subscriber = GlobalEventHub::subscribe(all, None);
+
+        let event = CloudEvent::new("fan-out-1", "zino", "fan-out.created");
+        assert_eq!(GlobalEventHub::publish(event), 2);
+        let event = CloudEvent::new("fan-out-2", "zino", "fan-out.deleted");
+        assert_eq!(GlobalEventHub::publish(event), 1);
+
+        let event = block_on(created_subscriber.next()).unwrap();
+        assert_eq!(event.id(), "fan-out-1");
+        let event = block_on(all_subscriber.next()).unwrap();
+        assert_eq!(event.id(), "fan-out-1");
+        let event = block_on(all_subscriber.next()).unwrap();
+        assert_eq!(event.id(), "fan-out-2");
+
+        let subscription = Subscription::new(None, Some("fan-out.*".to_owned()));
+        let mut subscriber = GlobalEventHub::subscribe(subscription, Some("fan-out-1"));
+        let event = block_on(subscriber.next()).unwrap();
+        assert_eq!(event.id(), "fan-out-2");
+    }
+
+    #[test]
+    fn it_disconnects_lagging_subscribers() {
+        let subscription = Subscription::new(None, Some("lagging.*".to_owned()));
+        let mut subscriber = GlobalEventHub::subscribe(subscription, None);
+        let mut num_deliveries = 0;
+        for i in 0..10_000 {
+            let event = CloudEvent::new(format!("lagging-{i}"), "zino", "lagging.created");
+            if GlobalEventHub::publish(event) == 0 {
+                break;
+            }
+            num_deliveries += 1;
+        }
+        assert!(num_deliveries < 10_000);
+        for _ in 0..num_deliveries {
+            assert!(block_on(subscriber.next()).is_some());
+        }
+        assert!(block_on(subscriber.next()).is_none());
+    }
+}
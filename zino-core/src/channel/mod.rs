@@ -1,7 +1,22 @@
 //! Cloud events and subscriptions.
+//!
+//! Events published via [`GlobalEventHub::publish`] are routed to the subscribers
+//! whose [`Subscription`] matches them. The event hub can be configured as follows:
+//!
+//! ```toml
+//! [channel]
+//! replay-capacity = 1000
+//! buffer-size = 256
+//! heartbeat-interval = "15s"
+//! require-auth = false
+//! sse-route = "/sse"
+//! websocket-route = "/websocket"
+//! ```
 
 mod cloud_event;
+mod event_hub;
 mod subscription;
 
 pub use cloud_event::CloudEvent;
+pub use event_hub::{EventSubscriber, GlobalEventHub};
 pub use subscription::Subscription;
@@ -1,3 +1,4 @@
+use super::CloudEvent;
 use serde::{Deserialize, Serialize};
 
 /// Subscription.
@@ -5,7 +6,9 @@ use serde::{Deserialize, Serialize};
 #[serde(rename_all = "snake_case")]
 #[serde(default)]
 pub struct Subscription {
-    /// Session ID.
+    /// Session ID. It can not be deserialized from the client input,
+    /// and should only be set for an authenticated session.
+    #[serde(skip_deserializing)]
     session_id: Option<String>,
     /// Source.
     source: Option<String>,
@@ -59,4 +62,79 @@ impl Subscription {
     pub fn topic(&self) -> Option<&str> {
         self.topic.as_deref()
     }
+
+    /// Returns `true` if the cloud event matches the subscription.
+    ///
+    /// An event with a session ID is only delivered to the subscription with the same session.
+    /// The topic is matched against the event type, and it supports a comma-separated list
+    /// of patterns such as `user.created` and `user.*`.
+    pub fn matches<T>(&self, event: &CloudEvent<T>) -> bool {
+        if let Some(session_id) = event.session_id() {
+            if self.session_id() != Some(session_id) {
+                return false;
+            }
+        }
+        if let Some(source) = self.source() {
+            if event.source() != source {
+                return false;
+            }
+        }
+        if let Some(topic) = self.topic() {
+            let event_type = event.event_type();
+            return topic.split(',').map(|s| s.trim()).any(|pattern| {
+                if pattern == "*" {
+                    true
+                } else if let Some(prefix) = pattern.strip_suffix('*') {
+                    event_type.starts_with(prefix)
+                } else {
+                    event_type == pattern
+                }
+            });
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Subscription;
+    use crate::channel::CloudEvent;
+
+    #[test]
+    fn it_matches_cloud_events() {
+        let mut event = CloudEvent::<()>::new("1", "zino", "user.created");
+        let mut subscription = Subscription::new(None, Some("user.*".to_owned()));
+        assert!(subscription.matches(&event));
+
+        subscription.set_topic(Some("tag.*, user.created".to_owned()));
+        assert!(subscription.matches(&event));
+
+        subscription.set_topic(Some("user.deleted".to_owned()));
+        assert!(!subscription.matches(&event));
+
+        subscription.set_topic(None);
+        subscription.set_source(Some("zino".to_owned()));
+        assert!(subscription.matches(&event));
+
+        event.set_session_id("abc");
+        assert!(!subscription.matches(&event));
+
+        subscription.set_session_id(Some("abc".to_owned()));
+        assert!(subscription.matches(&event));
+    }
+
+    #[test]
+    fn it_ignores_client_session_ids() {
+        let subscription = serde_json::from_str::<Subscription>(
+            r#"{"session_id": "abc", "source": "zino", "topic": "user.*"}"#,
+        )
+        .unwrap();
+        assert_eq!(subscription.session_id(), None);
+        assert_eq!(subscription.source(), Some("zino"));
+        assert_eq!(subscription.topic(), Some("user.*"));
+
+        let mut event = CloudEvent::<()>::new("1", "zino", "user.created");
+        event.set_session_id("abc");
+        assert!(!subscription.matches(&event));
+    }
 }
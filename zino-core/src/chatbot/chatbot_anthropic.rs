@@ -0,0 +1,430 @@
+use super::{
+    client::ChatbotClient::Anthropic, http_chat, ChatChoice, ChatChunk, ChatCompletion,
+    ChatMessage, ChatRole, ChatStream, ChatUsage, Chatbot, ChatbotErrorKind, ChatbotService,
+    Conversation, ToolCall, ToolCallChunk,
+};
+use crate::{
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    JsonValue, Map,
+};
+use futures::{future, StreamExt};
+use toml::Table;
+
+/// Chat completion of the Anthropic Messages API.
+pub(super) struct AnthropicChat {
+    /// Model.
+    model: String,
+    /// Base URL of the API.
+    api_base: String,
+    /// API key.
+    api_key: String,
+    /// API version.
+    api_version: String,
+    /// Default maximum number of tokens to generate.
+    max_tokens: u64,
+}
+
+impl AnthropicChat {
+    /// Builds the request body for the conversation.
+    fn build_body(&self, conversation: &Conversation, options: Option<Map>, stream: bool) -> Map {
+        let mut system_prompts = Vec::new();
+        let mut messages: Vec<Map> = Vec::new();
+        for message in conversation.messages() {
+            let content = message.content();
+            let (role, blocks) = match message.role() {
+                ChatRole::System => {
+                    system_prompts.push(content);
+                    continue;
+                }
+                ChatRole::User => ("user", vec![new_text_block(content)]),
+                ChatRole::Assistant => {
+                    let mut blocks = Vec::new();
+                    if !content.is_empty() {
+                        blocks.push(new_text_block(content));
+                    }
+                    for call in message.tool_calls() {
+                        let mut block = Map::new();
+                        block.upsert("type", "tool_use");
+                        block.upsert("id", call.id());
+                        block.upsert("name", call.name());
+                        block.upsert("input", call.parse_arguments().unwrap_or_default());
+                        blocks.push(block);
+                    }
+                    ("assistant", blocks)
+                }
+                ChatRole::Tool => {
+                    let mut block = Map::new();
+                    block.upsert("type", "tool_result");
+                    block.upsert("tool_use_id", message.tool_call_id());
+                    block.upsert("content", content);
+                    ("user", vec![block])
+                }
+            };
+
+            // Consecutive messages with the same role are merged,
+            // which is the case for the results of parallel tool calls.
+            let last_message = messages
+                .last_mut()
+                .filter(|message| message.get_str("role") == Some(role));
+            if let Some(message) = last_message {
+                if let Some(JsonValue::Array(content)) = message.get_mut("content") {
+                    content.extend(blocks.into_iter().map(JsonValue::Object));
+                }
+            } else {
+                let mut message = Map::new();
+                message.upsert("role", role);
+                message.upsert("content", blocks);
+                messages.push(message);
+            }
+        }
+
+        let (temperature, max_tokens) = http_chat::parse_options(options);
+        let mut body = Map::new();
+        body.upsert("model", self.model.as_str());
+        body.upsert("max_tokens", max_tokens.unwrap_or(self.max_tokens));
+        if !system_prompts.is_empty() {
+            body.upsert("system", system_prompts.join("\n\n"));
+        }
+        body.upsert("messages", messages);
+        if stream {
+            body.upsert("stream", true);
+        }
+        if let Some(temperature) = temperature {
+            body.upsert("temperature", temperature);
+        }
+        if !conversation.tools().is_empty() {
+            let tools = conversation
+                .tools()
+                .iter()
+                .map(|tool| {
+                    let mut map = Map::new();
+                    map.upsert("name", tool.name());
+                    map.upsert("description", tool.description());
+                    map.upsert("input_schema", tool.parameters().clone());
+                    map.retain(|_, value| !value.is_null());
+                    map
+                })
+                .collect::<Vec<_>>();
+            body.upsert("tools", tools);
+        }
+        body
+    }
+
+    /// Returns the headers of the requests.
+    fn headers(&self) -> Map {
+        let mut headers = Map::new();
+        headers.upsert("x-api-key", self.api_key.as_str());
+        headers.upsert("anthropic-version", self.api_version.as_str());
+        headers
+    }
+
+    /// Returns the URL of the messages API.
+    #[inline]
+    fn messages_url(&self) -> String {
+        format!("{}/v1/messages", self.api_base.trim_end_matches('/'))
+    }
+}
+
+impl ChatbotService for AnthropicChat {
+    fn try_new_chatbot(config: &Table) -> Result<Chatbot, Error> {
+        let name = config.get_str("name").unwrap_or("anthropic");
+        let model = config
+            .get_str("model")
+            .unwrap_or("claude-3-5-sonnet-latest");
+        let api_base = config
+            .get_str("api-base")
+            .unwrap_or("https://api.anthropic.com");
+        let api_version = config.get_str("api-version").unwrap_or("2023-06-01");
+        let chat = AnthropicChat {
+            model: model.to_owned(),
+            api_base: api_base.to_owned(),
+            api_key: config.get_str("api-key").unwrap_or_default().to_owned(),
+            api_version: api_version.to_owned(),
+            max_tokens: config.get_u64("max-tokens").unwrap_or(4096),
+        };
+        Ok(Chatbot::new("anthropic", name, Anthropic(chat)))
+    }
+
+    #[inline]
+    fn model(&self) -> &str {
+        self.model.as_str()
+    }
+
+    async fn try_send(&self, message: String, options: Option<Map>) -> Result<Vec<String>, Error> {
+        let mut conversation = Conversation::new();
+        conversation.add_user_message(message);
+        let completion = self.try_complete(&conversation, options).await?;
+        Ok(completion.into_contents())
+    }
+
+    async fn try_complete(
+        &self,
+        conversation: &Conversation,
+        options: Option<Map>,
+    ) -> Result<ChatCompletion, Error> {
+        let body = self.build_body(conversation, options, false);
+        let response =
+            http_chat::post_json("anthropic", &self.messages_url(), self.headers(), body).await?;
+        let data = response.json::<Map>().await?;
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        for block in data.get_array("content").into_iter().flatten() {
+            match block.get("type").and_then(|v| v.as_str()) {
+                Some("text") => {
+                    if let Some(text) = block.get("text").and_then(|v| v.as_str()) {
+                        content.push_str(text);
+                    }
+                }
+                Some("tool_use") => {
+                    let id = block.get("id").and_then(|v| v.as_str()).unwrap_or_default();
+                    let name = block.get("name").and_then(|v| v.as_str());
+                    let arguments = block.get("input").map(|v| v.to_string());
+                    tool_calls.push(ToolCall::new(
+                        id,
+                        name.unwrap_or_default(),
+                        arguments.unwrap_or_default(),
+                    ));
+                }
+                _ => (),
+            }
+        }
+
+        let mut message = ChatMessage::new(ChatRole::Assistant, content);
+        message.set_tool_calls(tool_calls);
+
+        let finish_reason = data.get_str("stop_reason").map(format_stop_reason);
+        let usage = data.get_object("usage").and_then(|usage| {
+            let input_tokens = usage.get_u32("input_tokens")?;
+            let output_tokens = usage.get_u32("output_tokens")?;
+            Some(ChatUsage::new(input_tokens, output_tokens))
+        });
+        let mut completion = ChatCompletion::new(data.get_str("model").unwrap_or(&self.model));
+        completion.add_choice(ChatChoice::new(message, finish_reason));
+        completion.set_usage(usage);
+        Ok(completion)
+    }
+
+    async fn try_stream(
+        &self,
+        conversation: &Conversation,
+        options: Option<Map>,
+    ) -> Result<ChatStream, Error> {
+        let body = self.build_body(conversation, options, true);
+        let response =
+            http_chat::post_json("anthropic", &self.messages_url(), self.headers(), body).await?;
+        let stream = http_chat::stream_sse_data(response)
+            .scan(StreamState::default(), |state, result| {
+                let item = result.and_then(|data| state.parse_event(&data));
+                future::ready(Some(item))
+            })
+            .filter_map(|result| future::ready(result.transpose()));
+        Ok(stream.boxed())
+    }
+}
+
+/// State of parsing the streamed events.
+#[derive(Debug, Default)]
+struct StreamState {
+    /// Number of the input tokens reported in the `message_start` event.
+    input_tokens: u32,
+    /// Indexes of the content blocks for the tool calls.
+    tool_blocks: Vec<u64>,
+}
+
+impl StreamState {
+    /// Parses an event as a chat chunk. The events without any deltas are skipped.
+    fn parse_event(&mut self, data: &JsonValue) -> Result<Option<ChatChunk>, Error> {
+        let event_type = data
+            .get("type")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let block_index = data.get("index").and_then(|v| v.as_u64());
+        let chunk = match event_type {
+            "message_start" => {
+                self.input_tokens = data
+                    .pointer("/message/usage/input_tokens")
+                    .and_then(|v| v.as_u64())
+                    .and_then(|n| n.try_into().ok())
+                    .unwrap_or_default();
+                None
+            }
+            "content_block_start" => {
+                let block = data.get("content_block");
+                if block.and_then(|b| b.get("type")).and_then(|v| v.as_str()) == Some("tool_use") {
+                    let index = self.tool_blocks.len();
+                    self.tool_blocks.push(block_index.unwrap_or_default());
+                    let tool_call = ToolCallChunk {
+                        index,
+                        id: http_chat::parse_string(block.and_then(|b| b.get("id"))),
+                        name: http_chat::parse_string(block.and_then(|b| b.get("name"))),
+                        arguments: None,
+                    };
+                    Some(ChatChunk {
+                        tool_calls: vec![tool_call],
+                        ..ChatChunk::default()
+                    })
+                } else {
+                    None
+                }
+            }
+            "content_block_delta" => {
+                let delta = data.get("delta");
+                match delta.and_then(|d| d.get("type")).and_then(|v| v.as_str()) {
+                    Some("text_delta") => Some(ChatChunk {
+                        content: http_chat::parse_string(delta.and_then(|d| d.get("text"))),
+                        ..ChatChunk::default()
+                    }),
+                    Some("input_json_delta") => {
+                        let index = self
+                            .tool_blocks
+                            .iter()
+                            .position(|&i| Some(i) == block_index)
+                            .unwrap_or_default();
+                        let tool_call = ToolCallChunk {
+                            index,
+                            arguments: http_chat::parse_string(
+                                delta.and_then(|d| d.get("partial_json")),
+                            ),
+                            ..ToolCallChunk::default()
+                        };
+                        Some(ChatChunk {
+                            tool_calls: vec![tool_call],
+                            ..ChatChunk::default()
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            "message_delta" => {
+                let stop_reason = data
+                    .pointer("/delta/stop_reason")
+                    .and_then(|v| v.as_str())
+                    .map(format_stop_reason);
+                let output_tokens = data
+                    .pointer("/usage/output_tokens")
+                    .and_then(|v| v.as_u64())
+                    .and_then(|n| n.try_into().ok())
+                    .unwrap_or_default();
+                Some(ChatChunk {
+                    finish_reason: stop_reason,
+                    usage: Some(ChatUsage::new(self.input_tokens, output_tokens)),
+                    ..ChatChunk::default()
+                })
+            }
+            "error" => {
+                let error_type = data
+                    .pointer("/error/type")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default();
+                let message = http_chat::parse_error_message(data).unwrap_or_default();
+                let status = match error_type {
+                    "authentication_error" => 401,
+                    "permission_error" => 403,
+                    "rate_limit_error" => 429,
+                    "invalid_request_error" => 400,
+                    "overloaded_error" | "api_error" => 503,
+                    _ => 0,
+                };
+                let kind = ChatbotErrorKind::classify(status, &message);
+                return Err(kind.into_error("anthropic", message));
+            }
+            _ => None,
+        };
+        Ok(chunk)
+    }
+}
+
+/// Creates a text content block.
+fn new_text_block(text: &str) -> Map {
+    let mut block = Map::new();
+    block.upsert("type", "text");
+    block.upsert("text", text);
+    block
+}
+
+/// Formats the stop reason as the finish reason used by OpenAI.
+fn format_stop_reason(reason: &str) -> String {
+    match reason {
+        "end_turn" | "stop_sequence" => "stop",
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        _ => reason,
+    }
+    .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StreamState;
+    use crate::chatbot::{ChatCompletion, ChatbotErrorKind};
+    use serde_json::json;
+
+    #[test]
+    fn it_parses_stream_events() {
+        let events = [
+            json!({
+                "type": "message_start",
+                "message": { "usage": { "input_tokens": 25, "output_tokens": 1 } },
+            }),
+            json!({
+                "type": "content_block_start",
+                "index": 0,
+                "content_block": { "type": "text", "text": "" },
+            }),
+            json!({
+                "type": "content_block_delta",
+                "index": 0,
+                "delta": { "type": "text_delta", "text": "Let me check." },
+            }),
+            json!({
+                "type": "content_block_start",
+                "index": 1,
+                "content_block": { "type": "tool_use", "id": "toolu_01", "name": "get_weather" },
+            }),
+            json!({
+                "type": "content_block_delta",
+                "index": 1,
+                "delta": { "type": "input_json_delta", "partial_json": "{\"city\": " },
+            }),
+            json!({
+                "type": "content_block_delta",
+                "index": 1,
+                "delta": { "type": "input_json_delta", "partial_json": "\"Paris\"}" },
+            }),
+            json!({
+                "type": "message_delta",
+                "delta": { "stop_reason": "tool_use" },
+                "usage": { "output_tokens": 12 },
+            }),
+            json!({ "type": "message_stop" }),
+        ];
+
+        let mut state = StreamState::default();
+        let mut completion = ChatCompletion::new("claude");
+        for event in &events {
+            if let Some(chunk) = state.parse_event(event).unwrap() {
+                completion.merge_chunk(chunk);
+            }
+        }
+        assert_eq!(completion.content(), Some("Let me check."));
+        assert_eq!(completion.usage().map(|u| u.total_tokens()), Some(37));
+
+        let choice = &completion.choices()[0];
+        assert_eq!(choice.finish_reason(), Some("tool_calls"));
+
+        let tool_call = &choice.message().tool_calls()[0];
+        assert_eq!(tool_call.id(), "toolu_01");
+        assert_eq!(
+            tool_call.parse_arguments().unwrap().get("city"),
+            Some(&"Paris".into())
+        );
+
+        let error = json!({
+            "type": "error",
+            "error": { "type": "overloaded_error", "message": "Overloaded" },
+        });
+        let err = state.parse_event(&error).unwrap_err();
+        assert_eq!(ChatbotErrorKind::of(&err), ChatbotErrorKind::Unavailable);
+    }
+}
@@ -0,0 +1,241 @@
+use super::{
+    client::ChatbotClient::Compatible, http_chat, ChatChoice, ChatCompletion, ChatMessage,
+    ChatRole, ChatStream, Chatbot, ChatbotService, Conversation, ToolCall,
+};
+use crate::{
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    Map,
+};
+use futures::StreamExt;
+use toml::Table;
+
+/// Chat completion of the OpenAI-compatible HTTP APIs,
+/// such as the llama.cpp server, vLLM and LM Studio.
+pub(super) struct CompatibleChat {
+    /// Service name.
+    service: String,
+    /// Model.
+    model: String,
+    /// Base URL of the API.
+    api_base: String,
+    /// Optional API key.
+    api_key: Option<String>,
+}
+
+impl CompatibleChat {
+    /// Builds the request body for the conversation.
+    fn build_body(&self, conversation: &Conversation, options: Option<Map>, stream: bool) -> Map {
+        let messages = conversation
+            .messages()
+            .iter()
+            .map(|message| {
+                let mut map = Map::new();
+                map.upsert("role", message.role().as_str());
+                map.upsert("content", message.content());
+                map.upsert("name", message.name());
+                map.upsert("tool_call_id", message.tool_call_id());
+                if !message.tool_calls().is_empty() {
+                    let tool_calls = message
+                        .tool_calls()
+                        .iter()
+                        .map(|call| {
+                            let mut function = Map::new();
+                            function.upsert("name", call.name());
+                            function.upsert("arguments", call.arguments());
+
+                            let mut map = Map::new();
+                            map.upsert("id", call.id());
+                            map.upsert("type", "function");
+                            map.upsert("function", function);
+                            map
+                        })
+                        .collect::<Vec<_>>();
+                    map.upsert("tool_calls", tool_calls);
+                }
+                map.retain(|_, value| !value.is_null());
+                map
+            })
+            .collect::<Vec<_>>();
+
+        let mut body = Map::new();
+        body.upsert("model", self.model.as_str());
+        body.upsert("messages", messages);
+        if stream {
+            body.upsert("stream", true);
+            body.upsert("stream_options", Map::from_entry("include_usage", true));
+        }
+        if !conversation.tools().is_empty() {
+            let tools = conversation
+                .tools()
+                .iter()
+                .map(|tool| {
+                    let mut function = Map::new();
+                    function.upsert("name", tool.name());
+                    function.upsert("description", tool.description());
+                    function.upsert("parameters", tool.parameters().clone());
+
+                    let mut map = Map::new();
+                    map.upsert("type", "function");
+                    map.upsert("function", function);
+                    map
+                })
+                .collect::<Vec<_>>();
+            body.upsert("tools", tools);
+        }
+
+        if let Some(num_choices) = options.as_ref().and_then(|m| m.get_u8("num-choices")) {
+            body.upsert("n", num_choices);
+        }
+        let (temperature, max_tokens) = http_chat::parse_options(options);
+        if let Some(temperature) = temperature {
+            body.upsert("temperature", temperature);
+        }
+        if let Some(max_tokens) = max_tokens {
+            body.upsert("max_tokens", max_tokens);
+        }
+        body
+    }
+
+    /// Returns the headers of the requests.
+    fn headers(&self) -> Map {
+        let mut headers = Map::new();
+        if let Some(api_key) = &self.api_key {
+            headers.upsert("authorization", format!("Bearer {api_key}"));
+        }
+        headers
+    }
+
+    /// Returns the URL of the chat completions API.
+    #[inline]
+    fn chat_url(&self) -> String {
+        format!("{}/chat/completions", self.api_base.trim_end_matches('/'))
+    }
+
+    /// Generates the embeddings for the input texts.
+    pub(super) async fn try_embed(
+        &self,
+        model: &str,
+        inputs: Vec<String>,
+    ) -> Result<Vec<Vec<f32>>, Error> {
+        let mut body = Map::new();
+        body.upsert("model", model);
+        body.upsert("input", inputs);
+
+        let url = format!("{}/embeddings", self.api_base.trim_end_matches('/'));
+        let response = http_chat::post_json(&self.service, &url, self.headers(), body).await?;
+        let data = response.json::<Map>().await?;
+        let mut embeddings = data
+            .get_array("data")
+            .into_iter()
+            .flatten()
+            .filter_map(|item| {
+                let index = item.get("index").and_then(|v| v.as_u64()).unwrap_or(0);
+                let embedding = item.get("embedding").and_then(http_chat::parse_embedding)?;
+                Some((index, embedding))
+            })
+            .collect::<Vec<_>>();
+        embeddings.sort_by_key(|&(index, _)| index);
+        Ok(embeddings
+            .into_iter()
+            .map(|(_, embedding)| embedding)
+            .collect())
+    }
+}
+
+impl ChatbotService for CompatibleChat {
+    fn try_new_chatbot(config: &Table) -> Result<Chatbot, Error> {
+        let service = config.get_str("service").unwrap_or("openai-compatible");
+        let name = config.get_str("name").unwrap_or(service);
+        let model = config.get_str("model").unwrap_or("default");
+        let api_base = config
+            .get_str("api-base")
+            .unwrap_or("http://127.0.0.1:8080/v1");
+        let chat = CompatibleChat {
+            service: service.to_owned(),
+            model: model.to_owned(),
+            api_base: api_base.to_owned(),
+            api_key: config.get_str("api-key").map(|s| s.to_owned()),
+        };
+        Ok(Chatbot::new(service, name, Compatible(chat)))
+    }
+
+    #[inline]
+    fn model(&self) -> &str {
+        self.model.as_str()
+    }
+
+    async fn try_send(&self, message: String, options: Option<Map>) -> Result<Vec<String>, Error> {
+        let mut conversation = Conversation::new();
+        conversation.add_user_message(message);
+        let completion = self.try_complete(&conversation, options).await?;
+        Ok(completion.into_contents())
+    }
+
+    async fn try_complete(
+        &self,
+        conversation: &Conversation,
+        options: Option<Map>,
+    ) -> Result<ChatCompletion, Error> {
+        let body = self.build_body(conversation, options, false);
+        let response =
+            http_chat::post_json(&self.service, &self.chat_url(), self.headers(), body).await?;
+        let data = response.json::<Map>().await?;
+        let mut completion = ChatCompletion::new(data.get_str("model").unwrap_or(&self.model));
+        for choice in data.get_array("choices").into_iter().flatten() {
+            let message = choice.get("message");
+            let content = message
+                .and_then(|m| m.get("content"))
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+            let mut chat_message = ChatMessage::new(ChatRole::Assistant, content);
+            if let Some(tool_calls) = message
+                .and_then(|m| m.get("tool_calls"))
+                .and_then(|v| v.as_array())
+            {
+                let tool_calls = tool_calls
+                    .iter()
+                    .map(|call| {
+                        let id = call.get("id").and_then(|v| v.as_str()).unwrap_or_default();
+                        let function = call.get("function");
+                        let name = function
+                            .and_then(|f| f.get("name"))
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default();
+                        let arguments = function
+                            .and_then(|f| f.get("arguments"))
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default();
+                        ToolCall::new(id, name, arguments)
+                    })
+                    .collect();
+                chat_message.set_tool_calls(tool_calls);
+            }
+            let finish_reason = choice
+                .get("finish_reason")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_owned());
+            completion.add_choice(ChatChoice::new(chat_message, finish_reason));
+        }
+        completion.set_usage(data.get("usage").and_then(http_chat::parse_usage));
+        Ok(completion)
+    }
+
+    async fn try_stream(
+        &self,
+        conversation: &Conversation,
+        options: Option<Map>,
+    ) -> Result<ChatStream, Error> {
+        let body = self.build_body(conversation, options, true);
+        let response =
+            http_chat::post_json(&self.service, &self.chat_url(), self.headers(), body).await?;
+        let stream = http_chat::stream_sse_data(response).flat_map(|result| {
+            let chunks = match result {
+                Ok(data) => http_chat::parse_chat_chunks(&data),
+                Err(err) => vec![Err(err)],
+            };
+            futures::stream::iter(chunks)
+        });
+        Ok(stream.boxed())
+    }
+}
@@ -0,0 +1,325 @@
+use super::{
+    client::ChatbotClient::Ollama, http_chat, ChatChoice, ChatChunk, ChatCompletion, ChatMessage,
+    ChatRole, ChatStream, ChatUsage, Chatbot, ChatbotErrorKind, ChatbotService, Conversation,
+    ToolCall, ToolCallChunk,
+};
+use crate::{
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    JsonValue, Map,
+};
+use futures::StreamExt;
+use toml::Table;
+
+/// Chat completion of the Ollama API for locally hosted models.
+pub(super) struct OllamaChat {
+    /// Model.
+    model: String,
+    /// Base URL of the API.
+    api_base: String,
+    /// Duration of keeping the model loaded in memory.
+    keep_alive: Option<String>,
+}
+
+impl OllamaChat {
+    /// Builds the request body for the conversation.
+    fn build_body(&self, conversation: &Conversation, options: Option<Map>, stream: bool) -> Map {
+        let messages = conversation
+            .messages()
+            .iter()
+            .map(|message| {
+                let mut map = Map::new();
+                map.upsert("role", message.role().as_str());
+                map.upsert("content", message.content());
+                if !message.tool_calls().is_empty() {
+                    let tool_calls = message
+                        .tool_calls()
+                        .iter()
+                        .map(|call| {
+                            let mut function = Map::new();
+                            function.upsert("name", call.name());
+                            function
+                                .upsert("arguments", call.parse_arguments().unwrap_or_default());
+                            Map::from_entry("function", function)
+                        })
+                        .collect::<Vec<_>>();
+                    map.upsert("tool_calls", tool_calls);
+                }
+                map
+            })
+            .collect::<Vec<_>>();
+
+        let mut body = Map::new();
+        body.upsert("model", self.model.as_str());
+        body.upsert("messages", messages);
+        body.upsert("stream", stream);
+        if !conversation.tools().is_empty() {
+            let tools = conversation
+                .tools()
+                .iter()
+                .map(|tool| {
+                    let mut function = Map::new();
+                    function.upsert("name", tool.name());
+                    function.upsert("description", tool.description());
+                    function.upsert("parameters", tool.parameters().clone());
+
+                    let mut map = Map::new();
+                    map.upsert("type", "function");
+                    map.upsert("function", function);
+                    map
+                })
+                .collect::<Vec<_>>();
+            body.upsert("tools", tools);
+        }
+        if let Some(keep_alive) = &self.keep_alive {
+            body.upsert("keep_alive", keep_alive.as_str());
+        }
+
+        let (temperature, max_tokens) = http_chat::parse_options(options);
+        let mut model_options = Map::new();
+        if let Some(temperature) = temperature {
+            model_options.upsert("temperature", temperature);
+        }
+        if let Some(max_tokens) = max_tokens {
+            model_options.upsert("num_predict", max_tokens);
+        }
+        if !model_options.is_empty() {
+            body.upsert("options", model_options);
+        }
+        body
+    }
+
+    /// Returns the URL of the chat API.
+    #[inline]
+    fn chat_url(&self) -> String {
+        format!("{}/api/chat", self.api_base.trim_end_matches('/'))
+    }
+
+    /// Generates the embeddings for the input texts.
+    pub(super) async fn try_embed(
+        &self,
+        model: &str,
+        inputs: Vec<String>,
+    ) -> Result<Vec<Vec<f32>>, Error> {
+        let mut body = Map::new();
+        body.upsert("model", model);
+        body.upsert("input", inputs);
+        if let Some(keep_alive) = &self.keep_alive {
+            body.upsert("keep_alive", keep_alive.as_str());
+        }
+
+        let url = format!("{}/api/embed", self.api_base.trim_end_matches('/'));
+        let response = http_chat::post_json("ollama", &url, Map::new(), body).await?;
+        let data = response.json::<Map>().await?;
+        let embeddings = data
+            .get_array("embeddings")
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(http_chat::parse_embedding)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(embeddings)
+    }
+}
+
+impl ChatbotService for OllamaChat {
+    fn try_new_chatbot(config: &Table) -> Result<Chatbot, Error> {
+        let name = config.get_str("name").unwrap_or("ollama");
+        let model = config.get_str("model").unwrap_or("llama3");
+        let api_base = config
+            .get_str("api-base")
+            .unwrap_or("http://127.0.0.1:11434");
+        let chat = OllamaChat {
+            model: model.to_owned(),
+            api_base: api_base.to_owned(),
+            keep_alive: config.get_str("keep-alive").map(|s| s.to_owned()),
+        };
+        Ok(Chatbot::new("ollama", name, Ollama(chat)))
+    }
+
+    #[inline]
+    fn model(&self) -> &str {
+        self.model.as_str()
+    }
+
+    async fn try_send(&self, message: String, options: Option<Map>) -> Result<Vec<String>, Error> {
+        let mut conversation = Conversation::new();
+        conversation.add_user_message(message);
+        let completion = self.try_complete(&conversation, options).await?;
+        Ok(completion.into_contents())
+    }
+
+    async fn try_complete(
+        &self,
+        conversation: &Conversation,
+        options: Option<Map>,
+    ) -> Result<ChatCompletion, Error> {
+        let body = self.build_body(conversation, options, false);
+        let response = http_chat::post_json("ollama", &self.chat_url(), Map::new(), body).await?;
+        let data = response.json::<JsonValue>().await?;
+        let chunk = parse_chat_chunk(&data)?;
+        let model = data.get("model").and_then(|v| v.as_str());
+        let mut completion = ChatCompletion::new(model.unwrap_or(&self.model));
+        let mut message = ChatMessage::new(ChatRole::Assistant, chunk.content.unwrap_or_default());
+        let tool_calls = chunk
+            .tool_calls
+            .into_iter()
+            .map(|call| {
+                ToolCall::new(
+                    call.id.unwrap_or_default(),
+                    call.name.unwrap_or_default(),
+                    call.arguments.unwrap_or_default(),
+                )
+            })
+            .collect();
+        message.set_tool_calls(tool_calls);
+        completion.add_choice(ChatChoice::new(message, chunk.finish_reason));
+        completion.set_usage(chunk.usage);
+        Ok(completion)
+    }
+
+    async fn try_stream(
+        &self,
+        conversation: &Conversation,
+        options: Option<Map>,
+    ) -> Result<ChatStream, Error> {
+        let body = self.build_body(conversation, options, true);
+        let response = http_chat::post_json("ollama", &self.chat_url(), Map::new(), body).await?;
+        let stream = http_chat::stream_lines(response).map(|result| {
+            let data = serde_json::from_str::<JsonValue>(&result?)?;
+            parse_chat_chunk(&data)
+        });
+        Ok(stream.boxed())
+    }
+}
+
+/// Parses a response of the chat API, which is also a line of the streamed responses.
+fn parse_chat_chunk(data: &JsonValue) -> Result<ChatChunk, Error> {
+    if let Some(message) = http_chat::parse_error_message(data) {
+        let kind = ChatbotErrorKind::classify(0, &message);
+        return Err(kind.into_error("ollama", message));
+    }
+
+    let message = data.get("message").and_then(|v| v.as_object());
+    let content = message
+        .and_then(|m| m.get_str("content"))
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_owned());
+    let tool_calls = message
+        .and_then(|m| m.get_array("tool_calls"))
+        .map(|calls| {
+            calls
+                .iter()
+                .filter_map(|call| call.get("function"))
+                .enumerate()
+                .map(|(index, function)| ToolCallChunk {
+                    index,
+                    id: Some(format!("call_{index}")),
+                    name: function
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_owned()),
+                    arguments: function.get("arguments").map(|v| v.to_string()),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut chunk = ChatChunk {
+        content,
+        tool_calls,
+        ..ChatChunk::default()
+    };
+    if data.get("done").and_then(|v| v.as_bool()) == Some(true) {
+        let finish_reason = if chunk.tool_calls.is_empty() {
+            data.get("done_reason")
+                .and_then(|v| v.as_str())
+                .map(|reason| if reason == "length" { "length" } else { "stop" })
+                .unwrap_or("stop")
+        } else {
+            "tool_calls"
+        };
+        chunk.finish_reason = Some(finish_reason.to_owned());
+
+        let prompt_tokens = data.get("prompt_eval_count").and_then(|v| v.as_u64());
+        let completion_tokens = data.get("eval_count").and_then(|v| v.as_u64());
+        if prompt_tokens.is_some() || completion_tokens.is_some() {
+            let usage = ChatUsage::new(
+                prompt_tokens.unwrap_or_default().try_into()?,
+                completion_tokens.unwrap_or_default().try_into()?,
+            );
+            chunk.usage = Some(usage);
+        }
+    }
+    Ok(chunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_chat_chunk;
+    use crate::chatbot::{ChatCompletion, ChatbotErrorKind};
+    use serde_json::json;
+
+    #[test]
+    fn it_parses_chat_chunks() {
+        let lines = [
+            json!({
+                "model": "llama3.1",
+                "message": { "role": "assistant", "content": "Let me " },
+                "done": false,
+            }),
+            json!({
+                "model": "llama3.1",
+                "message": { "role": "assistant", "content": "check." },
+                "done": false,
+            }),
+            json!({
+                "model": "llama3.1",
+                "message": { "role": "assistant", "content": "" },
+                "done": true,
+                "done_reason": "length",
+                "prompt_eval_count": 25,
+                "eval_count": 12,
+            }),
+        ];
+
+        let mut completion = ChatCompletion::new("llama3.1");
+        for line in &lines {
+            completion.merge_chunk(parse_chat_chunk(line).unwrap());
+        }
+        assert_eq!(completion.content(), Some("Let me check."));
+        assert_eq!(completion.usage().map(|u| u.total_tokens()), Some(37));
+        assert_eq!(completion.choices()[0].finish_reason(), Some("length"));
+
+        let tool_call = json!({
+            "message": {
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{
+                    "function": { "name": "get_weather", "arguments": { "city": "Paris" } },
+                }],
+            },
+            "done": true,
+            "done_reason": "stop",
+        });
+        let mut completion = ChatCompletion::new("llama3.1");
+        completion.merge_chunk(parse_chat_chunk(&tool_call).unwrap());
+
+        let choice = &completion.choices()[0];
+        assert_eq!(choice.finish_reason(), Some("tool_calls"));
+
+        let tool_call = &choice.message().tool_calls()[0];
+        assert_eq!(tool_call.id(), "call_0");
+        assert_eq!(tool_call.name(), "get_weather");
+        assert_eq!(
+            tool_call.parse_arguments().unwrap().get("city"),
+            Some(&"Paris".into())
+        );
+
+        let error = json!({ "error": "the input length exceeds the context length" });
+        let err = parse_chat_chunk(&error).unwrap_err();
+        assert_eq!(ChatbotErrorKind::of(&err), ChatbotErrorKind::ContextLength);
+    }
+}
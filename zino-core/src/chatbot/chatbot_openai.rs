@@ -1,19 +1,27 @@
-use super::{client::ChatbotClient::OpenAi, Chatbot, ChatbotService};
+use super::{
+    client::ChatbotClient::OpenAi, http_chat, ChatChoice, ChatCompletion, ChatMessage, ChatRole,
+    ChatStream, ChatUsage, Chatbot, ChatbotErrorKind, ChatbotService, Conversation, ToolCall,
+};
 use crate::{
     application::http_client,
     error::Error,
-    extension::{JsonObjectExt, TomlTableExt},
-    Map,
+    extension::{JsonObjectExt, JsonValueExt, TomlTableExt},
+    JsonValue, Map,
 };
 use async_openai::{
     config::{Config, OpenAIConfig},
+    error::OpenAIError,
     types::{
-        ChatCompletionRequestMessage, ChatCompletionRequestUserMessageArgs,
-        CreateChatCompletionRequestArgs, Role,
+        ChatCompletionMessageToolCall, ChatCompletionRequestAssistantMessageArgs,
+        ChatCompletionRequestMessage, ChatCompletionRequestSystemMessageArgs,
+        ChatCompletionRequestToolMessageArgs, ChatCompletionRequestUserMessageArgs,
+        ChatCompletionToolArgs, ChatCompletionToolType, CreateChatCompletionRequest,
+        CreateChatCompletionRequestArgs, CreateEmbeddingRequestArgs, FinishReason, FunctionCall,
+        FunctionObjectArgs, Role,
     },
     Chat, Client,
 };
-use futures::StreamExt;
+use futures::{stream, StreamExt};
 use toml::Table;
 
 /// OpenAI chat completion.
@@ -41,6 +49,30 @@ impl<C: Config> OpenAiChatCompletion<C> {
     pub(super) fn chat(&self) -> Chat<'_, C> {
         self.client.chat()
     }
+
+    /// Generates the embeddings for the input texts.
+    pub(super) async fn try_embed(
+        &self,
+        model: &str,
+        inputs: Vec<String>,
+    ) -> Result<Vec<Vec<f32>>, Error> {
+        let request = CreateEmbeddingRequestArgs::default()
+            .model(model)
+            .input(inputs)
+            .build()?;
+        let response = self
+            .client
+            .embeddings()
+            .create(request)
+            .await
+            .map_err(parse_openai_error)?;
+        let mut data = response.data;
+        data.sort_by_key(|embedding| embedding.index);
+        Ok(data
+            .into_iter()
+            .map(|embedding| embedding.embedding)
+            .collect())
+    }
 }
 
 impl ChatbotService for OpenAiChatCompletion<OpenAIConfig> {
@@ -65,7 +97,7 @@ impl ChatbotService for OpenAiChatCompletion<OpenAIConfig> {
         }
 
         let chat_completion = OpenAiChatCompletion::new(model, client);
-        let chatbot = Chatbot::new("openapi", name, OpenAi(chat_completion));
+        let chatbot = Chatbot::new("openai", name, OpenAi(chat_completion));
         Ok(chatbot)
     }
 
@@ -118,4 +150,223 @@ impl ChatbotService for OpenAiChatCompletion<OpenAIConfig> {
         }
         Ok(data)
     }
+
+    async fn try_complete(
+        &self,
+        conversation: &Conversation,
+        options: Option<Map>,
+    ) -> Result<ChatCompletion, Error> {
+        let request = self.build_request(conversation, options)?;
+        let response = self
+            .chat()
+            .create(request)
+            .await
+            .map_err(parse_openai_error)?;
+        let mut completion = ChatCompletion::new(response.model);
+        for choice in response.choices {
+            let message = choice.message;
+            let mut chat_message =
+                ChatMessage::new(ChatRole::Assistant, message.content.unwrap_or_default());
+            if let Some(tool_calls) = message.tool_calls {
+                let tool_calls = tool_calls
+                    .into_iter()
+                    .map(|call| ToolCall::new(call.id, call.function.name, call.function.arguments))
+                    .collect();
+                chat_message.set_tool_calls(tool_calls);
+            }
+            let finish_reason = choice.finish_reason.as_ref().map(format_finish_reason);
+            completion.add_choice(ChatChoice::new(chat_message, finish_reason));
+        }
+        if let Some(usage) = response.usage {
+            let usage = ChatUsage::new(usage.prompt_tokens, usage.completion_tokens);
+            completion.set_usage(Some(usage));
+        }
+        Ok(completion)
+    }
+
+    async fn try_stream(
+        &self,
+        conversation: &Conversation,
+        options: Option<Map>,
+    ) -> Result<ChatStream, Error> {
+        // The `stream_options` is not supported by the request type of `async-openai`,
+        // so the request is sent by the shared HTTP client to report the token usage.
+        let request = self.build_request(conversation, options)?;
+        let mut body = serde_json::to_value(request)?
+            .into_map_opt()
+            .unwrap_or_default();
+        body.upsert("stream", true);
+        body.upsert("stream_options", Map::from_entry("include_usage", true));
+
+        let config = self.client.config();
+        let mut headers = Map::new();
+        for (name, value) in config.headers().iter() {
+            if let Ok(value) = value.to_str() {
+                headers.upsert(name.as_str(), value);
+            }
+        }
+
+        let url = config.url("/chat/completions");
+        let response = http_chat::post_json("openai", &url, headers, body).await?;
+        let stream = http_chat::stream_sse_data(response).flat_map(|result| {
+            let chunks = match result {
+                Ok(data) => http_chat::parse_chat_chunks(&data),
+                Err(err) => vec![Err(err)],
+            };
+            stream::iter(chunks)
+        });
+        Ok(stream.boxed())
+    }
+}
+
+impl OpenAiChatCompletion<OpenAIConfig> {
+    /// Builds a request of the chat completion for the conversation.
+    fn build_request(
+        &self,
+        conversation: &Conversation,
+        options: Option<Map>,
+    ) -> Result<CreateChatCompletionRequest, Error> {
+        let messages = conversation
+            .messages()
+            .iter()
+            .map(parse_request_message)
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut sampling_temperature = 0.5;
+        let mut num_choices = 1;
+        let mut max_tokens = 4096;
+        if let Some(options) = options {
+            if let Some(temperature) = options.get_f32("temperature") {
+                sampling_temperature = temperature;
+            }
+            if let Some(choices) = options.get_u8("num-choices") {
+                num_choices = choices;
+            }
+            if let Some(tokens) = options.get_u16("max-tokens") {
+                max_tokens = tokens;
+            }
+        }
+
+        let mut builder = CreateChatCompletionRequestArgs::default();
+        builder
+            .model(self.model())
+            .messages(messages)
+            .temperature(sampling_temperature)
+            .n(num_choices)
+            .max_tokens(max_tokens);
+        if !conversation.tools().is_empty() {
+            let mut tools = Vec::with_capacity(conversation.tools().len());
+            for tool in conversation.tools() {
+                let mut function = FunctionObjectArgs::default();
+                function
+                    .name(tool.name())
+                    .parameters(JsonValue::Object(tool.parameters().clone()));
+                if let Some(description) = tool.description() {
+                    function.description(description);
+                }
+                let tool = ChatCompletionToolArgs::default()
+                    .r#type(ChatCompletionToolType::Function)
+                    .function(function.build()?)
+                    .build()?;
+                tools.push(tool);
+            }
+            builder.tools(tools);
+        }
+        builder.build().map_err(Error::from)
+    }
+}
+
+/// Parses the chat message as a request message.
+fn parse_request_message(message: &ChatMessage) -> Result<ChatCompletionRequestMessage, Error> {
+    let content = message.content();
+    let request_message = match message.role() {
+        ChatRole::System => {
+            let mut builder = ChatCompletionRequestSystemMessageArgs::default();
+            builder.content(content).role(Role::System);
+            if let Some(name) = message.name() {
+                builder.name(name);
+            }
+            ChatCompletionRequestMessage::System(builder.build()?)
+        }
+        ChatRole::User => {
+            let mut builder = ChatCompletionRequestUserMessageArgs::default();
+            builder.content(content).role(Role::User);
+            if let Some(name) = message.name() {
+                builder.name(name);
+            }
+            ChatCompletionRequestMessage::User(builder.build()?)
+        }
+        ChatRole::Assistant => {
+            let mut builder = ChatCompletionRequestAssistantMessageArgs::default();
+            builder.role(Role::Assistant);
+            if !content.is_empty() {
+                builder.content(content);
+            }
+            if let Some(name) = message.name() {
+                builder.name(name);
+            }
+            if !message.tool_calls().is_empty() {
+                let tool_calls = message
+                    .tool_calls()
+                    .iter()
+                    .map(|call| ChatCompletionMessageToolCall {
+                        id: call.id().to_owned(),
+                        r#type: ChatCompletionToolType::Function,
+                        function: FunctionCall {
+                            name: call.name().to_owned(),
+                            arguments: call.arguments().to_owned(),
+                        },
+                    })
+                    .collect::<Vec<_>>();
+                builder.tool_calls(tool_calls);
+            }
+            ChatCompletionRequestMessage::Assistant(builder.build()?)
+        }
+        ChatRole::Tool => {
+            let request_tool_message = ChatCompletionRequestToolMessageArgs::default()
+                .content(content)
+                .role(Role::Tool)
+                .tool_call_id(message.tool_call_id().unwrap_or_default())
+                .build()?;
+            ChatCompletionRequestMessage::Tool(request_tool_message)
+        }
+    };
+    Ok(request_message)
+}
+
+/// Parses the error returned by the OpenAI API as a classified error.
+fn parse_openai_error(err: OpenAIError) -> Error {
+    match err {
+        OpenAIError::ApiError(err) => {
+            let status = match err.code.as_ref().and_then(|v| v.as_str()) {
+                Some("invalid_api_key") => 401,
+                Some("rate_limit_exceeded") => 429,
+                Some("context_length_exceeded") => 400,
+                _ => match err.r#type.as_deref() {
+                    Some("invalid_request_error") => 400,
+                    Some("insufficient_quota" | "requests" | "tokens") => 429,
+                    Some("server_error") => 503,
+                    _ => 0,
+                },
+            };
+            let message = match err.code.as_ref().and_then(|v| v.as_str()) {
+                Some(code) => format!("{} ({code})", err.message),
+                None => err.message,
+            };
+            ChatbotErrorKind::classify(status, &message).into_error("openai", message)
+        }
+        _ => err.into(),
+    }
+}
+
+/// Formats the finish reason as a snake-cased str.
+fn format_finish_reason(reason: &FinishReason) -> String {
+    match reason {
+        FinishReason::Stop => "stop",
+        FinishReason::Length => "length",
+        FinishReason::ToolCalls => "tool_calls",
+        FinishReason::ContentFilter => "content_filter",
+        FinishReason::FunctionCall => "function_call",
+    }
+    .to_owned()
 }
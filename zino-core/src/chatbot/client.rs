@@ -1,10 +1,20 @@
 use self::ChatbotClient::*;
-use super::ChatbotService;
+use super::{
+    provider, ChatChunk, ChatCompletion, ChatStream, ChatbotProvider, ChatbotProviderFactory,
+    ChatbotService, Conversation, EmbeddingService,
+};
 use crate::{bail, error::Error, extension::TomlTableExt, Map};
+use futures::StreamExt;
 use toml::Table;
 
+#[cfg(feature = "chatbot-openai-compatible")]
+use super::CompatibleChat;
+#[cfg(feature = "chatbot-ollama")]
+use super::OllamaChat;
 #[cfg(feature = "chatbot-openai")]
 use super::OpenAiChatCompletion;
+#[cfg(feature = "chatbot-anthropic")]
+use super::{AnthropicChat, ChatbotErrorKind};
 
 /// Client for supported chatbot services.
 #[non_exhaustive]
@@ -12,6 +22,17 @@ pub(super) enum ChatbotClient {
     /// OpenAI
     #[cfg(feature = "chatbot-openai")]
     OpenAi(OpenAiChatCompletion),
+    /// Ollama
+    #[cfg(feature = "chatbot-ollama")]
+    Ollama(OllamaChat),
+    /// OpenAI-compatible APIs
+    #[cfg(feature = "chatbot-openai-compatible")]
+    Compatible(CompatibleChat),
+    /// Anthropic
+    #[cfg(feature = "chatbot-anthropic")]
+    Anthropic(AnthropicChat),
+    /// Custom provider
+    Custom(Box<dyn ChatbotProvider>),
 }
 
 /// A chatbot with the specific service and model.
@@ -20,6 +41,8 @@ pub struct Chatbot {
     service: String,
     /// Name
     name: String,
+    /// Embedding model
+    embedding_model: Option<String>,
     /// Client
     client: ChatbotClient,
 }
@@ -35,22 +58,56 @@ impl Chatbot {
         Self {
             service: service.into(),
             name: name.into(),
+            embedding_model: None,
             client,
         }
     }
 
     /// Constructs a new instance with the service and configuration,
     /// returning an error if it fails.
+    /// The registered providers take precedence over the built-in services.
     pub fn try_new(service: &str, config: &Table) -> Result<Chatbot, Error> {
+        let mut chatbot = if let Some(factory) = provider::find(service) {
+            let name = config.get_str("name").unwrap_or(service);
+            let provider = factory(config)?;
+            Self::new(service, name, Custom(provider))
+        } else {
+            Self::try_new_builtin(service, config)?
+        };
+        chatbot.embedding_model = config.get_str("embedding-model").map(|s| s.to_owned());
+        Ok(chatbot)
+    }
+
+    /// Constructs a new instance for the built-in services.
+    fn try_new_builtin(service: &str, config: &Table) -> Result<Chatbot, Error> {
         match service {
             #[cfg(feature = "chatbot-openai")]
             "openai" => OpenAiChatCompletion::try_new_chatbot(config),
+            #[cfg(feature = "chatbot-ollama")]
+            "ollama" => OllamaChat::try_new_chatbot(config),
+            #[cfg(feature = "chatbot-openai-compatible")]
+            "llamacpp" | "openai-compatible" => CompatibleChat::try_new_chatbot(config),
+            #[cfg(feature = "chatbot-anthropic")]
+            "anthropic" => AnthropicChat::try_new_chatbot(config),
             _ => {
+                let _ = config;
                 bail!("chatbot service `{}` is unsupported", service);
             }
         }
     }
 
+    /// Registers a provider for the chatbot service,
+    /// which should be called before the shared chatbots are initialized.
+    /// It returns an error if they have been initialized, since the providers
+    /// registered later would be ignored by [`GlobalChatbot`](super::GlobalChatbot).
+    #[inline]
+    pub fn register_provider(
+        service: &'static str,
+        factory: ChatbotProviderFactory,
+    ) -> Result<(), Error> {
+        provider::register(service, factory)
+    }
+
     /// Returns the service.
     #[inline]
     pub fn service(&self) -> &str {
@@ -62,6 +119,12 @@ impl Chatbot {
     pub fn name(&self) -> &str {
         self.name.as_str()
     }
+
+    /// Returns `true` if the embedding model has been configured.
+    #[inline]
+    pub(super) fn has_embedding_model(&self) -> bool {
+        self.embedding_model.is_some()
+    }
 }
 
 impl ChatbotService for Chatbot {
@@ -74,6 +137,13 @@ impl ChatbotService for Chatbot {
         match &self.client {
             #[cfg(feature = "chatbot-openai")]
             OpenAi(chat_completion) => chat_completion.model(),
+            #[cfg(feature = "chatbot-ollama")]
+            Ollama(chat) => chat.model(),
+            #[cfg(feature = "chatbot-openai-compatible")]
+            Compatible(chat) => chat.model(),
+            #[cfg(feature = "chatbot-anthropic")]
+            Anthropic(chat) => chat.model(),
+            Custom(provider) => provider.model(),
         }
     }
 
@@ -81,6 +151,109 @@ impl ChatbotService for Chatbot {
         match &self.client {
             #[cfg(feature = "chatbot-openai")]
             OpenAi(chat_completion) => chat_completion.try_send(message, options).await,
+            #[cfg(feature = "chatbot-ollama")]
+            Ollama(chat) => chat.try_send(message, options).await,
+            #[cfg(feature = "chatbot-openai-compatible")]
+            Compatible(chat) => chat.try_send(message, options).await,
+            #[cfg(feature = "chatbot-anthropic")]
+            Anthropic(chat) => chat.try_send(message, options).await,
+            Custom(provider) => {
+                let mut conversation = Conversation::new();
+                conversation.add_user_message(message);
+                let completion = provider.complete(&conversation, options).await?;
+                Ok(completion.into_contents())
+            }
+        }
+    }
+
+    async fn try_complete(
+        &self,
+        conversation: &Conversation,
+        options: Option<Map>,
+    ) -> Result<ChatCompletion, Error> {
+        let completion = match &self.client {
+            #[cfg(feature = "chatbot-openai")]
+            OpenAi(chat_completion) => chat_completion.try_complete(conversation, options).await,
+            #[cfg(feature = "chatbot-ollama")]
+            Ollama(chat) => chat.try_complete(conversation, options).await,
+            #[cfg(feature = "chatbot-openai-compatible")]
+            Compatible(chat) => chat.try_complete(conversation, options).await,
+            #[cfg(feature = "chatbot-anthropic")]
+            Anthropic(chat) => chat.try_complete(conversation, options).await,
+            Custom(provider) => provider.complete(conversation, options).await,
+        }?;
+        if let Some(usage) = completion.usage() {
+            usage.record(self.service(), completion.model());
+        }
+        Ok(completion)
+    }
+
+    async fn try_stream(
+        &self,
+        conversation: &Conversation,
+        options: Option<Map>,
+    ) -> Result<ChatStream, Error> {
+        let stream = match &self.client {
+            #[cfg(feature = "chatbot-openai")]
+            OpenAi(chat_completion) => chat_completion.try_stream(conversation, options).await,
+            #[cfg(feature = "chatbot-ollama")]
+            Ollama(chat) => chat.try_stream(conversation, options).await,
+            #[cfg(feature = "chatbot-openai-compatible")]
+            Compatible(chat) => chat.try_stream(conversation, options).await,
+            #[cfg(feature = "chatbot-anthropic")]
+            Anthropic(chat) => chat.try_stream(conversation, options).await,
+            Custom(provider) => provider.stream(conversation, options).await,
+        }?;
+        let service = self.service.clone();
+        let model = self.model().to_owned();
+        let stream = stream.inspect(move |result| {
+            if let Ok(ChatChunk {
+                usage: Some(usage), ..
+            }) = result
+            {
+                usage.record(&service, &model);
+            }
+        });
+        Ok(stream.boxed())
+    }
+}
+
+impl EmbeddingService for Chatbot {
+    /// Returns the embedding model, which defaults to the chat model.
+    fn embedding_model(&self) -> &str {
+        self.embedding_model
+            .as_deref()
+            .unwrap_or_else(|| self.model())
+    }
+
+    async fn try_embed(&self, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, Error> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let model = self.embedding_model();
+        let num_inputs = inputs.len();
+        let embeddings = match &self.client {
+            #[cfg(feature = "chatbot-openai")]
+            OpenAi(chat_completion) => chat_completion.try_embed(model, inputs).await,
+            #[cfg(feature = "chatbot-ollama")]
+            Ollama(chat) => chat.try_embed(model, inputs).await,
+            #[cfg(feature = "chatbot-openai-compatible")]
+            Compatible(chat) => chat.try_embed(model, inputs).await,
+            #[cfg(feature = "chatbot-anthropic")]
+            Anthropic(_) => {
+                let message = "the Messages API does not support text embeddings";
+                Err(ChatbotErrorKind::InvalidRequest.into_error("anthropic", message))
+            }
+            Custom(provider) => provider.embed(model, inputs).await,
+        }?;
+        if embeddings.len() != num_inputs {
+            bail!(
+                "{} embeddings are generated for {} inputs",
+                embeddings.len(),
+                num_inputs
+            );
         }
+        Ok(embeddings)
     }
 }
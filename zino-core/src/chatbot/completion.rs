@@ -0,0 +1,318 @@
+use super::{ChatMessage, ChatRole, ToolCall};
+use crate::error::Error;
+use futures::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+
+/// A stream of the chat completion chunks.
+pub type ChatStream = BoxStream<'static, Result<ChatChunk, Error>>;
+
+/// Token usage of a chat completion.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatUsage {
+    /// Number of tokens in the prompt.
+    prompt_tokens: u32,
+    /// Number of tokens in the generated completion.
+    completion_tokens: u32,
+}
+
+impl ChatUsage {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(prompt_tokens: u32, completion_tokens: u32) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+        }
+    }
+
+    /// Returns the number of tokens in the prompt.
+    #[inline]
+    pub fn prompt_tokens(&self) -> u32 {
+        self.prompt_tokens
+    }
+
+    /// Returns the number of tokens in the generated completion.
+    #[inline]
+    pub fn completion_tokens(&self) -> u32 {
+        self.completion_tokens
+    }
+
+    /// Returns the total number of tokens.
+    #[inline]
+    pub fn total_tokens(&self) -> u32 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    /// Records the usage in the metrics.
+    pub(super) fn record(&self, service: &str, model: &str) {
+        #[cfg(feature = "metrics")]
+        {
+            metrics::counter!(
+                "zino_chatbot_tokens_total",
+                "service" => service.to_owned(),
+                "model" => model.to_owned(),
+                "type" => "prompt",
+            )
+            .increment(self.prompt_tokens.into());
+            metrics::counter!(
+                "zino_chatbot_tokens_total",
+                "service" => service.to_owned(),
+                "model" => model.to_owned(),
+                "type" => "completion",
+            )
+            .increment(self.completion_tokens.into());
+        }
+        tracing::debug!(
+            service,
+            model,
+            prompt_tokens = self.prompt_tokens,
+            completion_tokens = self.completion_tokens,
+            "chat completion usage"
+        );
+    }
+}
+
+/// Delta of a tool call in a chunk.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolCallChunk {
+    /// Index of the tool call.
+    pub index: usize,
+    /// ID of the tool call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Name of the tool.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Partial arguments encoded as a JSON string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+}
+
+/// A chunk of the streamed chat completion.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatChunk {
+    /// Index of the choice.
+    pub index: usize,
+    /// Delta of the content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Deltas of the tool calls.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCallChunk>,
+    /// Reason why the model stopped generating tokens.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+    /// Token usage, which is only available in the last chunk for some services.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ChatUsage>,
+}
+
+/// A choice of the chat completion.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatChoice {
+    /// Generated message.
+    message: ChatMessage,
+    /// Reason why the model stopped generating tokens.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    finish_reason: Option<String>,
+}
+
+impl ChatChoice {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(message: ChatMessage, finish_reason: Option<String>) -> Self {
+        Self {
+            message,
+            finish_reason,
+        }
+    }
+
+    /// Returns the generated message.
+    #[inline]
+    pub fn message(&self) -> &ChatMessage {
+        &self.message
+    }
+
+    /// Returns the reason why the model stopped generating tokens.
+    #[inline]
+    pub fn finish_reason(&self) -> Option<&str> {
+        self.finish_reason.as_deref()
+    }
+
+    /// Returns `true` if the model has requested the tool calls.
+    #[inline]
+    pub fn has_tool_calls(&self) -> bool {
+        !self.message.tool_calls().is_empty()
+    }
+
+    /// Consumes `self` and returns the generated message.
+    #[inline]
+    pub fn into_message(self) -> ChatMessage {
+        self.message
+    }
+}
+
+/// A chat completion generated by the model.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatCompletion {
+    /// Model.
+    model: String,
+    /// Choices.
+    choices: Vec<ChatChoice>,
+    /// Token usage.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<ChatUsage>,
+}
+
+impl ChatCompletion {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            choices: Vec::new(),
+            usage: None,
+        }
+    }
+
+    /// Adds a choice.
+    #[inline]
+    pub fn add_choice(&mut self, choice: ChatChoice) {
+        self.choices.push(choice);
+    }
+
+    /// Sets the token usage.
+    #[inline]
+    pub fn set_usage(&mut self, usage: Option<ChatUsage>) {
+        self.usage = usage;
+    }
+
+    /// Merges a chunk of the streamed chat completion.
+    pub fn merge_chunk(&mut self, chunk: ChatChunk) {
+        let index = chunk.index;
+        if self.choices.len() <= index {
+            let message = ChatMessage::new(ChatRole::Assistant, String::new());
+            self.choices
+                .resize(index + 1, ChatChoice::new(message, None));
+        }
+
+        let choice = &mut self.choices[index];
+        if let Some(content) = chunk.content {
+            choice.message.content_mut().push_str(&content);
+        }
+        let tool_calls = choice.message.tool_calls_mut();
+        for delta in chunk.tool_calls {
+            if tool_calls.len() <= delta.index {
+                tool_calls.resize(delta.index + 1, ToolCall::default());
+            }
+            tool_calls[delta.index].append(
+                delta.id.as_deref(),
+                delta.name.as_deref(),
+                delta.arguments.as_deref(),
+            );
+        }
+        if chunk.finish_reason.is_some() {
+            choice.finish_reason = chunk.finish_reason;
+        }
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage;
+        }
+    }
+
+    /// Returns the model.
+    #[inline]
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Returns the choices.
+    #[inline]
+    pub fn choices(&self) -> &[ChatChoice] {
+        &self.choices
+    }
+
+    /// Returns the token usage.
+    #[inline]
+    pub fn usage(&self) -> Option<ChatUsage> {
+        self.usage
+    }
+
+    /// Returns the content of the first choice.
+    #[inline]
+    pub fn content(&self) -> Option<&str> {
+        self.choices.first().map(|choice| choice.message.content())
+    }
+
+    /// Consumes `self` and returns the contents of the choices.
+    #[inline]
+    pub fn into_contents(self) -> Vec<String> {
+        self.choices
+            .into_iter()
+            .map(|choice| choice.message.content().to_owned())
+            .collect()
+    }
+
+    /// Consumes `self` and returns the choices.
+    #[inline]
+    pub fn into_choices(self) -> Vec<ChatChoice> {
+        self.choices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChatChunk, ChatCompletion, ChatUsage, ToolCallChunk};
+
+    #[test]
+    fn it_merges_chat_chunks() {
+        let mut completion = ChatCompletion::new("gpt-4o");
+        completion.merge_chunk(ChatChunk {
+            content: Some("Hello".to_owned()),
+            ..ChatChunk::default()
+        });
+        completion.merge_chunk(ChatChunk {
+            content: Some(", world!".to_owned()),
+            ..ChatChunk::default()
+        });
+        completion.merge_chunk(ChatChunk {
+            index: 1,
+            tool_calls: vec![ToolCallChunk {
+                index: 0,
+                id: Some("call_1".to_owned()),
+                name: Some("get_weather".to_owned()),
+                arguments: Some(r#"{"city":"#.to_owned()),
+            }],
+            ..ChatChunk::default()
+        });
+        completion.merge_chunk(ChatChunk {
+            index: 1,
+            tool_calls: vec![ToolCallChunk {
+                index: 0,
+                arguments: Some(r#""Paris"}"#.to_owned()),
+                ..ToolCallChunk::default()
+            }],
+            finish_reason: Some("tool_calls".to_owned()),
+            usage: Some(ChatUsage::new(20, 8)),
+            ..ChatChunk::default()
+        });
+
+        assert_eq!(completion.content(), Some("Hello, world!"));
+        assert_eq!(completion.usage().map(|u| u.total_tokens()), Some(28));
+
+        let choice = &completion.choices()[1];
+        assert!(choice.has_tool_calls());
+        assert_eq!(choice.finish_reason(), Some("tool_calls"));
+
+        let tool_call = &choice.message().tool_calls()[0];
+        assert_eq!(tool_call.id(), "call_1");
+        assert_eq!(tool_call.name(), "get_weather");
+        assert_eq!(
+            tool_call.parse_arguments().unwrap().get("city"),
+            Some(&"Paris".into())
+        );
+    }
+}
@@ -0,0 +1,48 @@
+/// Normalizes the embedding as a unit vector, so that the cosine similarity
+/// can be computed as the dot product. The zero vector is left unchanged.
+pub fn normalize_embedding(embedding: &mut [f32]) {
+    let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 && norm.is_finite() {
+        for x in embedding.iter_mut() {
+            *x /= norm;
+        }
+    }
+}
+
+/// Computes the cosine similarity of two embeddings.
+/// It returns `0.0` if the lengths are different or any of them is a zero vector.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    let mut dot_product = 0.0;
+    let mut a_norm = 0.0;
+    let mut b_norm = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        dot_product += x * y;
+        a_norm += x * x;
+        b_norm += y * y;
+    }
+    if a_norm == 0.0 || b_norm == 0.0 {
+        0.0
+    } else {
+        dot_product / (a_norm.sqrt() * b_norm.sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cosine_similarity, normalize_embedding};
+
+    #[test]
+    fn it_computes_cosine_similarity() {
+        let mut a = vec![3.0, 4.0];
+        normalize_embedding(&mut a);
+        assert_eq!(a, vec![0.6, 0.8]);
+        assert!((cosine_similarity(&a, &[6.0, 8.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&a, &[-4.0, 3.0]).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&a, &[0.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&a, &[1.0]), 0.0);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::error::Error;
+
+/// Common kinds of the errors returned by the chatbot services.
+///
+/// The errors are created with an HTTP status prefix in the message,
+/// such as `429 Too Many Requests`, so that they can be classified
+/// by [`Rejection::from_error`](crate::response::Rejection::from_error) as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChatbotErrorKind {
+    /// The API key is missing or invalid.
+    Authentication,
+    /// The rate limit or quota has been exceeded.
+    RateLimit,
+    /// The conversation exceeds the context length of the model.
+    ContextLength,
+    /// The request is invalid.
+    InvalidRequest,
+    /// The service is overloaded or unavailable.
+    Unavailable,
+    /// Other errors.
+    Unknown,
+}
+
+impl ChatbotErrorKind {
+    /// Classifies the error with the HTTP status code and the error message.
+    pub fn classify(status: u16, message: &str) -> Self {
+        let message = message.to_ascii_lowercase();
+        let is_context_length = [
+            "context length",
+            "context_length",
+            "context window",
+            "maximum context",
+            "prompt is too long",
+            "too many tokens",
+        ]
+        .iter()
+        .any(|pattern| message.contains(pattern));
+        if is_context_length {
+            return Self::ContextLength;
+        }
+        match status {
+            401 | 403 => Self::Authentication,
+            429 => Self::RateLimit,
+            400 | 404 | 413 | 422 => Self::InvalidRequest,
+            500..=599 => Self::Unavailable,
+            _ => {
+                if message.contains("rate limit") || message.contains("rate_limit") {
+                    Self::RateLimit
+                } else if message.contains("api key") || message.contains("authentication") {
+                    Self::Authentication
+                } else {
+                    Self::Unknown
+                }
+            }
+        }
+    }
+
+    /// Returns the kind of an error created by [`into_error`](Self::into_error).
+    pub fn of(err: &Error) -> Self {
+        let message = err.message();
+        let status = message
+            .get(..3)
+            .and_then(|s| s.parse::<u16>().ok())
+            .unwrap_or_default();
+        Self::classify(status, message)
+    }
+
+    /// Returns the HTTP status prefix for the error message.
+    #[inline]
+    pub fn status_prefix(&self) -> &'static str {
+        match self {
+            Self::Authentication => "401 Unauthorized",
+            Self::RateLimit => "429 Too Many Requests",
+            Self::ContextLength => "400 Bad Request: context length exceeded",
+            Self::InvalidRequest => "400 Bad Request",
+            Self::Unavailable => "503 Service Unavailable",
+            Self::Unknown => "500 Internal Server Error",
+        }
+    }
+
+    /// Creates an error with the service name and the message.
+    #[inline]
+    pub fn into_error(self, service: &str, message: impl AsRef<str>) -> Error {
+        let prefix = self.status_prefix();
+        let message = message.as_ref();
+        Error::new(format!(
+            "{prefix}: the `{service}` chatbot fails: {message}"
+        ))
+    }
+
+    /// Returns `true` if the request can be retried later.
+    #[inline]
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::RateLimit | Self::Unavailable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChatbotErrorKind;
+
+    #[test]
+    fn it_classifies_chatbot_errors() {
+        let kind = ChatbotErrorKind::classify(400, "This model's maximum context length is 8192");
+        assert_eq!(kind, ChatbotErrorKind::ContextLength);
+        assert_eq!(
+            ChatbotErrorKind::classify(429, "Too many requests"),
+            ChatbotErrorKind::RateLimit
+        );
+
+        let err = ChatbotErrorKind::Authentication.into_error("anthropic", "invalid x-api-key");
+        assert!(err.message().starts_with("401 Unauthorized"));
+        assert_eq!(ChatbotErrorKind::of(&err), ChatbotErrorKind::Authentication);
+
+        let err = ChatbotErrorKind::ContextLength.into_error("ollama", "input is truncated");
+        assert_eq!(ChatbotErrorKind::of(&err), ChatbotErrorKind::ContextLength);
+    }
+}
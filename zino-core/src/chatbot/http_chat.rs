@@ -0,0 +1,286 @@
+use super::{ChatChunk, ChatUsage, ChatbotErrorKind, ToolCallChunk};
+use crate::{application::http_client, error::Error, extension::JsonObjectExt, JsonValue, Map};
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use reqwest::Response;
+
+/// Sends a JSON request to the chat API with the shared HTTP client,
+/// which retries the transient failures. The error response is classified
+/// by [`ChatbotErrorKind`].
+pub(super) async fn post_json(
+    service: &str,
+    url: &str,
+    headers: Map,
+    body: Map,
+) -> Result<Response, Error> {
+    let mut options = Map::new();
+    options.upsert("method", "POST");
+    options.upsert("data_type", "json");
+    options.upsert("headers", headers);
+    options.upsert("body", body);
+
+    let response = http_client::request_builder(url, Some(&options))?
+        .send()
+        .await?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let text = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<JsonValue>(&text)
+        .ok()
+        .and_then(|data| parse_error_message(&data))
+        .unwrap_or(text);
+    let kind = ChatbotErrorKind::classify(status.as_u16(), &message);
+    Err(kind.into_error(service, message))
+}
+
+/// Parses the error message in the response data, which can be
+/// `{ "error": { "message": "..." } }` or `{ "error": "..." }`.
+pub(super) fn parse_error_message(data: &JsonValue) -> Option<String> {
+    let error = data.get("error")?;
+    let message = match error {
+        JsonValue::String(message) => message.to_owned(),
+        JsonValue::Object(error) => error.get_str("message")?.to_owned(),
+        _ => error.to_string(),
+    };
+    Some(message)
+}
+
+/// Streams the lines of the response body. The empty lines are skipped.
+pub(super) fn stream_lines(response: Response) -> BoxStream<'static, Result<String, Error>> {
+    let state = (response, Vec::new(), false);
+    stream::try_unfold(state, |(mut response, mut buffer, mut eof)| async move {
+        loop {
+            if let Some(line) = take_line(&mut buffer, eof) {
+                return Ok(Some((line, (response, buffer, eof))));
+            }
+            if eof {
+                return Ok(None);
+            }
+            match response.chunk().await? {
+                Some(chunk) => buffer.extend_from_slice(&chunk),
+                None => eof = true,
+            }
+        }
+    })
+    .boxed()
+}
+
+/// Takes the next non-empty line from the buffered bytes. The bytes are split
+/// on `\n` before decoding so that a multi-byte character spanning chunks is kept intact.
+/// The remaining bytes are taken as the last line if `eof` is `true`.
+fn take_line(buffer: &mut Vec<u8>, eof: bool) -> Option<String> {
+    while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+        let bytes = buffer.drain(..=pos).collect::<Vec<_>>();
+        let line = String::from_utf8_lossy(&bytes).trim().to_owned();
+        if !line.is_empty() {
+            return Some(line);
+        }
+    }
+    if eof && !buffer.is_empty() {
+        let bytes = std::mem::take(buffer);
+        let line = String::from_utf8_lossy(&bytes).trim().to_owned();
+        if !line.is_empty() {
+            return Some(line);
+        }
+    }
+    None
+}
+
+/// Streams the data of the server-sent events as JSON values,
+/// ending with the `[DONE]` message.
+pub(super) fn stream_sse_data(response: Response) -> BoxStream<'static, Result<JsonValue, Error>> {
+    stream_lines(response)
+        .take_while(|result| {
+            let done = result
+                .as_ref()
+                .is_ok_and(|line| line.strip_prefix("data:").map(|s| s.trim()) == Some("[DONE]"));
+            futures::future::ready(!done)
+        })
+        .filter_map(|result| async move {
+            match result {
+                Ok(line) => {
+                    let data = line.strip_prefix("data:")?.trim();
+                    Some(serde_json::from_str::<JsonValue>(data).map_err(Error::from))
+                }
+                Err(err) => Some(Err(err)),
+            }
+        })
+        .boxed()
+}
+
+/// Parses an embedding encoded as an array of numbers.
+pub(super) fn parse_embedding(value: &JsonValue) -> Option<Vec<f32>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|v| v.as_f64().map(|x| x as f32))
+        .collect()
+}
+
+/// Parses the `temperature` and `max-tokens` options of the request.
+pub(super) fn parse_options(options: Option<Map>) -> (Option<f32>, Option<u64>) {
+    let Some(options) = options else {
+        return (None, None);
+    };
+    let temperature = options.get_f32("temperature");
+    let max_tokens = options.get_u64("max-tokens");
+    (temperature, max_tokens)
+}
+
+/// Parses the chunks in the data of a server-sent event.
+/// The usage is reported in the last event with no choices.
+pub(super) fn parse_chat_chunks(data: &JsonValue) -> Vec<Result<ChatChunk, Error>> {
+    let mut chunks = Vec::new();
+    if let Some(choices) = data.get("choices").and_then(|v| v.as_array()) {
+        for choice in choices {
+            let index = choice.get("index").and_then(|v| v.as_u64()).unwrap_or(0);
+            let delta = choice.get("delta");
+            let content = delta
+                .and_then(|d| d.get("content"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_owned());
+            let tool_calls = delta
+                .and_then(|d| d.get("tool_calls"))
+                .and_then(|v| v.as_array())
+                .map(|calls| {
+                    calls
+                        .iter()
+                        .map(|call| {
+                            let function = call.get("function");
+                            let index = call.get("index").and_then(|v| v.as_u64()).unwrap_or(0);
+                            ToolCallChunk {
+                                index: index.try_into().unwrap_or_default(),
+                                id: parse_string(call.get("id")),
+                                name: parse_string(function.and_then(|f| f.get("name"))),
+                                arguments: parse_string(function.and_then(|f| f.get("arguments"))),
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            chunks.push(Ok(ChatChunk {
+                index: index.try_into().unwrap_or_default(),
+                content,
+                tool_calls,
+                finish_reason: parse_string(choice.get("finish_reason")),
+                usage: None,
+            }));
+        }
+    }
+    if let Some(usage) = data.get("usage").and_then(parse_usage) {
+        chunks.push(Ok(ChatChunk {
+            usage: Some(usage),
+            ..ChatChunk::default()
+        }));
+    }
+    chunks
+}
+
+/// Parses the token usage.
+pub(super) fn parse_usage(usage: &JsonValue) -> Option<ChatUsage> {
+    let prompt_tokens = usage.get("prompt_tokens")?.as_u64()?;
+    let completion_tokens = usage.get("completion_tokens")?.as_u64()?;
+    Some(ChatUsage::new(
+        prompt_tokens.try_into().ok()?,
+        completion_tokens.try_into().ok()?,
+    ))
+}
+
+/// Parses an optional string value.
+#[inline]
+pub(super) fn parse_string(value: Option<&JsonValue>) -> Option<String> {
+    value.and_then(|v| v.as_str()).map(|s| s.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_chat_chunks, take_line};
+    use crate::chatbot::ChatCompletion;
+    use serde_json::json;
+
+    #[test]
+    fn it_splits_lines_on_bytes() {
+        let text = "data: {\"content\": \"你好\"}\n\ndata: [DONE]";
+        let bytes = text.as_bytes();
+        let split = text.find('你').unwrap() + 1;
+
+        let mut buffer = bytes[..split].to_vec();
+        assert_eq!(take_line(&mut buffer, false), None);
+
+        buffer.extend_from_slice(&bytes[split..]);
+        assert_eq!(
+            take_line(&mut buffer, false).as_deref(),
+            Some("data: {\"content\": \"你好\"}")
+        );
+        assert_eq!(take_line(&mut buffer, false), None);
+        assert_eq!(
+            take_line(&mut buffer, true).as_deref(),
+            Some("data: [DONE]")
+        );
+        assert!(buffer.is_empty());
+        assert_eq!(take_line(&mut buffer, true), None);
+    }
+
+    #[test]
+    fn it_parses_chat_chunks() {
+        let events = [
+            json!({
+                "choices": [{
+                    "index": 0,
+                    "delta": { "role": "assistant", "content": "Let me " },
+                }],
+            }),
+            json!({
+                "choices": [{
+                    "index": 0,
+                    "delta": {
+                        "content": "check.",
+                        "tool_calls": [{
+                            "index": 0,
+                            "id": "call_01",
+                            "function": { "name": "get_weather", "arguments": "{\"city\": " },
+                        }],
+                    },
+                }],
+            }),
+            json!({
+                "choices": [{
+                    "index": 0,
+                    "delta": {
+                        "tool_calls": [{
+                            "index": 0,
+                            "function": { "arguments": "\"Paris\"}" },
+                        }],
+                    },
+                    "finish_reason": "tool_calls",
+                }],
+            }),
+            json!({
+                "choices": [],
+                "usage": { "prompt_tokens": 25, "completion_tokens": 12, "total_tokens": 37 },
+            }),
+        ];
+
+        let mut completion = ChatCompletion::new("gpt");
+        for event in &events {
+            for chunk in parse_chat_chunks(event) {
+                completion.merge_chunk(chunk.unwrap());
+            }
+        }
+        assert_eq!(completion.content(), Some("Let me check."));
+        assert_eq!(completion.usage().map(|u| u.total_tokens()), Some(37));
+
+        let choice = &completion.choices()[0];
+        assert_eq!(choice.finish_reason(), Some("tool_calls"));
+
+        let tool_call = &choice.message().tool_calls()[0];
+        assert_eq!(tool_call.id(), "call_01");
+        assert_eq!(tool_call.name(), "get_weather");
+        assert_eq!(tool_call.arguments(), "{\"city\": \"Paris\"}");
+    }
+}
@@ -0,0 +1,288 @@
+use super::ToolDefinition;
+use crate::{error::Error, Map};
+use serde::{Deserialize, Serialize};
+
+/// Role of the author of a chat message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    /// System prompts.
+    System,
+    /// Messages from the user.
+    #[default]
+    User,
+    /// Messages generated by the model.
+    Assistant,
+    /// Results of the tool calls.
+    Tool,
+}
+
+impl ChatRole {
+    /// Returns the role as a str.
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::System => "system",
+            Self::User => "user",
+            Self::Assistant => "assistant",
+            Self::Tool => "tool",
+        }
+    }
+}
+
+/// A tool call generated by the model.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolCall {
+    /// ID of the tool call.
+    id: String,
+    /// Name of the tool.
+    name: String,
+    /// Arguments encoded as a JSON string.
+    arguments: String,
+}
+
+impl ToolCall {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        arguments: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            arguments: arguments.into(),
+        }
+    }
+
+    /// Returns the ID of the tool call.
+    #[inline]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the name of the tool.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the arguments encoded as a JSON string.
+    #[inline]
+    pub fn arguments(&self) -> &str {
+        &self.arguments
+    }
+
+    /// Parses the arguments as a map.
+    #[inline]
+    pub fn parse_arguments(&self) -> Result<Map, Error> {
+        if self.arguments.trim().is_empty() {
+            Ok(Map::new())
+        } else {
+            serde_json::from_str(&self.arguments).map_err(Error::from)
+        }
+    }
+
+    /// Appends the delta of a streamed tool call.
+    pub(super) fn append(&mut self, id: Option<&str>, name: Option<&str>, arguments: Option<&str>) {
+        if let Some(id) = id.filter(|s| !s.is_empty()) {
+            self.id = id.to_owned();
+        }
+        if let Some(name) = name {
+            self.name.push_str(name);
+        }
+        if let Some(arguments) = arguments {
+            self.arguments.push_str(arguments);
+        }
+    }
+}
+
+/// A message in the chat conversation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatMessage {
+    /// Role of the author.
+    role: ChatRole,
+    /// Content of the message.
+    content: String,
+    /// Optional name of the participant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// Tool calls generated by the model.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<ToolCall>,
+    /// ID of the tool call that this message responds to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(role: ChatRole, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Creates a system prompt.
+    #[inline]
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::System, content)
+    }
+
+    /// Creates a message from the user.
+    #[inline]
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::User, content)
+    }
+
+    /// Creates a message of the model.
+    #[inline]
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::Assistant, content)
+    }
+
+    /// Creates a message for the result of a tool call.
+    #[inline]
+    pub fn tool(tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id.into()),
+            ..Self::new(ChatRole::Tool, content)
+        }
+    }
+
+    /// Sets the name of the participant.
+    #[inline]
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = Some(name.into());
+    }
+
+    /// Sets the tool calls generated by the model.
+    #[inline]
+    pub fn set_tool_calls(&mut self, tool_calls: Vec<ToolCall>) {
+        self.tool_calls = tool_calls;
+    }
+
+    /// Returns the role of the author.
+    #[inline]
+    pub fn role(&self) -> ChatRole {
+        self.role
+    }
+
+    /// Returns the content of the message.
+    #[inline]
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Returns the name of the participant.
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the tool calls generated by the model.
+    #[inline]
+    pub fn tool_calls(&self) -> &[ToolCall] {
+        &self.tool_calls
+    }
+
+    /// Returns the ID of the tool call that this message responds to.
+    #[inline]
+    pub fn tool_call_id(&self) -> Option<&str> {
+        self.tool_call_id.as_deref()
+    }
+
+    /// Returns a mutable reference to the content.
+    #[inline]
+    pub(super) fn content_mut(&mut self) -> &mut String {
+        &mut self.content
+    }
+
+    /// Returns a mutable reference to the tool calls.
+    #[inline]
+    pub(super) fn tool_calls_mut(&mut self) -> &mut Vec<ToolCall> {
+        &mut self.tool_calls
+    }
+}
+
+/// A chat conversation with the messages and the available tools.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Conversation {
+    /// Messages in the conversation.
+    messages: Vec<ChatMessage>,
+    /// Tools the model may call.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<ToolDefinition>,
+}
+
+impl Conversation {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new instance with the system prompt.
+    #[inline]
+    pub fn with_system_prompt(prompt: impl Into<String>) -> Self {
+        Self {
+            messages: vec![ChatMessage::system(prompt)],
+            tools: Vec::new(),
+        }
+    }
+
+    /// Adds a message.
+    #[inline]
+    pub fn add_message(&mut self, message: ChatMessage) {
+        self.messages.push(message);
+    }
+
+    /// Adds a message from the user.
+    #[inline]
+    pub fn add_user_message(&mut self, content: impl Into<String>) {
+        self.messages.push(ChatMessage::user(content));
+    }
+
+    /// Adds a message of the model.
+    #[inline]
+    pub fn add_assistant_message(&mut self, content: impl Into<String>) {
+        self.messages.push(ChatMessage::assistant(content));
+    }
+
+    /// Adds the result of a tool call.
+    #[inline]
+    pub fn add_tool_result(&mut self, tool_call_id: impl Into<String>, content: impl Into<String>) {
+        self.messages.push(ChatMessage::tool(tool_call_id, content));
+    }
+
+    /// Adds a tool the model may call.
+    #[inline]
+    pub fn add_tool(&mut self, tool: ToolDefinition) {
+        self.tools.push(tool);
+    }
+
+    /// Returns the messages.
+    #[inline]
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    /// Returns the tools.
+    #[inline]
+    pub fn tools(&self) -> &[ToolDefinition] {
+        &self.tools
+    }
+
+    /// Returns the last message.
+    #[inline]
+    pub fn last_message(&self) -> Option<&ChatMessage> {
+        self.messages.last()
+    }
+}
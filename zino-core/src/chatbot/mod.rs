@@ -2,24 +2,98 @@
 //!
 //! ## Supported chatbot services
 //!
-//! | Chatbot service  | Description            | Feature flag           |
-//! |------------------|------------------------|------------------------|
-//! | `openai`         | OpenAI                 | `chatbot-openai`       |
+//! | Chatbot service     | Description            | Feature flag                |
+//! |---------------------|------------------------|-----------------------------|
+//! | `anthropic`         | Anthropic              | `chatbot-anthropic`         |
+//! | `llamacpp`          | llama.cpp server       | `chatbot-openai-compatible` |
+//! | `ollama`            | Ollama                 | `chatbot-ollama`            |
+//! | `openai`            | OpenAI                 | `chatbot-openai`            |
+//! | `openai-compatible` | OpenAI-compatible APIs | `chatbot-openai-compatible` |
 //!
+//! Other services can be supported by implementing [`ChatbotProvider`]
+//! and registering the factory with [`Chatbot::register_provider`].
+//! Errors of the services are classified by [`ChatbotErrorKind`].
+//!
+//! A chat conversation consists of the system, user, assistant and tool messages.
+//! The tools can be derived from the model schemas or the OpenAPI operations
+//! of the controller actions, and the generated tokens can be streamed.
+//!
+//! The text embeddings are generated by the chatbot with an `embedding-model`,
+//! which can be selected by `[embedding] chatbot`. They are used to keep the
+//! `#[schema(embedding = "field")]` columns in sync for the semantic search.
+//!
+//! The retrieval-augmented answering is supported by splitting the documents with
+//! [`TextSplitter`], assembling the retrieved passages into a conversation
+//! with [`build_rag_conversation`] and parsing the citations with [`parse_citations`].
+//!
+//! ```toml
+//! [[chatbot]]
+//! service = "openai"
+//! name = "gpt"
+//! model = "gpt-4o"
+//! api-key = "sk-..."
+//! api-base = "http://127.0.0.1:8080/v1"
+//!
+//! [[chatbot]]
+//! service = "ollama"
+//! name = "local"
+//! model = "llama3"
+//! api-base = "http://127.0.0.1:11434"
+//! embedding-model = "nomic-embed-text"
+//!
+//! [embedding]
+//! chatbot = "local"
+//! ```
 
 use crate::{
-    application::StaticRecord, error::Error, extension::TomlTableExt, state::State, LazyLock, Map,
+    application::StaticRecord, bail, error::Error, extension::TomlTableExt, state::State, warn,
+    LazyLock, Map,
 };
+use futures::StreamExt;
 use toml::Table;
 
 mod client;
+mod completion;
+mod embedding;
+mod error_kind;
+mod message;
+mod provider;
+mod retrieval;
+mod tool;
 
 /// Supported chatbot services.
+#[cfg(feature = "chatbot-anthropic")]
+mod chatbot_anthropic;
+#[cfg(feature = "chatbot-openai-compatible")]
+mod chatbot_compatible;
+#[cfg(feature = "chatbot-ollama")]
+mod chatbot_ollama;
 #[cfg(feature = "chatbot-openai")]
 mod chatbot_openai;
 
+#[cfg(any(
+    feature = "chatbot-anthropic",
+    feature = "chatbot-ollama",
+    feature = "chatbot-openai",
+    feature = "chatbot-openai-compatible",
+))]
+mod http_chat;
+
 pub use client::Chatbot;
+pub use completion::{ChatChoice, ChatChunk, ChatCompletion, ChatStream, ChatUsage, ToolCallChunk};
+pub use embedding::{cosine_similarity, normalize_embedding};
+pub use error_kind::ChatbotErrorKind;
+pub use message::{ChatMessage, ChatRole, Conversation, ToolCall};
+pub use provider::{ChatbotProvider, ChatbotProviderFactory};
+pub use retrieval::{build_rag_conversation, parse_citations, RetrievedPassage, TextSplitter};
+pub use tool::ToolDefinition;
 
+#[cfg(feature = "chatbot-anthropic")]
+use chatbot_anthropic::AnthropicChat;
+#[cfg(feature = "chatbot-openai-compatible")]
+use chatbot_compatible::CompatibleChat;
+#[cfg(feature = "chatbot-ollama")]
+use chatbot_ollama::OllamaChat;
 #[cfg(feature = "chatbot-openai")]
 use chatbot_openai::OpenAiChatCompletion;
 
@@ -34,6 +108,81 @@ pub trait ChatbotService {
 
     /// Attempts to send a message to generate chat completions.
     async fn try_send(&self, message: String, options: Option<Map>) -> Result<Vec<String>, Error>;
+
+    /// Attempts to generate a chat completion for the conversation.
+    ///
+    /// The default implementation sends the last message with [`try_send`](Self::try_send),
+    /// so the earlier messages and the tools are not supported.
+    async fn try_complete(
+        &self,
+        conversation: &Conversation,
+        options: Option<Map>,
+    ) -> Result<ChatCompletion, Error> {
+        let model = self.model();
+        if !conversation.tools().is_empty() {
+            bail!("the tool calling is not supported by the model `{}`", model);
+        }
+
+        let message = conversation
+            .last_message()
+            .map(|message| message.content().to_owned())
+            .ok_or_else(|| warn!("the conversation should be nonempty"))?;
+        let mut completion = ChatCompletion::new(model);
+        for content in self.try_send(message, options).await? {
+            let message = ChatMessage::assistant(content);
+            completion.add_choice(ChatChoice::new(message, Some("stop".to_owned())));
+        }
+        Ok(completion)
+    }
+
+    /// Attempts to stream the chunks of a chat completion for the conversation.
+    ///
+    /// The default implementation emits a single chunk for each choice of
+    /// the completion generated by [`try_complete`](Self::try_complete).
+    async fn try_stream(
+        &self,
+        conversation: &Conversation,
+        options: Option<Map>,
+    ) -> Result<ChatStream, Error> {
+        let completion = self.try_complete(conversation, options).await?;
+        let usage = completion.usage();
+        let chunks = completion
+            .into_choices()
+            .into_iter()
+            .enumerate()
+            .map(move |(index, choice)| {
+                let finish_reason = choice.finish_reason().map(|s| s.to_owned());
+                Ok(ChatChunk {
+                    index,
+                    content: Some(choice.into_message().content().to_owned()),
+                    finish_reason,
+                    usage: if index == 0 { usage } else { None },
+                    ..ChatChunk::default()
+                })
+            })
+            .collect::<Vec<_>>();
+        Ok(futures::stream::iter(chunks).boxed())
+    }
+}
+
+/// Underlying trait of the text embedding services.
+pub trait EmbeddingService {
+    /// Returns the embedding model.
+    fn embedding_model(&self) -> &str;
+
+    /// Attempts to generate the embeddings for the input texts.
+    async fn try_embed(&self, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, Error>;
+
+    /// Attempts to generate the embedding for a single text.
+    async fn try_embed_one(&self, input: String) -> Result<Vec<f32>, Error> {
+        let mut embeddings = self.try_embed(vec![input]).await?;
+        embeddings.pop().ok_or_else(|| {
+            warn!(
+                "no embeddings are generated by `{}`",
+                self.embedding_model()
+            )
+        })
+    }
 }
 
 /// Global access to the shared chatbot services.
@@ -46,10 +195,28 @@ impl GlobalChatbot {
     pub fn get(name: &str) -> Option<&'static Chatbot> {
         SHARED_CHATBOT_SERVICES.find(name)
     }
+
+    /// Gets the chatbot for generating the text embeddings, which is specified by
+    /// `[embedding] chatbot` or the first chatbot with an `embedding-model`.
+    pub fn embedder() -> Option<&'static Chatbot> {
+        let config = State::shared().config();
+        if let Some(name) = config
+            .get_table("embedding")
+            .and_then(|t| t.get_str("chatbot"))
+        {
+            SHARED_CHATBOT_SERVICES.find(name)
+        } else {
+            SHARED_CHATBOT_SERVICES
+                .iter()
+                .find_map(|(_, chatbot)| chatbot.has_embedding_model().then_some(chatbot))
+        }
+    }
 }
 
 /// Shared chatbot services.
 static SHARED_CHATBOT_SERVICES: LazyLock<StaticRecord<Chatbot>> = LazyLock::new(|| {
+    provider::seal();
+
     let mut chatbot_services = StaticRecord::new();
     if let Some(chatbots) = State::shared().config().get_array("chatbot") {
         for chatbot in chatbots.iter().filter_map(|v| v.as_table()) {
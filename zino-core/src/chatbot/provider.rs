@@ -0,0 +1,140 @@
+use super::{ChatCompletion, ChatStream, ChatbotErrorKind, Conversation};
+use crate::{bail, error::Error, BoxFuture, LazyLock, Map};
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use toml::Table;
+
+/// A chatbot provider implemented outside of zino-core.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use zino_core::{
+///     chatbot::{ChatCompletion, ChatStream, Chatbot, ChatbotProvider, Conversation},
+///     error::Error,
+///     BoxFuture, Map,
+/// };
+///
+/// struct EchoProvider;
+///
+/// impl ChatbotProvider for EchoProvider {
+///     fn model(&self) -> &str {
+///         "echo"
+///     }
+///
+///     fn complete<'a>(
+///         &'a self,
+///         conversation: &'a Conversation,
+///         _options: Option<Map>,
+///     ) -> BoxFuture<'a, Result<ChatCompletion, Error>> {
+///         Box::pin(async move { todo!() })
+///     }
+///
+///     fn stream<'a>(
+///         &'a self,
+///         conversation: &'a Conversation,
+///         _options: Option<Map>,
+///     ) -> BoxFuture<'a, Result<ChatStream, Error>> {
+///         Box::pin(async move { todo!() })
+///     }
+/// }
+///
+/// Chatbot::register_provider("echo", |_config| Ok(Box::new(EchoProvider)))?;
+/// ```
+pub trait ChatbotProvider: Send + Sync {
+    /// Returns the model.
+    fn model(&self) -> &str;
+
+    /// Generates a chat completion for the conversation.
+    fn complete<'a>(
+        &'a self,
+        conversation: &'a Conversation,
+        options: Option<Map>,
+    ) -> BoxFuture<'a, Result<ChatCompletion, Error>>;
+
+    /// Streams the chunks of a chat completion for the conversation.
+    fn stream<'a>(
+        &'a self,
+        conversation: &'a Conversation,
+        options: Option<Map>,
+    ) -> BoxFuture<'a, Result<ChatStream, Error>>;
+
+    /// Generates the embeddings for the input texts.
+    /// The default implementation returns an error.
+    fn embed<'a>(
+        &'a self,
+        model: &'a str,
+        _inputs: Vec<String>,
+    ) -> BoxFuture<'a, Result<Vec<Vec<f32>>, Error>> {
+        Box::pin(async move {
+            let message = format!("the embedding model `{model}` is unsupported");
+            Err(ChatbotErrorKind::InvalidRequest.into_error("custom", message))
+        })
+    }
+}
+
+/// A function to construct a chatbot provider with the config.
+pub type ChatbotProviderFactory = fn(config: &Table) -> Result<Box<dyn ChatbotProvider>, Error>;
+
+/// Registers a factory of the chatbot provider for the service.
+/// It fails if the providers have been sealed.
+pub(super) fn register(
+    service: &'static str,
+    factory: ChatbotProviderFactory,
+) -> Result<(), Error> {
+    let mut providers = CHATBOT_PROVIDERS.write();
+    if PROVIDERS_SEALED.load(Relaxed) {
+        bail!(
+            "the provider for `{}` should be registered before the shared chatbots are initialized",
+            service
+        );
+    }
+    providers.push((service, factory));
+    Ok(())
+}
+
+/// Seals the providers so that no more providers can be registered.
+/// It is called once the shared chatbot services are initialized.
+pub(super) fn seal() {
+    let _providers = CHATBOT_PROVIDERS.write();
+    PROVIDERS_SEALED.store(true, Relaxed);
+}
+
+/// Finds the factory of the chatbot provider for the service.
+/// The provider registered last takes precedence.
+#[inline]
+pub(super) fn find(service: &str) -> Option<ChatbotProviderFactory> {
+    CHATBOT_PROVIDERS
+        .read()
+        .iter()
+        .rev()
+        .find_map(|&(name, factory)| (name == service).then_some(factory))
+}
+
+/// Registered chatbot providers.
+static CHATBOT_PROVIDERS: LazyLock<RwLock<Vec<(&'static str, ChatbotProviderFactory)>>> =
+    LazyLock::new(|| RwLock::new(Vec::new()));
+
+/// Whether the providers have been sealed.
+static PROVIDERS_SEALED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(test)]
+mod tests {
+    use super::{find, register, seal, ChatbotProvider};
+    use crate::error::Error;
+    use toml::Table;
+
+    fn new_provider(_config: &Table) -> Result<Box<dyn ChatbotProvider>, Error> {
+        Err(Error::new("unimplemented"))
+    }
+
+    #[test]
+    fn it_rejects_late_registration() {
+        assert!(register("provider_test", new_provider).is_ok());
+        assert!(find("provider_test").is_some());
+
+        seal();
+        assert!(register("provider_test_late", new_provider).is_err());
+        assert!(find("provider_test_late").is_none());
+    }
+}
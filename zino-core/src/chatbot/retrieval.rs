@@ -0,0 +1,298 @@
+use super::Conversation;
+use crate::extension::TomlTableExt;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use toml::Table;
+
+/// Default system prompt for the retrieval-augmented answering.
+const DEFAULT_SYSTEM_PROMPT: &str = "Answer the question using only the numbered passages \
+    in the context. Cite the passages supporting each statement as [n]. \
+    If the passages do not contain the answer, say that you do not know.";
+
+/// A splitter to chunk the text into overlapping passages by the number of characters.
+///
+/// The text is split into sentences, which are packed into the chunks.
+/// A sentence longer than the chunk size is split at the character boundaries.
+#[derive(Debug, Clone, Copy)]
+pub struct TextSplitter {
+    /// Maximum number of characters in a chunk.
+    chunk_size: usize,
+    /// Maximum number of characters shared by two adjacent chunks.
+    chunk_overlap: usize,
+}
+
+impl TextSplitter {
+    /// Creates a new instance.
+    /// The chunk overlap is reduced to a half of the chunk size if it is larger.
+    #[inline]
+    pub fn new(chunk_size: usize, chunk_overlap: usize) -> Self {
+        let chunk_size = chunk_size.max(1);
+        Self {
+            chunk_size,
+            chunk_overlap: chunk_overlap.min(chunk_size / 2),
+        }
+    }
+
+    /// Creates a new instance with the `chunk-size` and `chunk-overlap` in the config.
+    pub fn with_config(config: &Table) -> Self {
+        let default = Self::default();
+        let chunk_size = config.get_usize("chunk-size").unwrap_or(default.chunk_size);
+        let chunk_overlap = config
+            .get_usize("chunk-overlap")
+            .unwrap_or(default.chunk_overlap);
+        Self::new(chunk_size, chunk_overlap)
+    }
+
+    /// Splits the text into chunks.
+    pub fn split(&self, text: &str) -> Vec<String> {
+        let chunk_size = self.chunk_size;
+        let mut units = Vec::new();
+        for sentence in split_sentences(text) {
+            let length = sentence.chars().count();
+            if length > chunk_size {
+                let chars = sentence.chars().collect::<Vec<_>>();
+                for part in chars.chunks(chunk_size) {
+                    units.push((part.iter().collect::<String>(), part.len()));
+                }
+            } else {
+                units.push((sentence.to_owned(), length));
+            }
+        }
+
+        let mut chunks = Vec::new();
+        let mut window: Vec<(String, usize)> = Vec::new();
+        for (unit, length) in units {
+            if !window.is_empty() && window_length(&window) + 1 + length > chunk_size {
+                chunks.push(join_units(&window));
+
+                let mut overlap_length = 0;
+                let mut num_kept = 0;
+                for (_, length) in window.iter().rev() {
+                    if overlap_length + length + 1 > self.chunk_overlap {
+                        break;
+                    }
+                    overlap_length += length + 1;
+                    num_kept += 1;
+                }
+                window.drain(..window.len() - num_kept);
+                while !window.is_empty() && window_length(&window) + 1 + length > chunk_size {
+                    window.remove(0);
+                }
+            }
+            window.push((unit, length));
+        }
+        if !window.is_empty() {
+            chunks.push(join_units(&window));
+        }
+        chunks
+    }
+}
+
+impl Default for TextSplitter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(1000, 200)
+    }
+}
+
+/// A passage retrieved for the question, which is cited as `[n]` in the answer.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetrievedPassage {
+    /// Passage ID.
+    id: String,
+    /// Title of the document.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    title: String,
+    /// Source of the document.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    source: String,
+    /// Content.
+    content: String,
+}
+
+impl RetrievedPassage {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            title: String::new(),
+            source: String::new(),
+            content: content.into(),
+        }
+    }
+
+    /// Sets the title of the document.
+    #[inline]
+    pub fn set_title(&mut self, title: impl Into<String>) {
+        self.title = title.into();
+    }
+
+    /// Sets the source of the document.
+    #[inline]
+    pub fn set_source(&mut self, source: impl Into<String>) {
+        self.source = source.into();
+    }
+
+    /// Returns the passage ID.
+    #[inline]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the title of the document.
+    #[inline]
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Returns the source of the document.
+    #[inline]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the content.
+    #[inline]
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+/// Assembles a conversation to answer the question with the retrieved passages.
+/// The passages are numbered from `1` in the context, and the answer should cite them as `[n]`.
+pub fn build_rag_conversation(
+    question: &str,
+    passages: &[RetrievedPassage],
+    system_prompt: Option<&str>,
+) -> Conversation {
+    let mut context = String::new();
+    for (index, passage) in passages.iter().enumerate() {
+        let number = index + 1;
+        let title = passage.title();
+        let source = passage.source();
+        let _ = match (title.is_empty(), source.is_empty()) {
+            (false, false) => writeln!(context, "[{number}] {title} ({source})"),
+            (false, true) => writeln!(context, "[{number}] {title}"),
+            (true, false) => writeln!(context, "[{number}] ({source})"),
+            (true, true) => writeln!(context, "[{number}]"),
+        };
+        let _ = writeln!(context, "{}\n", passage.content().trim());
+    }
+
+    let mut conversation =
+        Conversation::with_system_prompt(system_prompt.unwrap_or(DEFAULT_SYSTEM_PROMPT));
+    conversation.add_user_message(format!("Context:\n{context}Question: {question}"));
+    conversation
+}
+
+/// Parses the citations `[n]` in the answer, returning the distinct numbers
+/// of the passages in the order of their first appearance.
+/// Both `[1, 2]` and `[1][2]` are supported, and the numbers out of range are ignored.
+pub fn parse_citations(answer: &str, num_passages: usize) -> Vec<usize> {
+    let mut citations = Vec::new();
+    let mut remainder = answer;
+    while let Some(start) = remainder.find('[') {
+        remainder = &remainder[start + 1..];
+        let Some(end) = remainder.find(']') else {
+            break;
+        };
+        for number in remainder[..end].split(',') {
+            if let Ok(number) = number.trim().parse::<usize>() {
+                if (1..=num_passages).contains(&number) && !citations.contains(&number) {
+                    citations.push(number);
+                }
+            }
+        }
+        remainder = &remainder[end + 1..];
+    }
+    citations
+}
+
+/// Splits the text into trimmed sentences.
+fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let is_boundary = match c {
+            '\n' | '。' | '！' | '？' => true,
+            '.' | '!' | '?' => chars.peek().map_or(true, |&(_, next)| next.is_whitespace()),
+            _ => false,
+        };
+        if is_boundary {
+            let end = index + c.len_utf8();
+            let sentence = text[start..end].trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence);
+            }
+            start = end;
+        }
+    }
+
+    let sentence = text[start..].trim();
+    if !sentence.is_empty() {
+        sentences.push(sentence);
+    }
+    sentences
+}
+
+/// Returns the number of characters of the units joined by spaces.
+#[inline]
+fn window_length(units: &[(String, usize)]) -> usize {
+    let length = units.iter().map(|(_, length)| length).sum::<usize>();
+    length + units.len().saturating_sub(1)
+}
+
+/// Joins the units by spaces.
+#[inline]
+fn join_units(units: &[(String, usize)]) -> String {
+    units
+        .iter()
+        .map(|(unit, _)| unit.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_citations, TextSplitter};
+
+    #[test]
+    fn it_splits_text() {
+        let splitter = TextSplitter::new(24, 12);
+        let text = "Zino is a framework. It is modular!\n\nIt supports ORM. Chatbots too.";
+        assert_eq!(
+            splitter.split(text),
+            vec![
+                "Zino is a framework.",
+                "It is modular!",
+                "It supports ORM.",
+                "Chatbots too.",
+            ]
+        );
+
+        let splitter = TextSplitter::new(40, 20);
+        assert_eq!(
+            splitter.split(text),
+            vec![
+                "Zino is a framework. It is modular!",
+                "It is modular! It supports ORM.",
+                "It supports ORM. Chatbots too.",
+            ]
+        );
+        assert_eq!(
+            TextSplitter::new(4, 0).split("abcdefghij"),
+            vec!["abcd", "efgh", "ij"]
+        );
+        assert!(splitter.split(" \n ").is_empty());
+    }
+
+    #[test]
+    fn it_parses_citations() {
+        let answer = "Zino supports ORM [2]. It also has chatbots [1, 2][3] and [9].";
+        assert_eq!(parse_citations(answer, 3), vec![2, 1, 3]);
+        assert_eq!(parse_citations("no citations [a]", 3), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,234 @@
+use crate::{extension::JsonObjectExt, JsonValue, Map};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "orm")]
+use crate::orm::Schema;
+
+/// Definition of a tool that the model may call.
+///
+/// The parameters are described as a JSON Schema object. They can be derived from
+/// the columns of a model or the OpenAPI operation of a controller action.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolDefinition {
+    /// Name of the tool.
+    name: String,
+    /// Description of the tool.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    /// Parameters as a JSON Schema object.
+    parameters: Map,
+}
+
+impl ToolDefinition {
+    /// Creates a new instance with no parameters.
+    #[inline]
+    pub fn new(name: impl Into<String>) -> Self {
+        let mut parameters = Map::new();
+        parameters.upsert("type", "object");
+        parameters.upsert("properties", Map::new());
+        Self {
+            name: name.into(),
+            description: None,
+            parameters,
+        }
+    }
+
+    /// Constructs a new instance with the columns of a model.
+    /// The read-only columns are ignored.
+    #[cfg(feature = "orm")]
+    pub fn from_model<M: Schema>() -> Self {
+        let mut tool = Self::new(M::model_name());
+        tool.set_description(format!("Fields of the `{}` model", M::model_name()));
+        for col in M::columns().iter().filter(|col| !col.is_read_only()) {
+            let mut definition = col.definition();
+            if let Some(comment) = col.comment() {
+                definition.upsert("description", comment);
+            }
+            let required =
+                col.is_not_null() && !col.is_option_type() && col.default_value().is_none();
+            tool.add_parameter(col.name(), definition, required);
+        }
+        tool
+    }
+
+    /// Constructs a new instance with the OpenAPI operation of the endpoint.
+    /// The path parameters, query parameters and JSON request body are merged
+    /// as the properties.
+    pub fn from_endpoint(path: &str, method: &str) -> Option<Self> {
+        let operation = crate::openapi::find_operation(path, method)?;
+        let operation = serde_json::to_value(operation).ok()?.as_object().cloned()?;
+        let name = operation
+            .get_str("operationId")
+            .map(|s| s.to_owned())
+            .unwrap_or_else(|| format_tool_name(method, path));
+        let mut tool = Self::new(format_tool_name("", &name));
+        if let Some(description) = operation
+            .get_str("description")
+            .or_else(|| operation.get_str("summary"))
+        {
+            tool.set_description(description);
+        }
+        tool.parameters = parse_operation_parameters(&operation);
+        Some(tool)
+    }
+
+    /// Sets the description of the tool.
+    #[inline]
+    pub fn set_description(&mut self, description: impl Into<String>) {
+        self.description = Some(description.into());
+    }
+
+    /// Adds a parameter with the JSON Schema definition.
+    pub fn add_parameter(&mut self, name: &str, definition: Map, required: bool) {
+        if let Some(properties) = self
+            .parameters
+            .get_mut("properties")
+            .and_then(|v| v.as_object_mut())
+        {
+            properties.upsert(name, definition);
+        }
+        if required {
+            if let Some(JsonValue::Array(vec)) = self.parameters.get_mut("required") {
+                vec.push(name.into());
+            } else {
+                self.parameters.upsert("required", vec![name]);
+            }
+        }
+    }
+
+    /// Returns the name of the tool.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the description of the tool.
+    #[inline]
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Returns the parameters as a JSON Schema object.
+    #[inline]
+    pub fn parameters(&self) -> &Map {
+        &self.parameters
+    }
+}
+
+/// Formats the tool name, which can only contain `a-z`, `A-Z`, `0-9`, `_` and `-`.
+fn format_tool_name(method: &str, path: &str) -> String {
+    let name = [method.to_ascii_lowercase().as_str(), path]
+        .join("_")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    name.split('_')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Parses the parameters of the OpenAPI operation as a JSON Schema object.
+fn parse_operation_parameters(operation: &Map) -> Map {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    if let Some(parameters) = operation.get_array("parameters") {
+        for parameter in parameters.iter().filter_map(|v| v.as_object()) {
+            let Some(name) = parameter.get_str("name") else {
+                continue;
+            };
+            let mut definition = parameter
+                .get("schema")
+                .and_then(|v| v.as_object())
+                .cloned()
+                .unwrap_or_else(|| Map::from_entry("type", "string"));
+            if let Some(description) = parameter.get_str("description") {
+                definition.upsert("description", description);
+            }
+            if parameter.get_bool("required") == Some(true) {
+                required.push(name.to_owned());
+            }
+            properties.upsert(name, definition);
+        }
+    }
+
+    let body_schema = operation
+        .get("requestBody")
+        .and_then(|v| v.pointer("/content/application~1json/schema"))
+        .and_then(|v| v.as_object());
+    if let Some(schema) = body_schema {
+        if let Some(body_properties) = schema.get("properties").and_then(|v| v.as_object()) {
+            properties.extend(body_properties.clone());
+        }
+        if let Some(body_required) = schema.get_array("required") {
+            required.extend(
+                body_required
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|s| s.to_owned()),
+            );
+        }
+    }
+
+    let mut parameters = Map::new();
+    parameters.upsert("type", "object");
+    parameters.upsert("properties", properties);
+    if !required.is_empty() {
+        parameters.upsert("required", required);
+    }
+    parameters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_tool_name, parse_operation_parameters};
+    use crate::extension::JsonObjectExt;
+    use serde_json::json;
+
+    #[test]
+    fn it_parses_operation_parameters() {
+        assert_eq!(
+            format_tool_name("GET", "/user/{id}/view"),
+            "get_user_id_view"
+        );
+
+        let operation = json!({
+            "parameters": [
+                { "name": "id", "in": "path", "required": true, "schema": { "type": "string" } },
+                { "name": "fields", "in": "query", "description": "Selected fields" },
+            ],
+            "requestBody": {
+                "content": {
+                    "application/json": {
+                        "schema": {
+                            "type": "object",
+                            "properties": { "status": { "type": "string" } },
+                            "required": ["status"],
+                        }
+                    }
+                }
+            }
+        });
+        let parameters = parse_operation_parameters(operation.as_object().unwrap());
+        assert_eq!(
+            parameters.get_str_array("required"),
+            Some(vec!["id", "status"])
+        );
+
+        let properties = parameters.get_object("properties").unwrap();
+        assert_eq!(properties.len(), 3);
+        assert_eq!(
+            properties
+                .get_object("fields")
+                .and_then(|m| m.get_str("description")),
+            Some("Selected fields")
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Streaming ingestion of the row changes in the databases.
+//!
+//! ## Supported change streams
+//!
+//! | Change stream      | Description                           | Feature flag             |
+//! |--------------------|---------------------------------------|--------------------------|
+//! | `PgChangeStream`   | Postgres logical decoding (pgoutput)  | `connector-postgres`     |
+//! | `MySqlChangeStream`| MySQL row-based binlog                | `connector-mysql-binlog` |
+//!
+//! The changes are polled in batches of committed transactions. After a batch has been
+//! processed, the checkpoint should be committed so that the next poll resumes from it.
+//! The checkpoint is also saved in the state, which is persisted by the job store
+//! when the state is the data of a scheduled job.
+
+use crate::{channel::CloudEvent, datetime::DateTime, extension::JsonObjectExt, Map, Record, Uuid};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "connector-mysql-binlog")]
+mod mysql_binlog;
+#[cfg(feature = "connector-postgres")]
+mod pg_output;
+
+#[cfg(feature = "connector-mysql-binlog")]
+pub use mysql_binlog::MySqlChangeStream;
+#[cfg(feature = "connector-postgres")]
+pub use pg_output::PgChangeStream;
+
+/// Operation of a row change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeOperation {
+    /// A row has been inserted.
+    Insert,
+    /// A row has been updated.
+    Update,
+    /// A row has been deleted.
+    Delete,
+    /// A table has been truncated.
+    Truncate,
+}
+
+impl ChangeOperation {
+    /// Returns the operation as a str.
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Insert => "insert",
+            Self::Update => "update",
+            Self::Delete => "delete",
+            Self::Truncate => "truncate",
+        }
+    }
+}
+
+/// A row change captured from a database.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    /// Source of the change stream.
+    source: String,
+    /// Schema or database name.
+    schema: String,
+    /// Table name.
+    table: String,
+    /// Operation.
+    operation: ChangeOperation,
+    /// Row before the change.
+    before: Option<Map>,
+    /// Row after the change.
+    after: Option<Map>,
+    /// Position of the change in the log.
+    position: String,
+    /// Commit time of the transaction.
+    committed_at: Option<DateTime>,
+}
+
+impl ChangeEvent {
+    /// Creates a new instance.
+    #[inline]
+    pub(crate) fn new(
+        source: &str,
+        schema: impl Into<String>,
+        table: impl Into<String>,
+        operation: ChangeOperation,
+    ) -> Self {
+        Self {
+            source: source.to_owned(),
+            schema: schema.into(),
+            table: table.into(),
+            operation,
+            before: None,
+            after: None,
+            position: String::new(),
+            committed_at: None,
+        }
+    }
+
+    /// Sets the row before the change.
+    #[inline]
+    pub(crate) fn set_before(&mut self, before: Option<Map>) {
+        self.before = before;
+    }
+
+    /// Sets the row after the change.
+    #[inline]
+    pub(crate) fn set_after(&mut self, after: Option<Map>) {
+        self.after = after;
+    }
+
+    /// Sets the position of the change in the log.
+    #[inline]
+    pub(crate) fn set_position(&mut self, position: impl Into<String>) {
+        self.position = position.into();
+    }
+
+    /// Sets the commit time of the transaction.
+    #[inline]
+    pub(crate) fn set_committed_at(&mut self, committed_at: Option<DateTime>) {
+        self.committed_at = committed_at;
+    }
+
+    /// Returns the source of the change stream.
+    #[inline]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the schema or database name.
+    #[inline]
+    pub fn schema(&self) -> &str {
+        &self.schema
+    }
+
+    /// Returns the table name.
+    #[inline]
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// Returns the operation.
+    #[inline]
+    pub fn operation(&self) -> ChangeOperation {
+        self.operation
+    }
+
+    /// Returns the row before the change.
+    /// It only contains the replica identity columns for some databases.
+    #[inline]
+    pub fn before(&self) -> Option<&Map> {
+        self.before.as_ref()
+    }
+
+    /// Returns the row after the change.
+    #[inline]
+    pub fn after(&self) -> Option<&Map> {
+        self.after.as_ref()
+    }
+
+    /// Returns the position of the change in the log.
+    #[inline]
+    pub fn position(&self) -> &str {
+        &self.position
+    }
+
+    /// Returns the commit time of the transaction.
+    #[inline]
+    pub fn committed_at(&self) -> Option<DateTime> {
+        self.committed_at
+    }
+
+    /// Converts `self` into a map.
+    pub fn into_map(self) -> Map {
+        let mut map = Map::new();
+        map.upsert("source", self.source);
+        map.upsert("schema", self.schema);
+        map.upsert("table", self.table);
+        map.upsert("operation", self.operation.as_str());
+        map.upsert("before", self.before);
+        map.upsert("after", self.after);
+        map.upsert("position", self.position);
+        map.upsert("committed_at", self.committed_at.map(|dt| dt.to_string()));
+        map
+    }
+
+    /// Converts `self` into an Avro record.
+    #[inline]
+    pub fn into_record(self) -> Record {
+        self.into_map().into_avro_record()
+    }
+
+    /// Converts `self` into a cloud event with the type `{source}.{schema}.{table}.{operation}`.
+    /// The subject is the position of the change, and the data contains the rows.
+    pub fn into_cloud_event(self) -> CloudEvent {
+        let event_type = format!(
+            "{}.{}.{}.{}",
+            self.source,
+            self.schema,
+            self.table,
+            self.operation.as_str()
+        );
+        let mut event = CloudEvent::new(Uuid::now_v7(), &self.source, event_type);
+        event.set_subject(self.position.clone());
+        event.set_data(self.into_map());
+        event
+    }
+}
+
+/// A batch of changes in the committed transactions.
+#[derive(Debug, Clone, Default)]
+pub struct ChangeBatch {
+    /// Change events.
+    events: Vec<ChangeEvent>,
+    /// Checkpoint after the last transaction.
+    checkpoint: Option<String>,
+}
+
+impl ChangeBatch {
+    /// Creates a new instance.
+    #[inline]
+    pub(crate) fn new(events: Vec<ChangeEvent>, checkpoint: Option<String>) -> Self {
+        Self { events, checkpoint }
+    }
+
+    /// Returns the change events.
+    #[inline]
+    pub fn events(&self) -> &[ChangeEvent] {
+        &self.events
+    }
+
+    /// Returns the checkpoint after the last transaction, or `None` if there are no changes.
+    #[inline]
+    pub fn checkpoint(&self) -> Option<&str> {
+        self.checkpoint.as_deref()
+    }
+
+    /// Returns `true` if there are no changes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Consumes `self` and returns the change events.
+    #[inline]
+    pub fn into_events(self) -> Vec<ChangeEvent> {
+        self.events
+    }
+
+    /// Consumes `self` and returns the change events as Avro records.
+    #[inline]
+    pub fn into_records(self) -> Vec<Record> {
+        self.events.into_iter().map(|e| e.into_record()).collect()
+    }
+
+    /// Consumes `self` and returns the change events as cloud events.
+    #[inline]
+    pub fn into_cloud_events(self) -> Vec<CloudEvent> {
+        self.events
+            .into_iter()
+            .map(|e| e.into_cloud_event())
+            .collect()
+    }
+}
@@ -0,0 +1,376 @@
+use super::{ChangeBatch, ChangeEvent, ChangeOperation};
+use crate::{
+    bail,
+    datetime::DateTime,
+    encoding::base64,
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    state::State,
+    warn, JsonValue, Map,
+};
+use futures::StreamExt;
+use mysql_async::{
+    binlog::{
+        events::{EventData, RowsEventData},
+        row::BinlogRow,
+        value::BinlogValue,
+    },
+    prelude::Queryable,
+    BinlogStreamRequest, Conn, Opts, Row, Value,
+};
+use std::{collections::HashMap, time::Duration};
+use toml::Table;
+
+/// A change stream of MySQL with the row-based binlog.
+///
+/// The server should be configured with `binlog_format = ROW`, and the user should have
+/// the `REPLICATION SLAVE` and `REPLICATION CLIENT` privileges. The checkpoint is
+/// the binlog position `{file}:{position}` after the last committed transaction.
+/// Only the changes of the tables in the configured `database` are captured.
+///
+/// The column names and types are looked up from the `information_schema`,
+/// and they are reloaded when a DDL statement has been received
+/// or the number of columns does not match.
+///
+/// # Examples
+///
+/// ```toml
+/// [[change-stream]]
+/// type = "mysql"
+/// name = "orders"
+/// host = "127.0.0.1"
+/// port = 3306
+/// database = "data_cube"
+/// username = "root"
+/// password = "Mu4dABNGxuAvGIhUrhNAkbOkkWYzYBqlWLrgP5MtWKg="
+/// server-id = 1001
+/// max-changes = 1000
+/// idle-timeout = "1s"
+/// ```
+#[derive(Debug, Clone)]
+pub struct MySqlChangeStream {
+    /// Source name.
+    name: String,
+    /// Database name.
+    database: String,
+    /// Connection options.
+    opts: Opts,
+    /// Server ID of the replica, which should be unique in the replication topology.
+    server_id: u32,
+    /// Max number of changes in a poll.
+    max_changes: usize,
+    /// Idle timeout for waiting for the next binlog event.
+    idle_timeout: Duration,
+    /// Key of the checkpoint in the state.
+    state_key: String,
+}
+
+impl MySqlChangeStream {
+    /// Attempts to create a new instance with the config.
+    pub fn try_from_config(config: &Table) -> Result<Self, Error> {
+        let name = config.get_str("name").unwrap_or("mysql");
+        let database = config.get_str("database").unwrap_or("mysql");
+        let authority = State::format_authority(config, Some(3306));
+        let dsn = format!("mysql://{authority}/{database}");
+        let opts = Opts::from_url(&dsn)?;
+        let server_id = config.get_u32("server-id").unwrap_or(1001);
+        let max_changes = config.get_usize("max-changes").unwrap_or(1000);
+        let idle_timeout = config
+            .get_duration("idle-timeout")
+            .unwrap_or_else(|| Duration::from_secs(1));
+        let state_key = config.get_str("state-key").unwrap_or("checkpoint");
+        Ok(Self {
+            name: name.to_owned(),
+            database: database.to_owned(),
+            opts,
+            server_id,
+            max_changes,
+            idle_timeout,
+            state_key: state_key.to_owned(),
+        })
+    }
+
+    /// Polls the changes of the committed transactions since the checkpoint in the state.
+    /// If there is no checkpoint, it starts from the current binlog position of the server,
+    /// and the start position is returned as the checkpoint if no transactions are received.
+    ///
+    /// The poll returns when the max number of changes has been reached
+    /// or no binlog events have been received within the idle timeout.
+    pub async fn poll(&self, state: &Map) -> Result<ChangeBatch, Error> {
+        let mut conn = Conn::new(self.opts.clone()).await?;
+        let last_checkpoint = self.last_checkpoint(state);
+        let (mut file, position) = match last_checkpoint {
+            Some(checkpoint) => parse_checkpoint(checkpoint)?,
+            None => query_binlog_position(&mut conn).await?,
+        };
+        let mut checkpoint = last_checkpoint
+            .is_none()
+            .then(|| format!("{file}:{position}"));
+        let mut table_columns = HashMap::new();
+        let mut info_conn = Conn::new(self.opts.clone()).await?;
+
+        let request = BinlogStreamRequest::new(self.server_id)
+            .with_filename(file.as_bytes())
+            .with_pos(position);
+        let mut stream = conn.get_binlog_stream(request).await?;
+        let mut events = Vec::new();
+        let mut pending_events = Vec::new();
+        while events.len() < self.max_changes {
+            let event = match tokio::time::timeout(self.idle_timeout, stream.next()).await {
+                Ok(Some(event)) => event?,
+                Ok(None) | Err(_) => break,
+            };
+            let header = event.header();
+            let log_pos = header.log_pos();
+            let committed_at = DateTime::from_timestamp(header.timestamp().into());
+            let Some(data) = event.read_data()? else {
+                continue;
+            };
+            match data {
+                EventData::RotateEvent(rotate_event) => {
+                    file = rotate_event.name().into_owned();
+                }
+                EventData::QueryEvent(query_event) => {
+                    if query_event.query().eq_ignore_ascii_case("BEGIN") {
+                        pending_events.clear();
+                    } else {
+                        // The columns may have been changed by a DDL statement.
+                        table_columns.clear();
+                    }
+                }
+                EventData::RowsEvent(rows_event) => {
+                    let operation = match rows_event {
+                        RowsEventData::WriteRowsEvent(_) => ChangeOperation::Insert,
+                        RowsEventData::UpdateRowsEvent(_)
+                        | RowsEventData::PartialUpdateRowsEvent(_) => ChangeOperation::Update,
+                        RowsEventData::DeleteRowsEvent(_) => ChangeOperation::Delete,
+                        _ => continue,
+                    };
+                    let Some(tme) = stream.get_tme(rows_event.table_id()) else {
+                        bail!("the table map event has not been received");
+                    };
+                    let schema = tme.database_name();
+                    if schema != self.database {
+                        continue;
+                    }
+
+                    let table = tme.table_name().into_owned();
+                    let num_columns = usize::try_from(tme.columns_count())?;
+                    if table_columns
+                        .get(&table)
+                        .map_or(true, |columns: &Vec<_>| columns.len() != num_columns)
+                    {
+                        let columns = query_columns(&mut info_conn, &schema, &table).await?;
+                        table_columns.insert(table.clone(), columns);
+                    }
+
+                    let columns = table_columns
+                        .get(&table)
+                        .map(|v| v.as_slice())
+                        .unwrap_or_default();
+                    let position = format!("{file}:{log_pos}");
+                    for row in rows_event.rows(tme) {
+                        let (before, after) = row?;
+                        let mut event = ChangeEvent::new(
+                            &self.name,
+                            schema.as_ref(),
+                            table.as_str(),
+                            operation,
+                        );
+                        event.set_before(before.map(|row| parse_binlog_row(row, columns)));
+                        event.set_after(after.map(|row| parse_binlog_row(row, columns)));
+                        event.set_position(position.as_str());
+                        event.set_committed_at(Some(committed_at));
+                        pending_events.push(event);
+                    }
+                }
+                EventData::XidEvent(_) => {
+                    events.append(&mut pending_events);
+                    checkpoint = Some(format!("{file}:{log_pos}"));
+                }
+                _ => (),
+            }
+        }
+        info_conn.disconnect().await?;
+        Ok(ChangeBatch::new(events, checkpoint))
+    }
+
+    /// Commits the checkpoint by saving it in the state.
+    #[inline]
+    pub fn commit(&self, state: &mut Map, checkpoint: &str) {
+        state.upsert(&self.state_key, checkpoint);
+    }
+
+    /// Returns the last checkpoint saved in the state.
+    #[inline]
+    pub fn last_checkpoint<'a>(&self, state: &'a Map) -> Option<&'a str> {
+        state.get_str(&self.state_key)
+    }
+}
+
+/// Queries the current binlog position of the server. The statement
+/// `SHOW BINARY LOG STATUS` is used as a fallback for MySQL 8.4 or above,
+/// in which `SHOW MASTER STATUS` has been removed.
+async fn query_binlog_position(conn: &mut Conn) -> Result<(String, u64), Error> {
+    let row = match conn.query_first::<Row, _>("SHOW MASTER STATUS;").await {
+        Ok(row) => row,
+        Err(mysql_async::Error::Server(_)) => {
+            conn.query_first::<Row, _>("SHOW BINARY LOG STATUS;")
+                .await?
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let row = row.ok_or_else(|| warn!("the binlog of the MySQL server is not enabled"))?;
+    let file = row
+        .get_opt::<String, _>(0)
+        .transpose()?
+        .ok_or_else(|| warn!("the binlog file should be specified"))?;
+    let position = row
+        .get_opt::<u64, _>(1)
+        .transpose()?
+        .ok_or_else(|| warn!("the binlog position should be specified"))?;
+    Ok((file, position))
+}
+
+/// A column of the table in the binlog rows.
+#[derive(Debug, Clone)]
+struct BinlogColumn {
+    /// Column name.
+    name: String,
+    /// A flag to indicate that the column contains binary data.
+    binary: bool,
+}
+
+impl BinlogColumn {
+    /// Creates a new instance with the column name and the data type.
+    fn new(name: String, data_type: &str) -> Self {
+        let binary = matches!(
+            data_type.to_ascii_lowercase().as_str(),
+            "binary"
+                | "varbinary"
+                | "tinyblob"
+                | "blob"
+                | "mediumblob"
+                | "longblob"
+                | "bit"
+                | "geometry"
+        );
+        Self { name, binary }
+    }
+}
+
+/// Queries the columns of a table in the ordinal positions.
+async fn query_columns(
+    conn: &mut Conn,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<BinlogColumn>, Error> {
+    let sql = "SELECT COLUMN_NAME, DATA_TYPE FROM information_schema.COLUMNS \
+        WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ? ORDER BY ORDINAL_POSITION;";
+    let columns = conn
+        .exec::<(String, String), _, _>(sql, (schema, table))
+        .await?
+        .into_iter()
+        .map(|(name, data_type)| BinlogColumn::new(name, &data_type))
+        .collect();
+    Ok(columns)
+}
+
+/// Parses the binlog row as a map. The columns without names are named `@{index}`.
+fn parse_binlog_row(row: BinlogRow, columns: &[BinlogColumn]) -> Map {
+    parse_binlog_values(row.unwrap(), columns)
+}
+
+/// Parses the binlog values as a map.
+fn parse_binlog_values(values: Vec<BinlogValue>, columns: &[BinlogColumn]) -> Map {
+    let mut map = Map::new();
+    for (index, value) in values.into_iter().enumerate() {
+        let column = columns.get(index);
+        let name = column
+            .map(|col| col.name.clone())
+            .unwrap_or_else(|| format!("@{index}"));
+        let value = match value {
+            BinlogValue::Value(value) => {
+                parse_mysql_value(value, column.is_some_and(|col| col.binary))
+            }
+            BinlogValue::Jsonb(value) => JsonValue::try_from(value).unwrap_or_default(),
+            _ => JsonValue::Null,
+        };
+        map.insert(name, value);
+    }
+    map
+}
+
+/// Parses a MySQL value as a JSON value. The bytes are encoded as a base64 string
+/// if the column is binary or the bytes are not valid UTF-8.
+fn parse_mysql_value(value: Value, binary: bool) -> JsonValue {
+    match value {
+        Value::NULL => JsonValue::Null,
+        Value::Bytes(bytes) if binary => base64::encode(bytes).into(),
+        Value::Bytes(bytes) => match String::from_utf8(bytes) {
+            Ok(s) => s.into(),
+            Err(err) => base64::encode(err.into_bytes()).into(),
+        },
+        Value::Int(i) => i.into(),
+        Value::UInt(u) => u.into(),
+        Value::Float(f) => f.into(),
+        Value::Double(d) => d.into(),
+        Value::Date(year, month, day, hour, minute, second, micros) => {
+            format!("{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}:{second:02}.{micros:06}")
+                .into()
+        }
+        Value::Time(negative, days, hours, minutes, seconds, micros) => {
+            let sign = if negative { "-" } else { "" };
+            let hours = u32::from(hours) + days * 24;
+            format!("{sign}{hours:02}:{minutes:02}:{seconds:02}.{micros:06}").into()
+        }
+    }
+}
+
+/// Parses the checkpoint `{file}:{position}`.
+fn parse_checkpoint(checkpoint: &str) -> Result<(String, u64), Error> {
+    let (file, position) = checkpoint
+        .rsplit_once(':')
+        .ok_or_else(|| warn!("invalid binlog checkpoint `{}`", checkpoint))?;
+    Ok((file.to_owned(), position.parse()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_binlog_values, parse_checkpoint, parse_mysql_value, BinlogColumn};
+    use crate::JsonValue;
+    use mysql_async::{binlog::value::BinlogValue, Value};
+
+    #[test]
+    fn it_parses_binlog_checkpoints() {
+        let (file, position) = parse_checkpoint("binlog.000042:1567").unwrap();
+        assert_eq!(file, "binlog.000042");
+        assert_eq!(position, 1567);
+        assert!(parse_checkpoint("binlog.000042").is_err());
+    }
+
+    #[test]
+    fn it_parses_binlog_values() {
+        let columns = vec![
+            BinlogColumn::new("id".to_owned(), "bigint"),
+            BinlogColumn::new("name".to_owned(), "varchar"),
+            BinlogColumn::new("avatar".to_owned(), "MEDIUMBLOB"),
+        ];
+        let values = vec![
+            BinlogValue::Value(Value::Int(1)),
+            BinlogValue::Value(Value::Bytes(b"alice".to_vec())),
+            BinlogValue::Value(Value::Bytes(b"abc".to_vec())),
+            BinlogValue::Value(Value::NULL),
+        ];
+        let map = parse_binlog_values(values, &columns);
+        assert_eq!(map.get("id"), Some(&JsonValue::from(1)));
+        assert_eq!(map.get("name"), Some(&JsonValue::from("alice")));
+        assert_eq!(map.get("avatar"), Some(&JsonValue::from("YWJj")));
+        assert_eq!(map.get("@3"), Some(&JsonValue::Null));
+
+        let value = parse_mysql_value(Value::Bytes(vec![0xff, 0xfe]), false);
+        assert_eq!(value, JsonValue::from("//4"));
+        let value = parse_mysql_value(Value::Time(true, 1, 2, 3, 4, 5), false);
+        assert_eq!(value, JsonValue::from("-26:03:04.000005"));
+    }
+}
@@ -0,0 +1,439 @@
+use super::{ChangeBatch, ChangeEvent, ChangeOperation};
+use crate::{
+    bail,
+    datetime::DateTime,
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    state::State,
+    warn, JsonValue, Map,
+};
+use sqlx::{
+    postgres::{PgPool, PgPoolOptions},
+    Row,
+};
+use std::collections::HashMap;
+use toml::Table;
+
+/// Microseconds between the Unix epoch and the Postgres epoch `2000-01-01`.
+const PG_EPOCH_MICROS: i64 = 946_684_800_000_000;
+
+/// A change stream of Postgres with the logical decoding output plugin `pgoutput`.
+///
+/// The changes are peeked from the logical replication slot via SQL functions,
+/// so that no replication connection is required. The slot is only advanced
+/// when the checkpoint is committed, which gives the at-least-once delivery.
+///
+/// # Examples
+///
+/// ```toml
+/// [[change-stream]]
+/// type = "postgres"
+/// name = "orders"
+/// host = "127.0.0.1"
+/// port = 5432
+/// database = "data_cube"
+/// username = "postgres"
+/// password = "QAx01wnh1i5ER713zfHmZi6dIUYn/Iq9ag+iUGtvKzEFJFYW"
+/// slot = "zino_orders"
+/// publication = "zino_orders"
+/// max-changes = 1000
+/// ```
+#[derive(Debug, Clone)]
+pub struct PgChangeStream {
+    /// Source name.
+    name: String,
+    /// Connection pool.
+    pool: PgPool,
+    /// Name of the logical replication slot.
+    slot: String,
+    /// Name of the publication.
+    publication: String,
+    /// Max number of changes in a poll.
+    max_changes: i32,
+    /// Key of the checkpoint in the state.
+    state_key: String,
+}
+
+impl PgChangeStream {
+    /// Attempts to create a new instance with the config.
+    pub fn try_from_config(config: &Table) -> Result<Self, Error> {
+        let name = config.get_str("name").unwrap_or("postgres");
+        let database = config.get_str("database").unwrap_or("postgres");
+        let authority = State::format_authority(config, Some(5432));
+        let dsn = format!("postgres://{authority}/{database}");
+        let pool = PgPoolOptions::new().max_connections(1).connect_lazy(&dsn)?;
+        let slot = config.get_str("slot").unwrap_or(name);
+        let publication = config.get_str("publication").unwrap_or(slot);
+        let max_changes = config.get_u32("max-changes").unwrap_or(1000);
+        let state_key = config.get_str("state-key").unwrap_or("checkpoint");
+        Ok(Self {
+            name: name.to_owned(),
+            pool,
+            slot: slot.to_owned(),
+            publication: publication.to_owned(),
+            max_changes: max_changes.try_into()?,
+            state_key: state_key.to_owned(),
+        })
+    }
+
+    /// Creates the logical replication slot if it does not exist.
+    /// It returns `true` if a new slot has been created.
+    pub async fn create_slot(&self) -> Result<bool, Error> {
+        let sql = "SELECT pg_create_logical_replication_slot($1, 'pgoutput') \
+            WHERE NOT EXISTS (SELECT 1 FROM pg_replication_slots WHERE slot_name = $1);";
+        let row = sqlx::query(sql)
+            .bind(&self.slot)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.is_some())
+    }
+
+    /// Polls the changes of the committed transactions since the last checkpoint.
+    pub async fn poll(&self) -> Result<ChangeBatch, Error> {
+        let sql = "SELECT lsn::text AS lsn, data \
+            FROM pg_logical_slot_peek_binary_changes($1, NULL, $2, \
+            'proto_version', '1', 'publication_names', $3);";
+        let rows = sqlx::query(sql)
+            .bind(&self.slot)
+            .bind(self.max_changes)
+            .bind(&self.publication)
+            .fetch_all(&self.pool)
+            .await?;
+        let mut decoder = PgOutputDecoder::new(&self.name);
+        let mut events = Vec::new();
+        for row in rows {
+            let lsn = row.try_get::<String, _>("lsn")?;
+            let data = row.try_get::<Vec<u8>, _>("data")?;
+            events.extend(decoder.decode(&lsn, &data)?);
+        }
+        Ok(ChangeBatch::new(events, decoder.checkpoint))
+    }
+
+    /// Commits the checkpoint by advancing the replication slot,
+    /// and saves it in the state.
+    pub async fn commit(&self, state: &mut Map, checkpoint: &str) -> Result<(), Error> {
+        sqlx::query("SELECT pg_replication_slot_advance($1, $2::pg_lsn);")
+            .bind(&self.slot)
+            .bind(checkpoint)
+            .execute(&self.pool)
+            .await?;
+        state.upsert(&self.state_key, checkpoint);
+        Ok(())
+    }
+
+    /// Returns the last checkpoint saved in the state.
+    #[inline]
+    pub fn last_checkpoint<'a>(&self, state: &'a Map) -> Option<&'a str> {
+        state.get_str(&self.state_key)
+    }
+}
+
+/// A relation in the `pgoutput` protocol.
+#[derive(Debug, Clone)]
+struct Relation {
+    /// Schema name.
+    schema: String,
+    /// Table name.
+    table: String,
+    /// Column names and type OIDs.
+    columns: Vec<(String, u32)>,
+}
+
+/// A decoder for the messages of the `pgoutput` protocol version 1.
+#[derive(Debug)]
+struct PgOutputDecoder<'a> {
+    /// Source name.
+    source: &'a str,
+    /// Relations.
+    relations: HashMap<u32, Relation>,
+    /// Commit time of the current transaction.
+    committed_at: Option<DateTime>,
+    /// Changes of the current transaction.
+    pending_events: Vec<ChangeEvent>,
+    /// End LSN of the last committed transaction.
+    checkpoint: Option<String>,
+}
+
+impl<'a> PgOutputDecoder<'a> {
+    /// Creates a new instance.
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            relations: HashMap::new(),
+            committed_at: None,
+            pending_events: Vec::new(),
+            checkpoint: None,
+        }
+    }
+
+    /// Decodes a message. It returns the changes of a transaction
+    /// when the commit message is received.
+    fn decode(&mut self, lsn: &str, data: &[u8]) -> Result<Vec<ChangeEvent>, Error> {
+        let mut reader = MessageReader::new(data);
+        match reader.read_u8()? {
+            b'B' => {
+                let _final_lsn = reader.read_i64()?;
+                let timestamp = reader.read_i64()?;
+                self.committed_at =
+                    Some(DateTime::from_timestamp_micros(timestamp + PG_EPOCH_MICROS));
+                self.pending_events.clear();
+            }
+            b'C' => {
+                let _flags = reader.read_u8()?;
+                let _commit_lsn = reader.read_i64()?;
+                let end_lsn = reader.read_i64()?;
+                self.checkpoint = Some(format_lsn(end_lsn));
+                return Ok(std::mem::take(&mut self.pending_events));
+            }
+            b'R' => {
+                let oid = reader.read_u32()?;
+                let schema = reader.read_cstr()?;
+                let table = reader.read_cstr()?;
+                let _replica_identity = reader.read_u8()?;
+                let num_columns = reader.read_i16()?;
+                let mut columns = Vec::with_capacity(num_columns.max(0) as usize);
+                for _ in 0..num_columns {
+                    let _flags = reader.read_u8()?;
+                    let name = reader.read_cstr()?;
+                    let type_oid = reader.read_u32()?;
+                    let _type_modifier = reader.read_i32()?;
+                    columns.push((name, type_oid));
+                }
+                let relation = Relation {
+                    schema,
+                    table,
+                    columns,
+                };
+                self.relations.insert(oid, relation);
+            }
+            b'I' => {
+                let relation = self.relation(reader.read_u32()?)?;
+                reader.expect_u8(b'N')?;
+                let after = reader.read_tuple(relation)?;
+                let mut event = self.new_event(relation, ChangeOperation::Insert, lsn);
+                event.set_after(Some(after));
+                self.pending_events.push(event);
+            }
+            b'U' => {
+                let relation = self.relation(reader.read_u32()?)?;
+                let before = match reader.read_u8()? {
+                    b'K' | b'O' => {
+                        let before = reader.read_tuple(relation)?;
+                        reader.expect_u8(b'N')?;
+                        Some(before)
+                    }
+                    b'N' => None,
+                    tag => bail!("unexpected tuple type `{}` in the update message", tag),
+                };
+                let after = reader.read_tuple(relation)?;
+                let mut event = self.new_event(relation, ChangeOperation::Update, lsn);
+                event.set_before(before);
+                event.set_after(Some(after));
+                self.pending_events.push(event);
+            }
+            b'D' => {
+                let relation = self.relation(reader.read_u32()?)?;
+                let _tuple_type = reader.read_u8()?;
+                let before = reader.read_tuple(relation)?;
+                let mut event = self.new_event(relation, ChangeOperation::Delete, lsn);
+                event.set_before(Some(before));
+                self.pending_events.push(event);
+            }
+            b'T' => {
+                let num_relations = reader.read_i32()?;
+                let _options = reader.read_u8()?;
+                for _ in 0..num_relations {
+                    let relation = self.relation(reader.read_u32()?)?;
+                    let event = self.new_event(relation, ChangeOperation::Truncate, lsn);
+                    self.pending_events.push(event);
+                }
+            }
+            _ => (),
+        }
+        Ok(Vec::new())
+    }
+
+    /// Returns the relation with the OID.
+    fn relation(&self, oid: u32) -> Result<&Relation, Error> {
+        self.relations
+            .get(&oid)
+            .ok_or_else(|| warn!("the relation `{}` has not been received", oid))
+    }
+
+    /// Creates a new change event for the relation.
+    fn new_event(&self, relation: &Relation, operation: ChangeOperation, lsn: &str) -> ChangeEvent {
+        let mut event = ChangeEvent::new(self.source, &relation.schema, &relation.table, operation);
+        event.set_position(lsn);
+        event.set_committed_at(self.committed_at);
+        event
+    }
+}
+
+/// A reader for the binary messages.
+struct MessageReader<'a> {
+    /// Message data.
+    data: &'a [u8],
+    /// Current position.
+    pos: usize,
+}
+
+impl<'a> MessageReader<'a> {
+    /// Creates a new instance.
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Reads the next `n` bytes.
+    fn read_bytes(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        let end = self.pos + n;
+        if end > self.data.len() {
+            bail!("unexpected end of the pgoutput message");
+        }
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    /// Reads a byte.
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    /// Reads a byte and checks that it is the expected one.
+    fn expect_u8(&mut self, expected: u8) -> Result<(), Error> {
+        let byte = self.read_u8()?;
+        if byte != expected {
+            bail!("expect the byte `{}` but got `{}`", expected, byte);
+        }
+        Ok(())
+    }
+
+    /// Reads a big-endian `i16`.
+    fn read_i16(&mut self) -> Result<i16, Error> {
+        Ok(i16::from_be_bytes(self.read_bytes(2)?.try_into()?))
+    }
+
+    /// Reads a big-endian `i32`.
+    fn read_i32(&mut self) -> Result<i32, Error> {
+        Ok(i32::from_be_bytes(self.read_bytes(4)?.try_into()?))
+    }
+
+    /// Reads a big-endian `u32`.
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_be_bytes(self.read_bytes(4)?.try_into()?))
+    }
+
+    /// Reads a big-endian `i64`.
+    fn read_i64(&mut self) -> Result<i64, Error> {
+        Ok(i64::from_be_bytes(self.read_bytes(8)?.try_into()?))
+    }
+
+    /// Reads a null-terminated string.
+    fn read_cstr(&mut self) -> Result<String, Error> {
+        let rest = &self.data[self.pos..];
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| warn!("the string in the pgoutput message is not terminated"))?;
+        let s = String::from_utf8_lossy(&rest[..len]).into_owned();
+        self.pos += len + 1;
+        Ok(s)
+    }
+
+    /// Reads the tuple data as a map of the column values.
+    fn read_tuple(&mut self, relation: &Relation) -> Result<Map, Error> {
+        let num_columns = self.read_i16()?;
+        let mut map = Map::new();
+        for index in 0..num_columns.max(0) as usize {
+            let value = match self.read_u8()? {
+                b'n' => JsonValue::Null,
+                b'u' => continue,
+                b't' | b'b' => {
+                    let len = self.read_i32()?;
+                    let bytes = self.read_bytes(len.max(0) as usize)?;
+                    let text = String::from_utf8_lossy(bytes);
+                    let type_oid = relation.columns.get(index).map(|col| col.1);
+                    parse_text_value(&text, type_oid.unwrap_or_default())
+                }
+                tag => bail!("unexpected column type `{}` in the tuple data", tag),
+            };
+            if let Some((name, _)) = relation.columns.get(index) {
+                map.insert(name.to_owned(), value);
+            }
+        }
+        Ok(map)
+    }
+}
+
+/// Parses a value in the text format with the type OID.
+fn parse_text_value(text: &str, type_oid: u32) -> JsonValue {
+    match type_oid {
+        16 => JsonValue::Bool(text == "t"),
+        20 | 21 | 23 => text.parse::<i64>().map(JsonValue::from).unwrap_or_default(),
+        700 | 701 => text.parse::<f64>().map(JsonValue::from).unwrap_or_default(),
+        114 | 3802 => serde_json::from_str(text).unwrap_or_else(|_| text.into()),
+        _ => text.into(),
+    }
+}
+
+/// Formats the LSN as `XXX/XXX`.
+#[inline]
+fn format_lsn(lsn: i64) -> String {
+    let lsn = lsn as u64;
+    format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFF_FFFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PgOutputDecoder;
+    use crate::{connector::change_stream::ChangeOperation, extension::JsonObjectExt};
+
+    #[test]
+    fn it_decodes_pgoutput_messages() {
+        let mut decoder = PgOutputDecoder::new("postgres");
+
+        let mut begin = vec![b'B'];
+        begin.extend(0x16B3748_i64.to_be_bytes());
+        begin.extend(0_i64.to_be_bytes());
+        begin.extend(7_i32.to_be_bytes());
+        assert!(decoder.decode("0/16B3700", &begin).unwrap().is_empty());
+
+        let mut relation = vec![b'R'];
+        relation.extend(16384_u32.to_be_bytes());
+        relation.extend(b"public\0orders\0d");
+        relation.extend(2_i16.to_be_bytes());
+        relation.extend(b"\x01id\0");
+        relation.extend(23_u32.to_be_bytes());
+        relation.extend((-1_i32).to_be_bytes());
+        relation.extend(b"\x00status\0");
+        relation.extend(25_u32.to_be_bytes());
+        relation.extend((-1_i32).to_be_bytes());
+        assert!(decoder.decode("0/16B3700", &relation).unwrap().is_empty());
+
+        let mut insert = vec![b'I'];
+        insert.extend(16384_u32.to_be_bytes());
+        insert.push(b'N');
+        insert.extend(2_i16.to_be_bytes());
+        insert.push(b't');
+        insert.extend(2_i32.to_be_bytes());
+        insert.extend(b"42");
+        insert.push(b't');
+        insert.extend(4_i32.to_be_bytes());
+        insert.extend(b"paid");
+        assert!(decoder.decode("0/16B3710", &insert).unwrap().is_empty());
+
+        let mut commit = vec![b'C', 0];
+        commit.extend(0x16B3748_i64.to_be_bytes());
+        commit.extend(0x16B3778_i64.to_be_bytes());
+        commit.extend(0_i64.to_be_bytes());
+        let events = decoder.decode("0/16B3748", &commit).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].operation(), ChangeOperation::Insert);
+        assert_eq!(events[0].table(), "orders");
+
+        let after = events[0].after().unwrap();
+        assert_eq!(after.get_i64("id"), Some(42));
+        assert_eq!(after.get_str("status"), Some("paid"));
+        assert_eq!(decoder.checkpoint.as_deref(), Some("0/16B3778"));
+    }
+}
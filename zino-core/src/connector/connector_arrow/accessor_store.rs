@@ -0,0 +1,454 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt, TryFutureExt, TryStreamExt,
+};
+use object_store::{
+    path::Path, GetOptions, GetRange, GetResult, GetResultPayload, ListResult, MultipartId, ObjectMeta,
+    ObjectStore, PutOptions, PutResult,
+};
+use opendal::{EntryMode, ErrorKind, Metadata, Metakey, Operator};
+use std::{
+    fmt::{self, Display},
+    ops::Range,
+};
+
+/// Size of the chunks for the range requests.
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// An object store backed by the storage operator of an accessor.
+///
+/// The objects are read lazily with range requests, so that DataFusion
+/// can scan the files in the storage services without copying them to disk.
+#[derive(Debug, Clone)]
+pub(super) struct AccessorObjectStore {
+    /// Accessor name.
+    name: String,
+    /// Storage operator.
+    operator: Operator,
+}
+
+impl AccessorObjectStore {
+    /// Creates a new instance.
+    #[inline]
+    pub(super) fn new(name: &str, operator: Operator) -> Self {
+        Self {
+            name: name.to_owned(),
+            operator,
+        }
+    }
+
+    /// Returns the URL of the object store.
+    #[inline]
+    pub(super) fn url(&self) -> String {
+        format!("accessor://{}/", self.name)
+    }
+
+    /// Returns the object metadata for the path.
+    async fn object_meta(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+        let path = location.as_ref();
+        let metadata = self
+            .operator
+            .stat(path)
+            .await
+            .map_err(|err| into_object_store_error(err, path))?;
+        Ok(new_object_meta(location.clone(), &metadata))
+    }
+}
+
+impl Display for AccessorObjectStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AccessorObjectStore({})", self.name)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for AccessorObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        bytes: Bytes,
+        _opts: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        let path = location.as_ref();
+        self.operator
+            .write(path, bytes)
+            .await
+            .map_err(|err| into_object_store_error(err, path))?;
+        Ok(PutResult {
+            e_tag: None,
+            version: None,
+        })
+    }
+
+    /// Multipart uploads are not supported by the accessor store,
+    /// so this method always returns [`NotImplemented`](object_store::Error::NotImplemented).
+    async fn put_multipart(
+        &self,
+        _location: &Path,
+    ) -> object_store::Result<(MultipartId, Box<dyn tokio::io::AsyncWrite + Unpin + Send>)> {
+        Err(object_store::Error::NotImplemented)
+    }
+
+    /// Always returns [`NotImplemented`](object_store::Error::NotImplemented)
+    /// since multipart uploads are not supported.
+    async fn abort_multipart(
+        &self,
+        _location: &Path,
+        _multipart_id: &MultipartId,
+    ) -> object_store::Result<()> {
+        Err(object_store::Error::NotImplemented)
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        let meta = self.object_meta(location).await?;
+        check_preconditions(&options, &meta)?;
+
+        let range = resolve_range(options.range.as_ref(), &meta)?;
+        let payload = if options.head {
+            GetResultPayload::Stream(stream::empty().boxed())
+        } else {
+            let operator = self.operator.clone();
+            let path = location.to_string();
+            let chunks = stream::unfold(range.clone(), move |range| {
+                let operator = operator.clone();
+                let path = path.clone();
+                async move {
+                    if range.start >= range.end {
+                        return None;
+                    }
+                    let end = range.end.min(range.start + CHUNK_SIZE);
+                    let result = operator
+                        .read_with(&path)
+                        .range(range.start as u64..end as u64)
+                        .await
+                        .map(Bytes::from)
+                        .map_err(|err| into_object_store_error(err, &path));
+                    let next_range = if result.is_ok() { end..range.end } else { 0..0 };
+                    Some((result, next_range))
+                }
+            });
+            GetResultPayload::Stream(chunks.boxed())
+        };
+        Ok(GetResult {
+            payload,
+            meta,
+            range,
+        })
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> object_store::Result<Bytes> {
+        let path = location.as_ref();
+        self.operator
+            .read_with(path)
+            .range(range.start as u64..range.end as u64)
+            .await
+            .map(Bytes::from)
+            .map_err(|err| into_object_store_error(err, path))
+    }
+
+    async fn head(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+        self.object_meta(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> object_store::Result<()> {
+        let path = location.as_ref();
+        self.operator
+            .delete(path)
+            .await
+            .map_err(|err| into_object_store_error(err, path))
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, object_store::Result<ObjectMeta>> {
+        let dir = dir_path(prefix);
+        let lister = async move {
+            let lister = self
+                .operator
+                .lister_with(&dir)
+                .recursive(true)
+                .metakey(Metakey::Mode | Metakey::ContentLength | Metakey::LastModified)
+                .await
+                .map_err(|err| into_object_store_error(err, &dir))?;
+            let objects = lister
+                .map_err(move |err| into_object_store_error(err, &dir))
+                .try_filter_map(|entry| async move {
+                    let metadata = entry.metadata();
+                    if metadata.mode() == EntryMode::FILE {
+                        let location = Path::from(entry.path());
+                        Ok(Some(new_object_meta(location, metadata)))
+                    } else {
+                        Ok(None)
+                    }
+                });
+            Ok::<_, object_store::Error>(objects)
+        };
+        lister.try_flatten_stream().boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        let dir = dir_path(prefix);
+        let mut lister = self
+            .operator
+            .lister_with(&dir)
+            .metakey(Metakey::Mode | Metakey::ContentLength | Metakey::LastModified)
+            .await
+            .map_err(|err| into_object_store_error(err, &dir))?;
+        let mut common_prefixes = Vec::new();
+        let mut objects = Vec::new();
+        while let Some(entry) = lister.next().await {
+            let entry = entry.map_err(|err| into_object_store_error(err, &dir))?;
+            let path = entry.path();
+            let metadata = entry.metadata();
+            match metadata.mode() {
+                EntryMode::DIR if path != dir => {
+                    common_prefixes.push(Path::from(path.trim_end_matches('/')));
+                }
+                EntryMode::FILE => {
+                    objects.push(new_object_meta(Path::from(path), metadata));
+                }
+                _ => (),
+            }
+        }
+        Ok(ListResult {
+            common_prefixes,
+            objects,
+        })
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        let path = from.as_ref();
+        self.operator
+            .copy(path, to.as_ref())
+            .await
+            .map_err(|err| into_object_store_error(err, path))
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        let path = to.as_ref();
+        let exists = self
+            .operator
+            .is_exist(path)
+            .await
+            .map_err(|err| into_object_store_error(err, path))?;
+        if exists {
+            let err = format!("the object `{path}` already exists");
+            return Err(object_store::Error::AlreadyExists {
+                path: path.to_owned(),
+                source: err.into(),
+            });
+        }
+        self.copy(from, to).await
+    }
+}
+
+/// Checks the preconditions of the get request against the object metadata.
+fn check_preconditions(options: &GetOptions, meta: &ObjectMeta) -> object_store::Result<()> {
+    // An object without the ETag never matches any entity tags except `*`.
+    let etag = meta.e_tag.as_deref().unwrap_or("*");
+    let last_modified = meta.last_modified;
+    let path = meta.location.to_string();
+    if let Some(tags) = &options.if_match {
+        if tags != "*" && tags.split(',').map(str::trim).all(|tag| tag != etag) {
+            return Err(object_store::Error::Precondition {
+                path,
+                source: format!("the ETag `{etag}` does not match `{tags}`").into(),
+            });
+        }
+    } else if let Some(date) = options.if_unmodified_since {
+        if last_modified > date {
+            return Err(object_store::Error::Precondition {
+                path,
+                source: format!("the object has been modified since `{date}`").into(),
+            });
+        }
+    }
+    if let Some(tags) = &options.if_none_match {
+        if tags == "*" || tags.split(',').map(str::trim).any(|tag| tag == etag) {
+            return Err(object_store::Error::NotModified {
+                path,
+                source: format!("the ETag `{etag}` matches `{tags}`").into(),
+            });
+        }
+    } else if let Some(date) = options.if_modified_since {
+        if last_modified <= date {
+            return Err(object_store::Error::NotModified {
+                path,
+                source: format!("the object has not been modified since `{date}`").into(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Resolves the requested range into the byte range of the object.
+fn resolve_range(range: Option<&GetRange>, meta: &ObjectMeta) -> object_store::Result<Range<usize>> {
+    let size = meta.size;
+    let range = match range {
+        Some(GetRange::Bounded(range)) if range.start >= range.end || range.start >= size => {
+            let err = format!(
+                "the range `{}..{}` is invalid for the object of {size} bytes",
+                range.start, range.end
+            );
+            return Err(object_store::Error::Generic {
+                store: "accessor",
+                source: err.into(),
+            });
+        }
+        Some(GetRange::Bounded(range)) => range.start..range.end.min(size),
+        Some(GetRange::Offset(offset)) if *offset >= size => {
+            let err = format!("the offset `{offset}` is invalid for the object of {size} bytes");
+            return Err(object_store::Error::Generic {
+                store: "accessor",
+                source: err.into(),
+            });
+        }
+        Some(GetRange::Offset(offset)) => *offset..size,
+        Some(GetRange::Suffix(length)) => size.saturating_sub(*length)..size,
+        None => 0..size,
+    };
+    Ok(range)
+}
+
+/// Returns the directory path in the storage for the prefix.
+fn dir_path(prefix: Option<&Path>) -> String {
+    match prefix.map(|path| path.as_ref()) {
+        Some(path) if !path.is_empty() => format!("{path}/"),
+        _ => "/".to_owned(),
+    }
+}
+
+/// Creates a new object metadata for the location.
+fn new_object_meta(location: Path, metadata: &Metadata) -> ObjectMeta {
+    ObjectMeta {
+        location,
+        last_modified: metadata.last_modified().unwrap_or_default(),
+        size: metadata.content_length() as usize,
+        e_tag: metadata.etag().map(|etag| etag.to_owned()),
+        version: None,
+    }
+}
+
+/// Converts the storage error into an object store error.
+fn into_object_store_error(err: opendal::Error, path: &str) -> object_store::Error {
+    match err.kind() {
+        ErrorKind::NotFound => object_store::Error::NotFound {
+            path: path.to_owned(),
+            source: Box::new(err),
+        },
+        ErrorKind::AlreadyExists => object_store::Error::AlreadyExists {
+            path: path.to_owned(),
+            source: Box::new(err),
+        },
+        ErrorKind::Unsupported => object_store::Error::NotSupported {
+            source: Box::new(err),
+        },
+        _ => object_store::Error::Generic {
+            store: "accessor",
+            source: Box::new(err),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_preconditions, resolve_range};
+    use chrono::{TimeDelta, Utc};
+    use object_store::{path::Path, GetOptions, GetRange, ObjectMeta};
+
+    fn object_meta() -> ObjectMeta {
+        ObjectMeta {
+            location: Path::from("data/users.csv"),
+            last_modified: Utc::now(),
+            size: 10,
+            e_tag: Some("v1".to_owned()),
+            version: None,
+        }
+    }
+
+    #[test]
+    fn it_resolves_ranges() {
+        let meta = object_meta();
+        assert_eq!(resolve_range(None, &meta).ok(), Some(0..10));
+        assert_eq!(
+            resolve_range(Some(&GetRange::Bounded(2..5)), &meta).ok(),
+            Some(2..5)
+        );
+        assert_eq!(
+            resolve_range(Some(&GetRange::Bounded(2..100)), &meta).ok(),
+            Some(2..10)
+        );
+        assert!(resolve_range(Some(&GetRange::Bounded(5..5)), &meta).is_err());
+        assert!(resolve_range(Some(&GetRange::Bounded(10..12)), &meta).is_err());
+        assert_eq!(
+            resolve_range(Some(&GetRange::Offset(4)), &meta).ok(),
+            Some(4..10)
+        );
+        assert!(resolve_range(Some(&GetRange::Offset(10)), &meta).is_err());
+        assert_eq!(
+            resolve_range(Some(&GetRange::Suffix(3)), &meta).ok(),
+            Some(7..10)
+        );
+        assert_eq!(
+            resolve_range(Some(&GetRange::Suffix(20)), &meta).ok(),
+            Some(0..10)
+        );
+    }
+
+    #[test]
+    fn it_checks_preconditions() {
+        let meta = object_meta();
+        let past = meta.last_modified - TimeDelta::hours(1);
+        let future = meta.last_modified + TimeDelta::hours(1);
+        let check = |options: GetOptions| check_preconditions(&options, &meta);
+
+        assert!(check(GetOptions::default()).is_ok());
+        assert!(check(GetOptions {
+            if_match: Some("v0, v1".to_owned()),
+            ..GetOptions::default()
+        })
+        .is_ok());
+        assert!(matches!(
+            check(GetOptions {
+                if_match: Some("v2".to_owned()),
+                ..GetOptions::default()
+            }),
+            Err(object_store::Error::Precondition { .. })
+        ));
+        assert!(matches!(
+            check(GetOptions {
+                if_unmodified_since: Some(past),
+                ..GetOptions::default()
+            }),
+            Err(object_store::Error::Precondition { .. })
+        ));
+        assert!(matches!(
+            check(GetOptions {
+                if_none_match: Some("*".to_owned()),
+                ..GetOptions::default()
+            }),
+            Err(object_store::Error::NotModified { .. })
+        ));
+        assert!(check(GetOptions {
+            if_none_match: Some("v2".to_owned()),
+            ..GetOptions::default()
+        })
+        .is_ok());
+        assert!(matches!(
+            check(GetOptions {
+                if_modified_since: Some(future),
+                ..GetOptions::default()
+            }),
+            Err(object_store::Error::NotModified { .. })
+        ));
+        assert!(check(GetOptions {
+            if_modified_since: Some(past),
+            ..GetOptions::default()
+        })
+        .is_ok());
+    }
+}
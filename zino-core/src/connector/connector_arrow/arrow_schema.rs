@@ -1,5 +1,5 @@
 use super::ArrowFieldExt;
-use crate::{bail, error::Error, Record, TomlValue};
+use crate::{bail, error::Error, AvroValue, Record, TomlValue};
 use datafusion::arrow::{
     array::Array,
     datatypes::{DataType, Field, Schema, UnionFields, UnionMode},
@@ -12,6 +12,10 @@ pub(super) trait ArrowSchemaExt {
     /// Attempts to create a `Schema` from an Avro record.
     fn try_from_avro_record(record: &Record) -> Result<Schema, Error>;
 
+    /// Attempts to create a `Schema` from the Avro records.
+    /// The data type of a field is inferred from the first non-null value.
+    fn try_from_avro_records(records: &[Record]) -> Result<Schema, Error>;
+
     /// Attempts to create a `Schema` from the TOML table configuration.
     fn try_from_toml_table(table: &Table) -> Result<Schema, Error>;
 
@@ -32,6 +36,31 @@ impl ArrowSchemaExt for Schema {
         Ok(Schema::new(fields))
     }
 
+    fn try_from_avro_records(records: &[Record]) -> Result<Schema, Error> {
+        let Some(record) = records.first() else {
+            bail!("the schema can not be inferred from empty records");
+        };
+        let mut fields = Vec::with_capacity(record.len());
+        for (field, _) in record {
+            let value = records.iter().find_map(|record| {
+                record.iter().find_map(|(key, value)| {
+                    let value = match value {
+                        AvroValue::Union(_, value) => value.as_ref(),
+                        _ => value,
+                    };
+                    (key == field && *value != AvroValue::Null).then_some(value)
+                })
+            });
+            let field = if let Some(value) = value {
+                Field::try_from_avro_record_entry(field, value)?
+            } else {
+                Field::new(field, DataType::Utf8, true)
+            };
+            fields.push(field);
+        }
+        Ok(Schema::new(fields))
+    }
+
     fn try_from_toml_table(table: &Table) -> Result<Schema, Error> {
         let mut fields = Vec::with_capacity(table.len());
         for (key, value) in table {
@@ -0,0 +1,639 @@
+use super::ArrowArrayExt;
+use crate::{bail, error::Error, extension::TomlTableExt, warn, AvroValue, JsonValue, Map, Record};
+use apache_avro::{Codec, Schema as AvroSchema, Writer};
+use datafusion::{
+    arrow::{
+        array::UInt64Array,
+        datatypes::{DataType, Schema, TimeUnit},
+        record_batch::RecordBatch,
+    },
+    common::parsers::CompressionTypeVariant,
+    config::{CsvOptions, JsonOptions, TableParquetOptions},
+    dataframe::{DataFrame, DataFrameWriteOptions},
+};
+use futures::StreamExt;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    fmt,
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+use tokio::{fs, io::AsyncWriteExt};
+use toml::Table;
+
+#[cfg(feature = "accessor")]
+use opendal::Operator;
+#[cfg(feature = "accessor")]
+use tokio::io::AsyncReadExt;
+
+/// Supported file formats for exporting the query results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Apache Avro.
+    Avro,
+    /// CSV.
+    Csv,
+    /// Newline delimited JSON.
+    NdJson,
+    /// Apache Parquet.
+    Parquet,
+}
+
+impl ExportFormat {
+    /// Returns the format name which is also used as the file extension.
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Avro => "avro",
+            Self::Csv => "csv",
+            Self::NdJson => "ndjson",
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "avro" => Ok(Self::Avro),
+            "csv" => Ok(Self::Csv),
+            "ndjson" | "json" => Ok(Self::NdJson),
+            "parquet" => Ok(Self::Parquet),
+            _ => Err(warn!("export format `{}` is unsupported", s)),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Options for exporting the query results.
+///
+/// # Examples
+///
+/// ```toml
+/// format = "parquet"
+/// partition-by = ["year", "month"]
+/// compression = "zstd(3)"
+/// single-file = false
+/// accessor = "s3"
+/// register-as = "monthly_orders"
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// File format.
+    format: ExportFormat,
+    /// Columns to partition the output files by.
+    partition_by: Vec<String>,
+    /// Compression codec.
+    compression: Option<String>,
+    /// A flag to write a single file instead of a directory.
+    single_file: bool,
+    /// Name of the storage accessor to write the files through.
+    accessor: Option<String>,
+    /// Table name to register the output as.
+    table_name: Option<String>,
+}
+
+impl ExportOptions {
+    /// Creates a new instance with the file format.
+    #[inline]
+    pub fn new(format: ExportFormat) -> Self {
+        Self {
+            format,
+            partition_by: Vec::new(),
+            compression: None,
+            single_file: false,
+            accessor: None,
+            table_name: None,
+        }
+    }
+
+    /// Attempts to create a new instance with the config.
+    pub fn try_from_config(config: &Table) -> Result<Self, Error> {
+        let format = config.get_str("format").unwrap_or("parquet").parse()?;
+        let mut options = Self::new(format);
+        if let Some(columns) = config.get_str_array("partition-by") {
+            options.partition_by = columns.into_iter().map(|s| s.to_owned()).collect();
+        }
+        if let Some(compression) = config.get_str("compression") {
+            options.compression = Some(compression.to_owned());
+        }
+        if let Some(single_file) = config.get_bool("single-file") {
+            options.single_file = single_file;
+        }
+        if let Some(accessor) = config.get_str("accessor") {
+            options.accessor = Some(accessor.to_owned());
+        }
+        if let Some(table_name) = config.get_str("register-as") {
+            options.table_name = Some(table_name.to_owned());
+        }
+        Ok(options)
+    }
+
+    /// Sets the columns to partition the output files by.
+    #[inline]
+    pub fn partition_by(mut self, columns: Vec<String>) -> Self {
+        self.partition_by = columns;
+        self
+    }
+
+    /// Sets the compression codec, such as `gzip` for CSV and NDJSON,
+    /// `zstd(3)` or `snappy` for Parquet, and `deflate` for Avro.
+    #[inline]
+    pub fn compression(mut self, compression: impl Into<String>) -> Self {
+        self.compression = Some(compression.into());
+        self
+    }
+
+    /// Writes a single file instead of a directory.
+    #[inline]
+    pub fn single_file(mut self, single_file: bool) -> Self {
+        self.single_file = single_file;
+        self
+    }
+
+    /// Writes the files through the storage accessor instead of the local file system.
+    #[inline]
+    pub fn accessor(mut self, accessor: impl Into<String>) -> Self {
+        self.accessor = Some(accessor.into());
+        self
+    }
+
+    /// Registers the output as a new table.
+    #[inline]
+    pub fn register_as(mut self, table_name: impl Into<String>) -> Self {
+        self.table_name = Some(table_name.into());
+        self
+    }
+
+    /// Returns the file format.
+    #[inline]
+    pub fn format(&self) -> ExportFormat {
+        self.format
+    }
+
+    /// Returns the columns to partition the output files by.
+    #[inline]
+    pub fn partition_columns(&self) -> &[String] {
+        &self.partition_by
+    }
+
+    /// Returns the compression codec.
+    #[inline]
+    pub fn compression_codec(&self) -> Option<&str> {
+        self.compression.as_deref()
+    }
+
+    /// Returns `true` if the output is a single file instead of a directory.
+    #[inline]
+    pub fn is_single_file(&self) -> bool {
+        self.single_file || self.format == ExportFormat::Avro
+    }
+
+    /// Returns the name of the storage accessor.
+    #[inline]
+    pub fn accessor_name(&self) -> Option<&str> {
+        self.accessor.as_deref()
+    }
+
+    /// Returns the table name to register the output as.
+    #[inline]
+    pub fn table_name(&self) -> Option<&str> {
+        self.table_name.as_deref()
+    }
+}
+
+/// Output of an export.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportOutput {
+    /// File format.
+    format: ExportFormat,
+    /// Location of the output.
+    location: String,
+    /// Name of the storage accessor.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    accessor: Option<String>,
+    /// Files which have been written.
+    files: Vec<String>,
+    /// Number of rows written.
+    num_rows: u64,
+    /// Table name which the output is registered as.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    table_name: Option<String>,
+}
+
+impl ExportOutput {
+    /// Creates a new instance.
+    pub(super) fn new(format: ExportFormat, location: String, num_rows: u64) -> Self {
+        Self {
+            format,
+            location,
+            accessor: None,
+            files: Vec::new(),
+            num_rows,
+            table_name: None,
+        }
+    }
+
+    /// Sets the name of the storage accessor.
+    #[inline]
+    pub(super) fn set_accessor(&mut self, accessor: Option<String>) {
+        self.accessor = accessor;
+    }
+
+    /// Sets the files which have been written.
+    #[inline]
+    pub(super) fn set_files(&mut self, files: Vec<String>) {
+        self.files = files;
+    }
+
+    /// Sets the table name which the output is registered as.
+    #[inline]
+    pub(super) fn set_table_name(&mut self, table_name: Option<String>) {
+        self.table_name = table_name;
+    }
+
+    /// Returns the file format.
+    #[inline]
+    pub fn format(&self) -> ExportFormat {
+        self.format
+    }
+
+    /// Returns the location of the output.
+    #[inline]
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    /// Returns the name of the storage accessor.
+    #[inline]
+    pub fn accessor(&self) -> Option<&str> {
+        self.accessor.as_deref()
+    }
+
+    /// Returns the files which have been written.
+    #[inline]
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    /// Returns the number of rows written.
+    #[inline]
+    pub fn num_rows(&self) -> u64 {
+        self.num_rows
+    }
+
+    /// Returns the table name which the output is registered as.
+    #[inline]
+    pub fn table_name(&self) -> Option<&str> {
+        self.table_name.as_deref()
+    }
+
+    /// Converts `self` to a JSON object, which can be saved in the `extra` field of a model.
+    #[inline]
+    pub fn to_map(&self) -> Map {
+        match serde_json::to_value(self) {
+            Ok(JsonValue::Object(map)) => map,
+            _ => Map::new(),
+        }
+    }
+}
+
+/// Writes the `DataFrame` to the local path and returns the number of rows written.
+pub(super) async fn write_data_frame(
+    df: DataFrame,
+    path: &Path,
+    options: &ExportOptions,
+) -> Result<u64, Error> {
+    let Some(path_str) = path.to_str() else {
+        bail!("the path `{}` is invalid", path.display());
+    };
+    let compression = options.compression.as_deref();
+    let write_options = DataFrameWriteOptions::new()
+        .with_single_file_output(options.single_file)
+        .with_partition_by(options.partition_by.clone());
+    let batches = match options.format {
+        ExportFormat::Avro => {
+            if !options.partition_by.is_empty() {
+                bail!("partitioning is unsupported for the Avro format");
+            }
+            return write_avro(df, path, compression).await;
+        }
+        ExportFormat::Csv => {
+            let mut csv_options = CsvOptions::default();
+            if let Some(compression) = compression {
+                csv_options.compression = compression.parse::<CompressionTypeVariant>()?;
+            }
+            df.write_csv(path_str, write_options, Some(csv_options))
+                .await?
+        }
+        ExportFormat::NdJson => {
+            let mut json_options = JsonOptions::default();
+            if let Some(compression) = compression {
+                json_options.compression = compression.parse::<CompressionTypeVariant>()?;
+            }
+            df.write_json(path_str, write_options, Some(json_options))
+                .await?
+        }
+        ExportFormat::Parquet => {
+            let mut parquet_options = TableParquetOptions::default();
+            if let Some(compression) = compression {
+                parquet_options.global.compression = Some(compression.to_owned());
+            }
+            df.write_parquet(path_str, write_options, Some(parquet_options))
+                .await?
+        }
+    };
+    Ok(count_rows(&batches).unwrap_or_default())
+}
+
+/// Returns the number of rows in the `count` column of the record batches
+/// for the `COPY TO` or `INSERT INTO` statements.
+pub(super) fn count_rows(batches: &[RecordBatch]) -> Option<u64> {
+    let mut num_rows = None;
+    for batch in batches {
+        if batch.num_columns() != 1 {
+            return None;
+        }
+        let counts = batch
+            .column_by_name("count")?
+            .as_any()
+            .downcast_ref::<UInt64Array>()?;
+        let count = counts.iter().flatten().sum::<u64>();
+        num_rows = Some(num_rows.unwrap_or_default() + count);
+    }
+    num_rows
+}
+
+/// Collects the files in the path recursively.
+pub(super) async fn collect_files(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut paths = vec![path.to_path_buf()];
+    while let Some(path) = paths.pop() {
+        let metadata = match fs::metadata(&path).await {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+        if metadata.is_dir() {
+            let mut entries = fs::read_dir(&path).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                paths.push(entry.path());
+            }
+        } else if metadata.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort_unstable();
+    Ok(files)
+}
+
+/// Uploads the local file to the storage in chunks.
+#[cfg(feature = "accessor")]
+pub(super) async fn upload_file(
+    operator: &Operator,
+    local_path: &Path,
+    location: &str,
+) -> Result<(), Error> {
+    let mut file = fs::File::open(local_path).await?;
+    let mut writer = operator.writer(location).await?;
+    let mut buf = vec![0; 8 * 1024 * 1024];
+    loop {
+        let size = match file.read(&mut buf).await {
+            Ok(size) => size,
+            Err(err) => {
+                writer.abort().await?;
+                return Err(err.into());
+            }
+        };
+        if size == 0 {
+            break;
+        }
+        if let Err(err) = writer.write(buf[..size].to_vec()).await {
+            writer.abort().await?;
+            return Err(err.into());
+        }
+    }
+    writer.close().await?;
+    Ok(())
+}
+
+/// Writes the `DataFrame` to a local Avro file.
+///
+/// The record batches are streamed and each batch is flushed as an Avro block,
+/// so the results are never collected in memory as a whole.
+async fn write_avro(df: DataFrame, path: &Path, compression: Option<&str>) -> Result<u64, Error> {
+    let codec = match compression {
+        Some("deflate") => Codec::Deflate,
+        Some("null") | None => Codec::Null,
+        Some(compression) => bail!("compression `{}` is unsupported for Avro", compression),
+    };
+    let schema = Schema::from(df.schema());
+    let avro_schema = AvroSchema::parse(&avro_schema_json(&schema))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+
+    let mut file = fs::File::create(path).await?;
+    let buffer = SharedBuffer::default();
+    let mut writer = Writer::with_codec(&avro_schema, buffer.clone(), codec);
+    let mut stream = df.execute_stream().await?;
+    let mut num_rows = 0;
+    while let Some(batch) = stream.next().await {
+        let batch = batch?;
+        let schema = batch.schema();
+        for index in 0..batch.num_rows() {
+            let mut record = Record::with_capacity(batch.num_columns());
+            for (field, array) in schema.fields().iter().zip(batch.columns()) {
+                let value = array.parse_avro_value(index)?;
+                record.push((field.name().to_owned(), value));
+            }
+            writer.append(AvroValue::Record(record).resolve(&avro_schema)?)?;
+        }
+        writer.flush()?;
+        file.write_all(&buffer.take()).await?;
+        num_rows += u64::try_from(batch.num_rows())?;
+    }
+    writer.into_inner()?;
+    file.write_all(&buffer.take()).await?;
+    file.flush().await?;
+    Ok(num_rows)
+}
+
+/// A buffer shared with the Avro writer, which is drained after each flush.
+#[derive(Debug, Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    /// Takes the bytes written so far.
+    #[inline]
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock())
+    }
+}
+
+impl Write for SharedBuffer {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Creates an Avro schema for the Arrow schema. All the fields are nullable.
+fn avro_schema_json(schema: &Schema) -> JsonValue {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let data_type = match field.data_type() {
+                DataType::Boolean => "boolean".into(),
+                DataType::Int8
+                | DataType::Int16
+                | DataType::Int32
+                | DataType::Int64
+                | DataType::UInt8
+                | DataType::UInt16
+                | DataType::UInt32
+                | DataType::UInt64 => "long".into(),
+                DataType::Float32 | DataType::Float64 => "double".into(),
+                DataType::Binary | DataType::LargeBinary => "bytes".into(),
+                DataType::Date32 => serde_json::json!({
+                    "type": "int",
+                    "logicalType": "date",
+                }),
+                DataType::Time32(_) => serde_json::json!({
+                    "type": "int",
+                    "logicalType": "time-millis",
+                }),
+                DataType::Time64(_) => serde_json::json!({
+                    "type": "long",
+                    "logicalType": "time-micros",
+                }),
+                DataType::Date64
+                | DataType::Timestamp(TimeUnit::Second | TimeUnit::Millisecond, _) => {
+                    serde_json::json!({
+                        "type": "long",
+                        "logicalType": "timestamp-millis",
+                    })
+                }
+                DataType::Timestamp(_, _) => serde_json::json!({
+                    "type": "long",
+                    "logicalType": "timestamp-micros",
+                }),
+                _ => "string".into(),
+            };
+            serde_json::json!({
+                "name": field.name(),
+                "type": ["null", data_type],
+                "default": null,
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "type": "record",
+        "name": "export",
+        "fields": fields,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{avro_schema_json, ExportFormat, ExportOptions};
+    use crate::{
+        connector::{ArrowConnector, Connector},
+        extension::JsonObjectExt,
+        Map, Uuid,
+    };
+    use apache_avro::Schema as AvroSchema;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use toml::Table;
+
+    #[test]
+    fn it_creates_avro_schemas() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("score", DataType::Float32, true),
+        ]);
+        let avro_schema = AvroSchema::parse(&avro_schema_json(&schema));
+        assert!(avro_schema.is_ok());
+        assert_eq!(
+            "json".parse::<ExportFormat>().ok(),
+            Some(ExportFormat::NdJson)
+        );
+        assert!("orc".parse::<ExportFormat>().is_err());
+    }
+
+    #[test]
+    fn it_exports_and_registers_outputs() {
+        let root = std::env::temp_dir().join(format!("zino-export-{}", Uuid::now_v7()));
+        let mut config = Table::new();
+        config.insert(
+            "root".to_owned(),
+            root.to_string_lossy().into_owned().into(),
+        );
+
+        let connector = ArrowConnector::with_config(&config);
+        let query = "SELECT * FROM (VALUES (1, 'a', 1.5), (2, 'b', NULL), (3, 'c''d', 2.5)) \
+            AS t(id, name, score)";
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let formats = [
+                ExportFormat::Avro,
+                ExportFormat::Csv,
+                ExportFormat::NdJson,
+                ExportFormat::Parquet,
+            ];
+            for (index, format) in formats.into_iter().enumerate() {
+                let table_name = format!("export_{format}");
+                let single_file = index % 2 == 0;
+                let options = ExportOptions::new(format)
+                    .single_file(single_file)
+                    .register_as(&table_name);
+                let path = format!("output/{table_name}.{format}");
+                let output = connector
+                    .export(query, None, &path, &options)
+                    .await
+                    .unwrap();
+                assert_eq!(output.num_rows(), 3);
+                assert_eq!(output.table_name(), Some(table_name.as_str()));
+                assert!(!output.files().is_empty());
+                assert!(output.files().iter().all(|file| file.starts_with(&path)));
+
+                let sql = format!("SELECT id, name, score FROM {table_name} ORDER BY id");
+                let rows = connector.query_as::<Map>(&sql, None).await.unwrap();
+                assert_eq!(rows.len(), 3);
+                assert_eq!(rows[0].get_str("name"), Some("a"));
+                assert_eq!(rows[2].get_str("name"), Some("c'd"));
+                assert!(rows[1].get("score").unwrap().is_null());
+            }
+
+            let options =
+                ExportOptions::new(ExportFormat::Avro).partition_by(vec!["id".to_owned()]);
+            assert!(connector
+                .export(query, None, "output/partitioned", &options)
+                .await
+                .is_err());
+        });
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
@@ -1,13 +1,18 @@
-use super::ArrowArrayExt;
+use super::{data_export, ArrowArrayExt, ExportOptions};
 use crate::{error::Error, Map, Record};
 use datafusion::{arrow::util, dataframe::DataFrame};
 use serde::de::DeserializeOwned;
+use std::path::Path;
 
 /// Executor trait for [`DataFrame`](datafusion::dataframe::DataFrame).
 pub trait DataFrameExecutor {
     /// Executes the `DataFrame` and returns the total number of rows affected.
     async fn execute(self) -> Result<Option<u64>, Error>;
 
+    /// Executes the `DataFrame` and writes the results to the local path
+    /// with the export options. It returns the total number of rows written.
+    async fn write_to(self, path: &Path, options: &ExportOptions) -> Result<u64, Error>;
+
     /// Executes the `DataFrame` and parses it as `Vec<Record>`.
     async fn query(self) -> Result<Vec<Record>, Error>;
 
@@ -26,8 +31,13 @@ pub trait DataFrameExecutor {
 
 impl DataFrameExecutor for DataFrame {
     async fn execute(self) -> Result<Option<u64>, Error> {
-        self.collect().await?;
-        Ok(None)
+        let batches = self.collect().await?;
+        Ok(data_export::count_rows(&batches))
+    }
+
+    #[inline]
+    async fn write_to(self, path: &Path, options: &ExportOptions) -> Result<u64, Error> {
+        data_export::write_data_frame(self, path, options).await
     }
 
     async fn query(self) -> Result<Vec<Record>, Error> {
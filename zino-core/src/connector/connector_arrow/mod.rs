@@ -1,6 +1,6 @@
 //! Utilities for DataFusion.
 
-use super::{Connector, DataSource, DataSourceConnector::Arrow};
+use super::{Connector, DataSource, DataSourceConnector::Arrow, HttpConnector};
 use crate::{
     application::{http_client, PROJECT_DIR},
     bail,
@@ -9,7 +9,10 @@ use crate::{
     helper, warn, LazyLock, Map, Record,
 };
 use datafusion::{
-    arrow::{datatypes::Schema, record_batch::RecordBatch},
+    arrow::{
+        datatypes::{DataType, Schema},
+        record_batch::RecordBatch,
+    },
     dataframe::DataFrame,
     datasource::file_format::file_compression_type::FileCompressionType,
     execution::{
@@ -28,14 +31,33 @@ use std::{
 };
 use toml::value::{Array, Table};
 
+#[cfg(feature = "accessor")]
+use crate::{accessor::GlobalAccessor, Uuid};
+#[cfg(feature = "accessor")]
+use accessor_store::AccessorObjectStore;
+#[cfg(feature = "accessor")]
+use url::Url;
+
+#[cfg(feature = "orm")]
+use crate::orm::GlobalPool;
+
+#[cfg(feature = "accessor")]
+mod accessor_store;
 mod arrow_array;
 mod arrow_field;
 mod arrow_schema;
+mod data_export;
 mod data_frame;
 mod scalar_provider;
 mod scalar_value;
+mod table_provider;
 
+pub use data_export::{ExportFormat, ExportOptions, ExportOutput};
 pub use data_frame::DataFrameExecutor;
+pub use table_provider::HttpTableProvider;
+
+#[cfg(feature = "orm")]
+pub use table_provider::SqlTableProvider;
 
 use arrow_array::ArrowArrayExt;
 use arrow_field::ArrowFieldExt;
@@ -44,6 +66,45 @@ use scalar_provider::ScalarValueProvider;
 use scalar_value::ScalarValueExt;
 
 /// A connector for Apache Arrow.
+///
+/// Besides the `avro`, `csv`, `ndjson` and `parquet` files, a table can be
+/// backed by a database table in the connection pool with the `sql` type,
+/// or by the JSON endpoint of an HTTP service with the `http` or `graphql` type,
+/// so that a query can join the data across different sources.
+/// The files can also be read lazily from the storage service of an accessor
+/// with range requests, where the path can be a glob or a partitioned prefix.
+///
+/// # Examples
+///
+/// ```toml
+/// [[connector]]
+/// type = "arrow"
+/// name = "analytics"
+///
+/// [[connector.tables]]
+/// type = "sql"
+/// name = "user"
+/// pool = "main"
+/// table = "zino_user"
+///
+/// [[connector.tables]]
+/// type = "parquet"
+/// name = "order"
+/// path = "orders.parquet"
+///
+/// [[connector.tables]]
+/// type = "parquet"
+/// name = "event"
+/// accessor = "s3-bucket"
+/// path = "events/"
+/// partition-by = ["year", "month"]
+///
+/// [[connector.tables]]
+/// type = "http"
+/// name = "product"
+/// base-url = "https://api.example.com/products"
+/// json-pointer = "/data"
+/// ```
 pub struct ArrowConnector {
     /// Session context.
     context: OnceLock<SessionContext>,
@@ -121,6 +182,39 @@ impl ArrowConnector {
                 let table_name = table
                     .get_str("name")
                     .ok_or_else(|| warn!("the `name` field should be a str"))?;
+                let table_schema = if let Some(schema) = table.get_table("schema") {
+                    Some(Schema::try_from_toml_table(schema)?)
+                } else {
+                    None
+                };
+                match data_type {
+                    "http" | "graphql" => {
+                        let connector = HttpConnector::try_from_config(table)?;
+                        let provider = HttpTableProvider::try_new(connector, table_schema).await?;
+                        ctx.register_table(table_name, Arc::new(provider))?;
+                        continue;
+                    }
+                    #[cfg(feature = "orm")]
+                    "sql" => {
+                        let pool = if let Some(pool_name) = table.get_str("pool") {
+                            GlobalPool::get(pool_name)
+                        } else {
+                            GlobalPool::iter().next()
+                        };
+                        let pool = pool.ok_or_else(|| {
+                            warn!(
+                                "the connection pool for the table `{}` is absent",
+                                table_name
+                            )
+                        })?;
+                        let db_table_name = table.get_str("table").unwrap_or(table_name);
+                        let provider =
+                            SqlTableProvider::try_new(pool, db_table_name, table_schema).await?;
+                        ctx.register_table(table_name, Arc::new(provider))?;
+                        continue;
+                    }
+                    _ => (),
+                }
                 let table_path = if let Some(url) = table.get_str("url") {
                     let table_file_path = root.join(format!("{table_name}.{data_type}"));
                     let mut table_file = File::create(&table_file_path)?;
@@ -129,20 +223,25 @@ impl ArrowConnector {
                         table_file.write_all(&chunk)?;
                     }
                     table_file_path.to_string_lossy().into_owned()
+                } else if let Some(accessor) = table.get_str("accessor") {
+                    let path = table.get_str("path").unwrap_or_default();
+                    register_accessor_store(ctx.runtime_env(), accessor, path)?
                 } else {
                     table
                         .get_str("path")
                         .map(|path| root.join(path).to_string_lossy().into_owned())
                         .ok_or_else(|| warn!("the path for the table `{}` is absent", table_name))?
                 };
-                let table_schema = if let Some(schema) = table.get_table("schema") {
-                    Some(Schema::try_from_toml_table(schema)?)
-                } else {
-                    None
-                };
+                let partition_cols = table
+                    .get_str_array("partition-by")
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|col| (col.to_owned(), DataType::Utf8))
+                    .collect::<Vec<_>>();
                 match data_type {
                     "avro" => {
                         let mut options = AvroReadOptions::default();
+                        options.table_partition_cols = partition_cols;
                         if table_schema.is_some() {
                             options.schema = table_schema.as_ref();
                         }
@@ -150,6 +249,7 @@ impl ArrowConnector {
                     }
                     "csv" => {
                         let mut options = CsvReadOptions::default();
+                        options.table_partition_cols = partition_cols;
                         if table_schema.is_some() {
                             options.schema = table_schema.as_ref();
                         }
@@ -168,6 +268,7 @@ impl ArrowConnector {
                     }
                     "ndjson" => {
                         let mut options = NdJsonReadOptions::default().file_extension(".ndjson");
+                        options.table_partition_cols = partition_cols;
                         if table_schema.is_some() {
                             options.schema = table_schema.as_ref();
                         }
@@ -189,6 +290,7 @@ impl ArrowConnector {
                     }
                     "parquet" => {
                         let mut options = ParquetReadOptions::default();
+                        options.table_partition_cols = partition_cols;
                         if let Some(parquet_pruning) = table.get_bool("parquet-pruning") {
                             options.parquet_pruning = Some(parquet_pruning);
                         }
@@ -223,6 +325,100 @@ impl ArrowConnector {
         let batch = RecordBatch::try_new(Arc::new(schema), columns)?;
         ctx.read_batch(batch).map_err(Error::from)
     }
+
+    /// Executes the query and exports the results with the options.
+    ///
+    /// The path is relative to the root dir for the local outputs,
+    /// or to the root of the storage if an accessor is specified.
+    /// In the latter case, the files are written to a temporary local dir
+    /// and then uploaded through the accessor.
+    pub async fn export(
+        &self,
+        query: &str,
+        params: Option<&Map>,
+        path: &str,
+        options: &ExportOptions,
+    ) -> Result<ExportOutput, Error> {
+        let ctx = self.try_get_session_context().await?;
+        let sql = helper::format_query(query, params);
+        let df = ctx.sql(&sql).await?;
+        let path = path.trim_start_matches('/');
+        let format = options.format();
+        if let Some(accessor) = options.accessor_name() {
+            #[cfg(feature = "accessor")]
+            {
+                let operator = GlobalAccessor::get(accessor)
+                    .ok_or_else(|| warn!("the accessor `{}` does not exist", accessor))?;
+                let local_dir = self.root.join(".export").join(Uuid::now_v7().to_string());
+                let result = async {
+                    let num_rows = df.write_to(&local_dir.join(path), options).await?;
+                    let local_files = data_export::collect_files(&local_dir).await?;
+
+                    let mut files = Vec::with_capacity(local_files.len());
+                    for local_file in local_files {
+                        let location = local_file
+                            .strip_prefix(&local_dir)?
+                            .to_string_lossy()
+                            .replace('\\', "/");
+                        data_export::upload_file(operator, &local_file, &location).await?;
+                        files.push(location);
+                    }
+
+                    let mut output = ExportOutput::new(format, path.to_owned(), num_rows);
+                    output.set_accessor(Some(accessor.to_owned()));
+                    output.set_files(files);
+                    Ok::<_, Error>(output)
+                }
+                .await;
+                if let Err(err) = tokio::fs::remove_dir_all(&local_dir).await {
+                    let local_dir = local_dir.to_string_lossy();
+                    tracing::warn!("fail to remove the temporary dir `{local_dir}`: {err}");
+                }
+
+                let mut output = result?;
+                if let Some(table_name) = options.table_name() {
+                    // A trailing slash is required for the object store to list a directory.
+                    let table_path = if options.is_single_file() {
+                        path.to_owned()
+                    } else {
+                        format!("{}/", path.trim_end_matches('/'))
+                    };
+                    let table_path =
+                        register_accessor_store(ctx.runtime_env(), accessor, &table_path)?;
+                    register_output(ctx, table_name, &table_path, options).await?;
+                    output.set_table_name(Some(table_name.to_owned()));
+                }
+                return Ok(output);
+            }
+            #[cfg(not(feature = "accessor"))]
+            bail!(
+                "the `accessor` feature should be enabled to export through `{}`",
+                accessor
+            );
+        }
+
+        let local_path = self.root.join(path);
+        let num_rows = df.write_to(&local_path, options).await?;
+        let local_files = data_export::collect_files(&local_path).await?;
+
+        let mut files = Vec::with_capacity(local_files.len());
+        for local_file in local_files {
+            let file = local_file
+                .strip_prefix(&self.root)?
+                .to_string_lossy()
+                .replace('\\', "/");
+            files.push(file);
+        }
+
+        let mut output = ExportOutput::new(format, path.to_owned(), num_rows);
+        output.set_files(files);
+        if let Some(table_name) = options.table_name() {
+            let table_path = local_path.to_string_lossy();
+            register_output(ctx, table_name, &table_path, options).await?;
+            output.set_table_name(Some(table_name.to_owned()));
+        }
+        Ok(output)
+    }
 }
 
 impl Default for ArrowConnector {
@@ -285,6 +481,100 @@ impl Connector for ArrowConnector {
     }
 }
 
+/// Registers the object store for the accessor and returns the table path in the store.
+#[cfg(feature = "accessor")]
+fn register_accessor_store(
+    runtime_env: Arc<RuntimeEnv>,
+    accessor: &str,
+    path: &str,
+) -> Result<String, Error> {
+    let operator = GlobalAccessor::get(accessor)
+        .ok_or_else(|| warn!("the accessor `{}` does not exist", accessor))?;
+    let store = AccessorObjectStore::new(accessor, operator.clone());
+    let url = Url::parse(&store.url())?;
+    runtime_env.register_object_store(&url, Arc::new(store));
+    Ok(format!("{url}{}", path.trim_start_matches('/')))
+}
+
+/// Registers the object store for the accessor and returns the table path in the store.
+#[cfg(not(feature = "accessor"))]
+fn register_accessor_store(
+    _runtime_env: Arc<RuntimeEnv>,
+    accessor: &str,
+    _path: &str,
+) -> Result<String, Error> {
+    bail!(
+        "the `accessor` feature should be enabled to read tables through `{}`",
+        accessor
+    );
+}
+
+/// Registers the output of an export as a table.
+async fn register_output(
+    ctx: &SessionContext,
+    table_name: &str,
+    table_path: &str,
+    options: &ExportOptions,
+) -> Result<(), Error> {
+    let partition_cols = options
+        .partition_columns()
+        .iter()
+        .map(|col| (col.to_owned(), DataType::Utf8))
+        .collect::<Vec<_>>();
+    let file_compression_type = options
+        .compression_codec()
+        .map(parse_file_compression_type)
+        .unwrap_or(FileCompressionType::UNCOMPRESSED);
+    match options.format() {
+        ExportFormat::Avro => {
+            let options = AvroReadOptions {
+                file_extension: "",
+                ..AvroReadOptions::default()
+            };
+            ctx.register_avro(table_name, table_path, options).await?;
+        }
+        ExportFormat::Csv => {
+            let options = CsvReadOptions {
+                file_extension: "",
+                table_partition_cols: partition_cols,
+                file_compression_type,
+                ..CsvReadOptions::default()
+            };
+            ctx.register_csv(table_name, table_path, options).await?;
+        }
+        ExportFormat::NdJson => {
+            let options = NdJsonReadOptions {
+                file_extension: "",
+                table_partition_cols: partition_cols,
+                file_compression_type,
+                ..NdJsonReadOptions::default()
+            };
+            ctx.register_json(table_name, table_path, options).await?;
+        }
+        ExportFormat::Parquet => {
+            let options = ParquetReadOptions {
+                file_extension: "",
+                table_partition_cols: partition_cols,
+                ..ParquetReadOptions::default()
+            };
+            ctx.register_parquet(table_name, table_path, options)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses the file compression type.
+fn parse_file_compression_type(compression_type: &str) -> FileCompressionType {
+    match compression_type {
+        "bzip2" => FileCompressionType::BZIP2,
+        "gzip" => FileCompressionType::GZIP,
+        "xz" => FileCompressionType::XZ,
+        "zstd" => FileCompressionType::ZSTD,
+        _ => FileCompressionType::UNCOMPRESSED,
+    }
+}
+
 /// Shared session state for DataFusion.
 static SHARED_SESSION_STATE: LazyLock<SessionState> = LazyLock::new(|| {
     let config = SessionConfig::new();
@@ -0,0 +1,705 @@
+use super::ArrowSchemaExt;
+use crate::{
+    connector::{Connector, HttpConnector},
+    error::Error,
+    Record,
+};
+use async_trait::async_trait;
+use datafusion::{
+    arrow::{
+        datatypes::{Schema, SchemaRef},
+        record_batch::{RecordBatch, RecordBatchOptions},
+    },
+    datasource::{TableProvider, TableType},
+    error::{DataFusionError, Result as DataFusionResult},
+    execution::context::SessionState,
+    logical_expr::{Expr, TableProviderFilterPushDown},
+    physical_plan::{memory::MemoryExec, ExecutionPlan},
+};
+use std::{any::Any, sync::Arc};
+
+#[cfg(feature = "orm")]
+use crate::{
+    model::DecodeRow,
+    orm::{ConnectionPool, Executor},
+};
+#[cfg(feature = "orm")]
+use datafusion::{
+    arrow::datatypes::DataType,
+    execution::{SendableRecordBatchStream, TaskContext},
+    logical_expr::{
+        expr::{Between, BinaryExpr, InList, Like},
+        Operator,
+    },
+    physical_plan::{
+        stream::RecordBatchReceiverStream,
+        streaming::{PartitionStream, StreamingTableExec},
+    },
+    scalar::ScalarValue,
+};
+#[cfg(feature = "orm")]
+use futures::TryStreamExt;
+
+/// Maximum number of rows in a record batch.
+const BATCH_SIZE: usize = 8192;
+
+/// A table provider backed by a table in the database connection pool.
+///
+/// The projection, the filters and the limit are pushed down into the SQL query
+/// when they can be expressed in the SQL dialect of the database. The rows are
+/// streamed from the database and converted into the record batches on the fly.
+#[cfg(feature = "orm")]
+pub struct SqlTableProvider {
+    /// Connection pool.
+    pool: &'static ConnectionPool,
+    /// Table name in the database.
+    table_name: String,
+    /// Table schema.
+    schema: SchemaRef,
+}
+
+#[cfg(feature = "orm")]
+impl SqlTableProvider {
+    /// Attempts to create a new instance for the table.
+    /// If the schema is absent, it will be inferred from the table rows.
+    pub async fn try_new(
+        pool: &'static ConnectionPool,
+        table_name: &str,
+        schema: Option<Schema>,
+    ) -> Result<Self, Error> {
+        let mut provider = Self {
+            pool,
+            table_name: table_name.to_owned(),
+            schema: Arc::new(Schema::empty()),
+        };
+        let schema = if let Some(schema) = schema {
+            schema
+        } else {
+            let sql = format!(
+                "SELECT * FROM {} LIMIT 100;",
+                quote_identifier(&provider.table_name)
+            );
+            let records = provider.fetch_records(&sql).await?;
+            Schema::try_from_avro_records(&records)?
+        };
+        provider.schema = Arc::new(schema);
+        Ok(provider)
+    }
+
+    /// Fetches the records with the SQL query.
+    async fn fetch_records(&self, sql: &str) -> Result<Vec<Record>, Error> {
+        let rows = self.pool.pool().fetch(sql).await?;
+        let mut records = Vec::with_capacity(rows.len());
+        for row in rows {
+            records.push(Record::decode_row(&row)?);
+        }
+        Ok(records)
+    }
+
+    /// Formats the SQL query for a scan.
+    fn format_query(
+        &self,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> String {
+        let fields = self.schema.fields();
+        let columns = match projection {
+            Some(indices) if !indices.is_empty() => indices
+                .iter()
+                .map(|&index| quote_identifier(fields[index].name()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            _ => "*".to_owned(),
+        };
+        let mut sql = format!(
+            "SELECT {columns} FROM {}",
+            quote_identifier(&self.table_name)
+        );
+        let conditions = filters.iter().filter_map(format_filter).collect::<Vec<_>>();
+        if !conditions.is_empty() {
+            sql += " WHERE ";
+            sql += &conditions.join(" AND ");
+        }
+        if let Some(limit) = limit {
+            sql += &format!(" LIMIT {limit}");
+        }
+        sql += ";";
+        sql
+    }
+}
+
+#[cfg(feature = "orm")]
+#[async_trait]
+impl TableProvider for SqlTableProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+        let driver_name = crate::orm::DRIVER_NAME;
+        let pushdowns = filters
+            .iter()
+            .map(|filter| filter_pushdown(filter, &self.schema, driver_name))
+            .collect();
+        Ok(pushdowns)
+    }
+
+    async fn scan(
+        &self,
+        _state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let sql = self.format_query(projection, filters, limit);
+        let projected_schema = match projection {
+            Some(indices) => Arc::new(self.schema.project(indices)?),
+            None => self.schema.clone(),
+        };
+        let partition = SqlPartitionStream {
+            pool: self.pool,
+            sql,
+            schema: projected_schema.clone(),
+        };
+        let exec = StreamingTableExec::try_new(
+            projected_schema,
+            vec![Arc::new(partition)],
+            None,
+            Vec::new(),
+            false,
+        )?;
+        Ok(Arc::new(exec))
+    }
+}
+
+/// A partition which streams the rows of a SQL query as record batches.
+#[cfg(feature = "orm")]
+struct SqlPartitionStream {
+    /// Connection pool.
+    pool: &'static ConnectionPool,
+    /// SQL query.
+    sql: String,
+    /// Projected schema.
+    schema: SchemaRef,
+}
+
+#[cfg(feature = "orm")]
+impl PartitionStream for SqlPartitionStream {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let pool = self.pool;
+        let sql = self.sql.clone();
+        let schema = self.schema.clone();
+        let mut builder = RecordBatchReceiverStream::builder(schema.clone(), 2);
+        let tx = builder.tx();
+        builder.spawn(async move {
+            let mut rows = sqlx::query(&sql).fetch(pool.pool());
+            let mut records = Vec::with_capacity(BATCH_SIZE);
+            while let Some(row) = rows
+                .try_next()
+                .await
+                .map_err(|err| DataFusionError::Execution(err.to_string()))?
+            {
+                let record = Record::decode_row(&row)
+                    .map_err(|err| DataFusionError::Execution(err.to_string()))?;
+                records.push(record);
+                if records.len() == BATCH_SIZE {
+                    let batch = create_record_batch(&schema, &records)?;
+                    records.clear();
+                    if tx.send(Ok(batch)).await.is_err() {
+                        // The stream has been dropped.
+                        return Ok(());
+                    }
+                }
+            }
+            if !records.is_empty() {
+                let batch = create_record_batch(&schema, &records)?;
+                tx.send(Ok(batch)).await.ok();
+            }
+            Ok(())
+        });
+        builder.build()
+    }
+}
+
+/// A table provider backed by the JSON endpoint of an HTTP service.
+///
+/// The records are fetched on each scan, and the filters are evaluated by DataFusion.
+pub struct HttpTableProvider {
+    /// HTTP connector.
+    connector: HttpConnector,
+    /// Table schema.
+    schema: SchemaRef,
+}
+
+impl HttpTableProvider {
+    /// Attempts to create a new instance with the HTTP connector.
+    /// If the schema is absent, it will be inferred from the response data.
+    pub async fn try_new(connector: HttpConnector, schema: Option<Schema>) -> Result<Self, Error> {
+        let schema = if let Some(schema) = schema {
+            schema
+        } else {
+            let records = connector.query("", None).await?;
+            Schema::try_from_avro_records(&records)?
+        };
+        Ok(Self {
+            connector,
+            schema: Arc::new(schema),
+        })
+    }
+}
+
+#[async_trait]
+impl TableProvider for HttpTableProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let mut records = self
+            .connector
+            .query("", None)
+            .await
+            .map_err(|err| DataFusionError::Execution(err.to_string()))?;
+        if let Some(limit) = limit {
+            records.truncate(limit);
+        }
+        create_memory_exec(&self.schema, projection, &records)
+    }
+}
+
+/// Creates an execution plan for the records with the projection.
+/// The records are split into the batches of at most `BATCH_SIZE` rows.
+fn create_memory_exec(
+    schema: &SchemaRef,
+    projection: Option<&Vec<usize>>,
+    records: &[Record],
+) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+    let projected_schema = match projection {
+        Some(indices) => Arc::new(schema.project(indices)?),
+        None => schema.clone(),
+    };
+    let batches = records
+        .chunks(BATCH_SIZE)
+        .map(|records| create_record_batch(&projected_schema, records))
+        .collect::<DataFusionResult<Vec<_>>>()?;
+    let exec = MemoryExec::try_new(&[batches], projected_schema, None)?;
+    Ok(Arc::new(exec))
+}
+
+/// Creates a record batch for the records with the schema.
+fn create_record_batch(schema: &SchemaRef, records: &[Record]) -> DataFusionResult<RecordBatch> {
+    let columns = schema.collect_columns_from_avro_records(records);
+    let options = RecordBatchOptions::new().with_row_count(Some(records.len()));
+    let batch = RecordBatch::try_new_with_options(schema.clone(), columns, &options)?;
+    Ok(batch)
+}
+
+/// Returns `true` if the SQL dialect of the database is MySQL.
+#[cfg(feature = "orm")]
+#[inline]
+fn is_mysql_dialect() -> bool {
+    matches!(crate::orm::DRIVER_NAME, "mariadb" | "mysql" | "tidb")
+}
+
+/// Kinds of the string comparisons whose results depend on the collation.
+#[cfg(feature = "orm")]
+#[derive(Debug, Clone, Copy)]
+enum StringComparison {
+    /// `=`, `<>` and `IN`.
+    Equality,
+    /// `<`, `<=`, `>`, `>=` and `BETWEEN`.
+    Ordering,
+    /// `LIKE`.
+    Like,
+}
+
+/// Returns how the filter can be pushed down into the SQL dialect of the driver.
+///
+/// DataFusion compares the strings byte by byte, while the results of the databases
+/// depend on the collation. The filter is `Inexact` if the database returns a superset
+/// of the rows, such as `=` and `LIKE` under the case-insensitive collation of MySQL
+/// or `LIKE` in SQLite, so that it will be evaluated again by DataFusion. It is
+/// not pushed down if some rows may be missed, such as the negated comparisons above
+/// and the ordering comparisons of strings in MySQL and PostgreSQL.
+#[cfg(feature = "orm")]
+fn filter_pushdown(expr: &Expr, schema: &Schema, driver_name: &str) -> TableProviderFilterPushDown {
+    if format_filter(expr).is_some() {
+        collation_pushdown(expr, schema, driver_name, true)
+    } else {
+        TableProviderFilterPushDown::Unsupported
+    }
+}
+
+/// Returns the pushdown of the filter under the collation of the database.
+/// The filter is in a negated context if `positive` is `false`.
+#[cfg(feature = "orm")]
+fn collation_pushdown(
+    expr: &Expr,
+    schema: &Schema,
+    driver_name: &str,
+    positive: bool,
+) -> TableProviderFilterPushDown {
+    let is_string = |expr: &Expr| match expr {
+        Expr::Literal(value) => matches!(value, ScalarValue::Utf8(_) | ScalarValue::LargeUtf8(_)),
+        Expr::Column(column) => schema
+            .field_with_name(&column.name)
+            .is_ok_and(|field| matches!(field.data_type(), DataType::Utf8 | DataType::LargeUtf8)),
+        _ => false,
+    };
+    match expr {
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => match op {
+            Operator::And | Operator::Or => {
+                let left = collation_pushdown(left, schema, driver_name, positive);
+                let right = collation_pushdown(right, schema, driver_name, positive);
+                match (left, right) {
+                    (TableProviderFilterPushDown::Exact, TableProviderFilterPushDown::Exact) => {
+                        TableProviderFilterPushDown::Exact
+                    }
+                    (TableProviderFilterPushDown::Unsupported, _)
+                    | (_, TableProviderFilterPushDown::Unsupported) => {
+                        TableProviderFilterPushDown::Unsupported
+                    }
+                    _ => TableProviderFilterPushDown::Inexact,
+                }
+            }
+            _ if !is_string(left) && !is_string(right) => TableProviderFilterPushDown::Exact,
+            Operator::Eq => string_pushdown(StringComparison::Equality, driver_name, positive),
+            Operator::NotEq => string_pushdown(StringComparison::Equality, driver_name, !positive),
+            _ => string_pushdown(StringComparison::Ordering, driver_name, positive),
+        },
+        Expr::Not(expr) => collation_pushdown(expr, schema, driver_name, !positive),
+        Expr::InList(InList {
+            expr,
+            list,
+            negated,
+        }) if is_string(expr) || list.iter().any(is_string) => string_pushdown(
+            StringComparison::Equality,
+            driver_name,
+            positive != *negated,
+        ),
+        Expr::Between(Between {
+            expr, low, high, ..
+        }) if is_string(expr) || is_string(low) || is_string(high) => {
+            string_pushdown(StringComparison::Ordering, driver_name, positive)
+        }
+        Expr::Like(Like {
+            negated, pattern, ..
+        }) => {
+            let has_backslash = matches!(
+                pattern.as_ref(),
+                Expr::Literal(ScalarValue::Utf8(Some(s)) | ScalarValue::LargeUtf8(Some(s)))
+                    if s.contains('\\')
+            );
+            if has_backslash {
+                // The backslash is the default escape character of `LIKE` in MySQL and PostgreSQL.
+                TableProviderFilterPushDown::Unsupported
+            } else {
+                string_pushdown(StringComparison::Like, driver_name, positive != *negated)
+            }
+        }
+        _ => TableProviderFilterPushDown::Exact,
+    }
+}
+
+/// Returns the pushdown of a string comparison in the SQL dialect of the driver.
+#[cfg(feature = "orm")]
+fn string_pushdown(
+    comparison: StringComparison,
+    driver_name: &str,
+    positive: bool,
+) -> TableProviderFilterPushDown {
+    let superset = if positive {
+        TableProviderFilterPushDown::Inexact
+    } else {
+        TableProviderFilterPushDown::Unsupported
+    };
+    match (driver_name, comparison) {
+        (_, StringComparison::Ordering) if driver_name != "sqlite" => {
+            TableProviderFilterPushDown::Unsupported
+        }
+        ("mariadb" | "mysql" | "tidb", _) => superset,
+        ("sqlite", StringComparison::Like) => superset,
+        _ => TableProviderFilterPushDown::Exact,
+    }
+}
+
+/// Quotes an identifier in the SQL dialect of the database.
+#[cfg(feature = "orm")]
+fn quote_identifier(name: &str) -> String {
+    let quote = if is_mysql_dialect() { '`' } else { '"' };
+    name.split('.')
+        .map(|s| format!("{quote}{}{quote}", s.replace(quote, "")))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Formats the filter as a SQL condition, or returns `None` if it is unsupported.
+#[cfg(feature = "orm")]
+fn format_filter(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Column(column) => Some(quote_identifier(&column.name)),
+        Expr::Literal(value) => format_literal(value),
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+            let op = match op {
+                Operator::Eq => "=",
+                Operator::NotEq => "<>",
+                Operator::Lt => "<",
+                Operator::LtEq => "<=",
+                Operator::Gt => ">",
+                Operator::GtEq => ">=",
+                Operator::And => "AND",
+                Operator::Or => "OR",
+                _ => return None,
+            };
+            let left = format_filter(left)?;
+            let right = format_filter(right)?;
+            Some(format!("({left} {op} {right})"))
+        }
+        Expr::Not(expr) => Some(format!("(NOT {})", format_filter(expr)?)),
+        Expr::IsNull(expr) => Some(format!("({} IS NULL)", format_filter(expr)?)),
+        Expr::IsNotNull(expr) => Some(format!("({} IS NOT NULL)", format_filter(expr)?)),
+        Expr::InList(InList {
+            expr,
+            list,
+            negated,
+        }) => {
+            let expr = format_filter(expr)?;
+            let list = list
+                .iter()
+                .map(format_filter)
+                .collect::<Option<Vec<_>>>()?
+                .join(", ");
+            let op = if *negated { "NOT IN" } else { "IN" };
+            Some(format!("({expr} {op} ({list}))"))
+        }
+        Expr::Between(Between {
+            expr,
+            negated,
+            low,
+            high,
+        }) => {
+            let expr = format_filter(expr)?;
+            let low = format_filter(low)?;
+            let high = format_filter(high)?;
+            let op = if *negated { "NOT BETWEEN" } else { "BETWEEN" };
+            Some(format!("({expr} {op} {low} AND {high})"))
+        }
+        Expr::Like(Like {
+            negated,
+            expr,
+            pattern,
+            escape_char: None,
+            case_insensitive: false,
+        }) => {
+            let expr = format_filter(expr)?;
+            let pattern = format_filter(pattern)?;
+            let op = if *negated { "NOT LIKE" } else { "LIKE" };
+            Some(format!("({expr} {op} {pattern})"))
+        }
+        _ => None,
+    }
+}
+
+/// Formats the literal value, or returns `None` if it is unsupported.
+/// The non-finite floats are not pushed down since they have no SQL literals.
+#[cfg(feature = "orm")]
+fn format_literal(value: &ScalarValue) -> Option<String> {
+    if value.is_null() {
+        return Some("NULL".to_owned());
+    }
+    match value {
+        ScalarValue::Boolean(Some(b)) => Some(if *b { "TRUE" } else { "FALSE" }.to_owned()),
+        ScalarValue::Int8(Some(_))
+        | ScalarValue::Int16(Some(_))
+        | ScalarValue::Int32(Some(_))
+        | ScalarValue::Int64(Some(_))
+        | ScalarValue::UInt8(Some(_))
+        | ScalarValue::UInt16(Some(_))
+        | ScalarValue::UInt32(Some(_))
+        | ScalarValue::UInt64(Some(_)) => Some(value.to_string()),
+        ScalarValue::Float32(Some(f)) if f.is_finite() => Some(value.to_string()),
+        ScalarValue::Float64(Some(f)) if f.is_finite() => Some(value.to_string()),
+        ScalarValue::Utf8(Some(s)) | ScalarValue::LargeUtf8(Some(s)) => {
+            Some(quote_string(s, is_mysql_dialect()))
+        }
+        _ => None,
+    }
+}
+
+/// Quotes a string literal. The backslashes are also escaped for MySQL,
+/// since they are treated as escape characters in the string literals.
+#[cfg(feature = "orm")]
+fn quote_string(s: &str, escape_backslash: bool) -> String {
+    let s = if escape_backslash {
+        s.replace('\\', "\\\\")
+    } else {
+        s.to_owned()
+    };
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+#[cfg(all(test, feature = "orm"))]
+mod tests {
+    use super::{
+        create_memory_exec, filter_pushdown, format_filter, format_literal, quote_identifier,
+        quote_string, BATCH_SIZE,
+    };
+    use crate::Record;
+    use datafusion::{
+        arrow::datatypes::{DataType, Field, Schema},
+        logical_expr::{col, TableProviderFilterPushDown},
+        physical_plan::ExecutionPlanProperties,
+        prelude::lit,
+        scalar::ScalarValue,
+    };
+    use std::sync::Arc;
+
+    #[test]
+    fn it_formats_filters() {
+        let filter = col("status").eq(lit("Active")).and(col("age").gt(lit(18)));
+        let sql = format_filter(&filter).unwrap();
+        assert!(sql.contains("'Active'"));
+        assert!(sql.contains("AND"));
+        assert!(format_filter(&col("name").ilike(lit("%a%"))).is_none());
+
+        let filter = col("name").in_list(vec![lit("a"), lit("b'c")], false);
+        assert!(format_filter(&filter).unwrap().contains("'b''c'"));
+
+        let filter = !col("age").between(lit(18), lit(60));
+        let sql = format_filter(&filter).unwrap();
+        assert!(sql.contains("BETWEEN 18 AND 60"));
+        assert!(sql.starts_with("(NOT"));
+
+        let filter = col("name").like(lit("a%")).or(col("name").is_null());
+        let sql = format_filter(&filter).unwrap();
+        assert!(sql.contains("LIKE 'a%'"));
+        assert!(sql.contains("IS NULL"));
+
+        let filter = col("score").gt(lit(f64::NAN));
+        assert!(format_filter(&filter).is_none());
+        let filter = col("score")
+            .lt(lit(f32::INFINITY))
+            .and(col("id").eq(lit(1)));
+        assert!(format_filter(&filter).is_none());
+    }
+
+    #[test]
+    fn it_pushes_down_filters_by_collations() {
+        use TableProviderFilterPushDown::{Exact, Inexact, Unsupported};
+
+        let schema = Schema::new(vec![
+            Field::new("name", DataType::Utf8, true),
+            Field::new("age", DataType::Int64, true),
+        ]);
+        let pushdown = |filter, driver_name| filter_pushdown(&filter, &schema, driver_name);
+
+        let filter = col("age").gt(lit(18));
+        assert_eq!(pushdown(filter.clone(), "mysql"), Exact);
+        assert_eq!(pushdown(filter, "postgres"), Exact);
+
+        let filter = col("name").eq(lit("a")).and(col("age").gt(lit(18)));
+        assert_eq!(pushdown(filter.clone(), "mysql"), Inexact);
+        assert_eq!(pushdown(filter.clone(), "postgres"), Exact);
+        assert_eq!(pushdown(filter.clone(), "sqlite"), Exact);
+        assert_eq!(pushdown(!filter, "mysql"), Unsupported);
+
+        let filter = col("name").not_eq(lit("a"));
+        assert_eq!(pushdown(filter.clone(), "mysql"), Unsupported);
+        assert_eq!(pushdown(!filter, "mysql"), Inexact);
+
+        let filter = col("name").gt(lit("a"));
+        assert_eq!(pushdown(filter.clone(), "mysql"), Unsupported);
+        assert_eq!(pushdown(filter.clone(), "postgres"), Unsupported);
+        assert_eq!(pushdown(filter, "sqlite"), Exact);
+
+        let filter = col("name").like(lit("a%"));
+        assert_eq!(pushdown(filter.clone(), "mysql"), Inexact);
+        assert_eq!(pushdown(filter.clone(), "postgres"), Exact);
+        assert_eq!(pushdown(filter.clone(), "sqlite"), Inexact);
+        assert_eq!(pushdown(!filter, "sqlite"), Unsupported);
+        assert_eq!(
+            pushdown(col("name").like(lit("a\\%")), "postgres"),
+            Unsupported
+        );
+
+        let filter = col("name").in_list(vec![lit("a"), lit("b")], true);
+        assert_eq!(pushdown(filter, "mysql"), Unsupported);
+    }
+
+    #[test]
+    fn it_splits_records_into_batches() {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)]));
+        let records = (0..BATCH_SIZE + 1)
+            .map(|id| {
+                let mut record = Record::new();
+                record.push(("id".to_owned(), (id as i64).into()));
+                record
+            })
+            .collect::<Vec<_>>();
+        let exec = create_memory_exec(&schema, None, &records).unwrap();
+        assert_eq!(exec.output_partitioning().partition_count(), 1);
+
+        let batches = exec
+            .as_any()
+            .downcast_ref::<datafusion::physical_plan::memory::MemoryExec>()
+            .unwrap()
+            .partitions()[0]
+            .iter()
+            .map(|batch| batch.num_rows())
+            .collect::<Vec<_>>();
+        assert_eq!(batches, vec![BATCH_SIZE, 1]);
+    }
+
+    #[test]
+    fn it_formats_literals() {
+        assert_eq!(format_literal(&ScalarValue::Null).unwrap(), "NULL");
+        assert_eq!(format_literal(&ScalarValue::Int64(None)).unwrap(), "NULL");
+        assert_eq!(
+            format_literal(&ScalarValue::Boolean(Some(true))).unwrap(),
+            "TRUE"
+        );
+        assert_eq!(
+            format_literal(&ScalarValue::Float64(Some(1.5))).unwrap(),
+            "1.5"
+        );
+        assert!(format_literal(&ScalarValue::Float64(Some(f64::NAN))).is_none());
+        assert!(format_literal(&ScalarValue::Float64(Some(f64::NEG_INFINITY))).is_none());
+        assert!(format_literal(&ScalarValue::Float32(Some(f32::INFINITY))).is_none());
+        assert!(format_literal(&ScalarValue::Binary(Some(vec![1]))).is_none());
+
+        assert_eq!(quote_string("it's", false), "'it''s'");
+        assert_eq!(quote_string("a\\' OR 1=1 --", false), "'a\\'' OR 1=1 --'");
+        assert_eq!(quote_string("a\\' OR 1=1 --", true), "'a\\\\'' OR 1=1 --'");
+        assert_eq!(quote_identifier("public.user"), "\"public\".\"user\"");
+    }
+}
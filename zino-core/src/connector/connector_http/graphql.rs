@@ -0,0 +1,280 @@
+use crate::{
+    error::Error,
+    extension::{JsonObjectExt, JsonValueExt, TomlTableExt, TomlValueExt},
+    warn, JsonValue, Map,
+};
+use toml::Table;
+
+/// Options for the GraphQL requests.
+#[derive(Debug, Clone)]
+pub(super) struct GraphQLOptions {
+    /// Default GraphQL document used when the query is empty.
+    document: Option<String>,
+    /// Default variables.
+    variables: Map,
+    /// Operation name.
+    operation_name: Option<String>,
+    /// JSON Pointer for looking up the records from the `data` field.
+    data_path: Option<String>,
+    /// Variable name of the cursor for pagination.
+    cursor_variable: String,
+    /// Max number of pages to fetch.
+    max_pages: usize,
+}
+
+impl GraphQLOptions {
+    /// Creates a new instance with the config.
+    pub(super) fn with_config(config: &Table) -> Self {
+        let mut options = Self::default();
+        if let Some(document) = config.get_str("query") {
+            options.document = Some(document.to_owned());
+        }
+        if let Some(JsonValue::Object(variables)) =
+            config.get("variables").map(|v| v.to_json_value())
+        {
+            options.variables = variables;
+        }
+        if let Some(operation_name) = config.get_str("operation-name") {
+            options.operation_name = Some(operation_name.to_owned());
+        }
+        if let Some(data_path) = config.get_str("data-path") {
+            options.data_path = Some(data_path.to_owned());
+        }
+        if let Some(cursor_variable) = config.get_str("cursor-variable") {
+            options.cursor_variable = cursor_variable.to_owned();
+        }
+        if let Some(max_pages) = config.get_usize("max-pages") {
+            options.max_pages = max_pages.max(1);
+        }
+        options
+    }
+
+    /// Sets the operation name.
+    #[inline]
+    pub(super) fn set_operation_name(&mut self, operation_name: String) {
+        self.operation_name = Some(operation_name);
+    }
+
+    /// Sets the JSON Pointer for looking up the records from the `data` field.
+    #[inline]
+    pub(super) fn set_data_path(&mut self, data_path: String) {
+        self.data_path = Some(data_path);
+    }
+
+    /// Returns the max number of pages to fetch.
+    #[inline]
+    pub(super) fn max_pages(&self) -> usize {
+        self.max_pages
+    }
+
+    /// Returns the GraphQL document for the query.
+    pub(super) fn document<'a>(&'a self, query: &'a str) -> Result<&'a str, Error> {
+        if !query.trim().is_empty() {
+            Ok(query)
+        } else {
+            self.document
+                .as_deref()
+                .ok_or_else(|| warn!("the GraphQL document should be specified"))
+        }
+    }
+
+    /// Returns the variables by merging the default variables with the params.
+    pub(super) fn variables(&self, params: Option<&Map>) -> Map {
+        let mut variables = self.variables.clone();
+        if let Some(params) = params {
+            variables.extend(params.clone());
+        }
+        variables
+    }
+
+    /// Returns `true` if the document declares the cursor variable,
+    /// which means that the pages can be fetched by updating the variable.
+    /// The variable name should be matched exactly, so `$afterId` does not
+    /// declare the cursor variable `after`.
+    pub(super) fn supports_pagination(&self, document: &str) -> bool {
+        let cursor_variable = format!("${}", self.cursor_variable);
+        document.match_indices(&cursor_variable).any(|(index, s)| {
+            !document[index + s.len()..]
+                .starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+        })
+    }
+
+    /// Updates the cursor variable for the next page.
+    #[inline]
+    pub(super) fn set_cursor(&self, variables: &mut Map, cursor: String) {
+        variables.upsert(&self.cursor_variable, cursor);
+    }
+
+    /// Creates a new payload for the GraphQL request.
+    pub(super) fn new_payload(&self, document: &str, variables: &Map) -> Map {
+        let mut payload = Map::from_entry("query", document);
+        payload.upsert("variables", variables.clone());
+        if let Some(operation_name) = self.operation_name.as_deref() {
+            payload.upsert("operationName", operation_name);
+        }
+        payload
+    }
+
+    /// Parses the GraphQL response into the records and the cursor of the next page.
+    pub(super) fn parse_response(
+        &self,
+        mut response: Map,
+    ) -> Result<(Vec<Map>, Option<String>), Error> {
+        if let Some(errors) = response.get_array("errors").filter(|v| !v.is_empty()) {
+            let messages = errors
+                .iter()
+                .map(|error| {
+                    error
+                        .get("message")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_owned())
+                        .unwrap_or_else(|| error.to_string())
+                })
+                .collect::<Vec<_>>();
+            return Err(warn!("GraphQL errors: {}", messages.join("; ")));
+        }
+
+        let data = match response.remove("data") {
+            Some(JsonValue::Object(data)) => data,
+            Some(JsonValue::Null) | None => return Ok((Vec::new(), None)),
+            _ => return Err(warn!("the GraphQL `data` field should be an object")),
+        };
+        let connection = if let Some(data_path) = self.data_path.as_deref() {
+            JsonValue::Object(data)
+                .pointer(data_path)
+                .cloned()
+                .unwrap_or_default()
+        } else if data.len() == 1 {
+            data.into_iter()
+                .next()
+                .map(|(_, value)| value)
+                .unwrap_or_default()
+        } else {
+            JsonValue::Object(data)
+        };
+        Ok(parse_connection(connection))
+    }
+}
+
+impl Default for GraphQLOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            document: None,
+            variables: Map::new(),
+            operation_name: None,
+            data_path: None,
+            cursor_variable: "after".to_owned(),
+            max_pages: 100,
+        }
+    }
+}
+
+/// Flattens a value into the records and the cursor of the next page.
+///
+/// It supports the [cursor connections](https://relay.dev/graphql/connections.htm)
+/// with `edges` or `nodes`, a list of objects, or a single object.
+fn parse_connection(value: JsonValue) -> (Vec<Map>, Option<String>) {
+    match value {
+        JsonValue::Array(vec) => {
+            let records = vec.into_iter().filter_map(|v| v.into_map_opt()).collect();
+            (records, None)
+        }
+        JsonValue::Object(mut map) => {
+            let page_info = map.remove("pageInfo").and_then(|v| v.into_map_opt());
+            let has_next_page = page_info
+                .as_ref()
+                .and_then(|m| m.get_bool("hasNextPage"))
+                .unwrap_or(false);
+            let mut end_cursor = page_info
+                .as_ref()
+                .and_then(|m| m.get_str("endCursor"))
+                .map(|s| s.to_owned());
+            let records = if let Some(JsonValue::Array(edges)) = map.remove("edges") {
+                if end_cursor.is_none() {
+                    end_cursor = edges
+                        .last()
+                        .and_then(|edge| edge.get("cursor"))
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_owned());
+                }
+                edges
+                    .into_iter()
+                    .filter_map(|edge| match edge {
+                        JsonValue::Object(mut edge) => match edge.remove("node") {
+                            Some(JsonValue::Object(node)) => Some(node),
+                            _ => Some(edge),
+                        },
+                        _ => None,
+                    })
+                    .collect()
+            } else if let Some(JsonValue::Array(nodes)) = map.remove("nodes") {
+                nodes.into_iter().filter_map(|v| v.into_map_opt()).collect()
+            } else if page_info.is_some() {
+                Vec::new()
+            } else {
+                vec![map]
+            };
+            let cursor = if has_next_page { end_cursor } else { None };
+            (records, cursor)
+        }
+        _ => (Vec::new(), None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GraphQLOptions;
+    use crate::{extension::JsonObjectExt, JsonValue};
+    use serde_json::json;
+
+    #[test]
+    fn it_parses_graphql_responses() {
+        let options = GraphQLOptions::default();
+        let response = json!({
+            "data": {
+                "users": {
+                    "edges": [
+                        { "cursor": "a", "node": { "id": 1 } },
+                        { "cursor": "b", "node": { "id": 2 } },
+                    ],
+                    "pageInfo": { "hasNextPage": true, "endCursor": "b" },
+                },
+            },
+        });
+        let (records, cursor) = options
+            .parse_response(response.as_object().cloned().unwrap())
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].get_i64("id"), Some(2));
+        assert_eq!(cursor.as_deref(), Some("b"));
+
+        let response = json!({
+            "data": null,
+            "errors": [{ "message": "Unauthorized" }],
+        });
+        let result = options.parse_response(response.as_object().cloned().unwrap());
+        assert!(result.is_err());
+
+        let mut options = GraphQLOptions::default();
+        options.set_data_path("/viewer/repositories/nodes".to_owned());
+        let response = json!({
+            "data": {
+                "viewer": {
+                    "repositories": { "nodes": [{ "name": "zino" }] },
+                },
+            },
+        });
+        let (records, cursor) = options
+            .parse_response(response.as_object().cloned().unwrap())
+            .unwrap();
+        assert_eq!(records[0].get("name"), Some(&JsonValue::from("zino")));
+        assert!(cursor.is_none());
+        assert!(options.supports_pagination("query($after: String) { users }"));
+        assert!(options.supports_pagination("query($first: Int, $after: String)"));
+        assert!(options.supports_pagination("query($afterId: ID, $after: String)"));
+        assert!(!options.supports_pagination("query($afterId: ID) { users }"));
+        assert!(!options.supports_pagination("query($after_cursor: String) { users }"));
+        assert!(!options.supports_pagination("query { users }"));
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{error::Error, extension::TomlTableExt, warn, JsonValue, Map};
+use std::cmp::Ordering;
+use toml::Table;
+
+/// Options for the incremental sync with a high-water mark.
+#[derive(Debug, Clone)]
+pub(super) struct IncrementalSync {
+    /// Field of the records used as the high-water mark.
+    field: String,
+    /// Param for passing the high-water mark to the requests.
+    param: String,
+    /// Key of the high-water mark in the sync state.
+    state_key: String,
+    /// Initial value of the high-water mark.
+    initial_value: Option<JsonValue>,
+}
+
+impl IncrementalSync {
+    /// Attempts to create a new instance with the config.
+    pub(super) fn try_from_config(config: &Table) -> Result<Self, Error> {
+        let field = config
+            .get_str("field")
+            .ok_or_else(|| warn!("the `field` for the incremental sync should be specified"))?;
+        let param = config.get_str("param").unwrap_or(field);
+        let state_key = config.get_str("state-key").unwrap_or("high_water_mark");
+        let initial_value = config.get_str("initial-value").map(JsonValue::from);
+        Ok(Self {
+            field: field.to_owned(),
+            param: param.to_owned(),
+            state_key: state_key.to_owned(),
+            initial_value,
+        })
+    }
+
+    /// Returns the param for passing the high-water mark to the requests.
+    #[inline]
+    pub(super) fn param(&self) -> &str {
+        &self.param
+    }
+
+    /// Returns the params by inserting the high-water mark in the state.
+    pub(super) fn params(&self, params: Option<&Map>, state: &Map) -> Map {
+        let mut params = params.cloned().unwrap_or_default();
+        let high_water_mark = state
+            .get(&self.state_key)
+            .filter(|v| !v.is_null())
+            .or(self.initial_value.as_ref());
+        if let Some(value) = high_water_mark {
+            params.insert(self.param.clone(), value.clone());
+        }
+        params
+    }
+
+    /// Updates the high-water mark in the state with the max value of the records.
+    /// It returns `true` if the high-water mark has been advanced.
+    pub(super) fn update_state(&self, state: &mut Map, records: &[Map]) -> bool {
+        let max_value = records
+            .iter()
+            .filter_map(|record| record.get(&self.field))
+            .filter(|value| !value.is_null())
+            .max_by(|a, b| compare_values(a, b));
+        if let Some(value) = max_value {
+            let current_value = state.get(&self.state_key).filter(|v| !v.is_null());
+            if current_value.map_or(true, |v| compare_values(value, v) == Ordering::Greater) {
+                state.insert(self.state_key.clone(), value.clone());
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Compares two JSON values. The numbers are compared numerically,
+/// and the other values are compared as strings, which works well for
+/// the RFC 3339 timestamps and the sortable IDs.
+fn compare_values(a: &JsonValue, b: &JsonValue) -> Ordering {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        _ => match (a.as_str(), b.as_str()) {
+            (Some(a), Some(b)) => a.cmp(b),
+            _ => a.to_string().cmp(&b.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IncrementalSync;
+    use crate::{extension::JsonObjectExt, Map};
+    use serde_json::json;
+    use toml::Table;
+
+    #[test]
+    fn it_updates_high_water_marks() {
+        let config = r#"
+            field = "updated_at"
+            param = "updated_since"
+        "#
+        .parse::<Table>()
+        .unwrap();
+        let sync = IncrementalSync::try_from_config(&config).unwrap();
+        let mut state = Map::new();
+        assert!(sync.params(None, &state).is_empty());
+
+        let records = vec![
+            json!({ "id": 1, "updated_at": "2024-04-01T08:00:00Z" }),
+            json!({ "id": 2, "updated_at": "2024-04-02T08:00:00Z" }),
+        ]
+        .into_iter()
+        .filter_map(|v| v.as_object().cloned())
+        .collect::<Vec<_>>();
+        assert!(sync.update_state(&mut state, &records));
+        assert!(!sync.update_state(&mut state, &records[..1]));
+        assert_eq!(
+            sync.params(None, &state).get_str("updated_since"),
+            Some("2024-04-02T08:00:00Z")
+        );
+    }
+}
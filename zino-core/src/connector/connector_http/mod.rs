@@ -0,0 +1,777 @@
+use super::{Connector, DataSource, DataSourceConnector::Http};
+use crate::{
+    application::http_client,
+    bail,
+    error::Error,
+    extension::{
+        AvroRecordExt, HeaderMapExt, JsonObjectExt, JsonValueExt, TomlTableExt, TomlValueExt,
+    },
+    helper,
+    trace::TraceContext,
+    warn, JsonValue, Map, Record,
+};
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt, TryStreamExt,
+};
+use http::{
+    header::{HeaderMap, HeaderName},
+    Method,
+};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC};
+use reqwest::Response;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::value::RawValue;
+use toml::Table;
+use url::Url;
+
+mod graphql;
+mod incremental;
+mod pagination;
+mod rate_limit;
+
+use graphql::GraphQLOptions;
+use incremental::IncrementalSync;
+use pagination::{PageState, PaginationOptions};
+use rate_limit::RateLimitOptions;
+
+/// A connector to HTTP services.
+///
+/// With the `graphql` type, the query is a GraphQL document and the params
+/// are sent as the variables. The records are looked up from the `data` field
+/// by the `data-path`, and all the pages are fetched for the cursor connections
+/// if the document declares the cursor variable.
+///
+/// For the REST APIs, the records can be fetched page by page with the `page`,
+/// `offset`, `cursor` or `link` pagination, and the throttled requests are retried
+/// by respecting the `Retry-After` and `X-RateLimit-*` headers. With the `incremental`
+/// config, only the new records since the high-water mark are fetched by [`sync`].
+///
+/// [`sync`]: HttpConnector::sync
+///
+/// ```toml
+/// [[connector]]
+/// type = "rest"
+/// name = "orders"
+/// base-url = "https://api.example.com/orders"
+/// json-pointer = "/data"
+///
+/// [connector.pagination]
+/// type = "page"
+/// page-param = "page"
+/// size-param = "per_page"
+/// page-size = 100
+///
+/// [connector.rate-limit]
+/// max-retries = 3
+/// max-delay = "1m"
+///
+/// [connector.incremental]
+/// field = "updated_at"
+/// param = "updated_since"
+/// ```
+///
+/// ```toml
+/// [[connector]]
+/// type = "graphql"
+/// name = "github"
+/// base-url = "https://api.github.com/graphql"
+/// data-path = "/viewer/repositories"
+/// cursor-variable = "after"
+/// max-pages = 10
+/// ```
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use zino_core::{connector::HttpConnector, error::Error, state::State, LazyLock, Map};
+///
+/// static AMAP_GEOCODE_CONNECTOR: LazyLock<HttpConnector> = LazyLock::new(|| {
+///     let config = State::shared()
+///         .get_config("amap")
+///         .expect("the `amap` field should be a table");
+///     let base_url = "https://restapi.amap.com/v3/geocode/geo";
+///     let mut connector = HttpConnector::try_new("GET", base_url)
+///         .expect("fail to construct AMap Geocode connector");
+///     connector.set_query(&json!({
+///         "key": config.get_str("key"),
+///         "address": "${address}",
+///         "city": "${city}",
+///         "output": "JSON",
+///     }));
+///     connector
+/// });
+///
+/// async fn get_lng_lat(city: &str, address: &str) -> Result<(f32, f32), Error> {
+///     let params = json!({
+///         "city": city,
+///         "address": address,
+///     });
+///     let data: Map = AMAP_GEOCODE_CONNECTOR
+///         .fetch_json(None, params.as_object())
+///         .await?;
+///     if let Some(Ok(postions)) = data
+///         .pointer("/geocodes/0/location")
+///         .and_then(|v| v.parse_array())
+///     {
+///         Ok((postions[0], postions[1]))
+///     } else {
+///         bail!("fail to parse the location");
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpConnector {
+    /// HTTP request method (VERB).
+    method: Method,
+    /// Base URL.
+    base_url: Url,
+    /// HTTP request headers.
+    headers: Map,
+    /// Optional request body.
+    body: Option<Box<RawValue>>,
+    /// JSON Pointer for looking up a value from the response data.
+    json_pointer: Option<String>,
+    /// Options for the GraphQL requests.
+    graphql: Option<GraphQLOptions>,
+    /// Options for fetching the pages.
+    pagination: Option<PaginationOptions>,
+    /// Options for respecting the rate limits.
+    rate_limit: RateLimitOptions,
+    /// Options for the incremental sync.
+    incremental: Option<IncrementalSync>,
+}
+
+impl HttpConnector {
+    /// Constructs a new instance, returning an error if it fails.
+    pub fn try_new(method: &str, base_url: &str) -> Result<Self, Error> {
+        Ok(Self {
+            method: method.parse()?,
+            base_url: base_url.parse()?,
+            headers: Map::new(),
+            body: None,
+            json_pointer: None,
+            graphql: None,
+            pagination: None,
+            rate_limit: RateLimitOptions::default(),
+            incremental: None,
+        })
+    }
+
+    /// Constructs a new instance for the GraphQL endpoint, returning an error if it fails.
+    #[inline]
+    pub fn try_new_graphql(endpoint: &str) -> Result<Self, Error> {
+        let mut connector = Self::try_new("POST", endpoint)?;
+        connector.graphql = Some(GraphQLOptions::default());
+        Ok(connector)
+    }
+
+    /// Attempts to construct a new instance from the config.
+    #[inline]
+    pub fn try_from_config(config: &Table) -> Result<Self, Error> {
+        let is_graphql = config.get_str("type") == Some("graphql");
+        let default_method = if is_graphql { "POST" } else { "GET" };
+        let method = config.get_str("method").unwrap_or(default_method);
+        let base_url = config
+            .get_str("base-url")
+            .ok_or_else(|| warn!("the base URL should be specified"))?;
+
+        let mut connector = HttpConnector::try_new(method, base_url)?;
+        let headers = config.get("headers").map(|v| v.to_json_value());
+        if let Some(JsonValue::Object(headers)) = headers {
+            connector.headers = headers;
+        }
+        if let Some(body) = config.get_table("body") {
+            let raw_value = serde_json::value::to_raw_value(body)?;
+            connector.body = Some(raw_value);
+        }
+        if let Some(json_pointer) = config.get_str("json-pointer") {
+            connector.json_pointer = Some(json_pointer.into());
+        }
+        if is_graphql {
+            connector.graphql = Some(GraphQLOptions::with_config(config));
+        }
+        if let Some(pagination) = config.get_table("pagination") {
+            connector.pagination = Some(PaginationOptions::try_from_config(pagination)?);
+        }
+        if let Some(rate_limit) = config.get_table("rate-limit") {
+            connector.rate_limit = RateLimitOptions::with_config(rate_limit);
+        }
+        if let Some(incremental) = config.get_table("incremental") {
+            connector.incremental = Some(IncrementalSync::try_from_config(incremental)?);
+        }
+
+        Ok(connector)
+    }
+
+    /// Inserts a key/value pair into the request headers.
+    #[inline]
+    pub fn insert_header(&mut self, key: &str, value: impl Into<JsonValue>) {
+        self.headers.upsert(key, value.into());
+    }
+
+    /// Sets the request path.
+    #[inline]
+    pub fn set_path(&mut self, path: &str) {
+        self.base_url.set_path(path);
+    }
+
+    /// Sets the request query.
+    #[inline]
+    pub fn set_query<T: Serialize>(&mut self, query: &T) {
+        if let Ok(query) = serde_qs::to_string(query) {
+            self.base_url.set_query(Some(&query));
+        }
+    }
+
+    /// Sets the request body.
+    #[inline]
+    pub fn set_body<T: Serialize>(&mut self, body: &T) {
+        self.body = serde_json::value::to_raw_value(body).ok();
+    }
+
+    /// Sets a JSON Pointer for looking up a value from the response data.
+    /// It only applies when the response data is a JSON object.
+    #[inline]
+    pub fn set_json_pointer(&mut self, pointer: impl Into<String>) {
+        self.json_pointer = Some(pointer.into());
+    }
+
+    /// Sets the operation name of the GraphQL requests.
+    #[inline]
+    pub fn set_graphql_operation_name(&mut self, operation_name: impl Into<String>) {
+        self.graphql
+            .get_or_insert_with(GraphQLOptions::default)
+            .set_operation_name(operation_name.into());
+    }
+
+    /// Sets a JSON Pointer for looking up the records from the `data` field
+    /// of the GraphQL responses.
+    #[inline]
+    pub fn set_graphql_data_path(&mut self, data_path: impl Into<String>) {
+        self.graphql
+            .get_or_insert_with(GraphQLOptions::default)
+            .set_data_path(data_path.into());
+    }
+
+    /// Returns `true` if the connector is used for a GraphQL endpoint.
+    #[inline]
+    pub fn is_graphql(&self) -> bool {
+        self.graphql.is_some()
+    }
+
+    /// Makes an HTTP request with the given query and params.
+    pub async fn fetch(
+        &self,
+        query: Option<&str>,
+        params: Option<&Map>,
+    ) -> Result<Response, Error> {
+        let resource = self.format_url(query, params);
+        let options = self.request_options(params);
+        self.send_with_retry(&resource, &options, params).await
+    }
+
+    /// Fetches the records page by page with the pagination strategy.
+    /// If the pagination is not configured, there is only one page.
+    pub fn fetch_pages<'a>(
+        &'a self,
+        query: Option<&'a str>,
+        params: Option<&'a Map>,
+    ) -> BoxStream<'a, Result<Vec<Record>, Error>> {
+        self.fetch_page_maps(query, params)
+            .map_ok(|maps| maps.into_iter().map(|m| m.into_avro_record()).collect())
+            .boxed()
+    }
+
+    /// Fetches the new records since the high-water mark in the state,
+    /// and advances the high-water mark after all the pages have been fetched.
+    ///
+    /// When it is called in a scheduled job with the job data as the state,
+    /// the high-water mark is persisted by the job store.
+    pub async fn sync(
+        &self,
+        query: Option<&str>,
+        params: Option<&Map>,
+        state: &mut Map,
+    ) -> Result<Vec<Record>, Error> {
+        let incremental = self
+            .incremental
+            .as_ref()
+            .ok_or_else(|| warn!("the incremental sync should be configured"))?;
+        let params = incremental.params(params, state);
+        let param = incremental.param();
+        let placeholder = format!("${{{param}}}");
+        let mut query = query
+            .filter(|s| !s.is_empty())
+            .or(self.base_url.query())
+            .unwrap_or_default()
+            .to_owned();
+        let referenced = query.contains(&placeholder)
+            || self
+                .body
+                .as_deref()
+                .is_some_and(|body| body.get().contains(&placeholder));
+        if !referenced && params.contains_key(param) {
+            if !query.is_empty() {
+                query.push('&');
+            }
+            query.push_str(&format!("{param}={placeholder}"));
+        }
+        if let Some(value) = params.get(param) {
+            // The value is encoded since it is interpolated into the URL verbatim.
+            query = query.replace(&placeholder, &encode_query_value(value));
+        }
+
+        let records = self
+            .fetch_page_maps(Some(&query), Some(&params))
+            .try_concat()
+            .await?;
+        incremental.update_state(state, &records);
+        Ok(records.into_iter().map(|m| m.into_avro_record()).collect())
+    }
+
+    /// Sends a GraphQL request with the document and variables,
+    /// and returns the records of all the pages.
+    #[inline]
+    pub async fn fetch_graphql(
+        &self,
+        document: &str,
+        variables: Option<&Map>,
+    ) -> Result<Vec<Map>, Error> {
+        self.fetch_graphql_pages(document, variables, None).await
+    }
+
+    /// Sends a GraphQL request with the document and variables,
+    /// and returns the records of the pages no more than the limit.
+    async fn fetch_graphql_pages(
+        &self,
+        document: &str,
+        variables: Option<&Map>,
+        page_limit: Option<usize>,
+    ) -> Result<Vec<Map>, Error> {
+        let default_options = GraphQLOptions::default();
+        let options = self.graphql.as_ref().unwrap_or(&default_options);
+        let document = options.document(document)?;
+        let supports_pagination = options.supports_pagination(document);
+        let mut variables = options.variables(variables);
+        let mut records = Vec::new();
+        let max_pages = page_limit.unwrap_or_else(|| options.max_pages());
+        for page in 1..=max_pages {
+            let mut request_options = Map::from_entry("method", "POST");
+            request_options.upsert("body", options.new_payload(document, &variables));
+            request_options.upsert("data_type", "json");
+
+            let response = self
+                .send_with_retry(self.base_url.as_str(), &request_options, Some(&variables))
+                .await?;
+            let status = response.status();
+            let data = match response.json::<Map>().await {
+                Ok(data) => data,
+                Err(err) if status.is_success() => return Err(err.into()),
+                Err(_) => bail!("the GraphQL request failed with the status `{}`", status),
+            };
+            let (items, cursor) = options.parse_response(data)?;
+            records.extend(items);
+            match cursor {
+                Some(_) if page_limit == Some(page) => return Ok(records),
+                Some(cursor) if supports_pagination => options.set_cursor(&mut variables, cursor),
+                _ => return Ok(records),
+            }
+        }
+        tracing::warn!(max_pages, "the GraphQL pages are truncated");
+        Ok(records)
+    }
+
+    /// Fetches the pages of the records as JSON objects.
+    fn fetch_page_maps<'a>(
+        &'a self,
+        query: Option<&'a str>,
+        params: Option<&'a Map>,
+    ) -> BoxStream<'a, Result<Vec<Map>, Error>> {
+        let first_page = self
+            .pagination
+            .as_ref()
+            .map(|pagination| pagination.first_page())
+            .unwrap_or_default();
+        stream::try_unfold(
+            Some(first_page),
+            move |state: Option<PageState>| async move {
+                let Some(state) = state else {
+                    return Ok(None);
+                };
+                if let Some(delay) = state.delay() {
+                    tokio::time::sleep(delay).await;
+                }
+
+                let mut url = self.format_url(query, params).parse::<Url>()?;
+                if let Some(pagination) = &self.pagination {
+                    pagination.apply(&mut url, &state);
+                }
+                let options = self.request_options(params);
+                let response = self
+                    .send_with_retry(url.as_str(), &options, params)
+                    .await?
+                    .error_for_status()?;
+                let headers = response.headers().clone();
+                let data: JsonValue = if headers.has_json_content_type() {
+                    response.json().await?
+                } else {
+                    let text = response.text().await?;
+                    serde_json::from_str(&text)?
+                };
+                let records = self.parse_records(&data)?;
+                let next_state = self.pagination.as_ref().and_then(|pagination| {
+                    let mut next_state =
+                        pagination.next_page(&url, &state, &headers, &data, records.len())?;
+                    next_state.set_delay(self.rate_limit.next_delay(&headers));
+                    Some(next_state)
+                });
+                Ok::<_, Error>(Some((records, next_state)))
+            },
+        )
+        .boxed()
+    }
+
+    /// Parses the records from the response data.
+    fn parse_records(&self, data: &JsonValue) -> Result<Vec<Map>, Error> {
+        let records = match data {
+            JsonValue::Array(vec) => vec
+                .iter()
+                .filter_map(|v| v.as_object().cloned())
+                .collect::<Vec<_>>(),
+            JsonValue::Object(map) => {
+                let data = if let Some(json_pointer) = &self.json_pointer {
+                    data.pointer(json_pointer)
+                } else {
+                    map.get("data").or_else(|| map.get("result"))
+                };
+                match data {
+                    Some(JsonValue::Array(vec)) => vec
+                        .iter()
+                        .filter_map(|v| v.as_object().cloned())
+                        .collect::<Vec<_>>(),
+                    Some(value) => vec![Map::from_entry("data", value.clone())],
+                    None => vec![map.clone()],
+                }
+            }
+            _ => bail!("invalid data format"),
+        };
+        Ok(records)
+    }
+
+    /// Formats the request URL with the query and params.
+    fn format_url(&self, query: Option<&str>, params: Option<&Map>) -> String {
+        let mut url = self.base_url.clone();
+        if let Some(query) = query.filter(|s| !s.is_empty()) {
+            url.set_query(Some(query));
+        }
+        helper::format_query(url.as_str(), params).into_owned()
+    }
+
+    /// Returns the request options with the params.
+    fn request_options(&self, params: Option<&Map>) -> Map {
+        let mut options = Map::from_entry("method", self.method.as_str());
+        if let Some(body) = self.body.as_deref().map(|v| v.get()) {
+            options.upsert("body", helper::format_query(body, params));
+        }
+        options
+    }
+
+    /// Sends an HTTP request and retries it if it has been throttled.
+    async fn send_with_retry(
+        &self,
+        resource: &str,
+        options: &Map,
+        params: Option<&Map>,
+    ) -> Result<Response, Error> {
+        let mut retries = 0;
+        loop {
+            let response = self.send(resource, options, params).await?;
+            let status = response.status();
+            match self
+                .rate_limit
+                .retry_delay(status, response.headers(), retries)
+            {
+                Some(delay) => {
+                    tracing::warn!(
+                        status = status.as_u16(),
+                        delay_secs = delay.as_secs(),
+                        "the request to `{resource}` is throttled"
+                    );
+                    tokio::time::sleep(delay).await;
+                    retries += 1;
+                }
+                None => return Ok(response),
+            }
+        }
+    }
+
+    /// Sends an HTTP request with the options and the params for the headers.
+    async fn send(
+        &self,
+        resource: &str,
+        options: &Map,
+        params: Option<&Map>,
+    ) -> Result<Response, Error> {
+        let mut headers = HeaderMap::new();
+        for (key, value) in self.headers.iter() {
+            if let Ok(header_name) = HeaderName::try_from(key) {
+                let header_value = value
+                    .as_str()
+                    .and_then(|s| helper::format_query(s, params).parse().ok());
+                if let Some(header_value) = header_value {
+                    headers.insert(header_name, header_value);
+                }
+            }
+        }
+
+        let mut trace_context = TraceContext::new();
+        let span_id = trace_context.span_id();
+        trace_context
+            .trace_state_mut()
+            .push("zino", format!("{span_id:x}"));
+        http_client::request_builder(resource, Some(options))?
+            .headers(headers)
+            .header("traceparent", trace_context.traceparent())
+            .header("tracestate", trace_context.tracestate())
+            .send()
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Checks the availability of the HTTP service by sending a `HEAD` request
+    /// to the base URL. It is unavailable only if the request fails or
+    /// the response has a server error status.
+    pub async fn check_availability(&self) -> Result<(), Error> {
+        let options = Map::from_entry("method", "HEAD");
+        let response = self.send(self.base_url.as_str(), &options, None).await?;
+        let status = response.status();
+        if status.is_server_error() {
+            bail!("the HTTP service responds with the status `{}`", status);
+        }
+        Ok(())
+    }
+
+    /// Makes an HTTP request with the given query and params,
+    /// and deserializes the response body via JSON.
+    pub async fn fetch_json<T: DeserializeOwned>(
+        &self,
+        query: Option<&str>,
+        params: Option<&Map>,
+    ) -> Result<T, Error> {
+        let response = self.fetch(query, params).await?.error_for_status()?;
+        let data = if response.headers().has_json_content_type() {
+            response.json().await?
+        } else {
+            let text = response.text().await?;
+            serde_json::from_str(&text)?
+        };
+        Ok(data)
+    }
+}
+
+impl Connector for HttpConnector {
+    fn try_new_data_source(config: &Table) -> Result<DataSource, Error> {
+        let name = config.get_str("name").unwrap_or("http");
+        let catalog = config.get_str("catalog").unwrap_or(name);
+
+        let connector = HttpConnector::try_from_config(config)?;
+        let data_source = DataSource::new("http", None, name, catalog, Http(connector));
+        Ok(data_source)
+    }
+
+    async fn execute(&self, query: &str, params: Option<&Map>) -> Result<Option<u64>, Error> {
+        if self.is_graphql() {
+            let records = self.fetch_graphql(query, params).await?;
+            return Ok(Some(records.len().try_into()?));
+        }
+
+        let data: JsonValue = self.fetch_json(Some(query), params).await?;
+        let rows_affected = data.into_map_opt().and_then(|map| {
+            map.get_u64("total")
+                .or_else(|| map.get_u64("total_rows"))
+                .or_else(|| map.get_u64("rows_affected"))
+                .or_else(|| map.get_u64("num_entries"))
+                .or_else(|| map.get_u64("num_items"))
+        });
+        Ok(rows_affected)
+    }
+
+    async fn query(&self, query: &str, params: Option<&Map>) -> Result<Vec<Record>, Error> {
+        if self.is_graphql() {
+            let records = self.fetch_graphql(query, params).await?;
+            return Ok(records.into_iter().map(|m| m.into_avro_record()).collect());
+        }
+        if self.pagination.is_some() {
+            return self.fetch_pages(Some(query), params).try_concat().await;
+        }
+
+        let records = match self.fetch_json(Some(query), params).await? {
+            JsonValue::Array(vec) => vec
+                .into_iter()
+                .filter_map(|v| v.into_map_opt())
+                .map(|m| m.into_avro_record())
+                .collect::<Vec<_>>(),
+            JsonValue::Object(mut map) => {
+                let data = if let Some(json_pointer) = &self.json_pointer {
+                    map.pointer(json_pointer).cloned()
+                } else {
+                    map.remove("data").or_else(|| map.remove("result"))
+                };
+                if let Some(value) = data {
+                    if let JsonValue::Array(vec) = value {
+                        vec.into_iter()
+                            .filter_map(|v| v.into_map_opt())
+                            .map(|m| m.into_avro_record())
+                            .collect::<Vec<_>>()
+                    } else {
+                        vec![Record::from_entry("data", value)]
+                    }
+                } else {
+                    vec![map.into_avro_record()]
+                }
+            }
+            _ => bail!("invalid data format"),
+        };
+        Ok(records)
+    }
+
+    async fn query_as<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        params: Option<&Map>,
+    ) -> Result<Vec<T>, Error> {
+        if self.is_graphql() {
+            let records = self.fetch_graphql(query, params).await?;
+            return serde_json::from_value(records.into()).map_err(Error::from);
+        }
+        if self.pagination.is_some() {
+            let records = self
+                .fetch_page_maps(Some(query), params)
+                .try_concat()
+                .await?;
+            return serde_json::from_value(records.into()).map_err(Error::from);
+        }
+
+        let data = match self.fetch_json(Some(query), params).await? {
+            JsonValue::Array(vec) => vec
+                .into_iter()
+                .filter_map(|value| value.into_map_opt())
+                .collect::<Vec<_>>(),
+            JsonValue::Object(mut map) => {
+                let data = if let Some(json_pointer) = &self.json_pointer {
+                    map.pointer(json_pointer).cloned()
+                } else {
+                    map.remove("data").or_else(|| map.remove("result"))
+                };
+                if let Some(value) = data {
+                    if let JsonValue::Array(vec) = value {
+                        vec.into_iter()
+                            .filter_map(|v| v.into_map_opt())
+                            .collect::<Vec<_>>()
+                    } else {
+                        vec![Map::from_entry("data", value)]
+                    }
+                } else {
+                    vec![map]
+                }
+            }
+            _ => bail!("invalid data format"),
+        };
+        serde_json::from_value(data.into()).map_err(Error::from)
+    }
+
+    async fn query_one(&self, query: &str, params: Option<&Map>) -> Result<Option<Record>, Error> {
+        if self.is_graphql() {
+            let records = self.fetch_graphql_pages(query, params, Some(1)).await?;
+            return Ok(records.into_iter().next().map(|m| m.into_avro_record()));
+        }
+
+        let record = match self.fetch_json(Some(query), params).await? {
+            JsonValue::Object(mut map) => {
+                let data = if let Some(json_pointer) = &self.json_pointer {
+                    map.pointer(json_pointer).cloned()
+                } else {
+                    map.remove("data").or_else(|| map.remove("result"))
+                };
+                if let Some(value) = data {
+                    if let JsonValue::Object(data) = value {
+                        data.into_avro_record()
+                    } else {
+                        Record::from_entry("data", value)
+                    }
+                } else {
+                    map.into_avro_record()
+                }
+            }
+            _ => bail!("invalid data format"),
+        };
+        Ok(Some(record))
+    }
+
+    async fn query_one_as<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        params: Option<&Map>,
+    ) -> Result<Option<T>, Error> {
+        if self.is_graphql() {
+            let records = self.fetch_graphql_pages(query, params, Some(1)).await?;
+            return match records.into_iter().next() {
+                Some(data) => serde_json::from_value(data.into())
+                    .map(Some)
+                    .map_err(Error::from),
+                None => Ok(None),
+            };
+        }
+
+        if let JsonValue::Object(mut map) = self.fetch_json(Some(query), params).await? {
+            let data = if let Some(json_pointer) = &self.json_pointer {
+                map.pointer(json_pointer).cloned()
+            } else {
+                map.remove("data").or_else(|| map.remove("result"))
+            };
+            if let Some(value) = data {
+                serde_json::from_value(value).map_err(Error::from)
+            } else {
+                serde_json::from_value(map.into()).map_err(Error::from)
+            }
+        } else {
+            bail!("invalid data format");
+        }
+    }
+}
+
+/// Characters to be percent-encoded in a query value, which are all
+/// the characters except the unreserved ones in RFC 3986.
+const QUERY_VALUE_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Percent-encodes the JSON value as a query value.
+fn encode_query_value(value: &JsonValue) -> String {
+    let value = match value {
+        JsonValue::String(s) => s.to_owned(),
+        _ => value.to_string(),
+    };
+    percent_encoding::utf8_percent_encode(&value, QUERY_VALUE_ENCODE_SET).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encode_query_value;
+    use crate::JsonValue;
+
+    #[test]
+    fn it_encodes_query_values() {
+        let value = JsonValue::from("2024-01-01 08:00:00.000+08:00");
+        assert_eq!(
+            encode_query_value(&value),
+            "2024-01-01%2008%3A00%3A00.000%2B08%3A00"
+        );
+        let value = JsonValue::from("a&b=c#d");
+        assert_eq!(encode_query_value(&value), "a%26b%3Dc%23d");
+        assert_eq!(encode_query_value(&JsonValue::from(42)), "42");
+    }
+}
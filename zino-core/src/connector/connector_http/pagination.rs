@@ -0,0 +1,308 @@
+use crate::{bail, error::Error, extension::TomlTableExt, JsonValue};
+use http::header::{HeaderMap, LINK};
+use std::time::Duration;
+use toml::Table;
+use url::Url;
+
+/// Pagination strategies for the REST APIs.
+#[derive(Debug, Clone)]
+enum Strategy {
+    /// Page number based pagination.
+    Page {
+        /// Query parameter of the page number.
+        page_param: String,
+        /// Query parameter of the page size.
+        size_param: Option<String>,
+        /// Page size.
+        page_size: Option<usize>,
+        /// Number of the first page.
+        start_page: usize,
+    },
+    /// Offset based pagination.
+    Offset {
+        /// Query parameter of the offset.
+        offset_param: String,
+        /// Query parameter of the limit.
+        limit_param: String,
+        /// Number of records in a page.
+        limit: usize,
+    },
+    /// Cursor based pagination.
+    Cursor {
+        /// Query parameter of the cursor.
+        cursor_param: String,
+        /// JSON Pointer for looking up the next cursor from the response data.
+        cursor_pointer: String,
+    },
+    /// Pagination with the `rel="next"` URL in the `Link` header.
+    /// The URL should have the same origin as the request URL.
+    Link,
+}
+
+/// Options for fetching the pages of a REST API.
+#[derive(Debug, Clone)]
+pub(super) struct PaginationOptions {
+    /// Pagination strategy.
+    strategy: Strategy,
+    /// Max number of pages to fetch.
+    max_pages: usize,
+}
+
+impl PaginationOptions {
+    /// Attempts to create a new instance with the config.
+    ///
+    /// The `type` field can be `page`, `offset`, `cursor` or `link`.
+    pub(super) fn try_from_config(config: &Table) -> Result<Self, Error> {
+        let strategy = match config.get_str("type").unwrap_or("page") {
+            "page" => Strategy::Page {
+                page_param: config.get_str("page-param").unwrap_or("page").to_owned(),
+                size_param: config.get_str("size-param").map(|s| s.to_owned()),
+                page_size: config.get_usize("page-size"),
+                start_page: config.get_usize("start-page").unwrap_or(1),
+            },
+            "offset" => Strategy::Offset {
+                offset_param: config
+                    .get_str("offset-param")
+                    .unwrap_or("offset")
+                    .to_owned(),
+                limit_param: config.get_str("limit-param").unwrap_or("limit").to_owned(),
+                limit: config.get_usize("limit").unwrap_or(100).max(1),
+            },
+            "cursor" => Strategy::Cursor {
+                cursor_param: config
+                    .get_str("cursor-param")
+                    .unwrap_or("cursor")
+                    .to_owned(),
+                cursor_pointer: config
+                    .get_str("cursor-pointer")
+                    .unwrap_or("/next_cursor")
+                    .to_owned(),
+            },
+            "link" => Strategy::Link,
+            strategy => bail!("pagination type `{}` is unsupported", strategy),
+        };
+        let max_pages = config.get_usize("max-pages").unwrap_or(1000).max(1);
+        Ok(Self {
+            strategy,
+            max_pages,
+        })
+    }
+
+    /// Returns the initial page state.
+    pub(super) fn first_page(&self) -> PageState {
+        let page = match self.strategy {
+            Strategy::Page { start_page, .. } => start_page,
+            _ => 0,
+        };
+        PageState {
+            page,
+            ..PageState::default()
+        }
+    }
+
+    /// Sets the query parameters of the page for the URL.
+    pub(super) fn apply(&self, url: &mut Url, state: &PageState) {
+        if let Some(next_url) = &state.next_url {
+            *url = next_url.clone();
+            return;
+        }
+        match &self.strategy {
+            Strategy::Page {
+                page_param,
+                size_param,
+                page_size,
+                ..
+            } => {
+                set_query_pair(url, page_param, &state.page.to_string());
+                if let (Some(size_param), Some(page_size)) = (size_param, page_size) {
+                    set_query_pair(url, size_param, &page_size.to_string());
+                }
+            }
+            Strategy::Offset {
+                offset_param,
+                limit_param,
+                limit,
+            } => {
+                set_query_pair(url, offset_param, &state.offset.to_string());
+                set_query_pair(url, limit_param, &limit.to_string());
+            }
+            Strategy::Cursor { cursor_param, .. } => {
+                if let Some(cursor) = &state.cursor {
+                    set_query_pair(url, cursor_param, cursor);
+                }
+            }
+            Strategy::Link => (),
+        }
+    }
+
+    /// Returns the state of the next page, or `None` if there are no more pages.
+    /// The URL is used to resolve the relative links.
+    pub(super) fn next_page(
+        &self,
+        url: &Url,
+        state: &PageState,
+        headers: &HeaderMap,
+        data: &JsonValue,
+        num_records: usize,
+    ) -> Option<PageState> {
+        if state.num_pages + 1 >= self.max_pages {
+            tracing::warn!(max_pages = self.max_pages, "the pages are truncated");
+            return None;
+        }
+
+        let mut next_state = PageState {
+            num_pages: state.num_pages + 1,
+            ..PageState::default()
+        };
+        match &self.strategy {
+            Strategy::Page { page_size, .. } => {
+                if num_records == 0 || page_size.is_some_and(|size| num_records < size) {
+                    return None;
+                }
+                next_state.page = state.page + 1;
+            }
+            Strategy::Offset { limit, .. } => {
+                if num_records < *limit {
+                    return None;
+                }
+                next_state.offset = state.offset + num_records;
+            }
+            Strategy::Cursor { cursor_pointer, .. } => {
+                let cursor = data.pointer(cursor_pointer).and_then(|v| match v {
+                    JsonValue::String(s) if !s.is_empty() => Some(s.to_owned()),
+                    JsonValue::Number(n) => Some(n.to_string()),
+                    _ => None,
+                })?;
+                if num_records == 0 || state.cursor.as_ref() == Some(&cursor) {
+                    return None;
+                }
+                next_state.cursor = Some(cursor);
+            }
+            Strategy::Link => {
+                let next_url = headers
+                    .get_all(LINK)
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .find_map(parse_next_link)?;
+                let next_url = url.join(&next_url).ok()?;
+                if next_url.origin() != url.origin() {
+                    tracing::warn!(
+                        next_url = next_url.as_str(),
+                        "the next link should have the same origin as the request URL"
+                    );
+                    return None;
+                }
+                next_state.next_url = Some(next_url);
+            }
+        }
+        Some(next_state)
+    }
+}
+
+/// State of a page.
+#[derive(Debug, Clone, Default)]
+pub(super) struct PageState {
+    /// Number of the pages fetched.
+    num_pages: usize,
+    /// Page number.
+    page: usize,
+    /// Offset of the records.
+    offset: usize,
+    /// Cursor of the page.
+    cursor: Option<String>,
+    /// URL of the page.
+    next_url: Option<Url>,
+    /// Delay before fetching the page.
+    delay: Option<Duration>,
+}
+
+impl PageState {
+    /// Sets the delay before fetching the page.
+    #[inline]
+    pub(super) fn set_delay(&mut self, delay: Option<Duration>) {
+        self.delay = delay;
+    }
+
+    /// Returns the delay before fetching the page.
+    #[inline]
+    pub(super) fn delay(&self) -> Option<Duration> {
+        self.delay
+    }
+}
+
+/// Sets the query pair for the URL, replacing the existing values of the key.
+fn set_query_pair(url: &mut Url, key: &str, value: &str) {
+    let pairs = url
+        .query_pairs()
+        .filter(|(k, _)| k != key)
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect::<Vec<_>>();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(key, value);
+}
+
+/// Parses the URL with `rel="next"` in the `Link` header.
+fn parse_next_link(value: &str) -> Option<String> {
+    value.split(',').find_map(|link| {
+        let (url, params) = link.trim().split_once(';')?;
+        let url = url.trim().strip_prefix('<')?.strip_suffix('>')?;
+        params
+            .split(';')
+            .filter_map(|param| param.trim().split_once('='))
+            .any(|(key, value)| {
+                key.trim() == "rel"
+                    && value
+                        .trim_matches('"')
+                        .split_whitespace()
+                        .any(|rel| rel == "next")
+            })
+            .then(|| url.to_owned())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_next_link, PaginationOptions};
+    use crate::JsonValue;
+    use http::header::{HeaderMap, HeaderValue, LINK};
+    use toml::Table;
+    use url::Url;
+
+    #[test]
+    fn it_parses_next_links() {
+        let link = r#"<https://api.github.com/repos?page=2>; rel="next", <https://api.github.com/repos?page=5>; rel="last""#;
+        assert_eq!(
+            parse_next_link(link).as_deref(),
+            Some("https://api.github.com/repos?page=2")
+        );
+
+        let link = r#"<https://api.github.com/repos?page=1>; rel="prev""#;
+        assert!(parse_next_link(link).is_none());
+    }
+
+    #[test]
+    fn it_follows_same_origin_links() {
+        let mut config = Table::new();
+        config.insert("type".to_owned(), "link".into());
+        let options = PaginationOptions::try_from_config(&config).unwrap();
+        let url = Url::parse("https://api.github.com/repos?page=1").unwrap();
+        let state = options.first_page();
+        let next_page = |link: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(LINK, HeaderValue::from_static(link));
+            options.next_page(&url, &state, &headers, &JsonValue::Null, 10)
+        };
+
+        let next_state = next_page(r#"</repos?page=2>; rel="next""#).unwrap();
+        let mut next_url = url.clone();
+        options.apply(&mut next_url, &next_state);
+        assert_eq!(next_url.as_str(), "https://api.github.com/repos?page=2");
+
+        assert!(next_page(r#"<https://api.github.com/repos?page=2>; rel="next""#).is_some());
+        assert!(next_page(r#"<https://evil.example.com/repos?page=2>; rel="next""#).is_none());
+        assert!(next_page(r#"<http://api.github.com/repos?page=2>; rel="next""#).is_none());
+        assert!(next_page(r#"<https://api.github.com:8443/repos>; rel="next""#).is_none());
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{datetime::DateTime, extension::TomlTableExt};
+use http::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::time::Duration;
+use toml::Table;
+
+/// Options for respecting the rate limits of an HTTP service.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct RateLimitOptions {
+    /// Max number of retries for the throttled requests.
+    max_retries: usize,
+    /// Max delay before a retry or the next request.
+    max_delay: Duration,
+}
+
+impl RateLimitOptions {
+    /// Creates a new instance with the config.
+    pub(super) fn with_config(config: &Table) -> Self {
+        let mut options = Self::default();
+        if let Some(max_retries) = config.get_usize("max-retries") {
+            options.max_retries = max_retries;
+        }
+        if let Some(max_delay) = config.get_duration("max-delay") {
+            options.max_delay = max_delay;
+        }
+        options
+    }
+
+    /// Returns the delay before retrying the request, or `None` if it should not be retried.
+    ///
+    /// Only the throttled requests with the status `429` or `503` are retried,
+    /// and the `Retry-After` header is respected.
+    pub(super) fn retry_delay(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        retries: usize,
+    ) -> Option<Duration> {
+        let throttled = matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+        );
+        if !throttled || retries >= self.max_retries {
+            return None;
+        }
+
+        let delay = parse_retry_after(headers)
+            .or_else(|| parse_rate_limit_reset(headers))
+            .unwrap_or_else(|| Duration::from_secs(1 << retries.min(6)));
+        Some(delay.min(self.max_delay))
+    }
+
+    /// Returns the delay before the next request if the rate limit has been exhausted
+    /// according to the `X-RateLimit-Remaining` and `X-RateLimit-Reset` headers.
+    pub(super) fn next_delay(&self, headers: &HeaderMap) -> Option<Duration> {
+        let remaining = headers
+            .get("x-ratelimit-remaining")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.trim().parse::<u64>().ok())?;
+        if remaining > 0 {
+            return None;
+        }
+        parse_rate_limit_reset(headers).map(|delay| delay.min(self.max_delay))
+    }
+}
+
+impl Default for RateLimitOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Parses the `Retry-After` header, which can be the seconds or an HTTP date.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        Some(Duration::from_secs(secs))
+    } else {
+        let retry_at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+        let secs = retry_at.timestamp() - DateTime::now().timestamp();
+        Some(Duration::from_secs(secs.try_into().unwrap_or_default()))
+    }
+}
+
+/// Parses the `X-RateLimit-Reset` header, which can be the seconds to wait
+/// or the Unix timestamp when the rate limit resets.
+fn parse_rate_limit_reset(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers
+        .get("x-ratelimit-reset")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<i64>()
+        .ok()?;
+    let now = DateTime::now().timestamp();
+    let secs = if value > 1_000_000_000 {
+        value - now
+    } else {
+        value
+    };
+    Some(Duration::from_secs(secs.try_into().unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimitOptions;
+    use http::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    #[test]
+    fn it_respects_rate_limits() {
+        let options = RateLimitOptions::default();
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("5"));
+        assert_eq!(
+            options.retry_delay(StatusCode::TOO_MANY_REQUESTS, &headers, 0),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(options.retry_delay(StatusCode::OK, &headers, 0), None);
+        assert_eq!(
+            options.retry_delay(StatusCode::TOO_MANY_REQUESTS, &headers, 3),
+            None
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("120"));
+        assert_eq!(options.next_delay(&headers), Some(Duration::from_secs(60)));
+
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("10"));
+        assert_eq!(options.next_delay(&headers), None);
+    }
+}
@@ -146,6 +146,38 @@ impl DataSource {
             None
         }
     }
+
+    /// Closes the connection pool of the data source if it has one.
+    pub async fn close(&self) {
+        match &self.connector {
+            #[cfg(feature = "connector-arrow")]
+            Arrow(_) => {}
+            #[cfg(feature = "connector-http")]
+            Http(_) => {}
+            #[cfg(feature = "connector-mysql")]
+            MySql(pool) => pool.close().await,
+            #[cfg(feature = "connector-postgres")]
+            Postgres(pool) => pool.close().await,
+            #[cfg(feature = "connector-sqlite")]
+            Sqlite(pool) => pool.close().await,
+        }
+    }
+
+    /// Checks the availability of the data source by probing the connector.
+    pub async fn check_availability(&self) -> Result<(), Error> {
+        match &self.connector {
+            #[cfg(feature = "connector-arrow")]
+            Arrow(connector) => connector.try_get_session_context().await.map(|_| ()),
+            #[cfg(feature = "connector-http")]
+            Http(connector) => connector.check_availability().await,
+            #[cfg(feature = "connector-mysql")]
+            MySql(pool) => pool.execute("SELECT 1;", None).await.map(|_| ()),
+            #[cfg(feature = "connector-postgres")]
+            Postgres(pool) => pool.execute("SELECT 1;", None).await.map(|_| ()),
+            #[cfg(feature = "connector-sqlite")]
+            Sqlite(pool) => pool.execute("SELECT 1;", None).await.map(|_| ()),
+        }
+    }
 }
 
 impl Connector for DataSource {
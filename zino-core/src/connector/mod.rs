@@ -37,6 +37,8 @@ pub use data_source::DataSource;
 use data_source::DataSourceConnector;
 
 /// Supported connectors.
+#[cfg(any(feature = "connector-postgres", feature = "connector-mysql-binlog"))]
+mod change_stream;
 #[cfg(feature = "connector-arrow")]
 mod connector_arrow;
 #[cfg(feature = "connector-http")]
@@ -54,8 +56,18 @@ mod connector_sqlite;
 ))]
 mod sqlx_common;
 
+#[cfg(feature = "connector-mysql-binlog")]
+pub use change_stream::MySqlChangeStream;
+#[cfg(feature = "connector-postgres")]
+pub use change_stream::PgChangeStream;
+#[cfg(any(feature = "connector-postgres", feature = "connector-mysql-binlog"))]
+pub use change_stream::{ChangeBatch, ChangeEvent, ChangeOperation};
+#[cfg(all(feature = "connector-arrow", feature = "orm"))]
+pub use connector_arrow::SqlTableProvider;
 #[cfg(feature = "connector-arrow")]
-pub use connector_arrow::{ArrowConnector, DataFrameExecutor};
+pub use connector_arrow::{
+    ArrowConnector, DataFrameExecutor, ExportFormat, ExportOptions, ExportOutput, HttpTableProvider,
+};
 #[cfg(feature = "connector-http")]
 pub use connector_http::HttpConnector;
 
@@ -110,6 +122,21 @@ impl GlobalConnector {
     pub fn get(name: &str) -> Option<&'static DataSource> {
         SHARED_DATA_SOURCE_CONNECTORS.find(name)
     }
+
+    /// Returns an iterator over the shared data sources with their service names.
+    #[inline]
+    pub fn iter() -> impl Iterator<Item = (&'static str, &'static DataSource)> {
+        SHARED_DATA_SOURCE_CONNECTORS.iter()
+    }
+
+    /// Closes the connection pools of the data sources which have been initialized.
+    pub async fn close_all() {
+        if let Some(data_sources) = LazyLock::get(&SHARED_DATA_SOURCE_CONNECTORS) {
+            for (_, data_source) in data_sources.iter() {
+                data_source.close().await;
+            }
+        }
+    }
 }
 
 /// Shared connectors.
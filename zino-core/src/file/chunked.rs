@@ -0,0 +1,626 @@
+use crate::{datetime::DateTime, Uuid};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+
+#[cfg(feature = "accessor")]
+use super::{
+    upload::{new_location, ChecksumWriter},
+    UploadLimits, UploadedFile,
+};
+#[cfg(feature = "accessor")]
+use crate::{
+    accessor::GlobalAccessor, error::Error, extension::TomlTableExt, response::Rejection, warn,
+};
+#[cfg(feature = "accessor")]
+use bytes::Bytes;
+#[cfg(feature = "accessor")]
+use mime::Mime;
+#[cfg(feature = "accessor")]
+use opendal::{ErrorKind, Metakey, Operator};
+#[cfg(feature = "accessor")]
+use std::{collections::HashMap, time::Duration};
+#[cfg(feature = "accessor")]
+use toml::Table;
+
+/// State of a resumable chunked upload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadSession {
+    /// Upload ID.
+    id: Uuid,
+    /// Owner of the upload.
+    #[serde(default)]
+    owner: Option<String>,
+    /// File name.
+    file_name: Option<String>,
+    /// Content type.
+    content_type: Option<String>,
+    /// Total size of the file.
+    total_size: u64,
+    /// Number of bytes received.
+    #[serde(default)]
+    offset: u64,
+    /// Sizes of the received parts.
+    #[serde(default)]
+    parts: Vec<u64>,
+    /// Location of the final object in the storage.
+    location: String,
+    /// Creation time.
+    #[serde(deserialize_with = "deserialize_datetime")]
+    created_at: DateTime,
+    /// Expiration time.
+    #[serde(deserialize_with = "deserialize_datetime")]
+    expires_at: DateTime,
+}
+
+impl UploadSession {
+    /// Returns the upload ID.
+    #[inline]
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Returns the owner of the upload.
+    #[inline]
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    /// Returns the file name.
+    #[inline]
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// Returns the content type.
+    #[inline]
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Returns the total size of the file.
+    #[inline]
+    pub fn total_size(&self) -> u64 {
+        self.total_size
+    }
+
+    /// Returns the number of bytes received, which is the offset of the next chunk.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the location of the final object in the storage.
+    #[inline]
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    /// Returns the creation time.
+    #[inline]
+    pub fn created_at(&self) -> DateTime {
+        self.created_at
+    }
+
+    /// Returns the expiration time.
+    #[inline]
+    pub fn expires_at(&self) -> DateTime {
+        self.expires_at
+    }
+
+    /// Returns `true` if all the bytes have been received.
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.offset == self.total_size
+    }
+
+    /// Returns `true` if the session has been expired.
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= DateTime::now()
+    }
+}
+
+/// Deserializes a date time in the same format as the serialization.
+fn deserialize_datetime<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(D::Error::custom)
+}
+
+/// A resumable chunked-upload protocol for the storage services
+/// which do not support presigned URLs.
+///
+/// It is similar to the [tus](https://tus.io) protocol: an upload session is
+/// created with the total size of the file, the chunks are appended with
+/// the expected offset, the current offset can be queried to resume an
+/// interrupted upload, and the parts are assembled into the final object
+/// when the upload is finalized. The session state and the parts are stored
+/// in the `.uploads` directory of the storage. The expired sessions can be removed by
+/// [`sweep_expired()`](Self::sweep_expired) periodically.
+///
+/// The session state is written once when it is created. Each chunk is stored
+/// as a part named by its offset, and the offset of the upload is derived from
+/// the contiguous parts, so the processes sharing the storage never overwrite
+/// the state of each other. If two requests append a chunk at the same offset,
+/// only one of them is kept and the other client will get a conflict on its next chunk.
+///
+/// An upload session is bound to the owner who creates it, such as the user ID,
+/// and it can only be accessed with the same owner.
+///
+/// # Examples
+///
+/// ```toml
+/// [chunked-upload]
+/// accessor = "fs"
+/// dir = "uploads"
+/// session-ttl = "1d"
+/// max-file-size = 10737418240
+/// mime-types = ["text/csv", "application/x-parquet"]
+/// ```
+#[cfg(feature = "accessor")]
+#[derive(Debug, Clone)]
+pub struct ChunkedUploader {
+    /// Storage operator.
+    operator: Operator,
+    /// Directory in the storage.
+    dir: String,
+    /// TTL of the upload sessions.
+    session_ttl: Duration,
+    /// Limits of the files.
+    limits: UploadLimits,
+}
+
+#[cfg(feature = "accessor")]
+impl ChunkedUploader {
+    /// Creates a new instance with the storage operator.
+    #[inline]
+    pub fn new(operator: Operator) -> Self {
+        Self {
+            operator,
+            dir: String::new(),
+            session_ttl: Duration::from_secs(86400),
+            limits: UploadLimits::default(),
+        }
+    }
+
+    /// Creates a new instance with the shared operator of the accessor.
+    #[inline]
+    pub fn with_accessor(name: &str) -> Option<Self> {
+        GlobalAccessor::get(name).map(|operator| Self::new(operator.clone()))
+    }
+
+    /// Creates a new instance with the config.
+    ///
+    /// The config contains the field `accessor`, and the optional fields `dir`,
+    /// `session-ttl`, `max-file-size` and `mime-types`.
+    pub fn with_config(config: &Table) -> Result<Self, Error> {
+        let accessor = config
+            .get_str("accessor")
+            .ok_or_else(|| warn!("the `accessor` should be specified"))?;
+        let mut uploader = Self::with_accessor(accessor)
+            .ok_or_else(|| warn!("the accessor `{}` does not exist", accessor))?;
+        if let Some(dir) = config.get_str("dir") {
+            uploader.dir = dir.to_owned();
+        }
+        if let Some(session_ttl) = config.get_duration("session-ttl") {
+            uploader.session_ttl = session_ttl;
+        }
+        uploader.limits = UploadLimits::with_config(config);
+        Ok(uploader)
+    }
+
+    /// Sets the directory in the storage.
+    #[inline]
+    pub fn dir(mut self, dir: impl Into<String>) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    /// Sets the TTL of the upload sessions.
+    #[inline]
+    pub fn session_ttl(mut self, session_ttl: Duration) -> Self {
+        self.session_ttl = session_ttl;
+        self
+    }
+
+    /// Sets the limits of the files.
+    #[inline]
+    pub fn limits(mut self, limits: UploadLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Creates a new upload session for a file with the total size.
+    pub async fn create(
+        &self,
+        owner: Option<&str>,
+        file_name: Option<&str>,
+        content_type: Option<&str>,
+        total_size: u64,
+    ) -> Result<UploadSession, Rejection> {
+        let mime = match content_type {
+            Some(content_type) => content_type
+                .parse::<Mime>()
+                .map_err(|err| Rejection::from_validation_entry("content_type", err))?,
+            None => file_name
+                .and_then(|s| mime_guess::from_path(s).first())
+                .unwrap_or(mime::APPLICATION_OCTET_STREAM),
+        };
+        self.limits
+            .check_mime_type(&mime)
+            .map_err(|err| Rejection::from_validation_entry("content_type", err))?;
+        self.limits
+            .check_file_size(total_size)
+            .map_err(|err| Rejection::from_validation_entry("total_size", err))?;
+
+        let created_at = DateTime::now();
+        let session = UploadSession {
+            id: Uuid::now_v7(),
+            owner: owner.map(|s| s.to_owned()),
+            file_name: file_name.map(|s| s.to_owned()),
+            content_type: Some(mime.essence_str().to_owned()),
+            total_size,
+            offset: 0,
+            parts: Vec::new(),
+            location: new_location(&self.dir, file_name, &mime),
+            created_at,
+            expires_at: created_at + self.session_ttl,
+        };
+        self.save_session(&session)
+            .await
+            .map_err(Rejection::from_error)?;
+        Ok(session)
+    }
+
+    /// Returns the state of the upload session.
+    pub async fn status(
+        &self,
+        upload_id: Uuid,
+        owner: Option<&str>,
+    ) -> Result<UploadSession, Rejection> {
+        let session = self
+            .load_state(upload_id)
+            .await
+            .map_err(Rejection::from_error)?;
+        check_owner(&session, owner).map_err(Rejection::from_error)?;
+        if session.is_expired() {
+            self.remove_session(upload_id).await?;
+            let err = warn!("404 Not Found: the upload `{}` has been expired", upload_id);
+            return Err(Rejection::from_error(err));
+        }
+        Ok(session)
+    }
+
+    /// Appends a chunk at the offset. The offset should be equal to
+    /// the number of bytes received, otherwise a conflict is returned
+    /// and the client should query the status to resume the upload.
+    pub async fn patch(
+        &self,
+        upload_id: Uuid,
+        owner: Option<&str>,
+        offset: u64,
+        chunk: Bytes,
+    ) -> Result<UploadSession, Rejection> {
+        let mut session = self.status(upload_id, owner).await?;
+        if offset != session.offset {
+            let err = warn!(
+                "409 Conflict: the offset `{}` does not match the current offset `{}`",
+                offset, session.offset
+            );
+            return Err(Rejection::from_error(err));
+        }
+
+        let chunk_size = chunk.len() as u64;
+        if chunk_size == 0 {
+            return Ok(session);
+        }
+        if offset + chunk_size > session.total_size {
+            let err = warn!(
+                "the chunk exceeds the total size of {} bytes",
+                session.total_size
+            );
+            return Err(Rejection::from_validation_entry("chunk", err));
+        }
+
+        let part_path = self.part_path(upload_id, offset);
+        self.operator
+            .write(&part_path, chunk)
+            .await
+            .map_err(Rejection::from_error)?;
+        session.parts.push(chunk_size);
+        session.offset += chunk_size;
+        Ok(session)
+    }
+
+    /// Assembles the parts into the final object and removes the upload session.
+    pub async fn finalize(
+        &self,
+        upload_id: Uuid,
+        owner: Option<&str>,
+    ) -> Result<UploadedFile, Rejection> {
+        let session = self.status(upload_id, owner).await?;
+        if !session.is_complete() {
+            let err = warn!(
+                "409 Conflict: only {} of {} bytes have been received",
+                session.offset, session.total_size
+            );
+            return Err(Rejection::from_error(err));
+        }
+
+        let content_type = session
+            .content_type
+            .as_deref()
+            .and_then(|s| s.parse::<Mime>().ok());
+        let mime = content_type
+            .clone()
+            .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+        let location = session.location.clone();
+        let mut writer = ChecksumWriter::try_new(&self.operator, location, &mime)
+            .await
+            .map_err(Rejection::from_error)?;
+        let mut offset = 0;
+        for part_size in &session.parts {
+            let part_path = self.part_path(upload_id, offset);
+            offset += part_size;
+            let part = match self.operator.read(&part_path).await {
+                Ok(part) => part,
+                Err(err) => {
+                    writer.abort().await;
+                    return Err(Rejection::from_error(err));
+                }
+            };
+            writer
+                .write(part.into())
+                .await
+                .map_err(Rejection::from_error)?;
+        }
+        let file = writer
+            .close(None, session.file_name, content_type)
+            .await
+            .map_err(Rejection::from_error)?;
+        self.remove_session(upload_id).await?;
+        Ok(file)
+    }
+
+    /// Cancels the upload session and removes the parts.
+    pub async fn cancel(&self, upload_id: Uuid, owner: Option<&str>) -> Result<(), Rejection> {
+        let session = self
+            .load_session(upload_id)
+            .await
+            .map_err(Rejection::from_error)?;
+        check_owner(&session, owner).map_err(Rejection::from_error)?;
+        self.remove_session(upload_id).await
+    }
+
+    /// Removes the upload session and the parts.
+    async fn remove_session(&self, upload_id: Uuid) -> Result<(), Rejection> {
+        self.operator
+            .remove_all(&self.session_dir(upload_id))
+            .await
+            .map_err(Rejection::from_error)
+    }
+
+    /// Removes the expired upload sessions together with their parts,
+    /// and returns the number of sessions removed. The sessions without
+    /// a valid state are also removed if they were created before the TTL.
+    pub async fn sweep_expired(&self) -> Result<usize, Error> {
+        let entries = match self.operator.list(&self.uploads_dir()).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut count = 0;
+        for entry in entries {
+            let Some(upload_id) = entry
+                .name()
+                .strip_suffix('/')
+                .and_then(|s| s.parse::<Uuid>().ok())
+            else {
+                continue;
+            };
+            let expired = match self.load_session(upload_id).await {
+                Ok(session) => session.is_expired(),
+                Err(_) => upload_id.get_timestamp().is_some_and(|ts| {
+                    let created_at = DateTime::from_timestamp(ts.to_unix().0 as i64);
+                    created_at + self.session_ttl <= DateTime::now()
+                }),
+            };
+            if expired {
+                self.operator
+                    .remove_all(&self.session_dir(upload_id))
+                    .await?;
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Loads the upload session.
+    async fn load_session(&self, upload_id: Uuid) -> Result<UploadSession, Error> {
+        let path = format!("{}session.json", self.session_dir(upload_id));
+        match self.operator.read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(Error::from),
+            Err(err) if err.kind() == ErrorKind::NotFound => Err(warn!(
+                "404 Not Found: the upload `{}` does not exist",
+                upload_id
+            )),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Loads the upload session with the offset derived from the contiguous parts.
+    /// The parts beyond a gap or the total size are ignored.
+    async fn load_state(&self, upload_id: Uuid) -> Result<UploadSession, Error> {
+        let mut session = self.load_session(upload_id).await?;
+        let entries = self
+            .operator
+            .list_with(&self.session_dir(upload_id))
+            .metakey(Metakey::ContentLength)
+            .await?;
+        let parts = entries
+            .iter()
+            .filter_map(|entry| {
+                let offset = entry.name().strip_suffix(".part")?.parse::<u64>().ok()?;
+                Some((offset, entry.metadata().content_length()))
+            })
+            .collect::<HashMap<_, _>>();
+        session.offset = 0;
+        session.parts.clear();
+        while let Some(&part_size) = parts.get(&session.offset) {
+            if part_size == 0 || session.offset + part_size > session.total_size {
+                break;
+            }
+            session.parts.push(part_size);
+            session.offset += part_size;
+        }
+        Ok(session)
+    }
+
+    /// Saves the upload session.
+    async fn save_session(&self, session: &UploadSession) -> Result<(), Error> {
+        let path = format!("{}session.json", self.session_dir(session.id));
+        self.operator
+            .write(&path, serde_json::to_vec(session)?)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the directory of the upload sessions.
+    fn uploads_dir(&self) -> String {
+        let dir = self.dir.trim_matches('/');
+        if dir.is_empty() {
+            ".uploads/".to_owned()
+        } else {
+            format!("{dir}/.uploads/")
+        }
+    }
+
+    /// Returns the directory of the upload session.
+    #[inline]
+    fn session_dir(&self, upload_id: Uuid) -> String {
+        format!("{}{upload_id}/", self.uploads_dir())
+    }
+
+    /// Returns the path of the part at the offset.
+    #[inline]
+    fn part_path(&self, upload_id: Uuid, offset: u64) -> String {
+        format!("{}{offset:020}.part", self.session_dir(upload_id))
+    }
+}
+
+/// Checks whether the upload session belongs to the owner.
+#[cfg(feature = "accessor")]
+fn check_owner(session: &UploadSession, owner: Option<&str>) -> Result<(), Error> {
+    if session.owner.as_deref() == owner {
+        Ok(())
+    } else {
+        Err(warn!(
+            "403 Forbidden: the upload `{}` does not belong to the current user",
+            session.id
+        ))
+    }
+}
+
+#[cfg(all(test, feature = "accessor-memory"))]
+mod tests {
+    use super::ChunkedUploader;
+    use crate::file::UploadLimits;
+    use bytes::Bytes;
+    use futures::executor::block_on;
+    use opendal::{services::Memory, Operator};
+    use std::time::Duration;
+
+    const OWNER: Option<&str> = Some("alice");
+
+    fn new_uploader() -> ChunkedUploader {
+        let operator = Operator::new(Memory::default()).unwrap().finish();
+        ChunkedUploader::new(operator).dir("uploads")
+    }
+
+    #[test]
+    fn it_uploads_files_in_chunks() {
+        let uploader = new_uploader().limits(UploadLimits::new().max_file_size(64));
+        assert!(block_on(uploader.create(OWNER, Some("a.csv"), None, 65)).is_err());
+        assert!(block_on(uploader.create(OWNER, Some("a.csv"), Some("text"), 8)).is_err());
+
+        let session = block_on(uploader.create(OWNER, Some("a.csv"), None, 11)).unwrap();
+        let upload_id = session.id();
+        assert_eq!(session.owner(), OWNER);
+        assert_eq!(session.content_type(), Some("text/csv"));
+        assert!(session.location().starts_with("uploads/"));
+        assert!(session.location().ends_with(".csv"));
+
+        let chunk = Bytes::from("id,name\n");
+        let session = block_on(uploader.patch(upload_id, OWNER, 0, chunk)).unwrap();
+        assert_eq!(session.offset(), 8);
+        assert!(block_on(uploader.patch(upload_id, OWNER, 0, Bytes::from("1,a"))).is_err());
+        assert!(block_on(uploader.patch(upload_id, OWNER, 8, Bytes::from("1,a\n"))).is_err());
+        assert!(block_on(uploader.finalize(upload_id, OWNER)).is_err());
+
+        let session = block_on(uploader.patch(upload_id, OWNER, 8, Bytes::from("1,a"))).unwrap();
+        assert!(session.is_complete());
+        let status = block_on(uploader.status(upload_id, OWNER)).unwrap();
+        assert_eq!(status.offset(), 11);
+
+        let file = block_on(uploader.finalize(upload_id, OWNER)).unwrap();
+        assert_eq!(file.file_name(), Some("a.csv"));
+        assert_eq!(file.location(), session.location());
+        assert_eq!(file.file_size(), 11);
+
+        let bytes = block_on(uploader.operator.read(file.location())).unwrap();
+        assert_eq!(bytes, b"id,name\n1,a");
+        assert!(block_on(uploader.status(upload_id, OWNER)).is_err());
+    }
+
+    #[test]
+    fn it_derives_offsets_from_parts() {
+        let uploader = new_uploader();
+        let session = block_on(uploader.create(OWNER, Some("a.txt"), None, 6)).unwrap();
+        let upload_id = session.id();
+
+        // Simulates two requests which append the chunks at the same offset.
+        let first = block_on(uploader.patch(upload_id, OWNER, 0, Bytes::from("abcd"))).unwrap();
+        assert_eq!(first.offset(), 4);
+        let part_path = uploader.part_path(upload_id, 0);
+        block_on(uploader.operator.write(&part_path, "ab")).unwrap();
+        assert!(block_on(uploader.patch(upload_id, OWNER, 4, Bytes::from("ef"))).is_err());
+
+        let status = block_on(uploader.status(upload_id, OWNER)).unwrap();
+        assert_eq!(status.offset(), 2);
+        block_on(uploader.patch(upload_id, OWNER, 2, Bytes::from("cdef"))).unwrap();
+        let file = block_on(uploader.finalize(upload_id, OWNER)).unwrap();
+        let bytes = block_on(uploader.operator.read(file.location())).unwrap();
+        assert_eq!(bytes, b"abcdef");
+    }
+
+    #[test]
+    fn it_checks_upload_owners() {
+        let uploader = new_uploader();
+        let session = block_on(uploader.create(OWNER, Some("a.txt"), None, 4)).unwrap();
+        let upload_id = session.id();
+
+        let other = Some("bob");
+        assert!(block_on(uploader.status(upload_id, other)).is_err());
+        assert!(block_on(uploader.status(upload_id, None)).is_err());
+        assert!(block_on(uploader.patch(upload_id, other, 0, Bytes::from("ab"))).is_err());
+        assert!(block_on(uploader.finalize(upload_id, other)).is_err());
+        assert!(block_on(uploader.cancel(upload_id, other)).is_err());
+
+        let session = block_on(uploader.patch(upload_id, OWNER, 0, Bytes::from("ab"))).unwrap();
+        assert_eq!(session.offset(), 2);
+        assert!(block_on(uploader.cancel(upload_id, OWNER)).is_ok());
+        assert!(block_on(uploader.status(upload_id, OWNER)).is_err());
+    }
+
+    #[test]
+    fn it_sweeps_expired_sessions() {
+        let uploader = new_uploader();
+        let active = block_on(uploader.create(OWNER, Some("a.txt"), None, 4)).unwrap();
+        assert_eq!(block_on(uploader.sweep_expired()).unwrap(), 0);
+
+        let uploader = uploader.session_ttl(Duration::ZERO);
+        let expired = block_on(uploader.create(OWNER, Some("b.txt"), None, 4)).unwrap();
+        assert_eq!(block_on(uploader.sweep_expired()).unwrap(), 1);
+        assert!(block_on(uploader.load_session(active.id())).is_ok());
+        assert!(block_on(uploader.load_session(expired.id())).is_err());
+    }
+}
@@ -16,6 +16,19 @@ use std::{
     path::Path,
 };
 
+mod chunked;
+mod presign;
+mod upload;
+
+pub use chunked::UploadSession;
+pub use presign::PresignedUrl;
+pub use upload::{UploadLimits, UploadedFile};
+
+#[cfg(feature = "accessor")]
+pub use chunked::ChunkedUploader;
+#[cfg(feature = "accessor")]
+pub use upload::UploadOptions;
+
 /// A file with an associated name.
 #[derive(Debug, Clone, Default)]
 pub struct NamedFile {
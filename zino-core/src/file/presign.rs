@@ -0,0 +1,167 @@
+use crate::{datetime::DateTime, Map};
+use serde::Serialize;
+
+#[cfg(feature = "accessor")]
+use crate::{error::Error, extension::JsonObjectExt, warn};
+#[cfg(feature = "accessor")]
+use opendal::{raw::PresignedRequest, Operator};
+#[cfg(feature = "accessor")]
+use std::time::Duration;
+
+/// A time-limited presigned URL to access an object in the storage directly.
+#[derive(Debug, Clone, Serialize)]
+pub struct PresignedUrl {
+    /// HTTP method.
+    method: String,
+    /// URL.
+    url: String,
+    /// HTTP headers which should be sent with the request.
+    headers: Map,
+    /// Expiration time.
+    expires_at: DateTime,
+}
+
+impl PresignedUrl {
+    /// Returns the HTTP method.
+    #[inline]
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Returns the URL.
+    #[inline]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the HTTP headers which should be sent with the request.
+    #[inline]
+    pub fn headers(&self) -> &Map {
+        &self.headers
+    }
+
+    /// Returns the expiration time.
+    #[inline]
+    pub fn expires_at(&self) -> DateTime {
+        self.expires_at
+    }
+
+    /// Returns `true` if the URL has been expired.
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= DateTime::now()
+    }
+}
+
+#[cfg(feature = "accessor")]
+impl PresignedUrl {
+    /// Returns `true` if the storage service supports presigned URLs for downloading,
+    /// such as `s3`, `oss`, `cos`, `gcs` and `azblob`.
+    #[inline]
+    pub fn supports_read(operator: &Operator) -> bool {
+        operator.info().full_capability().presign_read
+    }
+
+    /// Returns `true` if the storage service supports presigned URLs for uploading.
+    /// Otherwise, the [`ChunkedUploader`](super::ChunkedUploader) can be used instead.
+    #[inline]
+    pub fn supports_write(operator: &Operator) -> bool {
+        operator.info().full_capability().presign_write
+    }
+
+    /// Mints a presigned GET URL to download the object at the path.
+    pub async fn try_read(
+        operator: &Operator,
+        path: &str,
+        expires_in: Duration,
+    ) -> Result<Self, Error> {
+        if !Self::supports_read(operator) {
+            return Err(unsupported_error(operator));
+        }
+        let request = operator.presign_read(path, expires_in).await?;
+        Ok(Self::from_request(&request, expires_in))
+    }
+
+    /// Mints a presigned PUT URL to upload the object at the path.
+    pub async fn try_write(
+        operator: &Operator,
+        path: &str,
+        expires_in: Duration,
+    ) -> Result<Self, Error> {
+        if !Self::supports_write(operator) {
+            return Err(unsupported_error(operator));
+        }
+        let request = operator.presign_write(path, expires_in).await?;
+        Ok(Self::from_request(&request, expires_in))
+    }
+
+    /// Creates a new instance from the presigned request.
+    fn from_request(request: &PresignedRequest, expires_in: Duration) -> Self {
+        let mut headers = Map::new();
+        for (name, value) in request.header() {
+            if let Ok(value) = value.to_str() {
+                headers.upsert(name.as_str(), value);
+            }
+        }
+        Self {
+            method: request.method().to_string(),
+            url: request.uri().to_string(),
+            headers,
+            expires_at: DateTime::now() + expires_in,
+        }
+    }
+}
+
+/// Returns an error for the storage service which does not support presigned URLs.
+#[cfg(feature = "accessor")]
+fn unsupported_error(operator: &Operator) -> Error {
+    let scheme = operator.info().scheme();
+    warn!(
+        "405 Method Not Allowed: the storage service `{}` does not support presigned URLs",
+        scheme
+    )
+}
+
+#[cfg(all(test, feature = "accessor-memory"))]
+mod tests {
+    use super::PresignedUrl;
+    use futures::executor::block_on;
+    use opendal::{raw::PresignedRequest, services::Memory, Operator};
+    use std::time::Duration;
+
+    #[test]
+    fn it_rejects_unsupported_services() {
+        let operator = Operator::new(Memory::default()).unwrap().finish();
+        assert!(!PresignedUrl::supports_read(&operator));
+        assert!(!PresignedUrl::supports_write(&operator));
+
+        let expires_in = Duration::from_secs(60);
+        let err = block_on(PresignedUrl::try_write(&operator, "a.txt", expires_in)).unwrap_err();
+        assert!(err.message().starts_with("405 Method Not Allowed"));
+        assert!(block_on(PresignedUrl::try_read(&operator, "a.txt", expires_in)).is_err());
+    }
+
+    #[test]
+    fn it_converts_presigned_requests() {
+        let mut headers = http::HeaderMap::new();
+        headers.insert("x-amz-acl", "private".parse().unwrap());
+        let request = PresignedRequest::new(
+            http::Method::PUT,
+            "https://bucket.s3.amazonaws.com/a.txt?X-Amz-Expires=60"
+                .parse()
+                .unwrap(),
+            headers,
+        );
+        let url = PresignedUrl::from_request(&request, Duration::from_secs(60));
+        assert_eq!(url.method(), "PUT");
+        assert_eq!(
+            url.url(),
+            "https://bucket.s3.amazonaws.com/a.txt?X-Amz-Expires=60"
+        );
+        assert_eq!(url.headers().get("x-amz-acl").unwrap(), "private");
+        assert!(!url.is_expired());
+
+        let url = PresignedUrl::from_request(&request, Duration::ZERO);
+        assert!(url.is_expired());
+    }
+}
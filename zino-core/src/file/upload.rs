@@ -0,0 +1,783 @@
+use crate::{error::Error, warn};
+use bytes::Bytes;
+use mime::Mime;
+
+#[cfg(feature = "accessor")]
+use crate::{
+    accessor::GlobalAccessor, crypto, encoding::base64, extension::TomlTableExt,
+    response::Rejection, Uuid,
+};
+#[cfg(feature = "accessor")]
+use md5::{Digest, Md5};
+#[cfg(feature = "accessor")]
+use multer::{Field, Multipart};
+#[cfg(feature = "accessor")]
+use opendal::Operator;
+#[cfg(feature = "accessor")]
+use std::collections::HashMap;
+#[cfg(feature = "accessor")]
+use toml::Table;
+
+/// A file which has been uploaded to the storage.
+#[derive(Debug, Clone)]
+pub struct UploadedFile {
+    /// Field name.
+    field_name: Option<String>,
+    /// File name.
+    file_name: Option<String>,
+    /// Content type.
+    content_type: Option<Mime>,
+    /// Location in the storage.
+    location: String,
+    /// File size.
+    file_size: u64,
+    /// Checksum.
+    checksum: Bytes,
+    /// Content MD5.
+    content_md5: String,
+}
+
+impl UploadedFile {
+    /// Returns the field name corresponding to the file.
+    #[inline]
+    pub fn field_name(&self) -> Option<&str> {
+        self.field_name.as_deref()
+    }
+
+    /// Returns the file name.
+    #[inline]
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// Returns the content type.
+    #[inline]
+    pub fn content_type(&self) -> Option<&Mime> {
+        self.content_type.as_ref()
+    }
+
+    /// Returns the top-level type of the content, such as `image` or `video`.
+    #[inline]
+    pub fn category(&self) -> &str {
+        self.content_type
+            .as_ref()
+            .map(|mime| mime.type_().as_str())
+            .unwrap_or("application")
+    }
+
+    /// Returns the essence of the content type without the parameters.
+    #[inline]
+    pub fn mime_type(&self) -> &str {
+        self.content_type
+            .as_ref()
+            .map(|mime| mime.essence_str())
+            .unwrap_or("application/octet-stream")
+    }
+
+    /// Returns the location in the storage.
+    #[inline]
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    /// Returns the file size.
+    #[inline]
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    /// Returns the checksum computed by the same digest algorithm
+    /// as [`NamedFile::checksum()`](super::NamedFile::checksum).
+    #[inline]
+    pub fn checksum(&self) -> Bytes {
+        self.checksum.clone()
+    }
+
+    /// Returns the content MD5.
+    #[inline]
+    pub fn content_md5(&self) -> &str {
+        &self.content_md5
+    }
+}
+
+/// Limits of the files in a multipart upload.
+#[derive(Debug, Clone, Default)]
+pub struct UploadLimits {
+    /// Maximum file size in bytes.
+    max_file_size: Option<u64>,
+    /// Allowed MIME types, such as `image/png` or `image/*`.
+    mime_types: Vec<String>,
+}
+
+impl UploadLimits {
+    /// Creates a new instance without any limits.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum file size in bytes.
+    #[inline]
+    pub fn max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = Some(max_file_size);
+        self
+    }
+
+    /// Allows the MIME type. The pattern can be an exact type like `image/png`,
+    /// or a wildcard like `image/*`. All types are allowed if there is no pattern.
+    #[inline]
+    pub fn allow_mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.mime_types.push(mime_type.into());
+        self
+    }
+
+    /// Returns `true` if the MIME type is allowed.
+    pub fn is_allowed(&self, mime: &Mime) -> bool {
+        self.mime_types.is_empty()
+            || self.mime_types.iter().any(|pattern| {
+                if pattern == "*/*" || pattern == mime.essence_str() {
+                    true
+                } else if let Some(top_level) = pattern.strip_suffix("/*") {
+                    mime.type_() == top_level
+                } else {
+                    false
+                }
+            })
+    }
+
+    /// Checks the file size.
+    pub fn check_file_size(&self, file_size: u64) -> Result<(), Error> {
+        match self.max_file_size {
+            Some(max_file_size) if file_size > max_file_size => Err(warn!(
+                "the file size should be no more than {} bytes",
+                max_file_size
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the MIME type.
+    pub fn check_mime_type(&self, mime: &Mime) -> Result<(), Error> {
+        if self.is_allowed(mime) {
+            Ok(())
+        } else {
+            Err(warn!(
+                "the MIME type `{}` is not allowed",
+                mime.essence_str()
+            ))
+        }
+    }
+
+    /// Creates a new instance with the config.
+    #[cfg(feature = "accessor")]
+    pub(super) fn with_config(config: &Table) -> Self {
+        Self {
+            max_file_size: config
+                .get_usize("max-file-size")
+                .and_then(|size| u64::try_from(size).ok()),
+            mime_types: config
+                .get_str_array("mime-types")
+                .unwrap_or_default()
+                .into_iter()
+                .map(|s| s.to_owned())
+                .collect(),
+        }
+    }
+}
+
+/// Options for streaming the files in a multipart body to the storage of an accessor.
+///
+/// Each field is written to the storage chunk by chunk as it arrives,
+/// so the file is never buffered in memory as a whole. The checksums are
+/// computed incrementally, and the partial file is aborted if any limit is exceeded.
+/// The MIME type is sniffed from the leading bytes of the content instead of
+/// trusting the `Content-Type` of the field. If any field fails, the files
+/// which have been uploaded in the same request are removed.
+///
+/// # Examples
+///
+/// ```toml
+/// [upload]
+/// accessor = "s3"
+/// dir = "uploads"
+/// max-files = 10
+/// max-file-size = 1073741824
+/// mime-types = ["image/*", "video/*", "application/pdf"]
+///
+/// [upload.fields.avatar]
+/// max-file-size = 1048576
+/// mime-types = ["image/png", "image/jpeg"]
+/// ```
+#[cfg(feature = "accessor")]
+#[derive(Debug, Clone)]
+pub struct UploadOptions {
+    /// Storage operator.
+    operator: Operator,
+    /// Directory in the storage.
+    dir: String,
+    /// Maximum number of files.
+    max_files: usize,
+    /// Default limits.
+    limits: UploadLimits,
+    /// Limits for the specific fields.
+    field_limits: HashMap<String, UploadLimits>,
+}
+
+#[cfg(feature = "accessor")]
+impl UploadOptions {
+    /// Creates a new instance with the storage operator.
+    #[inline]
+    pub fn new(operator: Operator) -> Self {
+        Self {
+            operator,
+            dir: String::new(),
+            max_files: usize::MAX,
+            limits: UploadLimits::default(),
+            field_limits: HashMap::new(),
+        }
+    }
+
+    /// Creates a new instance with the shared operator of the accessor.
+    #[inline]
+    pub fn with_accessor(name: &str) -> Option<Self> {
+        GlobalAccessor::get(name).map(|operator| Self::new(operator.clone()))
+    }
+
+    /// Creates a new instance with the config.
+    ///
+    /// The config contains the field `accessor`, and the optional fields `dir`,
+    /// `max-files`, `max-file-size`, `mime-types` and `fields`.
+    pub fn with_config(config: &Table) -> Result<Self, Error> {
+        let accessor = config
+            .get_str("accessor")
+            .ok_or_else(|| warn!("the `accessor` should be specified"))?;
+        let mut options = Self::with_accessor(accessor)
+            .ok_or_else(|| warn!("the accessor `{}` does not exist", accessor))?;
+        if let Some(dir) = config.get_str("dir") {
+            options.dir = dir.to_owned();
+        }
+        if let Some(max_files) = config.get_usize("max-files") {
+            options.max_files = max_files;
+        }
+        options.limits = UploadLimits::with_config(config);
+        if let Some(fields) = config.get_table("fields") {
+            for (field, value) in fields {
+                if let Some(config) = value.as_table() {
+                    let limits = UploadLimits::with_config(config);
+                    options.field_limits.insert(field.to_owned(), limits);
+                }
+            }
+        }
+        Ok(options)
+    }
+
+    /// Sets the directory in the storage.
+    #[inline]
+    pub fn dir(mut self, dir: impl Into<String>) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    /// Sets the maximum number of files.
+    #[inline]
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Sets the default limits.
+    #[inline]
+    pub fn limits(mut self, limits: UploadLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the limits for the specific field.
+    #[inline]
+    pub fn field_limits(mut self, field_name: impl Into<String>, limits: UploadLimits) -> Self {
+        self.field_limits.insert(field_name.into(), limits);
+        self
+    }
+
+    /// Uploads all the files in the multipart stream. Non-file fields are skipped.
+    /// No file is kept if it fails.
+    pub async fn upload(&self, multipart: Multipart<'_>) -> Result<Vec<UploadedFile>, Rejection> {
+        let mut files = Vec::new();
+        if let Err(rejection) = self.upload_all(multipart, &mut files).await {
+            self.remove_files(&files).await;
+            return Err(rejection);
+        }
+        Ok(files)
+    }
+
+    /// Uploads all the files in the multipart stream into the vector.
+    async fn upload_all(
+        &self,
+        mut multipart: Multipart<'_>,
+        files: &mut Vec<UploadedFile>,
+    ) -> Result<(), Rejection> {
+        while let Some(field) = multipart
+            .next_field()
+            .await
+            .map_err(|err| Rejection::from_validation_entry("body", err))?
+        {
+            if field.file_name().is_some() {
+                if files.len() >= self.max_files {
+                    let err = warn!(
+                        "the number of files should be no more than {}",
+                        self.max_files
+                    );
+                    return Err(Rejection::from_validation_entry("files", err));
+                }
+                files.push(self.upload_field(field).await?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the uploaded files. Failures are logged.
+    async fn remove_files(&self, files: &[UploadedFile]) {
+        for file in files {
+            let location = file.location();
+            if let Err(err) = self.operator.delete(location).await {
+                tracing::warn!(location, "fail to remove the uploaded file: {err}");
+            }
+        }
+    }
+
+    /// Uploads a field in the multipart stream.
+    /// The leading bytes are buffered to sniff the MIME type before writing.
+    pub async fn upload_field(&self, mut field: Field<'_>) -> Result<UploadedFile, Rejection> {
+        let field_name = field.name().map(|s| s.to_owned());
+        let file_name = field.file_name().map(|s| s.to_owned());
+        let declared_type = field.content_type().cloned().or_else(|| {
+            file_name
+                .as_ref()
+                .and_then(|s| mime_guess::from_path(s).first())
+        });
+        let key = field_name.clone().unwrap_or_else(|| "file".to_owned());
+        let limits = field_name
+            .as_ref()
+            .and_then(|name| self.field_limits.get(name))
+            .unwrap_or(&self.limits);
+
+        let mut head = Vec::new();
+        let mut chunks = Vec::new();
+        while head.len() < SNIFF_LENGTH {
+            match field.chunk().await {
+                Ok(Some(chunk)) => {
+                    head.extend_from_slice(&chunk);
+                    chunks.push(chunk);
+                }
+                Ok(None) => break,
+                Err(err) => return Err(Rejection::from_validation_entry(key, err)),
+            }
+        }
+        if let Err(err) = limits.check_file_size(head.len() as u64) {
+            return Err(Rejection::from_validation_entry(key, err));
+        }
+
+        let mime = sniff_mime_type(&head, declared_type.as_ref());
+        if let Err(err) = limits.check_mime_type(&mime) {
+            return Err(Rejection::from_validation_entry(key, err));
+        }
+
+        let location = new_location(&self.dir, file_name.as_deref(), &mime);
+        let mut writer = ChecksumWriter::try_new(&self.operator, location, &mime)
+            .await
+            .map_err(Rejection::from_error)?;
+        for chunk in chunks {
+            writer.write(chunk).await.map_err(Rejection::from_error)?;
+        }
+        loop {
+            let chunk = match field.chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(err) => {
+                    writer.abort().await;
+                    return Err(Rejection::from_validation_entry(key, err));
+                }
+            };
+            if let Err(err) = limits.check_file_size(writer.file_size() + chunk.len() as u64) {
+                writer.abort().await;
+                return Err(Rejection::from_validation_entry(key, err));
+            }
+            writer.write(chunk).await.map_err(Rejection::from_error)?;
+        }
+        writer
+            .close(field_name, file_name, Some(mime))
+            .await
+            .map_err(Rejection::from_error)
+    }
+}
+
+/// Number of the leading bytes to sniff the MIME type.
+#[cfg(feature = "accessor")]
+const SNIFF_LENGTH: usize = 512;
+
+/// Sniffs the MIME type from the leading bytes of the content.
+///
+/// The declared type is only kept if it is compatible with the content:
+/// a `text/*`, JSON or XML type for the UTF-8 text, or a ZIP-based type
+/// such as the Office documents for the ZIP archive. Unrecognized binary
+/// content is `application/octet-stream`.
+#[cfg(feature = "accessor")]
+pub(super) fn sniff_mime_type(head: &[u8], declared_type: Option<&Mime>) -> Mime {
+    const SIGNATURES: [(&[u8], &str); 16] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"\x00\x00\x01\x00", "image/x-icon"),
+        (b"II*\x00", "image/tiff"),
+        (b"MM\x00*", "image/tiff"),
+        (b"%PDF-", "application/pdf"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"Rar!\x1a\x07", "application/vnd.rar"),
+        (b"\x1a\x45\xdf\xa3", "video/webm"),
+        (b"ID3", "audio/mpeg"),
+        (b"OggS", "audio/ogg"),
+        (b"fLaC", "audio/flac"),
+        (b"\x00asm", "application/wasm"),
+    ];
+
+    let sniffed_type = if let Some((_, mime_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| head.starts_with(signature))
+    {
+        mime_type
+    } else if head.starts_with(b"RIFF") && head.len() >= 12 {
+        match &head[8..12] {
+            b"WEBP" => "image/webp",
+            b"WAVE" => "audio/wav",
+            b"AVI " => "video/x-msvideo",
+            _ => "application/octet-stream",
+        }
+    } else if head.len() >= 12 && &head[4..8] == b"ftyp" {
+        match &head[8..12] {
+            b"avif" => "image/avif",
+            b"heic" | b"heix" | b"mif1" => "image/heic",
+            b"qt  " => "video/quicktime",
+            b"M4A " => "audio/mp4",
+            _ => "video/mp4",
+        }
+    } else if head.starts_with(b"PK\x03\x04") {
+        return declared_type
+            .filter(|mime| {
+                let subtype = mime.subtype().as_str();
+                mime.type_() == mime::APPLICATION
+                    && (mime.suffix().is_some_and(|suffix| suffix == "zip")
+                        || subtype.contains("zip")
+                        || subtype.contains("openxmlformats")
+                        || subtype.contains("opendocument")
+                        || subtype == "java-archive")
+            })
+            .cloned()
+            .unwrap_or_else(|| {
+                "application/zip"
+                    .parse()
+                    .unwrap_or(mime::APPLICATION_OCTET_STREAM)
+            });
+    } else if is_text(head) {
+        let text = String::from_utf8_lossy(head).to_ascii_lowercase();
+        if text.contains("<svg") {
+            return mime::IMAGE_SVG;
+        }
+        return declared_type
+            .filter(|mime| {
+                mime.type_() == mime::TEXT
+                    || [mime::JSON, mime::XML].iter().any(|name| {
+                        mime.type_() == mime::APPLICATION
+                            && (mime.subtype() == *name || mime.suffix() == Some(*name))
+                    })
+            })
+            .cloned()
+            .unwrap_or(mime::TEXT_PLAIN);
+    } else {
+        "application/octet-stream"
+    };
+    sniffed_type
+        .parse()
+        .unwrap_or(mime::APPLICATION_OCTET_STREAM)
+}
+
+/// Returns `true` if the leading bytes are UTF-8 text without control characters.
+/// A truncated character at the end is allowed.
+#[cfg(feature = "accessor")]
+fn is_text(head: &[u8]) -> bool {
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&head[..err.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return false,
+    };
+    text.chars()
+        .all(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r' | '\x0c'))
+}
+
+/// A writer to the storage which computes the checksums incrementally.
+#[cfg(feature = "accessor")]
+pub(super) struct ChecksumWriter {
+    /// Storage writer.
+    writer: opendal::Writer,
+    /// Location in the storage.
+    location: String,
+    /// Hasher for the checksum.
+    hasher: crypto::Digest,
+    /// Hasher for the content MD5.
+    md5_hasher: Md5,
+    /// Number of bytes written.
+    file_size: u64,
+}
+
+#[cfg(feature = "accessor")]
+impl ChecksumWriter {
+    /// Attempts to create a new instance for the location.
+    pub(super) async fn try_new(
+        operator: &Operator,
+        location: String,
+        mime: &Mime,
+    ) -> Result<Self, Error> {
+        let writer = operator
+            .writer_with(&location)
+            .content_type(mime.as_ref())
+            .await?;
+        Ok(Self {
+            writer,
+            location,
+            hasher: crypto::Digest::new(),
+            md5_hasher: Md5::new(),
+            file_size: 0,
+        })
+    }
+
+    /// Returns the number of bytes written.
+    #[inline]
+    pub(super) fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    /// Writes a chunk. The partial file is aborted if it fails.
+    pub(super) async fn write(&mut self, chunk: Bytes) -> Result<(), Error> {
+        self.hasher.update(&chunk);
+        self.md5_hasher.update(&chunk);
+        self.file_size += chunk.len() as u64;
+        if let Err(err) = self.writer.write(chunk).await {
+            if let Err(err) = self.writer.abort().await {
+                let location = self.location.as_str();
+                tracing::warn!(location, "fail to abort the partial file: {err}");
+            }
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    /// Aborts the writer and discards the partial file.
+    pub(super) async fn abort(mut self) {
+        if let Err(err) = self.writer.abort().await {
+            let location = self.location.as_str();
+            tracing::warn!(location, "fail to abort the partial file: {err}");
+        }
+    }
+
+    /// Closes the writer and returns the uploaded file.
+    pub(super) async fn close(
+        mut self,
+        field_name: Option<String>,
+        file_name: Option<String>,
+        content_type: Option<Mime>,
+    ) -> Result<UploadedFile, Error> {
+        self.writer.close().await?;
+        Ok(UploadedFile {
+            field_name,
+            file_name,
+            content_type,
+            location: self.location,
+            file_size: self.file_size,
+            checksum: Vec::from(self.hasher.finalize().as_slice()).into(),
+            content_md5: base64::encode(self.md5_hasher.finalize()),
+        })
+    }
+}
+
+/// Generates a new location in the directory for the file.
+/// The extension of the file name is kept only if it consists of
+/// 1 to 16 ASCII alphanumeric characters, otherwise it is derived from the MIME type.
+#[cfg(feature = "accessor")]
+pub(super) fn new_location(dir: &str, file_name: Option<&str>, mime: &Mime) -> String {
+    let extension = file_name
+        .and_then(|s| s.rsplit_once('.'))
+        .map(|(_, ext)| ext)
+        .filter(|ext| {
+            (1..=16).contains(&ext.len()) && ext.bytes().all(|b| b.is_ascii_alphanumeric())
+        })
+        .or_else(|| mime_guess::get_mime_extensions(mime).and_then(|exts| exts.first().copied()));
+    let id = Uuid::now_v7();
+    let file_name = if let Some(ext) = extension {
+        format!("{id}.{ext}")
+    } else {
+        id.to_string()
+    };
+    let dir = dir.trim_matches('/');
+    if dir.is_empty() {
+        file_name
+    } else {
+        format!("{dir}/{file_name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UploadLimits;
+
+    #[cfg(feature = "accessor-memory")]
+    use super::{new_location, sniff_mime_type, UploadOptions};
+
+    #[test]
+    fn it_matches_mime_types() {
+        let limits = UploadLimits::new();
+        assert!(limits.is_allowed(&mime::TEXT_PLAIN));
+
+        let limits = UploadLimits::new()
+            .allow_mime_type("image/*")
+            .allow_mime_type("application/pdf");
+        assert!(limits.is_allowed(&mime::IMAGE_PNG));
+        assert!(limits.is_allowed(&mime::APPLICATION_PDF));
+        assert!(!limits.is_allowed(&mime::TEXT_PLAIN));
+    }
+
+    #[cfg(feature = "accessor-memory")]
+    #[test]
+    fn it_sanitizes_file_extensions() {
+        let location = new_location("/uploads/", Some("report.PDF"), &mime::APPLICATION_PDF);
+        assert!(location.starts_with("uploads/"));
+        assert!(location.ends_with(".PDF"));
+
+        let location = new_location("", Some("a.b/../../etc"), &mime::IMAGE_PNG);
+        assert!(location.ends_with(".png"));
+        assert!(!location.contains('/'));
+
+        let location = new_location("", Some("archive.tar_gz"), &mime::APPLICATION_OCTET_STREAM);
+        assert!(!location.contains("tar_gz"));
+
+        let location = new_location("", Some("x.abcdefghijklmnopq"), &mime::TEXT_PLAIN);
+        assert!(!location.contains("abcdefghijklmnopq"));
+    }
+
+    #[cfg(feature = "accessor-memory")]
+    #[test]
+    fn it_sniffs_mime_types() {
+        let png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR";
+        assert_eq!(
+            sniff_mime_type(png, Some(&mime::TEXT_PLAIN)),
+            mime::IMAGE_PNG
+        );
+        assert_eq!(
+            sniff_mime_type(b"<html></html>", Some(&mime::IMAGE_PNG)),
+            mime::TEXT_PLAIN
+        );
+        assert_eq!(
+            sniff_mime_type(b"name,age\n", Some(&mime::TEXT_CSV)),
+            mime::TEXT_CSV
+        );
+        assert_eq!(
+            sniff_mime_type(b"<?xml version=\"1.0\"?><svg>", Some(&mime::IMAGE_PNG)),
+            mime::IMAGE_SVG
+        );
+        assert_eq!(
+            sniff_mime_type(b"\x00\x01\x02", None),
+            mime::APPLICATION_OCTET_STREAM
+        );
+
+        let docx = "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+            .parse::<mime::Mime>()
+            .unwrap();
+        assert_eq!(sniff_mime_type(b"PK\x03\x04", Some(&docx)), docx);
+        assert_eq!(
+            sniff_mime_type(b"PK\x03\x04", Some(&mime::APPLICATION_PDF)).essence_str(),
+            "application/zip"
+        );
+    }
+
+    #[cfg(feature = "accessor-memory")]
+    #[test]
+    fn it_streams_uploads_to_storage() {
+        use crate::crypto;
+        use bytes::Bytes;
+        use futures::{executor::block_on, stream};
+        use md5::{Digest, Md5};
+        use multer::Multipart;
+        use opendal::{services::Memory, Operator};
+
+        fn new_multipart(parts: &[(&str, &str, &str, &[u8])]) -> Multipart<'static> {
+            let mut chunks = Vec::new();
+            for (field_name, file_name, content_type, content) in parts {
+                let header = format!(
+                    "--BOUNDARY\r\nContent-Disposition: form-data; name=\"{field_name}\"; \
+                        filename=\"{file_name}\"\r\nContent-Type: {content_type}\r\n\r\n"
+                );
+                chunks.push(Bytes::from(header));
+                // Splits the content to make sure it is written chunk by chunk.
+                for chunk in content.chunks(7) {
+                    chunks.push(Bytes::copy_from_slice(chunk));
+                }
+                chunks.push(Bytes::from_static(b"\r\n"));
+            }
+            chunks.push(Bytes::from_static(b"--BOUNDARY--\r\n"));
+            let stream = stream::iter(chunks.into_iter().map(Ok::<_, std::io::Error>));
+            Multipart::new(stream, "BOUNDARY")
+        }
+
+        let operator = Operator::new(Memory::default()).unwrap().finish();
+        let content = b"The quick brown fox jumps over the lazy dog.".repeat(10);
+        let options = UploadOptions::new(operator.clone())
+            .dir("uploads")
+            .field_limits(
+                "avatar",
+                UploadLimits::new()
+                    .max_file_size(16)
+                    .allow_mime_type("image/*"),
+            );
+
+        let multipart = new_multipart(&[("doc", "fox.txt", "text/plain", &content)]);
+        let files = block_on(options.upload(multipart)).unwrap();
+        assert_eq!(files.len(), 1);
+
+        let file = &files[0];
+        assert_eq!(file.field_name(), Some("doc"));
+        assert_eq!(file.file_name(), Some("fox.txt"));
+        assert_eq!(file.category(), "text");
+        assert_eq!(file.mime_type(), "text/plain");
+        assert_eq!(file.file_size(), content.len() as u64);
+        assert!(file.location().starts_with("uploads/"));
+        assert!(file.location().ends_with(".txt"));
+
+        let mut hasher = crypto::Digest::new();
+        hasher.update(&content);
+        assert_eq!(file.checksum().as_ref(), hasher.finalize().as_slice());
+        assert_eq!(
+            file.content_md5(),
+            crate::encoding::base64::encode(Md5::digest(&content))
+        );
+
+        let stored = block_on(operator.read(file.location())).unwrap();
+        assert_eq!(stored, content);
+
+        let multipart = new_multipart(&[("avatar", "fox.png", "image/png", &content)]);
+        assert!(block_on(options.upload(multipart)).is_err());
+
+        let multipart = new_multipart(&[("avatar", "fox.txt", "text/plain", b"fox")]);
+        assert!(block_on(options.upload(multipart)).is_err());
+
+        let multipart = new_multipart(&[
+            ("doc", "fox.txt", "text/plain", &content),
+            ("avatar", "fox.png", "image/png", &content),
+        ]);
+        assert!(block_on(options.upload(multipart)).is_err());
+
+        let entries = block_on(operator.list("uploads/")).unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+}
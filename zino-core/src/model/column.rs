@@ -241,6 +241,12 @@ impl<'a> Column<'a> {
         self.index_type() == Some("text") || self.has_attribute("fuzzy_search")
     }
 
+    /// Returns the source field if the column stores the text embeddings of it.
+    #[inline]
+    pub fn embedding_source(&self) -> Option<&str> {
+        self.extra.get_str("embedding")
+    }
+
     /// Returns the Avro schema.
     pub fn schema(&self) -> Schema {
         let type_name = self.type_name();
@@ -259,6 +265,7 @@ impl<'a> Column<'a> {
             "Vec<Uuid>" => Schema::Array(Box::new(Schema::Uuid)),
             "Vec<i64>" | "Vec<u64>" => Schema::Array(Box::new(Schema::Long)),
             "Vec<i32>" | "Vec<u32>" => Schema::Array(Box::new(Schema::Int)),
+            "Vec<f32>" => Schema::Array(Box::new(Schema::Float)),
             "Option<String>" => {
                 if let Ok(union_schema) = UnionSchema::new(vec![Schema::Null, Schema::String]) {
                     Schema::Union(union_schema)
@@ -441,6 +448,13 @@ impl<'a> Column<'a> {
                 definition.upsert("type", "array");
                 definition.upsert("items", items);
             }
+            "Vec<f32>" => {
+                let mut items = Map::with_capacity(2);
+                items.upsert("type", "number");
+                items.upsert("format", "float");
+                definition.upsert("type", "array");
+                definition.upsert("items", items);
+            }
             "Map" => {
                 definition.upsert("type", "object");
             }
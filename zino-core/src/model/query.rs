@@ -110,6 +110,18 @@ impl Query {
                 "timestamp" | "nonce" | "signature" => {
                     extra.upsert(key, value.clone());
                 }
+                "$near" => {
+                    if value
+                        .as_object()
+                        .and_then(Self::parse_near_vector)
+                        .is_some()
+                    {
+                        filters.upsert(key, value.clone());
+                    } else {
+                        validation
+                            .record("$near", "should have a `$field` and a nonzero `$vector`");
+                    }
+                }
                 _ => {
                     if let Some(value) = value.as_str().filter(|&s| s != "all") {
                         if key.starts_with('$') {
@@ -139,6 +151,21 @@ impl Query {
         validation
     }
 
+    /// Parses the `$vector` of the `$near` filter as a unit vector.
+    /// It returns `None` if the `$field` is absent or the vector is zero or non-finite.
+    pub(crate) fn parse_near_vector(filter: &Map) -> Option<Vec<f32>> {
+        filter.get_str("$field")?;
+        let mut vector = filter.get_f32_array("$vector")?;
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm == 0.0 || !norm.is_finite() {
+            return None;
+        }
+        for x in vector.iter_mut() {
+            *x /= norm;
+        }
+        Some(vector)
+    }
+
     /// Parses the query expression with logical operators.
     fn parse_logical_query(expr: &str) -> Vec<Map> {
         let mut filters = Vec::new();
@@ -1,3 +1,5 @@
+use crate::JsonValue;
+
 /// A collection of values that can be decoded from a single row.
 ///
 /// This trait can be derived by `zino_derive::DecodeRow`.
@@ -7,4 +9,17 @@ pub trait DecodeRow<Row>: Default + Sized {
 
     /// Decodes a row and attempts to create an instance of `Self`.
     fn decode_row(row: &Row) -> Result<Self, Self::Error>;
+
+    /// Encodes `self` as a JSON value for the query cache.
+    /// Returns `None` if the type does not support caching.
+    #[inline]
+    fn encode_cached(&self) -> Option<JsonValue> {
+        None
+    }
+
+    /// Decodes a JSON value from the query cache and attempts to create an instance of `Self`.
+    #[inline]
+    fn decode_cached(_value: JsonValue) -> Option<Self> {
+        None
+    }
 }
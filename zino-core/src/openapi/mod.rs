@@ -75,6 +75,16 @@ pub(crate) fn openapi_info(title: &str, version: &str) -> Info {
     info
 }
 
+/// Finds the operation of the endpoint with the path and method.
+#[cfg(feature = "chatbot")]
+pub(crate) fn find_operation(
+    path: &str,
+    method: &str,
+) -> Option<&'static utoipa::openapi::path::Operation> {
+    let path_item_type = parser::parse_path_item_type(&method.to_ascii_uppercase());
+    OPENAPI_PATHS.get(path)?.operations.get(&path_item_type)
+}
+
 /// Returns the default OpenAPI paths.
 pub(crate) fn default_paths() -> Paths {
     let mut paths_builder = PathsBuilder::new();
@@ -0,0 +1,438 @@
+use super::Schema;
+use crate::{
+    crypto::Digest, datetime::DateTime, error::Error, extension::TomlTableExt, model::Query,
+    response::GlobalResponseCache, state::State, BoxFuture, JsonValue, LazyLock, Uuid,
+};
+use parking_lot::{Mutex, RwLock};
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::Digest as _;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Storage for the cached query results and the versions of the models.
+///
+/// The versions of the models are parts of the cache keys, so they should be
+/// stored with the query results in order to be shared by multiple processes.
+pub trait QueryCacheStore: Send + Sync {
+    /// Gets the cached value for the key.
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<JsonValue>, Error>>;
+
+    /// Puts the value into the cache with the TTL.
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        value: &'a JsonValue,
+        ttl: Duration,
+    ) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Removes the cached value for the key.
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Gets the version of the model.
+    fn get_version<'a>(
+        &'a self,
+        model_name: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, Error>>;
+
+    /// Sets the version of the model.
+    fn set_version<'a>(
+        &'a self,
+        model_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>>;
+}
+
+/// In-memory storage for the cached query results.
+///
+/// The entries are indexed by the expiration time, so that the expired entries
+/// or the ones which expire soonest are evicted without scanning all the entries.
+/// Both the cached entries and the versions of the models are process-local.
+#[derive(Debug)]
+pub struct MemoryQueryCacheStore {
+    /// Cached entries.
+    entries: Mutex<MemoryEntries>,
+    /// Versions of the models.
+    versions: Mutex<HashMap<String, String>>,
+    /// Maximum number of entries.
+    capacity: usize,
+}
+
+/// Cached entries in memory.
+#[derive(Debug, Default)]
+struct MemoryEntries {
+    /// Cached values with the expiration time and the sequence number.
+    values: HashMap<String, (JsonValue, DateTime, u64)>,
+    /// Keys indexed by the expiration time and the sequence number.
+    expirations: BTreeMap<(DateTime, u64), String>,
+    /// Sequence number of the last entry.
+    sequence: u64,
+}
+
+impl MemoryEntries {
+    /// Removes the entry for the key.
+    fn remove(&mut self, key: &str) {
+        if let Some((_, expires_at, sequence)) = self.values.remove(key) {
+            self.expirations.remove(&(expires_at, sequence));
+        }
+    }
+}
+
+impl MemoryQueryCacheStore {
+    /// Creates a new instance with the capacity.
+    #[inline]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(MemoryEntries::default()),
+            versions: Mutex::new(HashMap::new()),
+            capacity,
+        }
+    }
+}
+
+impl QueryCacheStore for MemoryQueryCacheStore {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<JsonValue>, Error>> {
+        let mut entries = self.entries.lock();
+        let value = match entries.values.get(key) {
+            Some((_, expires_at, _)) if *expires_at <= DateTime::now() => {
+                entries.remove(key);
+                None
+            }
+            entry => entry.map(|(value, ..)| value.clone()),
+        };
+        Box::pin(async move { Ok(value) })
+    }
+
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        value: &'a JsonValue,
+        ttl: Duration,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        if self.capacity > 0 {
+            let mut entries = self.entries.lock();
+            entries.remove(key);
+            while entries.values.len() >= self.capacity {
+                let Some((_, key)) = entries.expirations.pop_first() else {
+                    break;
+                };
+                entries.values.remove(&key);
+            }
+
+            let expires_at = DateTime::now() + ttl;
+            entries.sequence += 1;
+            let sequence = entries.sequence;
+            entries
+                .expirations
+                .insert((expires_at, sequence), key.to_owned());
+            entries
+                .values
+                .insert(key.to_owned(), (value.clone(), expires_at, sequence));
+        }
+        Box::pin(async { Ok(()) })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        self.entries.lock().remove(key);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn get_version<'a>(
+        &'a self,
+        model_name: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, Error>> {
+        let version = self.versions.lock().get(model_name).cloned();
+        Box::pin(async move { Ok(version) })
+    }
+
+    fn set_version<'a>(
+        &'a self,
+        model_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        self.versions
+            .lock()
+            .insert(model_name.to_owned(), version.to_owned());
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Global cache for the query results of the models.
+///
+/// The caching is opt-in for each model with the `cache_ttl` attribute of
+/// `#[schema(...)]` or the `[query-cache]` config. All the cached results of
+/// a model are invalidated on any write, since the version of the model
+/// is a part of the cache key. It can be bypassed for a query with the
+/// `no_cache` extra flag.
+///
+/// Only the row types which implement the `encode_cached` and `decode_cached` methods
+/// of [`DecodeRow`](crate::model::DecodeRow) are cached, such as `Map` and the structs
+/// deriving `DecodeRow` with the `cache_ttl` or `cacheable` attribute. The queries
+/// with other row types always hit the database.
+///
+/// # Examples
+///
+/// ```toml
+/// [query-cache]
+/// ttl = "1m"
+/// capacity = 10000
+/// models = ["tag", "user"]
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlobalQueryCache;
+
+impl GlobalQueryCache {
+    /// Replaces the shared store with a custom one.
+    #[inline]
+    pub fn set_store(store: impl QueryCacheStore + 'static) {
+        *SHARED_QUERY_CACHE_STORE.write() = Arc::new(store);
+    }
+
+    /// Returns the shared store.
+    #[inline]
+    pub fn store() -> Arc<dyn QueryCacheStore> {
+        SHARED_QUERY_CACHE_STORE.read().clone()
+    }
+
+    /// Returns the TTL of the cached query results for the model,
+    /// or `None` if the caching is not enabled.
+    pub fn ttl<M: Schema>() -> Option<Duration> {
+        let model_name = M::MODEL_NAME;
+        if QUERY_CACHE_MODELS.contains(&model_name) {
+            return Some(*QUERY_CACHE_TTL);
+        }
+        M::CACHE_TTL.and_then(|ttl| match crate::datetime::parse_duration(ttl) {
+            Ok(ttl) => Some(ttl),
+            Err(err) => {
+                tracing::warn!(model_name, "invalid cache TTL `{ttl}`: {err}");
+                None
+            }
+        })
+    }
+
+    /// Generates the cache key for the query result of the model.
+    /// The kind of the query and the type of the result are parts of the key.
+    /// The version of the model is loaded from the store.
+    pub async fn cache_key<M: Schema, T>(
+        kind: &str,
+        query: &Query,
+        args: &[&str],
+    ) -> Result<String, Error> {
+        let model_name = M::MODEL_NAME;
+        let version = Self::version(model_name).await?;
+        let mut hasher = Digest::new();
+        hasher.update(format!("{model_name}:{version}\n{kind}\n").as_bytes());
+        hasher.update(std::any::type_name::<T>().as_bytes());
+        hasher.update(b"\n");
+        hasher.update(query.fields().join(",").as_bytes());
+        hasher.update(b"\n");
+        hasher.update(
+            JsonValue::from(query.filters().clone())
+                .to_string()
+                .as_bytes(),
+        );
+        for (field, descending) in query.sort_order() {
+            let order = if *descending { "desc" } else { "asc" };
+            hasher.update(format!("\n{field} {order}").as_bytes());
+        }
+        hasher.update(format!("\n{}:{}", query.offset(), query.limit()).as_bytes());
+        for arg in args {
+            hasher.update(b"\n");
+            hasher.update(arg.as_bytes());
+        }
+        Ok(format!("{model_name}:{:x}", hasher.finalize()))
+    }
+
+    /// Invalidates the cached query results of the model by setting a new version.
+    /// Failures of the store are logged since the model has been written.
+    pub async fn invalidate(model_name: &str) {
+        let version = Uuid::now_v7().to_string();
+        if let Err(err) = Self::store().set_version(model_name, &version).await {
+            tracing::error!(
+                model_name,
+                "fail to invalidate the cached query results: {err}"
+            );
+        }
+    }
+
+    /// Returns the version of the model for the cache keys.
+    #[inline]
+    pub async fn version(model_name: &str) -> Result<String, Error> {
+        let version = Self::store().get_version(model_name).await?;
+        Ok(version.unwrap_or_default())
+    }
+}
+
+/// An entry of the query cache.
+pub(super) struct QueryCacheEntry {
+    /// Cache key.
+    key: String,
+    /// TTL of the entry.
+    ttl: Duration,
+}
+
+impl QueryCacheEntry {
+    /// Creates a new instance if the caching is enabled for the model and query.
+    /// The cache is bypassed if the version of the model can not be loaded.
+    pub(super) async fn new<M: Schema, T>(
+        kind: &str,
+        query: &Query,
+        args: &[&str],
+    ) -> Option<Self> {
+        if query.enabled("no_cache") {
+            return None;
+        }
+        let ttl = GlobalQueryCache::ttl::<M>()?;
+        match GlobalQueryCache::cache_key::<M, T>(kind, query, args).await {
+            Ok(key) => Some(Self { key, ttl }),
+            Err(err) => {
+                tracing::warn!(
+                    model_name = M::MODEL_NAME,
+                    "fail to get the version of the cached query results: {err}"
+                );
+                None
+            }
+        }
+    }
+
+    /// Loads the cached value. Errors of the store are logged and treated as misses.
+    pub(super) async fn load(&self) -> Option<JsonValue> {
+        let store = GlobalQueryCache::store();
+        let value = store.get(&self.key).await.unwrap_or_else(|err| {
+            tracing::warn!(
+                cache_key = self.key.as_str(),
+                "fail to get the cached query result: {err}"
+            );
+            None
+        });
+        #[cfg(feature = "metrics")]
+        {
+            let status = if value.is_some() { "hit" } else { "miss" };
+            metrics::counter!("zino_query_cache_lookups_total", "status" => status).increment(1);
+        }
+        value
+    }
+
+    /// Stores the value.
+    pub(super) async fn store(&self, value: JsonValue) {
+        let store = GlobalQueryCache::store();
+        if let Err(err) = store.put(&self.key, &value, self.ttl).await {
+            tracing::warn!(
+                cache_key = self.key.as_str(),
+                "fail to cache the query result: {err}"
+            );
+        }
+    }
+}
+
+/// Encodes the value as a JSON value for the query cache.
+/// It is used by the derived `DecodeRow` implementation of the cacheable structs.
+#[doc(hidden)]
+#[inline]
+pub fn encode_cached<T: Serialize>(value: &T) -> Option<JsonValue> {
+    serde_json::to_value(value).ok()
+}
+
+/// Decodes a JSON value from the query cache.
+/// It is used by the derived `DecodeRow` implementation of the cacheable structs.
+#[doc(hidden)]
+#[inline]
+pub fn decode_cached<T: DeserializeOwned>(value: JsonValue) -> Option<T> {
+    serde_json::from_value(value).ok()
+}
+
+/// Invalidates the cached query results and the cached responses related to the model.
+/// It is called by the methods of `Schema` once the rows of the model have been written,
+/// so that the invalidation does not depend on the default implementation of `ModelHooks`.
+pub(super) async fn invalidate_caches(model_name: &'static str) {
+    GlobalQueryCache::invalidate(model_name).await;
+    GlobalResponseCache::invalidate(model_name).await;
+}
+
+/// Models whose query results are cached by the config.
+static QUERY_CACHE_MODELS: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
+    State::shared()
+        .get_config("query-cache")
+        .and_then(|config| config.get_str_array("models"))
+        .unwrap_or_default()
+});
+
+/// Default TTL of the cached query results.
+static QUERY_CACHE_TTL: LazyLock<Duration> = LazyLock::new(|| {
+    State::shared()
+        .get_config("query-cache")
+        .and_then(|config| config.get_duration("ttl"))
+        .unwrap_or_else(|| Duration::from_secs(60))
+});
+
+/// Shared store for the cached query results.
+static SHARED_QUERY_CACHE_STORE: LazyLock<RwLock<Arc<dyn QueryCacheStore>>> = LazyLock::new(|| {
+    let capacity = State::shared()
+        .get_config("query-cache")
+        .and_then(|config| config.get_usize("capacity"))
+        .unwrap_or(10000);
+    RwLock::new(Arc::new(MemoryQueryCacheStore::new(capacity)))
+});
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_cached, encode_cached, GlobalQueryCache, MemoryQueryCacheStore};
+    use crate::{orm::QueryCacheStore, JsonValue, Map};
+    use futures::executor::block_on;
+    use std::time::Duration;
+
+    #[test]
+    fn it_evicts_cached_entries() {
+        let store = MemoryQueryCacheStore::new(2);
+        let ttl = Duration::from_secs(60);
+        block_on(store.put("a", &1.into(), ttl)).unwrap();
+        block_on(store.put("b", &2.into(), Duration::from_secs(1))).unwrap();
+        block_on(store.put("c", &3.into(), ttl)).unwrap();
+        assert_eq!(block_on(store.get("a")).unwrap(), Some(1.into()));
+        assert_eq!(block_on(store.get("b")).unwrap(), None);
+        assert_eq!(block_on(store.get("c")).unwrap(), Some(3.into()));
+
+        block_on(store.put("a", &4.into(), Duration::ZERO)).unwrap();
+        assert_eq!(block_on(store.get("a")).unwrap(), None);
+        block_on(store.put("d", &5.into(), ttl)).unwrap();
+        block_on(store.put("e", &6.into(), ttl)).unwrap();
+        assert_eq!(block_on(store.get("c")).unwrap(), None);
+        assert_eq!(block_on(store.get("d")).unwrap(), Some(5.into()));
+        assert_eq!(block_on(store.get("e")).unwrap(), Some(6.into()));
+
+        block_on(store.remove("d")).unwrap();
+        assert_eq!(block_on(store.get("d")).unwrap(), None);
+        assert_eq!(store.entries.lock().expirations.len(), 1);
+
+        let store = MemoryQueryCacheStore::new(0);
+        block_on(store.put("a", &1.into(), ttl)).unwrap();
+        assert_eq!(block_on(store.get("a")).unwrap(), None);
+    }
+
+    #[test]
+    fn it_invalidates_model_versions() {
+        let version = block_on(GlobalQueryCache::version("query_cache_test")).unwrap();
+        block_on(GlobalQueryCache::invalidate("query_cache_test"));
+        let store = GlobalQueryCache::store();
+        let stored_version = block_on(store.get_version("query_cache_test")).unwrap();
+        assert!(stored_version.is_some_and(|v| v != version));
+
+        let store = MemoryQueryCacheStore::new(0);
+        block_on(store.set_version("tag", "v1")).unwrap();
+        assert_eq!(
+            block_on(store.get_version("tag")).unwrap(),
+            Some("v1".to_owned())
+        );
+    }
+
+    #[test]
+    fn it_encodes_cached_values() {
+        let mut map = Map::new();
+        map.insert("name".to_owned(), "zino".into());
+        let value = encode_cached(&map).unwrap();
+        assert_eq!(decode_cached::<Map>(value), Some(map));
+        assert_eq!(decode_cached::<u64>(JsonValue::Null), None);
+    }
+}
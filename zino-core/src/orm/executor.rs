@@ -194,3 +194,80 @@ impl<'c> Executor for &'c sqlx::Pool<super::DatabaseDriver> {
 impl<'c> Executor for &'c mut super::DatabaseConnection {
     impl_sqlx_executor!();
 }
+
+/// Prepares the raw query with the parameters and binds them as strings.
+#[cfg(feature = "orm-sqlx")]
+fn prepare_raw_query(query: &str, params: &crate::Map) -> (String, Vec<String>) {
+    use crate::extension::JsonValueExt;
+
+    let placeholder = if super::DRIVER_NAME == "postgres" {
+        '$'
+    } else {
+        '?'
+    };
+    let (sql, values) = crate::helper::prepare_sql_query(query, Some(params), placeholder);
+    let arguments = values
+        .into_iter()
+        .map(|value| value.to_string_unquoted())
+        .collect();
+    (sql.into_owned(), arguments)
+}
+
+/// Formats an `INSERT` statement which does nothing if the primary key exists.
+/// The number of rows affected is `0` for a duplicate key.
+#[cfg(feature = "orm-sqlx")]
+pub(crate) fn format_insert_ignore(
+    table_name: &str,
+    primary_key: &str,
+    columns: &str,
+    values: &str,
+) -> String {
+    if super::DRIVER_NAME == "postgres" || super::DRIVER_NAME == "sqlite" {
+        format!(
+            "INSERT INTO {table_name} ({columns}) VALUES ({values}) \
+                ON CONFLICT ({primary_key}) DO NOTHING;"
+        )
+    } else {
+        format!("INSERT IGNORE INTO {table_name} ({columns}) VALUES ({values});")
+    }
+}
+
+/// Gets the database pool with the name.
+#[cfg(feature = "orm-sqlx")]
+fn get_database_pool(pool_name: &str) -> Result<&'static super::DatabasePool, Error> {
+    super::GlobalPool::get(pool_name)
+        .map(|cp| cp.pool())
+        .ok_or_else(|| crate::warn!("connection pool `{}` does not exist", pool_name))
+}
+
+/// Executes the raw query with the parameters in the connection pool
+/// and returns the number of rows affected.
+#[cfg(feature = "orm-sqlx")]
+pub(crate) async fn execute_raw(
+    pool_name: &str,
+    query: &str,
+    params: &crate::Map,
+) -> Result<u64, Error> {
+    let pool = get_database_pool(pool_name)?;
+    let (sql, arguments) = prepare_raw_query(query, params);
+    let query_result = pool.execute_with(&sql, &arguments).await?;
+    Ok(query_result.rows_affected())
+}
+
+/// Executes the raw query with the parameters in the connection pool
+/// and returns the text value of the column in the first row.
+#[cfg(feature = "orm-sqlx")]
+pub(crate) async fn fetch_raw_value(
+    pool_name: &str,
+    query: &str,
+    params: &crate::Map,
+    column: &str,
+) -> Result<Option<String>, Error> {
+    let pool = get_database_pool(pool_name)?;
+    let (sql, arguments) = prepare_raw_query(query, params);
+    if let Some(row) = pool.fetch_optional_with(&sql, &arguments).await? {
+        super::decode::<String>(&row, column).map(Some)
+    } else {
+        Ok(None)
+    }
+}
@@ -116,6 +116,7 @@ use std::sync::{
 };
 
 mod accessor;
+mod cache;
 mod column;
 mod executor;
 mod helper;
@@ -127,6 +128,9 @@ mod schema;
 mod transaction;
 
 pub use accessor::ModelAccessor;
+pub use cache::{
+    decode_cached, encode_cached, GlobalQueryCache, MemoryQueryCacheStore, QueryCacheStore,
+};
 pub use executor::Executor;
 pub use helper::ModelHelper;
 pub use manager::PoolManager;
@@ -142,6 +146,8 @@ mod scalar;
 #[cfg(feature = "orm-sqlx")]
 pub use decode::{decode, decode_array, decode_decimal, decode_uuid};
 #[cfg(feature = "orm-sqlx")]
+pub(crate) use executor::{execute_raw, fetch_raw_value, format_insert_ignore};
+#[cfg(feature = "orm-sqlx")]
 pub use scalar::ScalarQuery;
 
 cfg_if::cfg_if! {
@@ -149,7 +155,7 @@ cfg_if::cfg_if! {
         mod mysql;
 
         /// Driver name.
-        static DRIVER_NAME: &str = if cfg!(feature = "orm-mariadb") {
+        pub(crate) static DRIVER_NAME: &str = if cfg!(feature = "orm-mariadb") {
             "mariadb"
         } else if cfg!(feature = "orm-tidb") {
             "tidb"
@@ -172,7 +178,7 @@ cfg_if::cfg_if! {
         mod postgres;
 
         /// Driver name.
-        static DRIVER_NAME: &str = "postgres";
+        pub(crate) static DRIVER_NAME: &str = "postgres";
 
         /// PostgreSQL database driver.
         pub type DatabaseDriver = sqlx::Postgres;
@@ -189,7 +195,7 @@ cfg_if::cfg_if! {
         mod sqlite;
 
         /// Driver name.
-        static DRIVER_NAME: &str = "sqlite";
+        pub(crate) static DRIVER_NAME: &str = "sqlite";
 
         /// SQLite database driver.
         pub type DatabaseDriver = sqlx::Sqlite;
@@ -235,6 +241,12 @@ impl GlobalPool {
         SHARED_CONNECTION_POOLS.get_pool(name)
     }
 
+    /// Returns an iterator over the shared connection pools.
+    #[inline]
+    pub fn iter() -> impl Iterator<Item = &'static ConnectionPool> {
+        SHARED_CONNECTION_POOLS.0.iter()
+    }
+
     /// Iterates over the shared connection pools and
     /// attempts to establish a database connection for each of them.
     #[inline]
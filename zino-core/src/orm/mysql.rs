@@ -44,7 +44,7 @@ impl<'c> EncodeColumn<DatabaseDriver> for Column<'c> {
             "Uuid" | "Option<Uuid>" => "CHAR(36)",
             "Vec<u8>" => "BLOB",
             "Vec<String>" | "Vec<Uuid>" | "Vec<u64>" | "Vec<i64>" | "Vec<u32>" | "Vec<i32>"
-            | "Map" => "JSON",
+            | "Vec<f32>" | "Map" => "JSON",
             _ => "TEXT",
         }
     }
@@ -486,6 +486,16 @@ impl DecodeRow<DatabaseRow> for Map {
         }
         Ok(map)
     }
+
+    #[inline]
+    fn encode_cached(&self) -> Option<JsonValue> {
+        Some(self.clone().into())
+    }
+
+    #[inline]
+    fn decode_cached(value: JsonValue) -> Option<Self> {
+        value.into_map_opt()
+    }
 }
 
 #[cfg(feature = "orm-sqlx")]
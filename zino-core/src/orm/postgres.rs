@@ -53,6 +53,13 @@ impl<'c> EncodeColumn<DatabaseDriver> for Column<'c> {
             "Vec<Uuid>" => "UUID[]",
             "Vec<u64>" | "Vec<i64>" => "BIGINT[]",
             "Vec<u32>" | "Vec<i32>" => "INT[]",
+            "Vec<f32>" => {
+                if self.embedding_source().is_some() {
+                    "vector"
+                } else {
+                    "REAL[]"
+                }
+            }
             "Map" => "JSONB",
             _ => "TEXT",
         }
@@ -82,6 +89,12 @@ impl<'c> EncodeColumn<DatabaseDriver> for Column<'c> {
                         self.format_value(value)
                     }
                 }
+                JsonValue::Array(value)
+                    if value.is_empty() && self.embedding_source().is_some() =>
+                {
+                    // The `vector` type of pgvector does not accept an empty array.
+                    "NULL".into()
+                }
                 JsonValue::Array(value) => {
                     let values = value
                         .iter()
@@ -500,6 +513,16 @@ impl DecodeRow<DatabaseRow> for Map {
         }
         Ok(map)
     }
+
+    #[inline]
+    fn encode_cached(&self) -> Option<JsonValue> {
+        Some(self.clone().into())
+    }
+
+    #[inline]
+    fn decode_cached(value: JsonValue) -> Option<Self> {
+        value.into_map_opt()
+    }
 }
 
 #[cfg(feature = "orm-sqlx")]
@@ -132,6 +132,22 @@ pub(super) trait QueryExt<DB> {
                         conditions.push(condition);
                     }
                 }
+                "$near" => {
+                    if let Some((filter, similarity)) = value
+                        .as_object()
+                        .and_then(|filter| Some((filter, Self::format_similarity(filter)?)))
+                    {
+                        let field = filter.get_str("$field").unwrap_or_default();
+                        let field = Self::format_field(field);
+                        conditions.push(format!("{field} IS NOT NULL"));
+                        if let Some(min_similarity) = filter.get_f32("$min_similarity") {
+                            conditions.push(format!("{similarity} >= {min_similarity}"));
+                        }
+                    } else {
+                        // No rows match an invalid filter instead of ignoring it.
+                        conditions.push("1 = 0".to_owned());
+                    }
+                }
                 "$ovlp" => {
                     if let Some(values) = value.parse_str_array() {
                         if let [start_field, end_field, start_value, end_value] = values.as_slice()
@@ -314,22 +330,66 @@ pub(super) trait QueryExt<DB> {
         }
     }
 
+    /// Formats the cosine similarity between the embedding field and the vector
+    /// in the `$near` filter, such as
+    /// `{ "$field": "embedding", "$vector": [0.1, 0.2], "$min_similarity": 0.5 }`.
+    ///
+    /// The similarity is computed by the `<=>` operator of pgvector for PostgreSQL,
+    /// and by a brute-force scan over the JSON arrays for other databases,
+    /// where the stored embeddings are assumed to be normalized.
+    fn format_similarity(filter: &Map) -> Option<String> {
+        let vector = crate::model::Query::parse_near_vector(filter)?;
+        let field = Self::format_field(filter.get_str("$field")?);
+        let vector = vector
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let similarity = if cfg!(any(
+            feature = "orm-mariadb",
+            feature = "orm-mysql",
+            feature = "orm-tidb"
+        )) {
+            let columns = "COLUMNS (i FOR ORDINALITY, v DOUBLE PATH '$')";
+            format!(
+                "(SELECT SUM(a.v * b.v) FROM JSON_TABLE({field}, '$[*]' {columns}) AS a \
+                    JOIN JSON_TABLE('[{vector}]', '$[*]' {columns}) AS b ON a.i = b.i)"
+            )
+        } else if cfg!(feature = "orm-postgres") {
+            format!("(1 - ({field} <=> '[{vector}]'::vector))")
+        } else {
+            format!(
+                "(SELECT total(a.value * b.value) FROM json_each({field}) AS a \
+                    JOIN json_each('[{vector}]') AS b ON a.key = b.key)"
+            )
+        };
+        Some(similarity)
+    }
+
     /// Formats the query sort to generate SQL `ORDER BY` expression.
+    /// The rows are ordered by the similarity first if there is a `$near` filter.
     fn format_sort(&self) -> String {
-        let sort_order = self.query_order();
+        let mut sort_order = self
+            .query_order()
+            .iter()
+            .map(|(sort, descending)| {
+                if *descending {
+                    format!("{sort} DESC")
+                } else {
+                    format!("{sort} ASC")
+                }
+            })
+            .collect::<Vec<_>>();
+        if let Some(similarity) = self
+            .query_filters()
+            .get_object("$near")
+            .and_then(Self::format_similarity)
+        {
+            sort_order.insert(0, format!("{similarity} DESC"));
+        }
         if sort_order.is_empty() {
             String::new()
         } else {
-            let sort_order = sort_order
-                .iter()
-                .map(|(sort, descending)| {
-                    if *descending {
-                        format!("{sort} DESC")
-                    } else {
-                        format!("{sort} ASC")
-                    }
-                })
-                .collect::<Vec<_>>();
             format!("ORDER BY {}", sort_order.join(", "))
         }
     }
@@ -345,3 +405,73 @@ pub(super) trait QueryExt<DB> {
         format!("LIMIT {limit} OFFSET {offset}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::QueryExt;
+    use crate::{model::Query, orm::DatabaseDriver, Map};
+    use serde_json::json;
+
+    #[test]
+    fn it_formats_similarity() {
+        let filter = json!({
+            "$field": "embedding",
+            "$vector": [3.0, 4.0],
+            "$min_similarity": 0.5,
+        });
+        let filter = filter.as_object().unwrap();
+        let similarity = <Query as QueryExt<DatabaseDriver>>::format_similarity(filter).unwrap();
+        let expected = if cfg!(any(
+            feature = "orm-mariadb",
+            feature = "orm-mysql",
+            feature = "orm-tidb"
+        )) {
+            "(SELECT SUM(a.v * b.v) FROM JSON_TABLE(`embedding`, '$[*]' \
+                COLUMNS (i FOR ORDINALITY, v DOUBLE PATH '$')) AS a \
+                JOIN JSON_TABLE('[0.6,0.8]', '$[*]' \
+                COLUMNS (i FOR ORDINALITY, v DOUBLE PATH '$')) AS b ON a.i = b.i)"
+        } else if cfg!(feature = "orm-postgres") {
+            r#"(1 - ("embedding" <=> '[0.6,0.8]'::vector))"#
+        } else {
+            "(SELECT total(a.value * b.value) FROM json_each(`embedding`) AS a \
+                JOIN json_each('[0.6,0.8]') AS b ON a.key = b.key)"
+        };
+        assert_eq!(similarity, expected);
+
+        let mut query = Query::from_entry("$near", filter.clone());
+        query.order_by("created_at", true);
+        assert_eq!(
+            <Query as QueryExt<DatabaseDriver>>::format_sort(&query),
+            format!("ORDER BY {expected} DESC, created_at DESC")
+        );
+
+        let mut filter = filter.clone();
+        filter.insert("$vector".to_owned(), json!([0.0, 0.0]));
+        assert_eq!(
+            <Query as QueryExt<DatabaseDriver>>::format_similarity(&filter),
+            None
+        );
+        assert_eq!(
+            <Query as QueryExt<DatabaseDriver>>::format_similarity(&Map::new()),
+            None
+        );
+    }
+
+    #[test]
+    fn it_validates_near_filters() {
+        let mut query = Query::default();
+        let data = json!({
+            "$near": { "$field": "embedding", "$vector": [0.0, 0.0] },
+        });
+        let validation = query.read_map(data.as_object().unwrap());
+        assert!(!validation.is_success());
+        assert!(query.filters().is_empty());
+
+        let data = json!({
+            "$near": { "$field": "embedding", "$vector": [3.0, 4.0] },
+        });
+        let validation = query.read_map(data.as_object().unwrap());
+        assert!(validation.is_success());
+        assert!(query.filters().contains_key("$near"));
+    }
+}
@@ -1,6 +1,9 @@
 use super::{
-    column::ColumnExt, mutation::MutationExt, query::QueryExt, ConnectionPool, DatabaseRow,
-    Executor, GlobalPool, ModelHelper,
+    cache::{invalidate_caches, QueryCacheEntry},
+    column::ColumnExt,
+    mutation::MutationExt,
+    query::QueryExt,
+    ConnectionPool, DatabaseRow, Executor, GlobalPool, ModelHelper,
 };
 use crate::{
     bail,
@@ -27,6 +30,8 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     const WRITER_NAME: &'static str = "main";
     /// Optional custom table name.
     const TABLE_NAME: Option<&'static str> = None;
+    /// Optional TTL of the cached query results, such as `"30s"`.
+    const CACHE_TTL: Option<&'static str> = None;
 
     /// Returns the primary key.
     fn primary_key(&self) -> &Self::PrimaryKey;
@@ -101,6 +106,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     }
 
     /// Gets a column for the field if it is writable.
+    /// The embedding columns are not writable since they are maintained by the hooks.
     #[inline]
     fn get_writable_column(key: &str) -> Option<&Column<'static>> {
         let key = if let Some((name, field)) = key.split_once('.') {
@@ -112,9 +118,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         } else {
             key
         };
-        Self::columns()
-            .iter()
-            .find(|col| col.name() == key && !col.is_read_only())
+        Self::columns().iter().find(|col| {
+            col.name() == key && !col.is_read_only() && col.embedding_source().is_none()
+        })
     }
 
     /// Returns `true` if the model has a column for the specific field.
@@ -187,6 +193,16 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
             }
         }
 
+        if cfg!(feature = "orm-postgres")
+            && columns.iter().any(|col| col.embedding_source().is_some())
+        {
+            let sql = "CREATE EXTENSION IF NOT EXISTS vector;";
+            if let Err(err) = pool.execute(sql).await {
+                tracing::error!(table_name, "fail to enable the `pgvector` extension");
+                return Err(err);
+            }
+        }
+
         let definitions = definitions.join(",\n  ");
         let sql = format!("CREATE TABLE IF NOT EXISTS {table_name_escaped} (\n  {definitions}\n);");
         if let Err(err) = pool.execute(&sql).await {
@@ -372,6 +388,14 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
                                 ON {table_name_escaped} ({column_name});"
                         );
                         rows = pool.execute(&sql).await?.rows_affected().max(rows);
+                    } else if matches!(index_type, "hnsw" | "ivfflat") {
+                        // Indexes of pgvector for the cosine distance used by `$near`
+                        let sql = format!(
+                            "CREATE INDEX IF NOT EXISTS {table_name}_{column_name}_index \
+                                ON {table_name_escaped} \
+                                    USING {index_type}({column_name} vector_cosine_ops);"
+                        );
+                        rows = pool.execute(&sql).await?.rows_affected().max(rows);
                     } else {
                         let sort_order = if index_type == "btree" { " DESC" } else { "" };
                         let sql = format!(
@@ -412,6 +436,139 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         Ok(rows)
     }
 
+    /// Returns the primary key and the source fields of the embedding columns
+    /// whose values differ from the stored ones, which should be used as the data
+    /// of the `before_save` hook. The stored values are read without the query cache.
+    #[cfg(feature = "chatbot")]
+    async fn embedding_sources(&self) -> Result<Map, Error> {
+        let mut data = Map::new();
+        let sources = Self::columns()
+            .iter()
+            .filter_map(|col| col.embedding_source())
+            .collect::<Vec<_>>();
+        if sources.is_empty() {
+            return Ok(data);
+        }
+
+        let JsonValue::Object(mut map) = serde_json::to_value(self)? else {
+            return Ok(data);
+        };
+        let primary_key = self.primary_key_value();
+        let mut query = Query::from_entry(Self::PRIMARY_KEY_NAME, primary_key.clone());
+        query.allow_fields(&sources);
+        query.set_extra_flag("no_cache", true);
+
+        let stored_data = Self::find_one::<Map>(&query).await?.unwrap_or_default();
+        for source in sources {
+            if let Some(value) = map.remove(source) {
+                if stored_data.get(source) != Some(&value) {
+                    data.upsert(source, value);
+                }
+            }
+        }
+        if !data.is_empty() {
+            data.upsert(Self::PRIMARY_KEY_NAME, primary_key);
+        }
+        Ok(data)
+    }
+
+    /// Synchronizes the embedding columns with the source fields in the data
+    /// returned by [`embedding_sources()`](Self::embedding_sources).
+    /// It should be called in the `after_save` hook, and the cached query results
+    /// and responses of the model are invalidated once the embeddings are updated.
+    ///
+    /// The embeddings are normalized as unit vectors, and they are set to `NULL`
+    /// if the source texts are cleared. Failures of the embedding service are returned
+    /// as errors although the model has been saved.
+    #[cfg(feature = "chatbot")]
+    async fn sync_embeddings(ctx: &QueryContext, data: &Map) -> Result<(), Error> {
+        if !ctx.is_success() {
+            ctx.record_error("fail to save a model into the table");
+            return Ok(());
+        }
+
+        let Some(primary_key) = data.get(Self::PRIMARY_KEY_NAME) else {
+            return Ok(());
+        };
+        let mut mutations = Vec::new();
+        let mut columns = Vec::new();
+        let mut inputs = Vec::new();
+        for col in Self::columns() {
+            if let Some(source) = col.embedding_source() {
+                if !data.contains_key(source) {
+                    continue;
+                }
+                if let Some(text) = data.get_str(source).filter(|s| !s.trim().is_empty()) {
+                    columns.push(col);
+                    inputs.push(text.to_owned());
+                } else {
+                    let field = Query::format_field(col.name());
+                    mutations.push(format!("{field} = NULL"));
+                }
+            }
+        }
+        if !inputs.is_empty() {
+            let embeddings = generate_embeddings(Self::MODEL_NAME, inputs).await?;
+            for (col, embedding) in columns.into_iter().zip(embeddings) {
+                let field = Query::format_field(col.name());
+                let value = JsonValue::from(embedding);
+                let value = col.encode_value(Some(&value));
+                mutations.push(format!("{field} = {value}"));
+            }
+        }
+        if mutations.is_empty() {
+            return Ok(());
+        }
+
+        let pool = Self::acquire_writer().await?.pool();
+        let table_name = Query::table_name_escaped::<Self>();
+        let primary_key_name = Self::PRIMARY_KEY_NAME;
+        let primary_key = Self::primary_key_column().encode_value(Some(primary_key));
+        let mutations = mutations.join(", ");
+        let sql = format!(
+            "UPDATE {table_name} SET {mutations} WHERE {primary_key_name} = {primary_key};"
+        );
+        let mut ctx = Self::before_scan(&sql).await?;
+        let rows_affected = pool.execute(&sql).await?.rows_affected();
+        ctx.set_query(sql);
+        ctx.set_query_result(Some(rows_affected), rows_affected == 1);
+        Self::after_scan(&ctx).await?;
+        invalidate_caches(Self::MODEL_NAME).await;
+        Ok(())
+    }
+
+    /// Converts the models into maps with the embeddings of the source fields,
+    /// which are generated in a batch. The embeddings are `null` if the source texts
+    /// are empty.
+    #[cfg(feature = "chatbot")]
+    async fn embed_models(models: Vec<Self>) -> Result<Vec<Map>, Error> {
+        let mut maps = models
+            .into_iter()
+            .map(|model| model.into_map())
+            .collect::<Vec<_>>();
+        let mut targets = Vec::new();
+        let mut inputs = Vec::new();
+        for (index, map) in maps.iter_mut().enumerate() {
+            for col in Self::columns() {
+                if let Some(source) = col.embedding_source() {
+                    let field = col.name();
+                    if let Some(text) = map.get_str(source).filter(|s| !s.trim().is_empty()) {
+                        inputs.push(text.to_owned());
+                        targets.push((index, field));
+                    }
+                    map.upsert(field, JsonValue::Null);
+                }
+            }
+        }
+        if !inputs.is_empty() {
+            let embeddings = generate_embeddings(Self::MODEL_NAME, inputs).await?;
+            for ((index, field), embedding) in targets.into_iter().zip(embeddings) {
+                maps[index].upsert(field, embedding);
+            }
+        }
+        Ok(maps)
+    }
+
     /// Inserts the model into the table.
     async fn insert(mut self) -> Result<QueryContext, Error> {
         let pool = Self::acquire_writer().await?.pool();
@@ -425,7 +582,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let values = columns
             .iter()
             .filter_map(|col| {
-                if col.auto_increment() {
+                if col.auto_increment() || col.embedding_source().is_some() {
                     None
                 } else {
                     let name = col.name();
@@ -448,6 +605,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        if rows_affected > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+        }
         Self::after_insert(&ctx, model_data).await?;
         if success {
             Ok(ctx)
@@ -466,7 +626,10 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         }
 
         let pool = Self::acquire_writer().await?.pool();
-        let columns = Self::columns();
+        let columns = Self::columns()
+            .iter()
+            .filter(|col| col.embedding_source().is_none())
+            .collect::<Vec<_>>();
         let mut values = Vec::with_capacity(models.len());
         for mut model in models.into_iter() {
             let _model_data = model.before_insert().await?;
@@ -481,7 +644,11 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         }
 
         let table_name = Query::table_name_escaped::<Self>();
-        let fields = Self::fields().join(", ");
+        let fields = columns
+            .iter()
+            .map(|col| col.name())
+            .collect::<Vec<_>>()
+            .join(", ");
         let values = values.join(", ");
         let sql = format!("INSERT INTO {table_name} ({fields}) VALUES {values};");
         let mut ctx = Self::before_scan(&sql).await?;
@@ -490,6 +657,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), true);
         Self::after_scan(&ctx).await?;
+        if rows_affected > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+        }
         Ok(ctx)
     }
 
@@ -507,7 +677,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let mut mutations = Vec::with_capacity(num_writable_fields);
         for col in Self::columns() {
             let field = col.name();
-            if !read_only_fields.contains(&field) {
+            if !read_only_fields.contains(&field) && col.embedding_source().is_none() {
                 let value = col.encode_value(map.get(field));
                 let field = Query::format_field(field);
                 mutations.push(format!("{field} = {value}"));
@@ -525,6 +695,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        if rows_affected > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+        }
         Self::after_update(&ctx, model_data).await?;
         if success {
             Ok(ctx)
@@ -571,6 +744,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        if rows_affected > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+        }
         Self::after_mutation(&ctx).await?;
         if success {
             Ok(ctx)
@@ -597,10 +773,94 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), true);
         Self::after_scan(&ctx).await?;
+        if rows_affected > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+        }
         Self::after_mutation(&ctx).await?;
         Ok(ctx)
     }
 
+    /// Claims at most one model selected by the query in the table by updating it
+    /// with the mutation, and decodes the updated model as an instance of type `T`.
+    ///
+    /// The selected row is locked by `FOR UPDATE SKIP LOCKED` except for SQLite
+    /// which serializes the writes, so that the concurrent claims never update
+    /// the same model and do not wait for each other.
+    async fn claim_one<T>(query: &Query, mutation: &mut Mutation) -> Result<Option<T>, Error>
+    where
+        T: DecodeRow<DatabaseRow, Error = Error>,
+    {
+        let pool = Self::acquire_writer().await?.pool();
+        Self::before_mutation(query, mutation).await?;
+
+        let primary_key_name = Self::PRIMARY_KEY_NAME;
+        let table_name = query.format_table_name::<Self>();
+        let filters = query.format_filters::<Self>();
+        let sort = query.format_sort();
+        let updates = mutation.format_updates::<Self>();
+        let (mut ctx, data) = if cfg!(any(
+            feature = "orm-mariadb",
+            feature = "orm-mysql",
+            feature = "orm-tidb"
+        )) {
+            // MySQL doesn't support `UPDATE ... RETURNING`,
+            // so the row is locked and updated inside of a transaction.
+            let mut transaction = pool.begin().await?;
+            let connection: &mut super::DatabaseConnection = &mut transaction;
+            let sql = format!(
+                "SELECT {primary_key_name} FROM {table_name} \
+                    {filters} {sort} LIMIT 1 FOR UPDATE SKIP LOCKED;"
+            );
+            let Some(row) = (&mut *connection).fetch_optional(&sql).await? else {
+                return Ok(None);
+            };
+            let map = Map::decode_row(&row)?;
+            let primary_key = Self::primary_key_column().encode_value(map.get(primary_key_name));
+            let sql = format!(
+                "UPDATE {table_name} SET {updates} WHERE {primary_key_name} = {primary_key};"
+            );
+            let mut ctx = Self::before_scan(&sql).await?;
+            ctx.set_query(sql);
+            (&mut *connection).execute(ctx.query()).await?;
+
+            let select_sql =
+                format!("SELECT * FROM {table_name} WHERE {primary_key_name} = {primary_key};");
+            let data = match (&mut *connection).fetch_optional(&select_sql).await? {
+                Some(row) => Some(T::decode_row(&row)?),
+                None => None,
+            };
+            transaction.commit().await?;
+            (ctx, data)
+        } else {
+            let lock = if cfg!(feature = "orm-postgres") {
+                "FOR UPDATE SKIP LOCKED"
+            } else {
+                ""
+            };
+            let sql = format!(
+                "UPDATE {table_name} SET {updates} WHERE {primary_key_name} IN \
+                    (SELECT {primary_key_name} FROM {table_name} {filters} {sort} LIMIT 1 {lock}) \
+                    RETURNING *;"
+            );
+            let mut ctx = Self::before_scan(&sql).await?;
+            let data = match pool.fetch_optional(&sql).await? {
+                Some(row) => Some(T::decode_row(&row)?),
+                None => None,
+            };
+            ctx.set_query(sql);
+            (ctx, data)
+        };
+
+        let rows_affected = u64::from(data.is_some());
+        ctx.set_query_result(Some(rows_affected), true);
+        Self::after_scan(&ctx).await?;
+        if rows_affected > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+        }
+        Self::after_mutation(&ctx).await?;
+        Ok(data)
+    }
+
     /// Updates or inserts the model into the table.
     async fn upsert(mut self) -> Result<QueryContext, Error> {
         let pool = Self::acquire_writer().await?.pool();
@@ -608,19 +868,24 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
         let map = self.into_map();
         let table_name = Query::table_name_escaped::<Self>();
-        let fields = Self::fields();
-        let num_fields = fields.len();
+        let num_fields = Self::fields().len();
         let read_only_fields = Self::read_only_fields();
         let num_writable_fields = num_fields - read_only_fields.len();
+        let mut fields = Vec::with_capacity(num_fields);
         let mut values = Vec::with_capacity(num_fields);
         let mut mutations = Vec::with_capacity(num_writable_fields);
         for col in Self::columns() {
+            if col.embedding_source().is_some() {
+                continue;
+            }
+
             let field = col.name();
             let value = col.encode_value(map.get(field));
             if !read_only_fields.contains(&field) {
                 let field = Query::format_field(field);
                 mutations.push(format!("{field} = {value}"));
             }
+            fields.push(field);
             values.push(value);
         }
 
@@ -656,6 +921,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        if rows_affected > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+        }
         Self::after_upsert(&ctx, model_data).await?;
         if success {
             Ok(ctx)
@@ -696,6 +964,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.add_argument(primary_key);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        if rows_affected > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+        }
         self.after_delete(&ctx, model_data).await?;
         if success {
             Ok(ctx)
@@ -727,6 +998,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        if rows_affected > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+        }
         Self::after_query(&ctx).await?;
         if success {
             Ok(ctx)
@@ -752,6 +1026,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query(sql);
         ctx.set_query_result(Some(rows_affected), true);
         Self::after_scan(&ctx).await?;
+        if rows_affected > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+        }
         Self::after_query(&ctx).await?;
         Ok(ctx)
     }
@@ -765,6 +1042,13 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let pool = Self::acquire_reader().await?.pool();
         Self::before_query(query).await?;
 
+        let cache_entry = QueryCacheEntry::new::<Self, T>("find", query, &[]).await;
+        if let Some(entry) = &cache_entry {
+            if let Some(data) = entry.load().await.and_then(decode_cached_rows) {
+                return Ok(data);
+            }
+        }
+
         let table_name = query.format_table_name::<Self>();
         let projection = query.format_table_fields::<Self>();
         let filters = query.format_filters::<Self>();
@@ -782,6 +1066,11 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query_result(Some(u64::try_from(data.len())?), true);
         Self::after_scan(&ctx).await?;
         Self::after_query(&ctx).await?;
+        if let Some(entry) = cache_entry {
+            if let Some(value) = encode_cached_rows(&data) {
+                entry.store(value).await;
+            }
+        }
         Ok(data)
     }
 
@@ -806,6 +1095,13 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let pool = Self::acquire_reader().await?.pool();
         Self::before_query(query).await?;
 
+        let cache_entry = QueryCacheEntry::new::<Self, T>("find_one", query, &[]).await;
+        if let Some(entry) = &cache_entry {
+            if let Some(data) = entry.load().await.and_then(decode_cached_rows) {
+                return Ok(data.into_iter().next());
+            }
+        }
+
         let table_name = query.format_table_name::<Self>();
         let projection = query.format_table_fields::<Self>();
         let filters = query.format_filters::<Self>();
@@ -822,6 +1118,11 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query_result(Some(num_rows), true);
         Self::after_scan(&ctx).await?;
         Self::after_query(&ctx).await?;
+        if let Some(entry) = cache_entry {
+            if let Some(value) = encode_cached_rows(data.as_slice()) {
+                entry.store(value).await;
+            }
+        }
         Ok(data)
     }
 
@@ -1108,6 +1409,13 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         let pool = Self::acquire_reader().await?.pool();
         Self::before_count(query).await?;
 
+        let cache_entry = QueryCacheEntry::new::<Self, u64>("count", query, &[]).await;
+        if let Some(entry) = &cache_entry {
+            if let Some(count) = entry.load().await.and_then(|value| value.as_u64()) {
+                return Ok(count);
+            }
+        }
+
         let table_name = query.format_table_name::<Self>();
         let filters = query.format_filters::<Self>();
         let sql = format!("SELECT count(*) AS count FROM {table_name} {filters};");
@@ -1122,6 +1430,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query_result(Some(count), true);
         Self::after_scan(&ctx).await?;
         Self::after_count(&ctx).await?;
+        if let Some(entry) = cache_entry {
+            entry.store(count.into()).await;
+        }
         Ok(count)
     }
 
@@ -1174,6 +1485,7 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
     }
 
     /// Executes the query in the table, and returns the total number of rows affected.
+    /// The cached query results of the model are invalidated if any rows are affected.
     async fn execute(query: &str, params: Option<&Map>) -> Result<QueryContext, Error> {
         let pool = Self::acquire_writer().await?.pool();
         let (sql, values) = Query::prepare_query(query, params);
@@ -1189,6 +1501,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.append_arguments(&mut arguments);
         ctx.set_query_result(Some(rows_affected), true);
         Self::after_scan(&ctx).await?;
+        if rows_affected > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+        }
         Ok(ctx)
     }
 
@@ -1298,6 +1613,9 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.add_argument(primary_key);
         ctx.set_query_result(Some(rows_affected), success);
         Self::after_scan(&ctx).await?;
+        if rows_affected > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+        }
         if success {
             Ok(ctx)
         } else {
@@ -1318,6 +1636,15 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
 
         let primary_key_name = Self::PRIMARY_KEY_NAME;
         let query = Self::default_query();
+        let primary_key_string = primary_key.to_string();
+        let cache_entry =
+            QueryCacheEntry::new::<Self, T>("find_by_id", &query, &[&primary_key_string]).await;
+        if let Some(entry) = &cache_entry {
+            if let Some(data) = entry.load().await.and_then(decode_cached_rows) {
+                return Ok(data.into_iter().next());
+            }
+        }
+
         let table_name = query.format_table_name::<Self>();
         let projection = query.format_projection();
         let placeholder = Query::placeholder(1);
@@ -1345,6 +1672,11 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         ctx.set_query_result(Some(num_rows), true);
         Self::after_scan(&ctx).await?;
         Self::after_query(&ctx).await?;
+        if let Some(entry) = cache_entry {
+            if let Some(value) = encode_cached_rows(data.as_slice()) {
+                entry.store(value).await;
+            }
+        }
         Ok(data)
     }
 
@@ -1475,3 +1807,49 @@ pub trait Schema: 'static + Send + Sync + ModelHooks {
         }
     }
 }
+
+/// Encodes the rows as a JSON array for the query cache.
+fn encode_cached_rows<T: DecodeRow<DatabaseRow>>(rows: &[T]) -> Option<JsonValue> {
+    rows.iter()
+        .map(|row| row.encode_cached())
+        .collect::<Option<Vec<_>>>()
+        .map(JsonValue::Array)
+}
+
+/// Decodes the rows from a JSON array in the query cache.
+fn decode_cached_rows<T: DecodeRow<DatabaseRow>>(value: JsonValue) -> Option<Vec<T>> {
+    match value {
+        JsonValue::Array(values) => values.into_iter().map(T::decode_cached).collect(),
+        _ => None,
+    }
+}
+
+/// Generates the text embeddings normalized as unit vectors for the model.
+#[cfg(feature = "chatbot")]
+async fn generate_embeddings(
+    model_name: &'static str,
+    inputs: Vec<String>,
+) -> Result<Vec<Vec<f32>>, Error> {
+    use crate::chatbot::{normalize_embedding, EmbeddingService, GlobalChatbot};
+
+    let num_inputs = inputs.len();
+    let Some(embedder) = GlobalChatbot::embedder() else {
+        bail!(
+            "no chatbot is configured for the text embeddings of the `{}` model",
+            model_name
+        );
+    };
+    let mut embeddings = embedder.try_embed(inputs).await?;
+    if embeddings.len() != num_inputs {
+        bail!(
+            "{} embeddings are generated for the `{}` model while {} are expected",
+            embeddings.len(),
+            model_name,
+            num_inputs
+        );
+    }
+    for embedding in embeddings.iter_mut() {
+        normalize_embedding(embedding);
+    }
+    Ok(embeddings)
+}
@@ -453,6 +453,16 @@ impl DecodeRow<DatabaseRow> for Map {
         }
         Ok(map)
     }
+
+    #[inline]
+    fn encode_cached(&self) -> Option<JsonValue> {
+        Some(self.clone().into())
+    }
+
+    #[inline]
+    fn decode_cached(value: JsonValue) -> Option<Self> {
+        value.into_map_opt()
+    }
 }
 
 #[cfg(feature = "orm-sqlx")]
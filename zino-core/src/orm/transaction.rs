@@ -1,5 +1,6 @@
 use super::{
-    executor::Executor, mutation::MutationExt, query::QueryExt, schema::Schema, DatabaseDriver,
+    cache::invalidate_caches, executor::Executor, mutation::MutationExt, query::QueryExt,
+    schema::Schema, DatabaseDriver,
 };
 use crate::{
     error::Error,
@@ -19,14 +20,16 @@ where
 {
     /// Executes the specific operations inside of a transaction.
     /// If the operations return an error, the transaction will be rolled back;
-    /// if not, the transaction will be committed.
+    /// if not, the transaction will be committed and the cached query results
+    /// of the model will be invalidated.
     async fn transaction<F, T>(tx: F) -> Result<T, Error>
     where
         F: for<'t> FnOnce(&'t mut Tx) -> BoxFuture<'t, Result<T, Error>>;
 
     /// Executes the queries sequentially inside of a transaction.
     /// If it returns an error, the transaction will be rolled back;
-    /// if not, the transaction will be committed.
+    /// if not, the transaction will be committed and the cached query results
+    /// of the model will be invalidated if any rows are affected.
     async fn transactional_execute(queries: &[&str], params: Option<&Map>) -> Result<u64, Error>;
 
     /// Inserts the model and its associations inside of a transaction.
@@ -40,6 +43,11 @@ where
 
     /// Deletes the models inside of a transaction.
     async fn transactional_delete<M: Schema>(queries: (&Query, &Query)) -> Result<u64, Error>;
+
+    /// Replaces the models selected by the query with the new models inside of a transaction.
+    /// The embeddings of the new models are generated in a batch if the `chatbot` feature
+    /// is enabled. The hooks of inserting are not called for the new models.
+    async fn transactional_replace(query: &Query, models: Vec<Self>) -> Result<u64, Error>;
 }
 
 #[cfg(feature = "orm-sqlx")]
@@ -57,6 +65,7 @@ where
         let mut transaction = Self::acquire_writer().await?.pool().begin().await?;
         let data = tx(&mut transaction).await?;
         transaction.commit().await?;
+        invalidate_caches(Self::MODEL_NAME).await;
         Ok(data)
     }
 
@@ -85,6 +94,9 @@ where
             Self::after_scan(&ctx).await?;
         }
         transaction.commit().await?;
+        if total_rows > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+        }
         Ok(total_rows)
     }
 
@@ -101,7 +113,7 @@ where
         let values = columns
             .iter()
             .filter_map(|col| {
-                if col.auto_increment() {
+                if col.auto_increment() || col.embedding_source().is_some() {
                     None
                 } else {
                     let name = col.name();
@@ -130,7 +142,10 @@ where
         Self::after_insert(&ctx, model_data).await?;
 
         // Inserts associations
-        let columns = S::columns();
+        let columns = S::columns()
+            .iter()
+            .filter(|col| col.embedding_source().is_none())
+            .collect::<Vec<_>>();
         let mut values = Vec::with_capacity(models.len());
         for mut model in models.into_iter() {
             let _model_data = model.before_insert().await?;
@@ -145,7 +160,11 @@ where
         }
 
         let table_name = Query::table_name_escaped::<S>();
-        let fields = S::fields().join(", ");
+        let fields = columns
+            .iter()
+            .map(|col| col.name())
+            .collect::<Vec<_>>()
+            .join(", ");
         let values = values.join(", ");
         let sql = format!("INSERT INTO {table_name} ({fields}) VALUES {values};");
         let mut ctx = S::before_scan(&sql).await?;
@@ -158,6 +177,10 @@ where
 
         // Commits the transaction
         transaction.commit().await?;
+        if total_rows > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+            invalidate_caches(S::MODEL_NAME).await;
+        }
         Ok(total_rows)
     }
 
@@ -205,6 +228,10 @@ where
 
         // Commits the transaction
         transaction.commit().await?;
+        if total_rows > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+            invalidate_caches(S::MODEL_NAME).await;
+        }
         Ok(total_rows)
     }
 
@@ -245,6 +272,80 @@ where
 
         // Commits the transaction
         transaction.commit().await?;
+        if total_rows > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+            invalidate_caches(S::MODEL_NAME).await;
+        }
+        Ok(total_rows)
+    }
+
+    async fn transactional_replace(query: &Query, models: Vec<Self>) -> Result<u64, Error> {
+        #[cfg(feature = "chatbot")]
+        let models = Self::embed_models(models).await?;
+        #[cfg(not(feature = "chatbot"))]
+        let models = models
+            .into_iter()
+            .map(|model| model.into_map())
+            .collect::<Vec<_>>();
+
+        let mut transaction = Self::acquire_writer().await?.pool().begin().await?;
+        let connection = transaction.acquire().await?;
+
+        // Deletes the models
+        Self::before_query(query).await?;
+
+        let table_name = query.format_table_name::<Self>();
+        let filters = query.format_filters::<Self>();
+        let sql = format!("DELETE FROM {table_name} {filters};");
+        let mut ctx = Self::before_scan(&sql).await?;
+
+        let mut total_rows = 0;
+        let rows_affected = connection.execute(&sql).await?.rows_affected();
+        total_rows += rows_affected;
+        ctx.set_query(sql);
+        ctx.set_query_result(Some(rows_affected), true);
+        Self::after_scan(&ctx).await?;
+        Self::after_query(&ctx).await?;
+
+        // Inserts the new models
+        if !models.is_empty() {
+            let columns = Self::columns()
+                .iter()
+                .filter(|col| !col.auto_increment())
+                .collect::<Vec<_>>();
+            let values = models
+                .iter()
+                .map(|map| {
+                    let entries = columns
+                        .iter()
+                        .map(|col| col.encode_value(map.get(col.name())))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    format!("({entries})")
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let fields = columns
+                .iter()
+                .map(|col| col.name())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let table_name = Query::table_name_escaped::<Self>();
+            let sql = format!("INSERT INTO {table_name} ({fields}) VALUES {values};");
+            let mut ctx = Self::before_scan(&sql).await?;
+
+            let rows_affected = connection.execute(&sql).await?.rows_affected();
+            total_rows += rows_affected;
+            ctx.set_query(sql);
+            ctx.set_query_result(Some(rows_affected), true);
+            Self::after_scan(&ctx).await?;
+        }
+
+        // Commits the transaction
+        transaction.commit().await?;
+        if total_rows > 0 {
+            invalidate_caches(Self::MODEL_NAME).await;
+        }
         Ok(total_rows)
     }
 }
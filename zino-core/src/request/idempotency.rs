@@ -0,0 +1,710 @@
+use crate::{
+    crypto::Digest, datetime::DateTime, error::Error, extension::TomlTableExt,
+    response::StoredResponse, state::State, BoxFuture, LazyLock,
+};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use sha2::Digest as _;
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering::Relaxed},
+        Arc,
+    },
+    time::Duration,
+};
+
+#[cfg(feature = "accessor")]
+use crate::response::stored::{read_json, write_json};
+
+#[cfg(feature = "orm")]
+use crate::{extension::JsonObjectExt, Map};
+#[cfg(feature = "orm")]
+use std::sync::atomic::AtomicBool;
+
+/// A record of the request with an idempotency key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct IdempotencyRecord {
+    /// Fingerprint of the request.
+    fingerprint: String,
+    /// Stored response. It is `None` if the request is in progress.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response: Option<StoredResponse>,
+    /// Expiration time.
+    expires_at: DateTime,
+}
+
+impl IdempotencyRecord {
+    /// Creates a new instance for the request in progress.
+    #[inline]
+    pub fn new(fingerprint: impl Into<String>, ttl: Duration) -> Self {
+        Self {
+            fingerprint: fingerprint.into(),
+            response: None,
+            expires_at: DateTime::now() + ttl,
+        }
+    }
+
+    /// Sets the response for the record.
+    #[inline]
+    pub fn set_response(&mut self, response: StoredResponse) {
+        self.response = Some(response);
+    }
+
+    /// Returns the fingerprint of the request.
+    #[inline]
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Returns the stored response.
+    #[inline]
+    pub fn response(&self) -> Option<&StoredResponse> {
+        self.response.as_ref()
+    }
+
+    /// Returns the expiration time.
+    #[inline]
+    pub fn expires_at(&self) -> DateTime {
+        self.expires_at
+    }
+
+    /// Returns `true` if the request is still in progress.
+    #[inline]
+    pub fn is_in_progress(&self) -> bool {
+        self.response.is_none()
+    }
+
+    /// Returns `true` if the record has been expired.
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= DateTime::now()
+    }
+}
+
+/// Status of a request with an idempotency key.
+#[derive(Debug, Clone)]
+pub enum IdempotencyStatus {
+    /// The key has been acquired by the request.
+    Acquired,
+    /// The key has been used by a completed request whose response can be replayed.
+    Completed(IdempotencyRecord),
+    /// The key is being used by a concurrent request in progress.
+    InProgress,
+    /// The key has been used by another request with a different fingerprint.
+    Mismatched,
+}
+
+/// Storage for the idempotency records.
+pub trait IdempotencyStore: Send + Sync {
+    /// Attempts to acquire the key for the record in progress. It returns `None` if the key
+    /// has been acquired, or the existing record if another request has used the key.
+    fn try_acquire<'a>(
+        &'a self,
+        key: &'a str,
+        record: &'a IdempotencyRecord,
+    ) -> BoxFuture<'a, Result<Option<IdempotencyRecord>, Error>>;
+
+    /// Gets the record for the key.
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<IdempotencyRecord>, Error>>;
+
+    /// Stores the completed record for the key.
+    fn store<'a>(
+        &'a self,
+        key: &'a str,
+        record: &'a IdempotencyRecord,
+    ) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Releases the key so that the request can be retried.
+    fn release<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>>;
+}
+
+/// In-memory storage for the idempotency records.
+///
+/// An expired record is replaced when its key is acquired again, and the expired records
+/// are purged only when the number of records has doubled since the last purge,
+/// so that the amortized cost of each call is constant.
+#[derive(Debug, Default)]
+pub struct MemoryIdempotencyStore {
+    /// Records.
+    records: Mutex<HashMap<String, IdempotencyRecord>>,
+    /// Number of records which triggers purging the expired ones.
+    purge_threshold: AtomicUsize,
+}
+
+impl MemoryIdempotencyStore {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IdempotencyStore for MemoryIdempotencyStore {
+    fn try_acquire<'a>(
+        &'a self,
+        key: &'a str,
+        record: &'a IdempotencyRecord,
+    ) -> BoxFuture<'a, Result<Option<IdempotencyRecord>, Error>> {
+        let mut records = self.records.lock();
+        let purge_threshold = self.purge_threshold.load(Relaxed).max(1024);
+        if records.len() >= purge_threshold {
+            records.retain(|_, record| !record.is_expired());
+            self.purge_threshold.store(records.len() * 2, Relaxed);
+        }
+        let result = match records.get(key) {
+            Some(existing_record) if !existing_record.is_expired() => Some(existing_record.clone()),
+            _ => {
+                records.insert(key.to_owned(), record.clone());
+                None
+            }
+        };
+        Box::pin(async move { Ok(result) })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<IdempotencyRecord>, Error>> {
+        let record = self
+            .records
+            .lock()
+            .get(key)
+            .filter(|record| !record.is_expired())
+            .cloned();
+        Box::pin(async move { Ok(record) })
+    }
+
+    fn store<'a>(
+        &'a self,
+        key: &'a str,
+        record: &'a IdempotencyRecord,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        self.records.lock().insert(key.to_owned(), record.clone());
+        Box::pin(async { Ok(()) })
+    }
+
+    fn release<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        self.records.lock().remove(key);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Storage for the idempotency records backed by an accessor operator.
+///
+/// Since the key-value services do not provide an atomic `try_acquire` operation,
+/// concurrent duplicates may not be detected within a very short interval.
+#[cfg(feature = "accessor")]
+#[derive(Debug, Clone)]
+pub struct AccessorIdempotencyStore {
+    /// Storage operator.
+    operator: &'static opendal::Operator,
+    /// Path prefix.
+    prefix: &'static str,
+}
+
+#[cfg(feature = "accessor")]
+impl AccessorIdempotencyStore {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(operator: &'static opendal::Operator, prefix: &'static str) -> Self {
+        Self { operator, prefix }
+    }
+
+    /// Returns the path for the key.
+    #[inline]
+    fn path(&self, key: &str) -> String {
+        [self.prefix, key].concat()
+    }
+}
+
+#[cfg(feature = "accessor")]
+impl IdempotencyStore for AccessorIdempotencyStore {
+    fn try_acquire<'a>(
+        &'a self,
+        key: &'a str,
+        record: &'a IdempotencyRecord,
+    ) -> BoxFuture<'a, Result<Option<IdempotencyRecord>, Error>> {
+        Box::pin(async move {
+            if let Some(existing_record) = self.get(key).await? {
+                return Ok(Some(existing_record));
+            }
+            self.store(key, record).await?;
+            Ok(None)
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<IdempotencyRecord>, Error>> {
+        Box::pin(async move {
+            let path = self.path(key);
+            match read_json::<IdempotencyRecord>(self.operator, &path).await? {
+                Some(record) if record.is_expired() => {
+                    self.operator.delete(&path).await?;
+                    Ok(None)
+                }
+                record => Ok(record),
+            }
+        })
+    }
+
+    fn store<'a>(
+        &'a self,
+        key: &'a str,
+        record: &'a IdempotencyRecord,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move { write_json(self.operator, &self.path(key), record).await })
+    }
+
+    fn release<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            self.operator.delete(&self.path(key)).await?;
+            Ok(())
+        })
+    }
+}
+
+/// Storage for the idempotency records backed by a database table.
+///
+/// The table has the columns `id` and `content`, in which the primary key `id`
+/// guarantees that only one request can acquire the key. The key is inserted
+/// by a statement ignoring the duplicates, so that the other errors are not
+/// mistaken for a used key. The table is created on the first acquisition
+/// if it does not exist.
+#[cfg(feature = "orm")]
+#[derive(Debug, Clone)]
+pub struct OrmIdempotencyStore {
+    /// Name of the connection pool.
+    pool_name: &'static str,
+    /// Table name.
+    table_name: &'static str,
+    /// A flag to indicate whether the table has been created.
+    table_created: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "orm")]
+impl OrmIdempotencyStore {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(pool_name: &'static str, table_name: &'static str) -> Self {
+        Self {
+            pool_name,
+            table_name,
+            table_created: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Creates the table if it does not exist.
+    pub async fn create_table(&self) -> Result<(), Error> {
+        if self.table_created.load(Relaxed) {
+            return Ok(());
+        }
+
+        let table_name = self.table_name;
+        let sql = format!(
+            "CREATE TABLE IF NOT EXISTS {table_name} \
+                (id VARCHAR(255) PRIMARY KEY, content TEXT NOT NULL);"
+        );
+        crate::orm::execute_raw(self.pool_name, &sql, &Map::new()).await?;
+        self.table_created.store(true, Relaxed);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "orm")]
+impl IdempotencyStore for OrmIdempotencyStore {
+    fn try_acquire<'a>(
+        &'a self,
+        key: &'a str,
+        record: &'a IdempotencyRecord,
+    ) -> BoxFuture<'a, Result<Option<IdempotencyRecord>, Error>> {
+        Box::pin(async move {
+            self.create_table().await?;
+
+            let query = crate::orm::format_insert_ignore(
+                self.table_name,
+                "id",
+                "id, content",
+                "#{id}, #{content}",
+            );
+            let mut params = Map::new();
+            params.upsert("id", key);
+            params.upsert("content", serde_json::to_string(record)?);
+            if crate::orm::execute_raw(self.pool_name, &query, &params).await? == 0 {
+                if let Some(existing_record) = self.get(key).await? {
+                    return Ok(Some(existing_record));
+                }
+
+                // The expired record has been removed.
+                if crate::orm::execute_raw(self.pool_name, &query, &params).await? == 0 {
+                    return self.get(key).await;
+                }
+            }
+            Ok(None)
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<IdempotencyRecord>, Error>> {
+        Box::pin(async move {
+            let table_name = self.table_name;
+            let query = format!("SELECT content FROM {table_name} WHERE id = #{{id}};");
+            let mut params = Map::new();
+            params.upsert("id", key);
+
+            let Some(content) =
+                crate::orm::fetch_raw_value(self.pool_name, &query, &params, "content").await?
+            else {
+                return Ok(None);
+            };
+            let record = serde_json::from_str::<IdempotencyRecord>(&content)?;
+            if record.is_expired() {
+                self.release(key).await?;
+                Ok(None)
+            } else {
+                Ok(Some(record))
+            }
+        })
+    }
+
+    fn store<'a>(
+        &'a self,
+        key: &'a str,
+        record: &'a IdempotencyRecord,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let table_name = self.table_name;
+            let query =
+                format!("UPDATE {table_name} SET content = #{{content}} WHERE id = #{{id}};");
+            let mut params = Map::new();
+            params.upsert("id", key);
+            params.upsert("content", serde_json::to_string(record)?);
+            crate::orm::execute_raw(self.pool_name, &query, &params).await?;
+            Ok(())
+        })
+    }
+
+    fn release<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let table_name = self.table_name;
+            let query = format!("DELETE FROM {table_name} WHERE id = #{{id}};");
+            let mut params = Map::new();
+            params.upsert("id", key);
+            crate::orm::execute_raw(self.pool_name, &query, &params).await?;
+            Ok(())
+        })
+    }
+}
+
+/// Global access to the shared idempotency store.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlobalIdempotency;
+
+impl GlobalIdempotency {
+    /// Replaces the shared store with a custom one.
+    #[inline]
+    pub fn set_store(store: impl IdempotencyStore + 'static) {
+        *SHARED_IDEMPOTENCY_STORE.write() = Arc::new(store);
+    }
+
+    /// Returns the shared store.
+    #[inline]
+    pub fn store() -> Arc<dyn IdempotencyStore> {
+        SHARED_IDEMPOTENCY_STORE.read().clone()
+    }
+
+    /// Computes the fingerprint of a request with the method, URI and body.
+    pub fn fingerprint(method: &str, uri: &str, body: &[u8]) -> String {
+        let mut hasher = Digest::new();
+        hasher.update(method.as_bytes());
+        hasher.update(b" ");
+        hasher.update(uri.as_bytes());
+        hasher.update(b"\n");
+        hasher.update(body);
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Returns the key in the store for the idempotency key sent by a client.
+    ///
+    /// The keys are namespaced by the authenticated identity, which is the subject
+    /// of a JWT token in the `authorization` header verified by the shared key,
+    /// or by the client IP if the request is not authenticated. The unverified
+    /// credentials are never used, so a client can not replay the responses to the others.
+    pub fn scoped_key(key: &str, authorization: Option<&str>, client_ip: Option<IpAddr>) -> String {
+        let scope = match (authenticated_identity(authorization), client_ip) {
+            (Some(identity), _) => format!("user:{identity}"),
+            (None, Some(ip)) => format!("ip:{ip}"),
+            (None, None) => "anonymous".to_owned(),
+        };
+        let mut hasher = Digest::new();
+        hasher.update(scope.as_bytes());
+        hasher.update(b"\n");
+        hasher.update(key.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Checks the status of a request with the scoped key and fingerprint.
+    /// The key will be acquired with a short lease if it has not been used,
+    /// so that it can be reused if the request is aborted before completion.
+    pub async fn check(key: &str, fingerprint: &str) -> Result<IdempotencyStatus, Error> {
+        let store = Self::store();
+        let record = IdempotencyRecord::new(fingerprint, Self::lease());
+        let status = match store.try_acquire(key, &record).await? {
+            Some(record) if record.fingerprint() != fingerprint => IdempotencyStatus::Mismatched,
+            Some(record) if record.is_in_progress() => IdempotencyStatus::InProgress,
+            Some(record) => IdempotencyStatus::Completed(record),
+            None => IdempotencyStatus::Acquired,
+        };
+        Ok(status)
+    }
+
+    /// Returns the time-to-live of the completed records.
+    #[inline]
+    pub fn ttl() -> Duration {
+        IDEMPOTENCY_CONFIG.ttl
+    }
+
+    /// Returns the lease of the records in progress.
+    #[inline]
+    pub fn lease() -> Duration {
+        IDEMPOTENCY_CONFIG.lease
+    }
+
+    /// Returns the maximum size of the response body to be stored.
+    /// The key will be released if the body is larger or its size is unknown.
+    #[inline]
+    pub fn max_body_size() -> usize {
+        IDEMPOTENCY_CONFIG.max_body_size
+    }
+
+    /// Returns `true` if the response header can be stored and replayed.
+    ///
+    /// Only the headers in the allowlist are replayed, which never includes `set-cookie`.
+    #[inline]
+    pub fn is_replayed_header(name: &str) -> bool {
+        StoredResponse::is_allowed_header(&IDEMPOTENCY_CONFIG.replayed_headers, name)
+    }
+
+    /// Returns the maximum duration to wait for a concurrent duplicate.
+    /// A `409 Conflict` response should be returned if it is zero or has elapsed.
+    #[inline]
+    pub fn wait_timeout() -> Duration {
+        IDEMPOTENCY_CONFIG.wait_timeout
+    }
+
+    /// Returns the header name of the idempotency key.
+    #[inline]
+    pub fn header_name() -> &'static str {
+        IDEMPOTENCY_CONFIG.header_name
+    }
+}
+
+/// Returns the subject of the JWT token in the `authorization` header
+/// if it can be verified by the shared key.
+#[cfg(feature = "jwt")]
+fn authenticated_identity(authorization: Option<&str>) -> Option<String> {
+    let claims = crate::auth::verify_authorization(authorization?)?;
+    claims.subject().map(|s| s.to_owned())
+}
+
+/// Returns `None` since the credentials can not be verified without the `jwt` feature.
+#[cfg(not(feature = "jwt"))]
+#[inline]
+fn authenticated_identity(_authorization: Option<&str>) -> Option<String> {
+    None
+}
+
+/// Idempotency config.
+#[derive(Debug)]
+struct IdempotencyConfig {
+    /// Time-to-live of the completed records.
+    ttl: Duration,
+    /// Lease of the records in progress.
+    lease: Duration,
+    /// Maximum size of the response body to be stored.
+    max_body_size: usize,
+    /// Response headers to be replayed.
+    replayed_headers: Vec<&'static str>,
+    /// Maximum duration to wait for a concurrent duplicate.
+    wait_timeout: Duration,
+    /// Header name of the idempotency key.
+    header_name: &'static str,
+}
+
+/// Shared idempotency config.
+static IDEMPOTENCY_CONFIG: LazyLock<IdempotencyConfig> = LazyLock::new(|| {
+    let mut ttl = Duration::from_secs(24 * 60 * 60);
+    let mut lease = Duration::from_secs(60);
+    let mut max_body_size = 1024 * 1024; // 1MB
+    let mut replayed_headers = vec![
+        "content-type",
+        "content-language",
+        "content-location",
+        "location",
+        "etag",
+        "last-modified",
+    ];
+    let mut wait_timeout = Duration::ZERO;
+    let mut header_name = "idempotency-key";
+    if let Some(config) = State::shared().get_config("idempotency") {
+        if let Some(duration) = config.get_duration("ttl") {
+            ttl = duration;
+        }
+        if let Some(duration) = config.get_duration("lease") {
+            lease = duration;
+        }
+        if let Some(size) = config.get_usize("max-body-size") {
+            max_body_size = size;
+        }
+        if let Some(headers) = config.get_str_array("replayed-headers") {
+            replayed_headers = headers;
+        }
+        if let Some(timeout) = config.get_duration("wait-timeout") {
+            wait_timeout = timeout;
+        }
+        if let Some(name) = config.get_str("header-name") {
+            header_name = name;
+        }
+    }
+    IdempotencyConfig {
+        ttl,
+        lease,
+        max_body_size,
+        replayed_headers,
+        wait_timeout,
+        header_name,
+    }
+});
+
+/// Shared idempotency store.
+static SHARED_IDEMPOTENCY_STORE: LazyLock<RwLock<Arc<dyn IdempotencyStore>>> =
+    LazyLock::new(|| {
+        #[cfg(feature = "orm")]
+        if let Some(config) = State::shared().get_config("idempotency") {
+            if let Some(table_name) = config.get_str("table") {
+                let pool_name = config.get_str("pool").unwrap_or("main");
+                let store = OrmIdempotencyStore::new(pool_name, table_name);
+                return RwLock::new(Arc::new(store));
+            }
+        }
+
+        #[cfg(feature = "accessor")]
+        if let Some(config) = State::shared().get_config("idempotency") {
+            if let Some(name) = config.get_str("accessor") {
+                if let Some(operator) = crate::accessor::GlobalAccessor::get(name) {
+                    let prefix = config.get_str("prefix").unwrap_or("idempotency/");
+                    let store = AccessorIdempotencyStore::new(operator, prefix);
+                    return RwLock::new(Arc::new(store));
+                } else {
+                    tracing::error!("fail to get the accessor `{name}` for the idempotency store");
+                }
+            }
+        }
+        RwLock::new(Arc::new(MemoryIdempotencyStore::new()))
+    });
+
+#[cfg(test)]
+mod tests {
+    use super::{GlobalIdempotency, IdempotencyRecord, IdempotencyStore, MemoryIdempotencyStore};
+    use crate::response::StoredResponse;
+    use std::{net::IpAddr, time::Duration};
+
+    #[test]
+    fn it_scopes_idempotency_keys() {
+        let ip = "10.0.0.1".parse::<IpAddr>().ok();
+        let key = GlobalIdempotency::scoped_key("order-1", None, ip);
+        assert_ne!(
+            key,
+            GlobalIdempotency::scoped_key("order-1", None, "10.0.0.2".parse().ok())
+        );
+
+        // The unverified credentials are ignored.
+        assert_eq!(
+            key,
+            GlobalIdempotency::scoped_key("order-1", Some("Bearer forged"), ip)
+        );
+        assert_eq!(
+            key,
+            GlobalIdempotency::scoped_key("order-1", Some("Basic YWxpY2U6c2VjcmV0"), ip)
+        );
+    }
+
+    #[cfg(feature = "jwt")]
+    #[test]
+    fn it_scopes_idempotency_keys_by_jwt_subjects() {
+        use crate::auth::JwtClaims;
+
+        let ip = "10.0.0.1".parse::<IpAddr>().ok();
+        let alice = JwtClaims::<crate::Map>::new("alice")
+            .access_token()
+            .unwrap();
+        let bob = JwtClaims::<crate::Map>::new("bob").access_token().unwrap();
+        let authorization = format!("Bearer {alice}");
+        let key = GlobalIdempotency::scoped_key("order-1", Some(&authorization), ip);
+        assert_eq!(
+            key,
+            GlobalIdempotency::scoped_key("order-1", Some(&authorization), None)
+        );
+        assert_ne!(
+            key,
+            GlobalIdempotency::scoped_key("order-1", Some(&format!("Bearer {bob}")), ip)
+        );
+        assert_ne!(key, GlobalIdempotency::scoped_key("order-1", None, ip));
+    }
+
+    #[test]
+    fn it_filters_replayed_headers() {
+        assert!(GlobalIdempotency::is_replayed_header("Content-Type"));
+        assert!(GlobalIdempotency::is_replayed_header("location"));
+        assert!(!GlobalIdempotency::is_replayed_header("set-cookie"));
+        assert!(!GlobalIdempotency::is_replayed_header("x-request-id"));
+    }
+
+    #[test]
+    fn it_acquires_idempotency_keys() {
+        let store = MemoryIdempotencyStore::new();
+        let record = IdempotencyRecord::new("fingerprint", GlobalIdempotency::lease());
+        let result = futures::executor::block_on(store.try_acquire("key", &record));
+        assert!(result.is_ok_and(|record| record.is_none()));
+
+        let result = futures::executor::block_on(store.try_acquire("key", &record));
+        assert!(result.is_ok_and(|record| record.is_some_and(|r| r.is_in_progress())));
+
+        let mut record = IdempotencyRecord::new("fingerprint", GlobalIdempotency::ttl());
+        let headers = vec![("location".to_owned(), "/user/1".to_owned())];
+        record.set_response(StoredResponse::new(201, headers, b"{}"));
+        assert!(futures::executor::block_on(store.store("key", &record)).is_ok());
+
+        let result = futures::executor::block_on(store.get("key"));
+        let record = result.ok().flatten().expect("the record should be stored");
+        let response = record.response().unwrap();
+        assert_eq!(response.status_code(), 201);
+        assert_eq!(response.decode_body().ok(), Some(b"{}".to_vec()));
+    }
+
+    #[test]
+    fn it_expires_in_progress_records() {
+        let store = MemoryIdempotencyStore::new();
+        let record = IdempotencyRecord::new("fingerprint", Duration::ZERO);
+        assert!(record.is_expired());
+
+        let result = futures::executor::block_on(store.try_acquire("key", &record));
+        assert!(result.is_ok_and(|record| record.is_none()));
+
+        let record = IdempotencyRecord::new("fingerprint", GlobalIdempotency::lease());
+        let result = futures::executor::block_on(store.try_acquire("key", &record));
+        assert!(result.is_ok_and(|record| record.is_none()));
+    }
+
+    #[test]
+    fn it_purges_expired_records() {
+        let store = MemoryIdempotencyStore::new();
+        let record = IdempotencyRecord::new("fingerprint", Duration::ZERO);
+        for i in 0..1024 {
+            let key = format!("expired-{i}");
+            assert!(futures::executor::block_on(store.try_acquire(&key, &record)).is_ok());
+        }
+        assert_eq!(store.records.lock().len(), 1024);
+
+        let record = IdempotencyRecord::new("fingerprint", GlobalIdempotency::lease());
+        assert!(futures::executor::block_on(store.try_acquire("key", &record)).is_ok());
+        assert_eq!(store.records.lock().len(), 1);
+    }
+}
@@ -15,10 +15,15 @@ use crate::{
     validation::Validation,
     warn, JsonValue, Map, SharedString, Uuid,
 };
+use bytes::Bytes;
+use futures::{stream::BoxStream, StreamExt};
 use multer::Multipart;
 use serde::de::DeserializeOwned;
 use std::{borrow::Cow, net::IpAddr, str::FromStr, time::Instant};
 
+#[cfg(feature = "accessor")]
+use crate::file::{ChunkedUploader, UploadOptions, UploadSession, UploadedFile};
+
 #[cfg(feature = "cookie")]
 use cookie::{Cookie, SameSite};
 
@@ -38,8 +43,19 @@ use fluent::FluentArgs;
 use unic_langid::LanguageIdentifier;
 
 mod context;
+mod idempotency;
 
 pub use context::Context;
+pub use idempotency::{
+    GlobalIdempotency, IdempotencyRecord, IdempotencyStatus, IdempotencyStore,
+    MemoryIdempotencyStore,
+};
+
+#[cfg(feature = "accessor")]
+pub use idempotency::AccessorIdempotencyStore;
+
+#[cfg(feature = "orm")]
+pub use idempotency::OrmIdempotencyStore;
 
 /// The URI component of a request.
 pub type Uri = http::Uri;
@@ -82,6 +98,10 @@ pub trait RequestContext {
     /// Reads the entire request body into a byte buffer.
     async fn read_body_bytes(&mut self) -> Result<Vec<u8>, Error>;
 
+    /// Takes the request body as a stream of byte chunks.
+    /// The body will be empty after the call.
+    fn take_body_stream(&mut self) -> BoxStream<'static, Result<Bytes, Error>>;
+
     /// Returns the request path regardless of nesting.
     #[inline]
     fn request_path(&self) -> &str {
@@ -413,8 +433,9 @@ pub trait RequestContext {
         };
         match multer::parse_boundary(content_type) {
             Ok(boundary) => {
-                let result = self.read_body_bytes().await.map_err(|err| err.to_string());
-                let stream = futures::stream::once(async { result });
+                let stream = self
+                    .take_body_stream()
+                    .map(|result| result.map_err(|err| err.to_string()));
                 Ok(Multipart::new(stream, boundary))
             }
             Err(err) => Err(Rejection::from_validation_entry("boundary", err).context(self)),
@@ -447,6 +468,51 @@ pub trait RequestContext {
             .map_err(|err| Rejection::from_validation_entry("body", err).context(self))
     }
 
+    /// Streams the files in the multipart body to the storage of an accessor.
+    /// Unlike [`parse_files()`](Self::parse_files), the files are never
+    /// buffered in memory as a whole.
+    #[cfg(feature = "accessor")]
+    async fn upload_files(
+        &mut self,
+        options: &UploadOptions,
+    ) -> Result<Vec<UploadedFile>, Rejection> {
+        let multipart = self.parse_multipart().await?;
+        options
+            .upload(multipart)
+            .await
+            .map_err(|rejection| rejection.context(self))
+    }
+
+    /// Appends the request body as a chunk of the resumable upload.
+    /// The offset of the chunk is read from the `upload-offset` header.
+    /// The owner should be derived from the authenticated user, such as the user ID.
+    #[cfg(feature = "accessor")]
+    async fn upload_chunk(
+        &mut self,
+        uploader: &ChunkedUploader,
+        upload_id: Uuid,
+        owner: Option<&str>,
+    ) -> Result<UploadSession, Rejection> {
+        let Some(offset) = self
+            .get_header("upload-offset")
+            .and_then(|s| s.parse::<u64>().ok())
+        else {
+            return Err(Rejection::from_validation_entry(
+                "upload_offset",
+                warn!("invalid `upload-offset` header"),
+            )
+            .context(self));
+        };
+        let bytes = self
+            .read_body_bytes()
+            .await
+            .map_err(|err| Rejection::from_validation_entry("body", err).context(self))?;
+        uploader
+            .patch(upload_id, owner, offset, bytes.into())
+            .await
+            .map_err(|rejection| rejection.context(self))
+    }
+
     /// Attempts to construct an instance of `Authentication` from an HTTP request.
     /// The value is extracted from the query or the `authorization` header.
     /// By default, the `Accept` header value is ignored and
@@ -783,15 +849,13 @@ pub trait RequestContext {
         }
     }
 
-    /// Constructs a new subscription instance.
+    /// Constructs a new subscription instance from the query.
+    ///
+    /// The session ID is not set, since the session-scoped events should only be
+    /// delivered after the request has been authenticated.
+    #[inline]
     fn subscription(&self) -> Subscription {
-        let mut subscription = self.parse_query::<Subscription>().unwrap_or_default();
-        if subscription.session_id().is_none() {
-            if let Some(session_id) = self.session_id() {
-                subscription.set_session_id(Some(session_id));
-            }
-        }
-        subscription
+        self.parse_query::<Subscription>().unwrap_or_default()
     }
 
     /// Constructs a new cloud event instance.
@@ -0,0 +1,764 @@
+use super::StoredResponse;
+use crate::{
+    crypto::Digest, datetime::DateTime, error::Error, extension::TomlTableExt, state::State,
+    BoxFuture, LazyLock, Uuid,
+};
+use etag::EntityTag;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use sha2::Digest as _;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+#[cfg(feature = "accessor")]
+use super::stored::{read_json, write_json};
+
+/// A cached HTTP response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct CachedResponse {
+    /// Stored response.
+    #[serde(flatten)]
+    response: StoredResponse,
+    /// Entity tag of the response.
+    #[serde(default)]
+    etag: String,
+    /// Expiration time.
+    expires_at: DateTime,
+}
+
+impl CachedResponse {
+    /// Creates a new instance.
+    ///
+    /// The entity tag is taken from the `x-etag` or `etag` header of the response,
+    /// and it will be generated from the body and added as the `x-etag` header
+    /// if there is no such header, so that the requests can be revalidated.
+    pub fn new(
+        status_code: u16,
+        headers: Vec<(String, String)>,
+        body: &[u8],
+        ttl: Duration,
+    ) -> Self {
+        let mut response = StoredResponse::new(status_code, headers, body);
+        let etag = match response
+            .get_header("x-etag")
+            .or_else(|| response.get_header("etag"))
+        {
+            Some(etag) => etag.to_owned(),
+            None => {
+                let etag = EntityTag::from_data(body).to_string();
+                response.insert_header("x-etag", etag.as_str());
+                etag
+            }
+        };
+        Self {
+            response,
+            etag,
+            expires_at: DateTime::now() + ttl,
+        }
+    }
+
+    /// Returns the stored response.
+    #[inline]
+    pub fn response(&self) -> &StoredResponse {
+        &self.response
+    }
+
+    /// Returns the entity tag.
+    #[inline]
+    pub fn etag(&self) -> &str {
+        &self.etag
+    }
+
+    /// Returns `true` if the entity tag matches the value of the `If-None-Match` header
+    /// with the weak comparison.
+    pub fn matches_etag(&self, if_none_match: &str) -> bool {
+        let Ok(etag) = self.etag.parse::<EntityTag>() else {
+            return false;
+        };
+        if_none_match.split(',').any(|value| {
+            let value = value.trim();
+            value == "*"
+                || value
+                    .parse::<EntityTag>()
+                    .is_ok_and(|tag| tag.weak_eq(&etag))
+        })
+    }
+
+    /// Returns the expiration time.
+    #[inline]
+    pub fn expires_at(&self) -> DateTime {
+        self.expires_at
+    }
+
+    /// Returns `true` if the response has been expired.
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= DateTime::now()
+    }
+}
+
+/// Storage for the cached responses and the versions of the models.
+///
+/// The versions of the models are parts of the cache keys, so they should be
+/// stored with the responses in order to be shared by multiple processes.
+pub trait ResponseCacheStore: Send + Sync {
+    /// Gets the cached response for the key.
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<CachedResponse>, Error>>;
+
+    /// Puts the response into the cache.
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        response: &'a CachedResponse,
+    ) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Removes the cached response for the key.
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Gets the version of the model.
+    fn get_version<'a>(
+        &'a self,
+        model_name: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, Error>>;
+
+    /// Sets the version of the model.
+    fn set_version<'a>(
+        &'a self,
+        model_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>>;
+}
+
+/// In-memory storage for the cached responses.
+///
+/// Both the cached responses and the versions of the models are process-local.
+#[derive(Debug)]
+pub struct MemoryResponseCacheStore {
+    /// Cached responses.
+    entries: Mutex<HashMap<String, CachedResponse>>,
+    /// Versions of the models.
+    versions: Mutex<HashMap<String, String>>,
+    /// Maximum number of entries.
+    capacity: usize,
+}
+
+impl MemoryResponseCacheStore {
+    /// Creates a new instance with the capacity.
+    #[inline]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            versions: Mutex::new(HashMap::new()),
+            capacity,
+        }
+    }
+}
+
+impl ResponseCacheStore for MemoryResponseCacheStore {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<CachedResponse>, Error>> {
+        let mut entries = self.entries.lock();
+        let response = match entries.get(key).cloned() {
+            Some(response) if response.is_expired() => {
+                entries.remove(key);
+                None
+            }
+            response => response,
+        };
+        Box::pin(async move { Ok(response) })
+    }
+
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        response: &'a CachedResponse,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        let mut entries = self.entries.lock();
+        if entries.len() >= self.capacity && !entries.contains_key(key) {
+            entries.retain(|_, response| !response.is_expired());
+            if entries.len() >= self.capacity {
+                let oldest_key = entries
+                    .iter()
+                    .min_by_key(|(_, response)| response.expires_at)
+                    .map(|(key, _)| key.clone());
+                if let Some(key) = oldest_key {
+                    entries.remove(&key);
+                }
+            }
+        }
+        if self.capacity > 0 {
+            entries.insert(key.to_owned(), response.clone());
+        }
+        Box::pin(async { Ok(()) })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        self.entries.lock().remove(key);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn get_version<'a>(
+        &'a self,
+        model_name: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, Error>> {
+        let version = self.versions.lock().get(model_name).cloned();
+        Box::pin(async move { Ok(version) })
+    }
+
+    fn set_version<'a>(
+        &'a self,
+        model_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        self.versions
+            .lock()
+            .insert(model_name.to_owned(), version.to_owned());
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Storage for the cached responses backed by an accessor operator.
+///
+/// The versions of the models are stored under the `versions/` directory
+/// of the prefix, so that the processes sharing the storage will observe
+/// the invalidations by each other.
+#[cfg(feature = "accessor")]
+#[derive(Debug, Clone)]
+pub struct AccessorResponseCacheStore {
+    /// Storage operator.
+    operator: &'static opendal::Operator,
+    /// Path prefix.
+    prefix: &'static str,
+}
+
+#[cfg(feature = "accessor")]
+impl AccessorResponseCacheStore {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(operator: &'static opendal::Operator, prefix: &'static str) -> Self {
+        Self { operator, prefix }
+    }
+
+    /// Returns the path for the key.
+    #[inline]
+    fn path(&self, key: &str) -> String {
+        [self.prefix, key].concat()
+    }
+
+    /// Returns the path for the version of the model.
+    #[inline]
+    fn version_path(&self, model_name: &str) -> String {
+        [self.prefix, "versions/", model_name].concat()
+    }
+}
+
+#[cfg(feature = "accessor")]
+impl ResponseCacheStore for AccessorResponseCacheStore {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<CachedResponse>, Error>> {
+        Box::pin(async move {
+            let path = self.path(key);
+            match read_json::<CachedResponse>(self.operator, &path).await? {
+                Some(response) if response.is_expired() => {
+                    self.operator.delete(&path).await?;
+                    Ok(None)
+                }
+                response => Ok(response),
+            }
+        })
+    }
+
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        response: &'a CachedResponse,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move { write_json(self.operator, &self.path(key), response).await })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            self.operator.delete(&self.path(key)).await?;
+            Ok(())
+        })
+    }
+
+    fn get_version<'a>(
+        &'a self,
+        model_name: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, Error>> {
+        Box::pin(async move { read_json(self.operator, &self.version_path(model_name)).await })
+    }
+
+    fn set_version<'a>(
+        &'a self,
+        model_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            write_json(self.operator, &self.version_path(model_name), &version).await
+        })
+    }
+}
+
+/// Parsed directives of the `Cache-Control` header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheControl {
+    /// The `no-cache` directive.
+    no_cache: bool,
+    /// The `no-store` directive.
+    no_store: bool,
+    /// The `private` directive.
+    private: bool,
+    /// The `s-maxage` or `max-age` directive.
+    max_age: Option<Duration>,
+}
+
+impl CacheControl {
+    /// Parses the value of the `Cache-Control` header.
+    pub fn parse(value: &str) -> Self {
+        let mut cache_control = Self::default();
+        let mut shared_max_age = None;
+        for directive in value.split(',') {
+            let (name, value) = directive
+                .trim()
+                .split_once('=')
+                .map(|(name, value)| (name, Some(value.trim_matches('"'))))
+                .unwrap_or((directive.trim(), None));
+            let max_age = value
+                .and_then(|value| value.parse::<u64>().ok())
+                .map(Duration::from_secs);
+            match name.to_ascii_lowercase().as_str() {
+                "no-cache" => cache_control.no_cache = true,
+                "no-store" => cache_control.no_store = true,
+                "private" => cache_control.private = true,
+                "max-age" => cache_control.max_age = max_age,
+                "s-maxage" => shared_max_age = max_age,
+                _ => (),
+            }
+        }
+        if shared_max_age.is_some() {
+            cache_control.max_age = shared_max_age;
+        }
+        cache_control
+    }
+
+    /// Returns `true` if the `no-cache` directive is present.
+    #[inline]
+    pub fn is_no_cache(&self) -> bool {
+        self.no_cache
+    }
+
+    /// Returns `true` if the `no-store` directive is present.
+    #[inline]
+    pub fn is_no_store(&self) -> bool {
+        self.no_store
+    }
+
+    /// Returns `true` if the `private` directive is present.
+    #[inline]
+    pub fn is_private(&self) -> bool {
+        self.private
+    }
+
+    /// Returns the maximum age.
+    #[inline]
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+}
+
+/// A route whose responses can be cached.
+#[derive(Debug, Clone)]
+pub struct CacheRoute {
+    /// Route path. It can be an exact path or a prefix ending with `*`.
+    path: &'static str,
+    /// Time-to-live of the cached responses.
+    ttl: Duration,
+    /// Related models whose writes invalidate the cached responses.
+    models: Vec<&'static str>,
+    /// A flag to indicate whether the responses are cached for each user.
+    user_scoped: bool,
+    /// Roles which are allowed to get the cached responses.
+    roles: Vec<&'static str>,
+}
+
+impl CacheRoute {
+    /// Returns `true` if the route matches the request path.
+    pub fn matches(&self, path: &str) -> bool {
+        if let Some(prefix) = self.path.strip_suffix('*') {
+            path.starts_with(prefix)
+        } else {
+            self.path == path
+        }
+    }
+
+    /// Returns the time-to-live of the cached responses.
+    #[inline]
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns the related models.
+    #[inline]
+    pub fn models(&self) -> &[&'static str] {
+        &self.models
+    }
+
+    /// Returns `true` if the responses are cached for each user.
+    #[inline]
+    pub fn is_user_scoped(&self) -> bool {
+        self.user_scoped
+    }
+
+    /// Returns the roles which are allowed to get the cached responses.
+    #[inline]
+    pub fn roles(&self) -> &[&'static str] {
+        &self.roles
+    }
+}
+
+/// Global access to the shared response cache.
+///
+/// The cached responses of a route are invalidated once any related model is written
+/// by the methods of `Schema`, since the version of the model is a part of the cache key.
+/// Only the headers in the `cached-headers` allowlist are cached, and the requests
+/// with an `If-None-Match` header are revalidated by the entity tags.
+///
+/// The requests to a user-scoped route are authenticated and authorized by
+/// [`GlobalResponseCache::authorize`] before looking up the cache, so a cached response
+/// is never replayed to a request which the handler would reject.
+///
+/// # Examples
+///
+/// ```toml
+/// [response-cache]
+/// ttl = "1m"
+/// capacity = 10000
+/// max-body-size = 1048576
+/// cached-headers = ["content-type", "etag", "x-etag"]
+///
+/// [[response-cache.routes]]
+/// path = "/user/list"
+/// ttl = "30s"
+/// models = ["user"]
+/// scope = "user"
+/// roles = ["admin"]
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlobalResponseCache;
+
+impl GlobalResponseCache {
+    /// Replaces the shared store with a custom one.
+    #[inline]
+    pub fn set_store(store: impl ResponseCacheStore + 'static) {
+        *SHARED_RESPONSE_CACHE_STORE.write() = Arc::new(store);
+    }
+
+    /// Returns the shared store.
+    #[inline]
+    pub fn store() -> Arc<dyn ResponseCacheStore> {
+        SHARED_RESPONSE_CACHE_STORE.read().clone()
+    }
+
+    /// Returns the cache route matching the request path.
+    #[inline]
+    pub fn route(path: &str) -> Option<&'static CacheRoute> {
+        RESPONSE_CACHE_ROUTES
+            .iter()
+            .find(|route| route.matches(path))
+    }
+
+    /// Returns `true` if the response header can be cached and replayed.
+    ///
+    /// Only the headers in the allowlist are cached, which never includes `set-cookie`.
+    #[inline]
+    pub fn is_cached_header(name: &str) -> bool {
+        StoredResponse::is_allowed_header(&RESPONSE_CACHE_CONFIG.cached_headers, name)
+    }
+
+    /// Returns the maximum size of the response body to be cached.
+    #[inline]
+    pub fn max_body_size() -> usize {
+        RESPONSE_CACHE_CONFIG.max_body_size
+    }
+
+    /// Authenticates and authorizes a request to the route, and returns the scope
+    /// of the cache key. It is empty for a public route.
+    ///
+    /// For a user-scoped route, the JWT token in the `authorization` header is verified
+    /// by the shared key on every request including the replays, and the scope consists of
+    /// the user ID, tenant ID and roles in the claims. If the token is absent or invalid,
+    /// or it has none of the `roles` of the route, `None` is returned and the cache
+    /// should be bypassed so that the handler can authenticate the request by itself.
+    pub fn authorize(route: &CacheRoute, authorization: Option<&str>) -> Option<String> {
+        if route.is_user_scoped() {
+            authorized_user_scope(route, authorization)
+        } else {
+            Some(String::new())
+        }
+    }
+
+    /// Generates the cache key for a request with the method, URI and user scope.
+    /// The versions of the related models are loaded from the store.
+    pub async fn cache_key(
+        route: &CacheRoute,
+        method: &str,
+        uri: &str,
+        scope: Option<&str>,
+    ) -> Result<String, Error> {
+        let store = Self::store();
+        let mut hasher = Digest::new();
+        hasher.update(method.as_bytes());
+        hasher.update(b" ");
+        hasher.update(uri.as_bytes());
+        if route.is_user_scoped() {
+            hasher.update(b"\n");
+            hasher.update(scope.unwrap_or_default().as_bytes());
+        }
+        for model_name in route.models() {
+            let version = store.get_version(model_name).await?.unwrap_or_default();
+            hasher.update(format!("\n{model_name}:{version}").as_bytes());
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Invalidates the cached responses related to the model by setting a new version.
+    /// Failures of the store are logged since the model has been written.
+    pub async fn invalidate(model_name: &str) {
+        let related = RESPONSE_CACHE_ROUTES
+            .iter()
+            .any(|route| route.models().contains(&model_name));
+        if related {
+            let version = Uuid::now_v7().to_string();
+            if let Err(err) = Self::store().set_version(model_name, &version).await {
+                tracing::error!(model_name, "fail to invalidate the cached responses: {err}");
+            }
+        }
+    }
+}
+
+/// Returns the user scope if the JWT token can be verified and
+/// has one of the roles of the route.
+#[cfg(feature = "jwt")]
+fn authorized_user_scope(route: &CacheRoute, authorization: Option<&str>) -> Option<String> {
+    use crate::auth::UserSession;
+
+    let claims = crate::auth::verify_authorization(authorization?)?;
+    let user_session = UserSession::<String>::try_from_jwt_claims(claims).ok()?;
+    let roles = user_session.roles();
+    if !route.roles().is_empty()
+        && !roles
+            .iter()
+            .any(|role| route.roles().contains(&role.as_str()))
+    {
+        return None;
+    }
+
+    let user_id = user_session.user_id();
+    let tenant_id = user_session
+        .tenant_id()
+        .map(|s| s.as_str())
+        .unwrap_or_default();
+    Some(format!("{user_id}\n{tenant_id}\n{}", roles.join(",")))
+}
+
+/// Returns `None` since the requests can not be authenticated without the `jwt` feature.
+#[cfg(not(feature = "jwt"))]
+#[inline]
+fn authorized_user_scope(_route: &CacheRoute, _authorization: Option<&str>) -> Option<String> {
+    None
+}
+
+/// Response cache config.
+#[derive(Debug)]
+struct ResponseCacheConfig {
+    /// Response headers to be cached.
+    cached_headers: Vec<&'static str>,
+    /// Maximum size of the response body to be cached.
+    max_body_size: usize,
+}
+
+/// Shared response cache config.
+static RESPONSE_CACHE_CONFIG: LazyLock<ResponseCacheConfig> = LazyLock::new(|| {
+    let mut cached_headers = vec![
+        "content-type",
+        "content-language",
+        "content-disposition",
+        "etag",
+        "x-etag",
+        "last-modified",
+        "vary",
+    ];
+    let mut max_body_size = 1024 * 1024; // 1MB
+    if let Some(config) = State::shared().get_config("response-cache") {
+        if let Some(headers) = config.get_str_array("cached-headers") {
+            cached_headers = headers;
+        }
+        if let Some(size) = config.get_usize("max-body-size") {
+            max_body_size = size;
+        }
+    }
+    ResponseCacheConfig {
+        cached_headers,
+        max_body_size,
+    }
+});
+
+/// Routes whose responses can be cached.
+static RESPONSE_CACHE_ROUTES: LazyLock<Vec<CacheRoute>> = LazyLock::new(|| {
+    let mut routes = Vec::new();
+    if let Some(config) = State::shared().get_config("response-cache") {
+        let default_ttl = config
+            .get_duration("ttl")
+            .unwrap_or_else(|| Duration::from_secs(60));
+        for route in config.get_array("routes").into_iter().flatten() {
+            if let Some(route) = route.as_table() {
+                let Some(path) = route.get_str("path") else {
+                    tracing::warn!("the `path` field should be specified for a cache route");
+                    continue;
+                };
+                routes.push(CacheRoute {
+                    path,
+                    ttl: route.get_duration("ttl").unwrap_or(default_ttl),
+                    models: route.get_str_array("models").unwrap_or_default(),
+                    user_scoped: route.get_str("scope") != Some("public"),
+                    roles: route.get_str_array("roles").unwrap_or_default(),
+                });
+            }
+        }
+    }
+    routes
+});
+
+/// Shared response cache store.
+static SHARED_RESPONSE_CACHE_STORE: LazyLock<RwLock<Arc<dyn ResponseCacheStore>>> =
+    LazyLock::new(|| {
+        let mut capacity = 10000;
+        if let Some(config) = State::shared().get_config("response-cache") {
+            #[cfg(feature = "accessor")]
+            if let Some(name) = config.get_str("accessor") {
+                if let Some(operator) = crate::accessor::GlobalAccessor::get(name) {
+                    let prefix = config.get_str("prefix").unwrap_or("response-cache/");
+                    let store = AccessorResponseCacheStore::new(operator, prefix);
+                    return RwLock::new(Arc::new(store));
+                } else {
+                    tracing::error!("fail to get the accessor `{name}` for the response cache");
+                }
+            }
+            if let Some(value) = config.get_usize("capacity") {
+                capacity = value;
+            }
+        }
+        RwLock::new(Arc::new(MemoryResponseCacheStore::new(capacity)))
+    });
+
+#[cfg(test)]
+mod tests {
+    use super::{CacheControl, CacheRoute, CachedResponse, GlobalResponseCache};
+    use std::time::Duration;
+
+    #[test]
+    fn it_parses_cache_control() {
+        let cache_control = CacheControl::parse("no-cache");
+        assert!(cache_control.is_no_cache());
+        assert!(!cache_control.is_no_store());
+
+        let cache_control = CacheControl::parse("private, max-age=60");
+        assert!(cache_control.is_private());
+        assert_eq!(cache_control.max_age(), Some(Duration::from_secs(60)));
+
+        let cache_control = CacheControl::parse("public, max-age=60, s-maxage=\"300\"");
+        assert!(!cache_control.is_private());
+        assert_eq!(cache_control.max_age(), Some(Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn it_revalidates_cached_responses() {
+        let headers = vec![("content-type".to_owned(), "text/plain".to_owned())];
+        let ttl = Duration::from_secs(60);
+        let cached_response = CachedResponse::new(200, headers, b"hello", ttl);
+        let etag = cached_response.etag().to_owned();
+        assert_eq!(
+            cached_response.response().get_header("x-etag"),
+            Some(etag.as_str())
+        );
+        assert!(cached_response.matches_etag(&etag));
+        assert!(cached_response.matches_etag(&format!("\"other\", W/{etag}")));
+        assert!(cached_response.matches_etag("*"));
+        assert!(!cached_response.matches_etag("\"other\""));
+
+        let headers = vec![("x-etag".to_owned(), "\"v1\"".to_owned())];
+        let cached_response = CachedResponse::new(200, headers, b"hello", ttl);
+        assert_eq!(cached_response.etag(), "\"v1\"");
+        assert!(cached_response.matches_etag("W/\"v1\""));
+    }
+
+    #[test]
+    fn it_filters_cached_headers() {
+        assert!(GlobalResponseCache::is_cached_header("Content-Type"));
+        assert!(GlobalResponseCache::is_cached_header("x-etag"));
+        assert!(!GlobalResponseCache::is_cached_header("set-cookie"));
+        assert!(!GlobalResponseCache::is_cached_header("x-request-id"));
+    }
+
+    #[test]
+    fn it_versions_cache_keys() {
+        let route = CacheRoute {
+            path: "/tag/list",
+            ttl: Duration::from_secs(60),
+            models: vec!["tag"],
+            user_scoped: false,
+            roles: Vec::new(),
+        };
+        let cache_key = |uri| {
+            futures::executor::block_on(GlobalResponseCache::cache_key(&route, "GET", uri, None))
+        };
+        let key = cache_key("/tag/list").unwrap();
+        assert_eq!(cache_key("/tag/list").unwrap(), key);
+        assert_ne!(cache_key("/tag/list?page=2").unwrap(), key);
+
+        let store = GlobalResponseCache::store();
+        assert!(futures::executor::block_on(store.set_version("tag", "v2")).is_ok());
+        assert_ne!(cache_key("/tag/list").unwrap(), key);
+    }
+
+    #[cfg(feature = "jwt")]
+    #[test]
+    fn it_authorizes_user_scoped_routes() {
+        use crate::auth::JwtClaims;
+
+        let mut route = CacheRoute {
+            path: "/user/list",
+            ttl: Duration::from_secs(60),
+            models: vec!["user"],
+            user_scoped: false,
+            roles: vec!["admin"],
+        };
+        assert_eq!(
+            GlobalResponseCache::authorize(&route, None),
+            Some(String::new())
+        );
+
+        route.user_scoped = true;
+        let access_token = |roles: &[&str]| {
+            let mut claims = JwtClaims::<crate::Map>::new("alice");
+            claims.add_data_entry("roles", roles);
+            format!("Bearer {}", claims.access_token().unwrap())
+        };
+        let admin = access_token(&["admin"]);
+        let user = access_token(&["user"]);
+        let scope = GlobalResponseCache::authorize(&route, Some(&admin));
+        assert!(scope.is_some_and(|scope| scope.starts_with("alice")));
+        assert_eq!(GlobalResponseCache::authorize(&route, Some(&user)), None);
+        assert_eq!(
+            GlobalResponseCache::authorize(&route, Some("Bearer forged")),
+            None
+        );
+        assert_eq!(GlobalResponseCache::authorize(&route, None), None);
+
+        route.roles.clear();
+        assert_ne!(
+            GlobalResponseCache::authorize(&route, Some(&admin)),
+            GlobalResponseCache::authorize(&route, Some(&user))
+        );
+    }
+}
@@ -22,14 +22,25 @@ use std::{
 #[cfg(feature = "cookie")]
 use cookie::Cookie;
 
+mod cache;
 mod rejection;
 mod response_code;
 mod webhook;
 
+pub(crate) mod stored;
+
+pub use cache::{
+    CacheControl, CacheRoute, CachedResponse, GlobalResponseCache, MemoryResponseCacheStore,
+    ResponseCacheStore,
+};
 pub use rejection::{ExtractRejection, Rejection};
 pub use response_code::ResponseCode;
+pub use stored::StoredResponse;
 pub use webhook::WebHook;
 
+#[cfg(feature = "accessor")]
+pub use cache::AccessorResponseCacheStore;
+
 /// An HTTP status code.
 pub type StatusCode = http::StatusCode;
 
@@ -0,0 +1,128 @@
+use crate::{encoding::base64, error::Error};
+use serde::{Deserialize, Serialize};
+
+/// An HTTP response stored to be replayed later.
+///
+/// It is shared by the response cache and the idempotency records.
+/// Only the headers in an allowlist should be stored, and `set-cookie`
+/// is never stored since it may leak the session of a user to the others.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct StoredResponse {
+    /// Status code.
+    status_code: u16,
+    /// Headers.
+    #[serde(default)]
+    headers: Vec<(String, String)>,
+    /// Base64-encoded body.
+    #[serde(default)]
+    body: String,
+}
+
+impl StoredResponse {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(status_code: u16, headers: Vec<(String, String)>, body: &[u8]) -> Self {
+        Self {
+            status_code,
+            headers,
+            body: base64::encode(body),
+        }
+    }
+
+    /// Returns the status code.
+    #[inline]
+    pub fn status_code(&self) -> u16 {
+        self.status_code
+    }
+
+    /// Returns the headers.
+    #[inline]
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Returns the value of the header with the name.
+    #[inline]
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Inserts a header, replacing the existing one with the same name.
+    pub fn insert_header(&mut self, name: &str, value: impl Into<String>) {
+        self.headers
+            .retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_owned(), value.into()));
+    }
+
+    /// Decodes the body.
+    #[inline]
+    pub fn decode_body(&self) -> Result<Vec<u8>, Error> {
+        base64::decode(&self.body).map_err(Error::from)
+    }
+
+    /// Returns `true` if the header can be stored with the allowlist.
+    /// The `set-cookie` header is always excluded.
+    pub(crate) fn is_allowed_header(allowlist: &[&str], name: &str) -> bool {
+        !name.eq_ignore_ascii_case("set-cookie")
+            && allowlist
+                .iter()
+                .any(|header| header.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Reads the JSON value from the accessor operator, returning `None` if it does not exist.
+#[cfg(feature = "accessor")]
+pub(crate) async fn read_json<T: serde::de::DeserializeOwned>(
+    operator: &opendal::Operator,
+    path: &str,
+) -> Result<Option<T>, Error> {
+    match operator.read(path).await {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(err) if err.kind() == opendal::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Writes the value as JSON with the accessor operator.
+#[cfg(feature = "accessor")]
+pub(crate) async fn write_json<T: Serialize>(
+    operator: &opendal::Operator,
+    path: &str,
+    value: &T,
+) -> Result<(), Error> {
+    let bytes = serde_json::to_vec(value)?;
+    operator.write(path, bytes).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StoredResponse;
+
+    #[test]
+    fn it_stores_responses() {
+        let headers = vec![("Content-Type".to_owned(), "text/plain".to_owned())];
+        let mut response = StoredResponse::new(200, headers, b"hello");
+        assert_eq!(response.get_header("content-type"), Some("text/plain"));
+
+        response.insert_header("content-type", "application/json");
+        assert_eq!(response.headers().len(), 1);
+        assert_eq!(
+            response.get_header("Content-Type"),
+            Some("application/json")
+        );
+        assert_eq!(response.decode_body().unwrap(), b"hello");
+
+        let allowlist = ["content-type", "etag", "Set-Cookie"];
+        assert!(StoredResponse::is_allowed_header(&allowlist, "ETag"));
+        assert!(!StoredResponse::is_allowed_header(&allowlist, "set-cookie"));
+        assert!(!StoredResponse::is_allowed_header(
+            &allowlist,
+            "x-request-id"
+        ));
+    }
+}
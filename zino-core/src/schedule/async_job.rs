@@ -1,19 +1,92 @@
 //! Scheduler for sync and async cron jobs.
 
-use super::AsyncScheduler;
-use crate::{datetime::DateTime, BoxFuture, Map, Uuid};
+use super::{
+    handle::{JobCommand, JobInfo},
+    AsyncScheduler, CatchUpPolicy, JobRun, JobRuntime, JobSchedule, JobState, JobStore,
+    OverlapPolicy, SchedulerHandle,
+};
+use crate::{
+    datetime::DateTime, error::Error, extension::TomlTableExt, state::State, BoxFuture, Map, Uuid,
+};
 use chrono::Local;
-use cron::Schedule;
-use std::{str::FromStr, time::Duration};
+use futures::{
+    future::{self, Either},
+    FutureExt,
+};
+use futures_timer::Delay;
+use parking_lot::Mutex;
+use std::{
+    any::Any,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 /// A function pointer of the async cron job.
 pub type AsyncCronJob =
     for<'a> fn(id: Uuid, data: &'a mut Map, last_tick: DateTime) -> BoxFuture<'a>;
 
+/// A boxed runner of the async job. It takes the job data and gives it back after the run.
+type AsyncJobRunner =
+    Arc<dyn Fn(JobContext) -> BoxFuture<'static, (Map, Result<(), Error>)> + Send + Sync>;
+
+/// Context of an async job run.
+#[derive(Debug, Clone)]
+pub struct JobContext {
+    /// Job ID.
+    job_id: Uuid,
+    /// Job name.
+    job_name: Option<&'static str>,
+    /// A snapshot of the job data.
+    data: Map,
+    /// Last time when running the job.
+    last_tick: DateTime,
+}
+
+impl JobContext {
+    /// Returns the job ID.
+    #[inline]
+    pub fn job_id(&self) -> Uuid {
+        self.job_id
+    }
+
+    /// Returns the job name.
+    #[inline]
+    pub fn job_name(&self) -> Option<&'static str> {
+        self.job_name
+    }
+
+    /// Returns a reference to the snapshot of the job data.
+    #[inline]
+    pub fn data(&self) -> &Map {
+        &self.data
+    }
+
+    /// Returns the last time when running the job.
+    #[inline]
+    pub fn last_tick(&self) -> DateTime {
+        self.last_tick
+    }
+}
+
+/// Activity of the job runs in the background.
+#[derive(Debug, Default)]
+struct JobActivity {
+    /// Number of the runs in progress.
+    running: usize,
+    /// Number of the queued runs.
+    queued: usize,
+    /// Completed runs with the job data.
+    completed: Vec<(Map, JobRun)>,
+}
+
 /// An async schedulable job.
 pub struct AsyncJob {
     /// Job ID.
     id: Uuid,
+    /// Job name.
+    name: Option<&'static str>,
     /// Job data.
     data: Map,
     /// Flag to indicate whether the job is disabled.
@@ -22,32 +95,144 @@ pub struct AsyncJob {
     immediate: bool,
     /// Remaining ticks.
     remaining_ticks: Option<usize>,
-    /// Cron expression parser.
-    schedule: Schedule,
+    /// Policy for the missed ticks.
+    catch_up: CatchUpPolicy,
+    /// Cron schedule.
+    schedule: JobSchedule,
+    /// Policy for the overlapping runs.
+    overlap: OverlapPolicy,
+    /// Timeout of a run.
+    timeout: Option<Duration>,
     /// Cron job to run.
-    run: AsyncCronJob,
+    run: AsyncJobRunner,
     /// Last time when running the job.
     last_tick: Option<chrono::DateTime<Local>>,
+    /// Runtime for executing the job in the background.
+    runtime: Option<Arc<dyn JobRuntime>>,
+    /// Activity of the runs in the background.
+    activity: Arc<Mutex<JobActivity>>,
 }
 
 impl AsyncJob {
     /// Creates a new instance.
+    ///
+    /// # Panics
+    ///
+    /// It will panic if the cron expression is invalid.
     #[inline]
     pub fn new(cron_expr: &str, exec: AsyncCronJob) -> Self {
-        let schedule = Schedule::from_str(cron_expr)
-            .unwrap_or_else(|err| panic!("invalid cron expression `{cron_expr}`: {err}"));
+        Self::try_new(cron_expr, exec).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Attempts to create a new instance.
+    #[inline]
+    pub fn try_new(cron_expr: &str, exec: AsyncCronJob) -> Result<Self, Error> {
+        JobSchedule::new(cron_expr).map(|schedule| Self::with_schedule(schedule, exec))
+    }
+
+    /// Creates a new instance with the schedule.
+    pub fn with_schedule(schedule: JobSchedule, exec: AsyncCronJob) -> Self {
+        let run: AsyncJobRunner = Arc::new(move |ctx: JobContext| {
+            Box::pin(async move {
+                let JobContext {
+                    job_id,
+                    mut data,
+                    last_tick,
+                    ..
+                } = ctx;
+                exec(job_id, &mut data, last_tick).await;
+                (data, Ok(()))
+            })
+        });
+        Self::with_runner(schedule, run)
+    }
+
+    /// Creates a new instance with an async closure.
+    /// The closure can capture the configuration, clients or channels.
+    ///
+    /// # Panics
+    ///
+    /// It will panic if the cron expression is invalid.
+    pub fn from_closure<F, Fut>(cron_expr: &str, exec: F) -> Self
+    where
+        F: Fn(JobContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        let run: AsyncJobRunner = Arc::new(move |ctx: JobContext| {
+            let data = ctx.data.clone();
+            let fut = exec(ctx);
+            Box::pin(async move { (data, fut.await) })
+        });
+        let schedule = JobSchedule::new(cron_expr).unwrap_or_else(|err| panic!("{err}"));
+        Self::with_runner(schedule, run)
+    }
+
+    /// Creates a new instance with an async closure and the typed state shared across runs.
+    pub fn with_state<S, F, Fut>(cron_expr: &str, state: S, exec: F) -> Self
+    where
+        S: Send + Sync + 'static,
+        F: Fn(Arc<S>, JobContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        let state = Arc::new(state);
+        Self::from_closure(cron_expr, move |ctx| exec(state.clone(), ctx))
+    }
+
+    /// Creates a new instance with the schedule and runner.
+    fn with_runner(schedule: JobSchedule, run: AsyncJobRunner) -> Self {
         Self {
             id: Uuid::now_v7(),
+            name: None,
             data: Map::new(),
             disabled: false,
             immediate: false,
             remaining_ticks: None,
+            catch_up: CatchUpPolicy::default(),
+            overlap: OverlapPolicy::default(),
+            timeout: None,
             schedule,
-            run: exec,
+            run,
             last_tick: None,
+            runtime: None,
+            activity: Arc::new(Mutex::new(JobActivity::default())),
         }
     }
 
+    /// Sets the job name. It is used as the identifier of the job across restarts.
+    #[inline]
+    pub fn named(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Sets the policy for the missed ticks. It defaults to [`CatchUpPolicy::RunOnce`].
+    #[inline]
+    pub fn catch_up(mut self, policy: CatchUpPolicy) -> Self {
+        self.catch_up = policy;
+        self
+    }
+
+    /// Sets the policy for the overlapping runs.
+    #[inline]
+    pub fn overlap(mut self, policy: OverlapPolicy) -> Self {
+        self.overlap = policy;
+        self
+    }
+
+    /// Sets the timeout of a run. The timer of the [`JobRuntime`] is used if it exists,
+    /// otherwise the timeout is enforced by the global timer of `futures-timer`.
+    #[inline]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the runtime for executing the job in the background.
+    #[inline]
+    pub fn set_runtime(&mut self, runtime: Arc<dyn JobRuntime>) {
+        self.runtime = Some(runtime);
+    }
+
     /// Enables the flag to indicate whether the job is disabled.
     #[inline]
     pub fn disable(mut self, disabled: bool) -> Self {
@@ -82,6 +267,12 @@ impl AsyncJob {
         self.id
     }
 
+    /// Returns the job name.
+    #[inline]
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
     /// Returns a reference to the job data.
     #[inline]
     pub fn data(&self) -> &Map {
@@ -112,6 +303,12 @@ impl AsyncJob {
         self.remaining_ticks == Some(0)
     }
 
+    /// Returns the number of the runs in progress.
+    #[inline]
+    pub fn running_count(&self) -> usize {
+        self.activity.lock().running
+    }
+
     /// Pauses the job by setting the `disabled` flag to `true`.
     #[inline]
     pub fn pause(&mut self) {
@@ -130,58 +327,378 @@ impl AsyncJob {
         self.last_tick = last_tick.map(|dt| dt.into());
     }
 
+    /// Returns the state of the job.
+    pub fn state(&self) -> JobState {
+        JobState {
+            schedule: self.schedule.to_string(),
+            data: self.data.clone(),
+            last_tick: self.last_tick.map(|dt| dt.into()),
+            remaining_ticks: self.remaining_ticks,
+        }
+    }
+
+    /// Restores the state of the job.
+    pub fn restore_state(&mut self, state: JobState) {
+        self.data = state.data;
+        self.last_tick = state.last_tick.map(|dt| dt.into());
+        self.remaining_ticks = state.remaining_ticks;
+    }
+
     /// Executes the missed runs asynchronously.
+    #[inline]
     pub async fn tick(&mut self) {
+        self.run_pending().await;
+    }
+
+    /// Executes the missed runs asynchronously and returns the records of the runs.
+    ///
+    /// If the job has a runtime, each run is spawned onto the runtime, and the records
+    /// of the runs completed in the background are returned in the subsequent calls.
+    pub async fn run_pending(&mut self) -> Vec<JobRun> {
         let now = Local::now();
-        let disabled = self.disabled;
-        let run = self.run;
-        if let Some(last_tick) = self.last_tick {
-            for event in self.schedule.after(&last_tick) {
-                if event > now || self.is_fused() {
+        let mut runs = self.collect_runs();
+        if !self.disabled {
+            let (num_runs, last_tick) = if let Some(last_tick) = self.last_tick {
+                let due_ticks = self
+                    .schedule
+                    .after(&last_tick)
+                    .take_while(|event| *event <= now);
+                (self.catch_up.num_runs(due_ticks), last_tick)
+            } else {
+                (usize::from(self.immediate), now)
+            };
+            for _ in 0..num_runs {
+                if self.is_fused() {
                     break;
                 }
-                if !disabled {
-                    run(self.id, &mut self.data, last_tick.into()).await;
-                    if let Some(ticks) = self.remaining_ticks {
-                        self.remaining_ticks = Some(ticks.saturating_sub(1));
+                if let Some(runtime) = self.runtime.clone() {
+                    if !self.spawn_run(runtime, last_tick.into()) {
+                        continue;
                     }
+                } else {
+                    runs.push(self.run_once(last_tick.into()).await);
+                }
+                if let Some(ticks) = self.remaining_ticks {
+                    self.remaining_ticks = Some(ticks.saturating_sub(1));
                 }
-            }
-        } else if !disabled && self.immediate && !self.is_fused() {
-            run(self.id, &mut self.data, now.into()).await;
-            if let Some(ticks) = self.remaining_ticks {
-                self.remaining_ticks = Some(ticks.saturating_sub(1));
             }
         }
         self.last_tick = Some(now);
+        runs
     }
 
     /// Executes the job manually.
     pub async fn execute(&mut self) {
         let now = Local::now();
-        let run = self.run;
-        run(self.id, &mut self.data, now.into()).await;
+        self.run_once(now.into()).await;
         self.last_tick = Some(now);
     }
+
+    /// Triggers a run immediately. The run is spawned onto the runtime if it exists.
+    pub async fn trigger(&mut self) {
+        let now = Local::now();
+        if let Some(runtime) = self.runtime.clone() {
+            self.spawn_run(runtime, now.into());
+        } else {
+            self.run_once(now.into()).await;
+        }
+    }
+
+    /// Returns the information of the job with the upcoming fire times.
+    pub fn info(&self) -> JobInfo {
+        let next_fire_times = self.schedule.upcoming(5);
+        JobInfo {
+            id: self.id,
+            name: self.name,
+            schedule: self.schedule.to_string(),
+            time_zone: self.schedule.time_zone(),
+            disabled: self.disabled,
+            fused: self.is_fused(),
+            running: self.running_count(),
+            last_tick: self.last_tick.map(DateTime::from),
+            next_fire_times,
+            data: self.data.clone(),
+        }
+    }
+
+    /// Returns the context of a run.
+    fn context(&self, last_tick: DateTime) -> JobContext {
+        JobContext {
+            job_id: self.id,
+            job_name: self.name,
+            data: self.data.clone(),
+            last_tick,
+        }
+    }
+
+    /// Returns `true` if a tick of the job is due or the job has not been ticked yet.
+    fn is_due(&self, now: chrono::DateTime<Local>) -> bool {
+        match self.last_tick {
+            Some(last_tick) => self
+                .schedule
+                .after(&last_tick)
+                .next()
+                .is_some_and(|event| event <= now),
+            None => true,
+        }
+    }
+
+    /// Returns `true` if there are runs in progress or completed runs not collected yet.
+    fn has_pending_runs(&self) -> bool {
+        let activity = self.activity.lock();
+        activity.running > 0 || !activity.completed.is_empty()
+    }
+
+    /// Collects the runs completed in the background and updates the job data.
+    fn collect_runs(&mut self) -> Vec<JobRun> {
+        let completed = std::mem::take(&mut self.activity.lock().completed);
+        let mut runs = Vec::with_capacity(completed.len());
+        for (data, run) in completed {
+            self.data = data;
+            runs.push(run);
+        }
+        runs
+    }
+
+    /// Spawns a run onto the runtime according to the overlap policy.
+    /// It returns `true` if the run has been spawned or queued.
+    fn spawn_run(&mut self, runtime: Arc<dyn JobRuntime>, last_tick: DateTime) -> bool {
+        {
+            let mut activity = self.activity.lock();
+            if activity.running > 0 {
+                match self.overlap {
+                    OverlapPolicy::Skip => {
+                        let job_id = self.id.to_string();
+                        tracing::warn!(job_id, job_name = self.name, "job is still running");
+                        #[cfg(feature = "metrics")]
+                        metrics::counter!(
+                            "zino_job_runs_skipped_total",
+                            "job_name" => job_label(self.id, self.name),
+                        )
+                        .increment(1);
+                        return false;
+                    }
+                    OverlapPolicy::Queue => {
+                        activity.queued += 1;
+                        return true;
+                    }
+                    OverlapPolicy::Allow => (),
+                }
+            }
+            activity.running += 1;
+        }
+
+        let run = self.run.clone();
+        let timeout = self.timeout;
+        let activity = self.activity.clone();
+        let mut ctx = self.context(last_tick);
+        let job_runtime = runtime.clone();
+        runtime.spawn(Box::pin(async move {
+            loop {
+                let (data, job_run) = run_job(&run, ctx.clone(), timeout, Some(&job_runtime)).await;
+                let mut guard = activity.lock();
+                guard.completed.push((data.clone(), job_run));
+                if guard.queued > 0 {
+                    guard.queued -= 1;
+                    ctx.data = data;
+                    ctx.last_tick = DateTime::now();
+                } else {
+                    guard.running -= 1;
+                    break;
+                }
+            }
+        }));
+        true
+    }
+
+    /// Executes the job once and returns the record of the run.
+    /// A panic in the job will be caught and recorded as an error.
+    async fn run_once(&mut self, last_tick: DateTime) -> JobRun {
+        let ctx = self.context(last_tick);
+        let runtime = self.runtime.as_ref();
+        let (data, job_run) = run_job(&self.run, ctx, self.timeout, runtime).await;
+        self.data = data;
+        job_run
+    }
+}
+
+/// Executes the job with the context and returns the job data and the record of the run.
+/// A panic or an error in the job will be recorded, and the timeout is enforced by
+/// the timer of the runtime if it is provided. The timer is dropped once the job finishes.
+async fn run_job(
+    run: &AsyncJobRunner,
+    ctx: JobContext,
+    timeout: Option<Duration>,
+    runtime: Option<&Arc<dyn JobRuntime>>,
+) -> (Map, JobRun) {
+    let job_id = ctx.job_id;
+    let job_name = ctx.job_name;
+    let data = ctx.data.clone();
+    let started_at = DateTime::now();
+    let start_time = Instant::now();
+
+    #[cfg(feature = "metrics")]
+    metrics::gauge!("zino_job_runs_in_progress", "job_name" => job_label(job_id, job_name))
+        .increment(1.0);
+
+    let future = AssertUnwindSafe(run(ctx)).catch_unwind();
+    let result = if let Some(timeout) = timeout {
+        let timer = match runtime {
+            Some(runtime) => runtime.sleep(timeout),
+            None => Box::pin(Delay::new(timeout)),
+        };
+        match future::select(Box::pin(future), timer).await {
+            Either::Left((result, _)) => Some(result),
+            Either::Right(_) => None,
+        }
+    } else {
+        Some(future.await)
+    };
+    let mut job_run = JobRun::new(job_id, started_at, start_time.elapsed());
+    let (data, status) = match result {
+        Some(Ok((data, Ok(())))) => (data, "success"),
+        Some(Ok((data, Err(err)))) => {
+            let job_id = job_id.to_string();
+            tracing::error!(job_id, job_name, "job failed: {err}");
+            job_run.set_error(err);
+            (data, "failure")
+        }
+        Some(Err(err)) => {
+            let message = panic_message(err);
+            let job_id = job_id.to_string();
+            tracing::error!(job_id, job_name, "job panicked: {message}");
+            job_run.set_error(message);
+            (data, "failure")
+        }
+        None => {
+            let message = format!("job timed out after {timeout:?}");
+            let job_id = job_id.to_string();
+            tracing::error!(job_id, job_name, message);
+            job_run.set_error(message);
+            (data, "timeout")
+        }
+    };
+
+    #[cfg(feature = "metrics")]
+    {
+        let job_label = job_label(job_id, job_name);
+        metrics::gauge!("zino_job_runs_in_progress", "job_name" => job_label.clone())
+            .decrement(1.0);
+        metrics::counter!(
+            "zino_job_runs_total",
+            "job_name" => job_label.clone(),
+            "status" => status,
+        )
+        .increment(1);
+        metrics::histogram!("zino_job_run_duration_seconds", "job_name" => job_label)
+            .record(job_run.duration().as_secs_f64());
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = status;
+
+    (data, job_run)
+}
+
+/// Extracts the message from the panic payload.
+fn panic_message(err: Box<dyn Any + Send>) -> String {
+    if let Some(s) = err.downcast_ref::<String>() {
+        s.to_owned()
+    } else if let Some(s) = err.downcast_ref::<&str>() {
+        (*s).to_owned()
+    } else {
+        "unknown panic message".to_owned()
+    }
+}
+
+/// Returns the label of the job for the metrics.
+#[cfg(feature = "metrics")]
+fn job_label(job_id: Uuid, job_name: Option<&'static str>) -> String {
+    job_name
+        .map(|name| name.to_owned())
+        .unwrap_or_else(|| job_id.to_string())
 }
 
 /// A type contains and executes the async scheduled jobs.
+///
+/// If a job store is provided, the state of the named jobs will be persisted,
+/// and a lease is acquired for each run so that only one instance executes
+/// a given job at a time.
 #[derive(Default)]
 pub struct AsyncJobScheduler {
     /// A list of async jobs.
     jobs: Vec<AsyncJob>,
+    /// Persistent store for the named jobs.
+    store: Option<Arc<dyn JobStore>>,
+    /// Instance ID as the holder of the job leases.
+    instance_id: String,
+    /// Duration of the job leases.
+    lease_duration: Duration,
+    /// Runtime for executing the jobs in the background.
+    runtime: Option<Arc<dyn JobRuntime>>,
+    /// Shared handle to the scheduler.
+    handle: SchedulerHandle,
+    /// Flag to indicate whether the jobs in the config have been loaded.
+    config_loaded: bool,
 }
 
 impl AsyncJobScheduler {
+    /// Maximum duration to sleep when there is no upcoming fire time.
+    const MAX_IDLE_DURATION: Duration = Duration::from_secs(60);
+
     /// Creates a new instance.
     #[inline]
     pub fn new() -> Self {
-        Self { jobs: Vec::new() }
+        Self::default()
+    }
+
+    /// Creates a new instance with a persistent store for the named jobs.
+    #[inline]
+    pub fn with_store(store: impl JobStore + 'static) -> Self {
+        Self {
+            jobs: Vec::new(),
+            store: Some(Arc::new(store)),
+            instance_id: Uuid::now_v7().to_string(),
+            lease_duration: Duration::from_secs(300),
+            runtime: None,
+            handle: SchedulerHandle::default(),
+            config_loaded: false,
+        }
+    }
+
+    /// Sets the duration of the job leases. It should be longer than the execution time
+    /// of any job, otherwise another instance may execute the job concurrently.
+    #[inline]
+    pub fn set_lease_duration(&mut self, lease_duration: Duration) {
+        self.lease_duration = lease_duration;
+    }
+
+    /// Returns a shared handle to manage the jobs when the scheduler is running.
+    #[inline]
+    pub fn handle(&self) -> SchedulerHandle {
+        self.handle.clone()
+    }
+
+    /// Returns the instance ID as the holder of the job leases.
+    #[inline]
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// Sets the runtime for executing the jobs in the background,
+    /// so that a slow job does not delay the others.
+    pub fn set_runtime(&mut self, runtime: Arc<dyn JobRuntime>) {
+        for job in self.jobs.iter_mut() {
+            job.set_runtime(runtime.clone());
+        }
+        self.runtime = Some(runtime);
     }
 
     /// Adds an async job to the scheduler and returns the job ID.
-    pub fn add(&mut self, job: AsyncJob) -> Uuid {
+    pub fn add(&mut self, mut job: AsyncJob) -> Uuid {
         let job_id = job.id;
+        if let Some(runtime) = self.runtime.as_ref() {
+            job.set_runtime(runtime.clone());
+        }
         self.jobs.push(job);
         job_id
     }
@@ -210,23 +727,36 @@ impl AsyncJobScheduler {
     }
 
     /// Returns the duration till the next job is supposed to run.
+    /// It is at most one second if there are runs in the background to be collected.
+    /// The scheduler should also wake up when [`SchedulerHandle::command_received`]
+    /// completes, so that the commands from the handle can be applied in time.
     pub fn time_till_next_job(&self) -> Duration {
         if self.jobs.is_empty() {
-            Duration::from_millis(500)
-        } else {
-            let mut duration = chrono::Duration::zero();
-            let now = Local::now();
-            for job in self.jobs.iter() {
-                for event in job.schedule.after(&now).take(1) {
-                    let interval = event - now;
-                    if duration.is_zero() || interval < duration {
-                        duration = interval;
-                    }
+            return Self::MAX_IDLE_DURATION;
+        }
+
+        let mut duration = chrono::Duration::zero();
+        let now = Local::now();
+        for job in self.jobs.iter() {
+            for event in job.schedule.after(&now).take(1) {
+                let interval = event - now;
+                if duration.is_zero() || interval < duration {
+                    duration = interval;
                 }
             }
+        }
+
+        let duration = if duration.is_zero() {
+            Self::MAX_IDLE_DURATION
+        } else {
             duration
                 .to_std()
                 .unwrap_or_else(|_| Duration::from_millis(500))
+        };
+        if self.jobs.iter().any(|job| job.has_pending_runs()) {
+            duration.min(Duration::from_secs(1))
+        } else {
+            duration
         }
     }
 
@@ -234,9 +764,31 @@ impl AsyncJobScheduler {
     /// It is recommended to sleep for at least 500 milliseconds between invocations of this method.
     #[inline]
     pub async fn tick(&mut self) {
+        if !self.config_loaded {
+            self.load_config();
+        }
+        self.apply_commands().await;
+
+        let now = Local::now();
         let mut fused_jobs = Vec::new();
         for job in &mut self.jobs {
-            job.tick().await;
+            if let (Some(store), Some(name)) = (&self.store, job.name()) {
+                // The store is only accessed when the job is due or has runs in the background,
+                // so that the frequent ticks do not put load on the database.
+                if !job.is_due(now) && !job.has_pending_runs() {
+                    continue;
+                }
+
+                let instance_id = &self.instance_id;
+                let lease_duration = self.lease_duration;
+                if let Err(err) =
+                    Self::tick_with_store(store, instance_id, lease_duration, job).await
+                {
+                    tracing::error!(job_name = name, "fail to tick the persistent job: {err}");
+                }
+            } else {
+                job.tick().await;
+            }
             if job.is_fused() {
                 fused_jobs.push(job.id());
             }
@@ -244,6 +796,111 @@ impl AsyncJobScheduler {
         for job_id in fused_jobs {
             self.remove(job_id);
         }
+        self.handle
+            .update_jobs(self.jobs.iter().map(|job| job.info()).collect());
+    }
+
+    /// Loads the jobs in the `[[scheduler.jobs]]` config with the registered handlers.
+    fn load_config(&mut self) {
+        self.config_loaded = true;
+        let Some(jobs) = State::shared()
+            .get_config("scheduler")
+            .and_then(|config| config.get_array("jobs"))
+        else {
+            return;
+        };
+        for config in jobs.iter().filter_map(|job| job.as_table()) {
+            let job_name = config.get_str("name").unwrap_or_default();
+            if let Err(validation) = self.handle.add_from_config(&config.to_map()) {
+                let invalid_params = validation.invalid_params().join(", ");
+                tracing::error!(
+                    job_name,
+                    "fail to add the job from the config with invalid params: {invalid_params}"
+                );
+            }
+        }
+    }
+
+    /// Applies the commands from the handle.
+    async fn apply_commands(&mut self) {
+        for command in self.handle.take_commands() {
+            match command {
+                JobCommand::Add(job) => {
+                    if let Some(name) = job.name() {
+                        if self.jobs.iter().any(|job| job.name() == Some(name)) {
+                            tracing::warn!(job_name = name, "the job already exists");
+                            continue;
+                        }
+                    }
+                    self.add(*job);
+                }
+                JobCommand::Remove(job_id) => {
+                    self.remove(job_id);
+                }
+                JobCommand::Pause(job_id) => {
+                    if let Some(job) = self.get_mut(job_id) {
+                        job.pause();
+                    }
+                }
+                JobCommand::Resume(job_id) => {
+                    if let Some(job) = self.get_mut(job_id) {
+                        job.resume();
+                    }
+                }
+                JobCommand::Trigger(job_id) => {
+                    if let Some(job) = self.get_mut(job_id) {
+                        job.trigger().await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Ticks the named job with the persistent store.
+    async fn tick_with_store(
+        store: &Arc<dyn JobStore>,
+        instance_id: &str,
+        lease_duration: Duration,
+        job: &mut AsyncJob,
+    ) -> Result<(), Error> {
+        let Some(name) = job.name() else {
+            return Ok(());
+        };
+        if !store.try_lock(name, instance_id, lease_duration).await? {
+            return Ok(());
+        }
+
+        // The state in the store is stale if the runs in the background
+        // have not been completed and saved.
+        let has_pending_runs = job.has_pending_runs();
+        let mut completed = false;
+        let result = async {
+            if !has_pending_runs {
+                if let Some(state) = store.load_state(name).await? {
+                    job.restore_state(state);
+                }
+            }
+
+            let mut runs = job.run_pending().await;
+            runs.append(&mut job.collect_runs());
+            completed = job.running_count() == 0;
+            if completed {
+                store.save_state(name, &job.state()).await?;
+            }
+            for run in runs {
+                store.record_run(name, instance_id, &run).await?;
+            }
+            Ok::<_, Error>(())
+        }
+        .await;
+
+        // The state is saved and the lease is released only after the runs
+        // in the background have been completed. Otherwise, the lease is held
+        // and will be renewed in the subsequent ticks.
+        if completed {
+            store.unlock(name, instance_id).await?;
+        }
+        result
     }
 
     /// Executes all the job manually.
@@ -257,7 +914,7 @@ impl AsyncJobScheduler {
 impl AsyncScheduler for AsyncJobScheduler {
     #[inline]
     fn is_ready(&self) -> bool {
-        !self.jobs.is_empty()
+        !self.jobs.is_empty() || SchedulerHandle::has_handlers()
     }
 
     #[inline]
@@ -269,4 +926,186 @@ impl AsyncScheduler for AsyncJobScheduler {
     async fn tick(&mut self) {
         self.tick().await;
     }
+
+    #[inline]
+    fn set_runtime(&mut self, runtime: Arc<dyn JobRuntime>) {
+        self.set_runtime(runtime);
+    }
+
+    #[inline]
+    fn handle(&self) -> Option<SchedulerHandle> {
+        Some(self.handle())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AsyncJob, AsyncJobScheduler, JobRuntime, OverlapPolicy};
+    use crate::{
+        bail, error::Error, extension::JsonObjectExt, schedule::SchedulerHandle, BoxFuture, Map,
+        Uuid,
+    };
+    use futures::executor::block_on;
+    use futures_timer::Delay;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering::Relaxed},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+
+    struct ThreadRuntime;
+
+    impl JobRuntime for ThreadRuntime {
+        fn spawn(&self, future: BoxFuture<'static>) {
+            std::thread::spawn(move || block_on(future));
+        }
+
+        fn sleep(&self, duration: Duration) -> BoxFuture<'static> {
+            Box::pin(Delay::new(duration))
+        }
+    }
+
+    fn new_slow_job(overlap: OverlapPolicy, counter: Arc<AtomicUsize>) -> AsyncJob {
+        let mut job = AsyncJob::with_state("0 0 0 1 1 * 2099", counter, |counter, _ctx| {
+            counter.fetch_add(1, Relaxed);
+            async {
+                Delay::new(Duration::from_millis(100)).await;
+                Ok(())
+            }
+        })
+        .overlap(overlap);
+        job.set_runtime(Arc::new(ThreadRuntime));
+        job
+    }
+
+    fn wait_for_runs(job: &mut AsyncJob) -> usize {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while job.running_count() > 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        job.collect_runs().len()
+    }
+
+    #[test]
+    fn it_runs_closure_jobs() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let mut job = AsyncJob::with_state("0 0 0 1 1 * 2099", counter.clone(), |counter, ctx| {
+            let count = counter.fetch_add(1, Relaxed) + 1;
+            let job_id = ctx.job_id();
+            async move {
+                if count > 1 {
+                    bail!("job `{}` has been executed", job_id);
+                }
+                Ok(())
+            }
+        })
+        .immediate(true);
+        job.data_mut().upsert("key", "value");
+
+        let runs = block_on(job.run_pending());
+        assert_eq!(runs.len(), 1);
+        assert!(runs[0].is_success());
+        assert_eq!(counter.load(Relaxed), 1);
+        assert_eq!(job.data().get_str("key"), Some("value"));
+
+        block_on(job.execute());
+        assert_eq!(counter.load(Relaxed), 2);
+    }
+
+    #[test]
+    fn it_enforces_timeouts() {
+        let job = AsyncJob::from_closure("0 0 0 1 1 * 2099", |_ctx| async {
+            Delay::new(Duration::from_secs(10)).await;
+            Ok(())
+        });
+        let mut job = job.timeout(Duration::from_millis(20)).immediate(true);
+
+        let start_time = Instant::now();
+        let runs = block_on(job.run_pending());
+        assert!(start_time.elapsed() < Duration::from_secs(5));
+        assert_eq!(runs.len(), 1);
+        assert!(runs[0].error().is_some_and(|err| err.contains("timed out")));
+
+        job.set_runtime(Arc::new(ThreadRuntime));
+        block_on(job.trigger());
+        assert_eq!(wait_for_runs(&mut job), 1);
+    }
+
+    #[test]
+    fn it_applies_overlap_policies() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let mut job = new_slow_job(OverlapPolicy::Skip, counter.clone());
+        block_on(job.trigger());
+        block_on(job.trigger());
+        assert_eq!(job.running_count(), 1);
+        assert_eq!(wait_for_runs(&mut job), 1);
+        assert_eq!(counter.load(Relaxed), 1);
+
+        let counter = Arc::new(AtomicUsize::new(0));
+        let mut job = new_slow_job(OverlapPolicy::Queue, counter.clone());
+        block_on(job.trigger());
+        block_on(job.trigger());
+        assert_eq!(job.running_count(), 1);
+        assert_eq!(wait_for_runs(&mut job), 2);
+        assert_eq!(counter.load(Relaxed), 2);
+
+        let counter = Arc::new(AtomicUsize::new(0));
+        let mut job = new_slow_job(OverlapPolicy::Allow, counter.clone());
+        block_on(job.trigger());
+        block_on(job.trigger());
+        assert_eq!(job.running_count(), 2);
+        assert_eq!(wait_for_runs(&mut job), 2);
+        assert_eq!(counter.load(Relaxed), 2);
+    }
+
+    #[test]
+    fn it_manages_jobs_with_handle() {
+        fn noop(
+            _id: Uuid,
+            _data: &mut Map,
+            _last_tick: crate::datetime::DateTime,
+        ) -> BoxFuture<'_> {
+            Box::pin(async {})
+        }
+
+        SchedulerHandle::register_handler("noop", noop);
+        let mut scheduler = AsyncJobScheduler::new();
+        scheduler.config_loaded = true;
+
+        let handle = scheduler.handle();
+        let mut config = Map::new();
+        config.upsert("name", "cleanup");
+        config.upsert("cron", "0 0 0 1 1 * 2099");
+        config.upsert("handler", "noop");
+        let job_id = handle.add_from_config(&config).unwrap();
+        assert!(handle.get("cleanup").is_none());
+
+        block_on(scheduler.tick());
+        let job = handle.get("cleanup").unwrap();
+        assert_eq!(job.id(), job_id);
+        assert!(!job.is_disabled());
+        assert!(!scheduler.get(job_id).unwrap().is_due(chrono::Local::now()));
+        assert!(handle.add_from_config(&config).is_err());
+
+        assert_eq!(handle.pause("cleanup").unwrap(), job_id);
+        block_on(scheduler.tick());
+        assert!(handle.get(&job_id.to_string()).unwrap().is_disabled());
+
+        handle.remove("cleanup").unwrap();
+        block_on(scheduler.tick());
+        assert!(handle.get("cleanup").is_none());
+        assert!(handle.pause("cleanup").is_err());
+
+        config.upsert("cron", "0 0 0 1 1 *");
+        handle.add_from_config(&config).unwrap();
+        block_on(scheduler.tick());
+        assert!(scheduler.time_till_next_job() > Duration::from_secs(1));
+
+        config.upsert("handler", "unknown");
+        config.remove("cron");
+        let validation = handle.add_from_config(&config).unwrap_err();
+        assert_eq!(validation.invalid_params().len(), 2);
+    }
 }
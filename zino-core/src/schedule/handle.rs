@@ -0,0 +1,409 @@
+use super::{AsyncCronJob, AsyncJob, JobSchedule};
+use crate::{
+    datetime::DateTime, error::Error, extension::JsonObjectExt, validation::Validation, warn,
+    BoxFuture, LazyLock, Map, Uuid,
+};
+use futures::channel::oneshot;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, OnceLock},
+};
+
+/// Information of a scheduled job.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub struct JobInfo {
+    /// Job ID.
+    pub(super) id: Uuid,
+    /// Job name.
+    pub(super) name: Option<&'static str>,
+    /// Cron expression of the schedule.
+    pub(super) schedule: String,
+    /// Time zone of the schedule.
+    pub(super) time_zone: Option<&'static str>,
+    /// Flag to indicate whether the job is disabled.
+    pub(super) disabled: bool,
+    /// Flag to indicate whether the job is fused.
+    pub(super) fused: bool,
+    /// Number of the runs in progress.
+    pub(super) running: usize,
+    /// Last time when running the job.
+    pub(super) last_tick: Option<DateTime>,
+    /// Upcoming fire times.
+    pub(super) next_fire_times: Vec<DateTime>,
+    /// Job data.
+    pub(super) data: Map,
+}
+
+impl JobInfo {
+    /// Returns the job ID.
+    #[inline]
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Returns the job name.
+    #[inline]
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    /// Returns the cron expression of the schedule.
+    #[inline]
+    pub fn schedule(&self) -> &str {
+        &self.schedule
+    }
+
+    /// Returns the time zone of the schedule.
+    #[inline]
+    pub fn time_zone(&self) -> Option<&'static str> {
+        self.time_zone
+    }
+
+    /// Returns `true` if the job is disabled.
+    #[inline]
+    pub fn is_disabled(&self) -> bool {
+        self.disabled
+    }
+
+    /// Returns `true` if the job is fused.
+    #[inline]
+    pub fn is_fused(&self) -> bool {
+        self.fused
+    }
+
+    /// Returns the number of the runs in progress.
+    #[inline]
+    pub fn running_count(&self) -> usize {
+        self.running
+    }
+
+    /// Returns the last time when running the job.
+    #[inline]
+    pub fn last_tick(&self) -> Option<DateTime> {
+        self.last_tick
+    }
+
+    /// Returns the upcoming fire times.
+    #[inline]
+    pub fn next_fire_times(&self) -> &[DateTime] {
+        &self.next_fire_times
+    }
+
+    /// Returns a reference to the job data.
+    #[inline]
+    pub fn data(&self) -> &Map {
+        &self.data
+    }
+}
+
+/// A command to the running scheduler.
+pub(super) enum JobCommand {
+    /// Adds a job.
+    Add(Box<AsyncJob>),
+    /// Removes a job.
+    Remove(Uuid),
+    /// Pauses a job.
+    Pause(Uuid),
+    /// Resumes a job.
+    Resume(Uuid),
+    /// Triggers a job immediately.
+    Trigger(Uuid),
+}
+
+/// Shared state of the scheduler handle.
+#[derive(Default)]
+struct HandleState {
+    /// Pending commands.
+    commands: Mutex<Vec<JobCommand>>,
+    /// Notifier to wake up the scheduler when a command is sent.
+    notifier: Mutex<Option<oneshot::Sender<()>>>,
+    /// Snapshots of the jobs.
+    jobs: RwLock<Vec<JobInfo>>,
+}
+
+/// A shared handle to the running async job scheduler.
+///
+/// The commands are applied in the subsequent tick of the scheduler,
+/// and the snapshots of the jobs are refreshed after each tick.
+/// The scheduler should wait for [`command_received`](Self::command_received)
+/// together with the next fire time, so that the commands are applied in time.
+#[derive(Clone, Default)]
+pub struct SchedulerHandle {
+    /// Shared state.
+    state: Arc<HandleState>,
+}
+
+impl SchedulerHandle {
+    /// Sets the shared handle which can be used by the admin endpoints.
+    #[inline]
+    pub fn set_shared(handle: SchedulerHandle) {
+        if SHARED_HANDLE.set(handle).is_err() {
+            tracing::warn!("the shared scheduler handle has already been set");
+        }
+    }
+
+    /// Returns the shared handle.
+    #[inline]
+    pub fn shared() -> Option<&'static SchedulerHandle> {
+        SHARED_HANDLE.get()
+    }
+
+    /// Registers an async job handler with the name,
+    /// so that the job can be added from the config.
+    #[inline]
+    pub fn register_handler(name: &'static str, handler: AsyncCronJob) {
+        JOB_HANDLERS.write().insert(name, handler);
+    }
+
+    /// Returns `true` if there are job handlers registered.
+    #[inline]
+    pub fn has_handlers() -> bool {
+        !JOB_HANDLERS.read().is_empty()
+    }
+
+    /// Returns the snapshots of the jobs.
+    #[inline]
+    pub fn jobs(&self) -> Vec<JobInfo> {
+        self.state.jobs.read().clone()
+    }
+
+    /// Returns the snapshot of a job with the ID or name.
+    pub fn get(&self, key: &str) -> Option<JobInfo> {
+        let job_id = key.parse::<Uuid>().ok();
+        self.state
+            .jobs
+            .read()
+            .iter()
+            .find(|job| Some(job.id) == job_id || job.name == Some(key))
+            .cloned()
+    }
+
+    /// Adds a job to the scheduler.
+    #[inline]
+    pub fn add(&self, job: AsyncJob) -> Uuid {
+        let job_id = job.id();
+        self.send(JobCommand::Add(Box::new(job)));
+        job_id
+    }
+
+    /// Adds a job from the config with a registered handler, and returns the job ID.
+    ///
+    /// The config contains the fields `cron`, `handler`, and the optional fields
+    /// `name`, `data`, `immediate`, `disabled`, `max-ticks`, `time-zone`, `jitter`,
+    /// `exclude-dates` and `exclude-windows`.
+    pub fn add_from_config(&self, config: &Map) -> Result<Uuid, Validation> {
+        let mut validation = Validation::new();
+        let schedule = if config.get_str("cron").is_some_and(|s| !s.is_empty()) {
+            JobSchedule::from_config(config)
+                .map_err(|err| validation.record_fail("cron", err))
+                .ok()
+        } else {
+            validation.record("cron", "should be nonempty");
+            None
+        };
+
+        let handler_name = config.get_str("handler").unwrap_or_default();
+        let handler = JOB_HANDLERS.read().get(handler_name).copied();
+        if handler.is_none() {
+            validation.record("handler", "should be a registered job handler");
+        }
+
+        let (Some(schedule), Some(handler)) = (schedule, handler) else {
+            return Err(validation);
+        };
+        if !validation.is_success() {
+            return Err(validation);
+        }
+
+        let name = config.get_str("name");
+        let mut job = AsyncJob::with_schedule(schedule, handler);
+        if let Some(name) = name {
+            job = job.named(intern_job_name(name));
+        }
+        if let Some(data) = config.get_object("data") {
+            *job.data_mut() = data.clone();
+        }
+        if let Some(immediate) = config.get_bool("immediate") {
+            job = job.immediate(immediate);
+        }
+        if let Some(disabled) = config.get_bool("disabled") {
+            job = job.disable(disabled);
+        }
+        if let Some(max_ticks) = config.get_usize("max-ticks") {
+            job = job.max_ticks(max_ticks);
+        }
+
+        // The uniqueness of the name is checked against both the snapshots and
+        // the pending commands while holding the lock, so that concurrent adds
+        // with the same name can not both pass.
+        let job_id = job.id();
+        let mut commands = self.state.commands.lock();
+        if let Some(name) = name {
+            let pending = commands
+                .iter()
+                .any(|command| matches!(command, JobCommand::Add(job) if job.name() == Some(name)));
+            if pending || self.get(name).is_some() {
+                validation.record("name", "the job already exists");
+                return Err(validation);
+            }
+        }
+        commands.push(JobCommand::Add(Box::new(job)));
+        drop(commands);
+        self.notify();
+        Ok(job_id)
+    }
+
+    /// Removes a job with the ID or name.
+    #[inline]
+    pub fn remove(&self, key: &str) -> Result<Uuid, Error> {
+        self.resolve(key).map(|id| {
+            self.send(JobCommand::Remove(id));
+            id
+        })
+    }
+
+    /// Pauses a job with the ID or name.
+    #[inline]
+    pub fn pause(&self, key: &str) -> Result<Uuid, Error> {
+        self.resolve(key).map(|id| {
+            self.send(JobCommand::Pause(id));
+            id
+        })
+    }
+
+    /// Resumes a job with the ID or name.
+    #[inline]
+    pub fn resume(&self, key: &str) -> Result<Uuid, Error> {
+        self.resolve(key).map(|id| {
+            self.send(JobCommand::Resume(id));
+            id
+        })
+    }
+
+    /// Triggers a job with the ID or name immediately.
+    #[inline]
+    pub fn trigger(&self, key: &str) -> Result<Uuid, Error> {
+        self.resolve(key).map(|id| {
+            self.send(JobCommand::Trigger(id));
+            id
+        })
+    }
+
+    /// Resolves the job ID with the ID or name.
+    fn resolve(&self, key: &str) -> Result<Uuid, Error> {
+        self.get(key)
+            .map(|job| job.id)
+            .ok_or_else(|| warn!("404 Not Found: the job `{}` does not exist", key))
+    }
+
+    /// Sends a command to the scheduler.
+    #[inline]
+    fn send(&self, command: JobCommand) {
+        self.state.commands.lock().push(command);
+        self.notify();
+    }
+
+    /// Wakes up the scheduler waiting for the commands.
+    #[inline]
+    fn notify(&self) {
+        if let Some(notifier) = self.state.notifier.lock().take() {
+            notifier.send(()).ok();
+        }
+    }
+
+    /// Returns a future which completes when a command is sent to the scheduler.
+    /// It completes immediately if there are pending commands.
+    pub fn command_received(&self) -> BoxFuture<'static> {
+        let (sender, receiver) = oneshot::channel();
+        let commands = self.state.commands.lock();
+        if commands.is_empty() {
+            *self.state.notifier.lock() = Some(sender);
+        } else {
+            sender.send(()).ok();
+        }
+        drop(commands);
+        Box::pin(async move {
+            receiver.await.ok();
+        })
+    }
+
+    /// Takes the pending commands.
+    #[inline]
+    pub(super) fn take_commands(&self) -> Vec<JobCommand> {
+        std::mem::take(&mut *self.state.commands.lock())
+    }
+
+    /// Updates the snapshots of the jobs.
+    #[inline]
+    pub(super) fn update_jobs(&self, jobs: Vec<JobInfo>) {
+        *self.state.jobs.write() = jobs;
+    }
+}
+
+/// Returns the job name with a static lifetime. Each distinct name is allocated only once,
+/// so that adding and removing the jobs repeatedly does not leak memory.
+fn intern_job_name(name: &str) -> &'static str {
+    if let Some(name) = JOB_NAMES.read().get(name) {
+        return name;
+    }
+
+    let mut job_names = JOB_NAMES.write();
+    if let Some(name) = job_names.get(name) {
+        name
+    } else {
+        let name: &'static str = name.to_owned().leak();
+        job_names.insert(name);
+        name
+    }
+}
+
+/// Shared scheduler handle.
+static SHARED_HANDLE: OnceLock<SchedulerHandle> = OnceLock::new();
+
+/// Registered async job handlers.
+static JOB_HANDLERS: LazyLock<RwLock<HashMap<&'static str, AsyncCronJob>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Names of the jobs added from the config.
+static JOB_NAMES: LazyLock<RwLock<HashSet<&'static str>>> =
+    LazyLock::new(|| RwLock::new(HashSet::new()));
+
+#[cfg(test)]
+mod tests {
+    use super::{intern_job_name, SchedulerHandle};
+    use crate::{datetime::DateTime, extension::JsonObjectExt, BoxFuture, Map, Uuid};
+    use futures::executor::block_on;
+
+    fn noop(_id: Uuid, _data: &mut Map, _last_tick: DateTime) -> BoxFuture<'_> {
+        Box::pin(async {})
+    }
+
+    #[test]
+    fn it_interns_job_names() {
+        let name = intern_job_name(&String::from("cleanup"));
+        assert_eq!(name, "cleanup");
+        assert!(std::ptr::eq(name, intern_job_name("cleanup")));
+        assert!(!std::ptr::eq(name, intern_job_name("backup")));
+    }
+
+    #[test]
+    fn it_rejects_pending_duplicate_jobs() {
+        SchedulerHandle::register_handler("noop", noop);
+        let handle = SchedulerHandle::default();
+        let mut config = Map::new();
+        config.upsert("cron", "0 0 0 1 1 * 2099");
+        config.upsert("handler", "noop");
+        config.upsert("name", "pending-cleanup");
+
+        let command_received = handle.command_received();
+        assert!(handle.add_from_config(&config).is_ok());
+        block_on(command_received);
+
+        let validation = handle.add_from_config(&config).unwrap_err();
+        assert_eq!(validation.invalid_params(), vec!["name"]);
+        assert_eq!(handle.take_commands().len(), 1);
+    }
+}
@@ -1,18 +1,22 @@
 //! Scheduler for sync and async cron jobs.
 
-use super::Scheduler;
-use crate::{datetime::DateTime, Map, Uuid};
+use super::{CatchUpPolicy, JobSchedule, Scheduler};
+use crate::{datetime::DateTime, error::Error, Map, Uuid};
 use chrono::Local;
-use cron::Schedule;
-use std::{str::FromStr, time::Duration};
+use std::time::Duration;
 
 /// A function pointer of the cron job.
 pub type CronJob = fn(id: Uuid, data: &mut Map, last_tick: DateTime);
 
+/// A boxed closure of the cron job.
+type CronJobRunner = Box<dyn Fn(Uuid, &mut Map, DateTime) + Send + Sync>;
+
 /// A schedulable job.
 pub struct Job {
     /// Job ID.
     id: Uuid,
+    /// Job name.
+    name: Option<&'static str>,
     /// Job data.
     data: Map,
     /// Flag to indicate whether the job is disabled.
@@ -21,32 +25,81 @@ pub struct Job {
     immediate: bool,
     /// Remaining ticks.
     remaining_ticks: Option<usize>,
-    /// Cron expression parser.
-    schedule: Schedule,
+    /// Policy for the missed ticks.
+    catch_up: CatchUpPolicy,
+    /// Cron schedule.
+    schedule: JobSchedule,
     /// Cron job to run.
-    run: CronJob,
+    run: CronJobRunner,
     /// Last time when running the job.
     last_tick: Option<chrono::DateTime<Local>>,
 }
 
 impl Job {
     /// Creates a new instance.
+    ///
+    /// # Panics
+    ///
+    /// It will panic if the cron expression is invalid.
     #[inline]
     pub fn new(cron_expr: &str, exec: CronJob) -> Self {
-        let schedule = Schedule::from_str(cron_expr)
-            .unwrap_or_else(|err| panic!("invalid cron expression `{cron_expr}`: {err}"));
+        Self::from_closure(cron_expr, exec)
+    }
+
+    /// Attempts to create a new instance.
+    #[inline]
+    pub fn try_new(cron_expr: &str, exec: CronJob) -> Result<Self, Error> {
+        JobSchedule::new(cron_expr).map(|schedule| Self::with_schedule(schedule, exec))
+    }
+
+    /// Creates a new instance with a closure.
+    /// The closure can capture the configuration, clients or channels.
+    ///
+    /// # Panics
+    ///
+    /// It will panic if the cron expression is invalid.
+    #[inline]
+    pub fn from_closure<F>(cron_expr: &str, exec: F) -> Self
+    where
+        F: Fn(Uuid, &mut Map, DateTime) + Send + Sync + 'static,
+    {
+        let schedule = JobSchedule::new(cron_expr).unwrap_or_else(|err| panic!("{err}"));
+        Self::with_schedule(schedule, exec)
+    }
+
+    /// Creates a new instance with the schedule and a closure.
+    pub fn with_schedule<F>(schedule: JobSchedule, exec: F) -> Self
+    where
+        F: Fn(Uuid, &mut Map, DateTime) + Send + Sync + 'static,
+    {
         Self {
             id: Uuid::now_v7(),
+            name: None,
             data: Map::new(),
             disabled: false,
             immediate: false,
             remaining_ticks: None,
+            catch_up: CatchUpPolicy::default(),
             schedule,
-            run: exec,
+            run: Box::new(exec),
             last_tick: None,
         }
     }
 
+    /// Sets the job name. It is used as the identifier of the job across restarts.
+    #[inline]
+    pub fn named(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Sets the policy for the missed ticks. It defaults to [`CatchUpPolicy::RunOnce`].
+    #[inline]
+    pub fn catch_up(mut self, policy: CatchUpPolicy) -> Self {
+        self.catch_up = policy;
+        self
+    }
+
     /// Enables the flag to indicate whether the job is disabled.
     #[inline]
     pub fn disable(mut self, disabled: bool) -> Self {
@@ -81,6 +134,12 @@ impl Job {
         self.id
     }
 
+    /// Returns the job name.
+    #[inline]
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
     /// Returns a reference to the job data.
     #[inline]
     pub fn data(&self) -> &Map {
@@ -133,10 +192,15 @@ impl Job {
     pub fn tick(&mut self) {
         let now = Local::now();
         let disabled = self.disabled;
-        let run = self.run;
+        let run = &self.run;
         if let Some(last_tick) = self.last_tick {
-            for event in self.schedule.after(&last_tick) {
-                if event > now || self.is_fused() {
+            let due_ticks = self
+                .schedule
+                .after(&last_tick)
+                .take_while(|event| *event <= now);
+            let num_runs = self.catch_up.num_runs(due_ticks);
+            for _ in 0..num_runs {
+                if self.is_fused() {
                     break;
                 }
                 if !disabled {
@@ -158,8 +222,7 @@ impl Job {
     /// Executes the job manually.
     pub fn execute(&mut self) {
         let now = Local::now();
-        let run = self.run;
-        run(self.id, &mut self.data, now.into());
+        (self.run)(self.id, &mut self.data, now.into());
         self.last_tick = Some(now);
     }
 }
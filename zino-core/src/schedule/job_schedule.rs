@@ -0,0 +1,277 @@
+use crate::{
+    datetime::{self, Date, DateTime},
+    error::Error,
+    extension::JsonObjectExt,
+    warn, Map,
+};
+use chrono::{Local, NaiveDate};
+use chrono_tz::Tz;
+use cron::Schedule;
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+    time::Duration,
+};
+
+/// An iterator of the fire times.
+type FireTimes<'a> = Box<dyn Iterator<Item = chrono::DateTime<Local>> + Send + 'a>;
+
+/// A calendar to exclude the fire times of a job, such as holidays and maintenance windows.
+#[derive(Debug, Clone, Default)]
+pub struct JobCalendar {
+    /// Excluded dates in the time zone of the schedule.
+    dates: Vec<NaiveDate>,
+    /// Excluded time windows.
+    windows: Vec<(DateTime, DateTime)>,
+}
+
+impl JobCalendar {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Excludes the date, which is evaluated in the time zone of the schedule.
+    #[inline]
+    pub fn exclude_date(mut self, date: Date) -> Self {
+        self.dates.push(date.into());
+        self
+    }
+
+    /// Excludes the time window from `start` (inclusive) to `end` (exclusive).
+    #[inline]
+    pub fn exclude_window(mut self, start: DateTime, end: DateTime) -> Self {
+        self.windows.push((start, end));
+        self
+    }
+
+    /// Returns `true` if the calendar has no exclusions.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.dates.is_empty() && self.windows.is_empty()
+    }
+
+    /// Returns `true` if the fire time is excluded.
+    fn is_excluded(&self, date: NaiveDate, dt: DateTime) -> bool {
+        self.dates.contains(&date)
+            || self
+                .windows
+                .iter()
+                .any(|&(start, end)| start <= dt && dt < end)
+    }
+}
+
+/// A cron schedule with an optional IANA time zone, an exclusion calendar and jitter.
+#[derive(Debug, Clone)]
+pub struct JobSchedule {
+    /// Cron expression parser.
+    schedule: Schedule,
+    /// Time zone to evaluate the cron expression. The local time zone is used by default.
+    time_zone: Option<Tz>,
+    /// Calendar to exclude the fire times.
+    calendar: JobCalendar,
+    /// Maximum jitter added to the fire times.
+    jitter: Duration,
+    /// Random seed for the jitter.
+    seed: u64,
+}
+
+impl JobSchedule {
+    /// Creates a new instance with the cron expression.
+    pub fn new(cron_expr: &str) -> Result<Self, Error> {
+        let schedule = Schedule::from_str(cron_expr)
+            .map_err(|err| warn!("invalid cron expression `{}`: {}", cron_expr, err))?;
+        Ok(Self {
+            schedule,
+            time_zone: None,
+            calendar: JobCalendar::default(),
+            jitter: Duration::ZERO,
+            seed: rand::random(),
+        })
+    }
+
+    /// Creates a new instance with the config.
+    ///
+    /// The config contains the field `cron`, and the optional fields `time-zone`,
+    /// `jitter`, `exclude-dates` and `exclude-windows`.
+    pub fn from_config(config: &Map) -> Result<Self, Error> {
+        let cron_expr = config
+            .get_str("cron")
+            .ok_or_else(|| warn!("the `cron` expression should be specified"))?;
+        let mut schedule = Self::new(cron_expr)?;
+        if let Some(time_zone) = config.get_str("time-zone") {
+            schedule = schedule.with_time_zone(time_zone)?;
+        }
+        if let Some(jitter) = config.get_str("jitter") {
+            schedule = schedule.jitter(datetime::parse_duration(jitter)?);
+        }
+
+        let mut calendar = JobCalendar::new();
+        if let Some(dates) = config.get_str_array("exclude-dates") {
+            for date in dates {
+                calendar = calendar.exclude_date(date.parse()?);
+            }
+        }
+        if let Some(windows) = config.get_map_array("exclude-windows") {
+            for window in windows {
+                let start = window
+                    .get_str("start")
+                    .ok_or_else(|| warn!("the `start` of a window should be specified"))?;
+                let end = window
+                    .get_str("end")
+                    .ok_or_else(|| warn!("the `end` of a window should be specified"))?;
+                calendar = calendar.exclude_window(start.parse()?, end.parse()?);
+            }
+        }
+        Ok(schedule.calendar(calendar))
+    }
+
+    /// Sets the IANA time zone to evaluate the cron expression, such as `America/New_York`.
+    pub fn with_time_zone(mut self, time_zone: &str) -> Result<Self, Error> {
+        let time_zone = time_zone
+            .parse::<Tz>()
+            .map_err(|err| warn!("invalid time zone `{}`: {}", time_zone, err))?;
+        self.time_zone = Some(time_zone);
+        Ok(self)
+    }
+
+    /// Sets the calendar to exclude the fire times.
+    #[inline]
+    pub fn calendar(mut self, calendar: JobCalendar) -> Self {
+        self.calendar = calendar;
+        self
+    }
+
+    /// Sets the maximum jitter added to the fire times. A random offset is chosen
+    /// for each fire time to avoid the thundering herds across replicas.
+    /// It should be less than the interval of the schedule.
+    #[inline]
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns the name of the time zone.
+    #[inline]
+    pub fn time_zone(&self) -> Option<&'static str> {
+        self.time_zone.map(|tz| tz.name())
+    }
+
+    /// Returns the upcoming fire times.
+    #[inline]
+    pub fn upcoming(&self, n: usize) -> Vec<DateTime> {
+        self.after(&Local::now())
+            .take(n)
+            .map(DateTime::from)
+            .collect()
+    }
+
+    /// Returns an iterator of the fire times after the time.
+    pub fn after(&self, dt: &chrono::DateTime<Local>) -> FireTimes<'_> {
+        let dt = *dt;
+        let jitter =
+            chrono::Duration::from_std(self.jitter).unwrap_or_else(|_| chrono::Duration::zero());
+        let start = dt - jitter;
+        let events: FireTimes<'_> = match self.time_zone {
+            Some(tz) => Box::new(
+                self.schedule
+                    .after(&start.with_timezone(&tz))
+                    .map(|event| event.with_timezone(&Local)),
+            ),
+            None => Box::new(self.schedule.after(&start)),
+        };
+        Box::new(
+            events
+                .filter(move |&event| !self.is_excluded(event))
+                .map(move |event| event + self.jitter_offset(event))
+                .filter(move |&event| event > dt),
+        )
+    }
+
+    /// Returns `true` if the fire time is excluded by the calendar.
+    fn is_excluded(&self, event: chrono::DateTime<Local>) -> bool {
+        if self.calendar.is_empty() {
+            return false;
+        }
+
+        let date = match self.time_zone {
+            Some(tz) => event.with_timezone(&tz).date_naive(),
+            None => event.date_naive(),
+        };
+        self.calendar.is_excluded(date, event.into())
+    }
+
+    /// Returns the jitter offset of the fire time.
+    fn jitter_offset(&self, event: chrono::DateTime<Local>) -> chrono::Duration {
+        let jitter_millis = u64::try_from(self.jitter.as_millis()).unwrap_or(u64::MAX);
+        if jitter_millis == 0 {
+            return chrono::Duration::zero();
+        }
+
+        let mut hasher = DefaultHasher::new();
+        (self.seed, event.timestamp()).hash(&mut hasher);
+        let offset = hasher.finish() % jitter_millis;
+        chrono::Duration::milliseconds(i64::try_from(offset).unwrap_or_default())
+    }
+}
+
+impl FromStr for JobSchedule {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl fmt::Display for JobSchedule {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.schedule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JobCalendar, JobSchedule};
+    use chrono::{Local, TimeZone, Timelike};
+    use std::time::Duration;
+
+    #[test]
+    fn it_evaluates_schedules() {
+        let schedule = JobSchedule::new("0 30 9 * * *")
+            .unwrap()
+            .with_time_zone("America/New_York")
+            .unwrap();
+        let dt = chrono_tz::America::New_York
+            .with_ymd_and_hms(2024, 3, 9, 0, 0, 0)
+            .unwrap()
+            .with_timezone(&Local);
+        let events = schedule.after(&dt).take(2).collect::<Vec<_>>();
+        let tz = chrono_tz::America::New_York;
+        assert_eq!(events[0].with_timezone(&tz).hour(), 9);
+        assert_eq!(events[1].with_timezone(&tz).hour(), 9);
+        assert_eq!((events[1] - events[0]).num_hours(), 23);
+
+        let calendar = JobCalendar::new().exclude_date("2024-03-09".parse().unwrap());
+        let schedule = schedule.calendar(calendar);
+        let event = schedule.after(&dt).next().unwrap();
+        assert_eq!(event, events[1]);
+
+        let schedule = JobSchedule::new("0 0 * * * *")
+            .unwrap()
+            .jitter(Duration::from_secs(60));
+        for event in schedule.after(&dt).take(3) {
+            assert!(event > dt);
+            assert!(event.minute() < 1);
+        }
+        assert!(JobSchedule::new("invalid").is_err());
+        assert!(JobSchedule::new("0 0 * * * *")
+            .unwrap()
+            .with_time_zone("Mars/Olympus")
+            .is_err());
+    }
+}
@@ -1,12 +1,77 @@
 //! Scheduler for sync and async cron jobs.
 
-use std::{future::Future, time::Duration};
+use crate::BoxFuture;
+use std::{future::Future, sync::Arc, time::Duration};
 
 mod async_job;
+mod handle;
 mod job;
+mod job_schedule;
+mod queue;
+mod store;
 
-pub use async_job::{AsyncCronJob, AsyncJob, AsyncJobScheduler};
+pub use async_job::{AsyncCronJob, AsyncJob, AsyncJobScheduler, JobContext};
+pub use handle::{JobInfo, SchedulerHandle};
 pub use job::{CronJob, Job, JobScheduler};
+pub use job_schedule::{JobCalendar, JobSchedule};
+pub use queue::{
+    JobOptions, JobQueue, MemoryQueueBackend, QueueBackend, QueuedJob, QueuedJobHandler,
+    QueuedJobStatus,
+};
+pub use store::{JobRun, JobState, JobStore};
+
+/// Policy for the missed ticks of a job.
+///
+/// It defaults to `RunOnce` so that a job will not be executed in a burst
+/// after the scheduler has been down for a long time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CatchUpPolicy {
+    /// Skips the missed ticks. The job is executed only if exactly one tick is due.
+    Skip,
+    /// Executes the job once no matter how many ticks are due.
+    #[default]
+    RunOnce,
+    /// Executes the job for each due tick.
+    RunAll,
+}
+
+impl CatchUpPolicy {
+    /// Returns the number of runs for the due ticks.
+    pub(crate) fn num_runs(self, mut due_ticks: impl Iterator) -> usize {
+        match self {
+            Self::Skip => {
+                if due_ticks.next().is_some() && due_ticks.next().is_none() {
+                    1
+                } else {
+                    0
+                }
+            }
+            Self::RunOnce => usize::from(due_ticks.next().is_some()),
+            Self::RunAll => due_ticks.count(),
+        }
+    }
+}
+
+/// Policy for a due run when the previous run of the job is still in progress.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverlapPolicy {
+    /// Skips the due run.
+    #[default]
+    Skip,
+    /// Queues the due run after the previous one.
+    Queue,
+    /// Executes the due run concurrently.
+    Allow,
+}
+
+/// An async runtime for executing the scheduled jobs in the background.
+pub trait JobRuntime: Send + Sync {
+    /// Spawns a future onto the runtime.
+    fn spawn(&self, future: BoxFuture<'static>);
+
+    /// Returns a future which completes after the duration.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static>;
+}
 
 /// An interface for scheduling sync jobs.
 pub trait Scheduler {
@@ -30,4 +95,33 @@ pub trait AsyncScheduler {
 
     /// Increments time for the scheduler and executes any pending jobs asynchronously.
     fn tick(&mut self) -> impl Future<Output = ()> + Send;
+
+    /// Sets the runtime for executing the jobs in the background.
+    /// The jobs are executed sequentially in the ticks by default.
+    #[inline]
+    fn set_runtime(&mut self, runtime: Arc<dyn JobRuntime>) {
+        let _ = runtime;
+    }
+
+    /// Returns a shared handle to manage the jobs when the scheduler is running.
+    #[inline]
+    fn handle(&self) -> Option<SchedulerHandle> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CatchUpPolicy;
+
+    #[test]
+    fn it_counts_catch_up_runs() {
+        assert_eq!(CatchUpPolicy::Skip.num_runs(0..0), 0);
+        assert_eq!(CatchUpPolicy::Skip.num_runs(0..1), 1);
+        assert_eq!(CatchUpPolicy::Skip.num_runs(0..3), 0);
+        assert_eq!(CatchUpPolicy::RunOnce.num_runs(0..0), 0);
+        assert_eq!(CatchUpPolicy::RunOnce.num_runs(0..3), 1);
+        assert_eq!(CatchUpPolicy::RunAll.num_runs(0..3), 3);
+        assert_eq!(CatchUpPolicy::default(), CatchUpPolicy::RunOnce);
+    }
 }
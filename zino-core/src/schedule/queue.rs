@@ -0,0 +1,761 @@
+use crate::{
+    bail, datetime::DateTime, error::Error, extension::TomlTableExt, state::State, warn, BoxFuture,
+    LazyLock, Map, Uuid,
+};
+use futures::{
+    future::{self, Either},
+    FutureExt,
+};
+use futures_timer::Delay;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::{panic::AssertUnwindSafe, pin::pin, sync::Arc, time::Duration};
+
+/// A function pointer of the queued job handler.
+pub type QueuedJobHandler = for<'a> fn(job: &'a QueuedJob) -> BoxFuture<'a, Result<(), Error>>;
+
+/// Status of a queued job.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueuedJobStatus {
+    /// The job is waiting to be executed.
+    #[default]
+    Pending,
+    /// The job is being executed by a worker.
+    Running,
+    /// The job has been executed successfully.
+    Succeeded,
+    /// The job has exhausted its attempts and been moved to the dead letters.
+    Dead,
+}
+
+impl QueuedJobStatus {
+    /// Returns the status as a `str`.
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "Pending",
+            Self::Running => "Running",
+            Self::Succeeded => "Succeeded",
+            Self::Dead => "Dead",
+        }
+    }
+
+    /// Parses the status from a `str`.
+    #[inline]
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "Pending" => Some(Self::Pending),
+            "Running" => Some(Self::Running),
+            "Succeeded" => Some(Self::Succeeded),
+            "Dead" => Some(Self::Dead),
+            _ => None,
+        }
+    }
+}
+
+/// Options for enqueuing a job.
+#[derive(Debug, Clone, Copy)]
+pub struct JobOptions {
+    /// Delay before the job can be executed.
+    delay: Duration,
+    /// Priority of the job. A job with higher priority is executed first.
+    priority: u16,
+    /// Maximum number of attempts.
+    max_attempts: u32,
+    /// Base interval of the exponential backoff.
+    backoff: Duration,
+}
+
+impl Default for JobOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            delay: Duration::ZERO,
+            priority: 0,
+            max_attempts: 3,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl JobOptions {
+    /// Creates a new instance with the default options.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the delay before the job can be executed.
+    #[inline]
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Sets the priority of the job.
+    #[inline]
+    pub fn priority(mut self, priority: u16) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Sets the maximum number of attempts.
+    #[inline]
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the base interval of the exponential backoff.
+    #[inline]
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+}
+
+/// A job in the queue.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct QueuedJob {
+    /// Job ID.
+    id: Uuid,
+    /// Job type.
+    job_type: String,
+    /// Payload.
+    payload: Map,
+    /// Priority.
+    priority: u16,
+    /// Number of attempts.
+    attempts: u32,
+    /// Maximum number of attempts.
+    max_attempts: u32,
+    /// Base interval of the exponential backoff in milliseconds.
+    backoff_ms: u64,
+    /// Time when the job can be executed.
+    run_at: DateTime,
+    /// Worker which holds the lease of the job.
+    locked_by: Option<String>,
+    /// Time until which the job is locked by a worker.
+    locked_until: Option<DateTime>,
+    /// Status.
+    status: QueuedJobStatus,
+    /// Error message of the last attempt.
+    last_error: Option<String>,
+    /// Creation time.
+    created_at: DateTime,
+}
+
+impl QueuedJob {
+    /// Creates a new instance.
+    pub fn new(job_type: impl Into<String>, payload: Map, options: JobOptions) -> Self {
+        let now = DateTime::now();
+        Self {
+            id: Uuid::now_v7(),
+            job_type: job_type.into(),
+            payload,
+            priority: options.priority,
+            attempts: 0,
+            max_attempts: options.max_attempts,
+            backoff_ms: options.backoff.as_millis().try_into().unwrap_or(u64::MAX),
+            run_at: now + options.delay,
+            locked_by: None,
+            locked_until: None,
+            status: QueuedJobStatus::Pending,
+            last_error: None,
+            created_at: now,
+        }
+    }
+
+    /// Returns the job ID.
+    #[inline]
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Returns the job type.
+    #[inline]
+    pub fn job_type(&self) -> &str {
+        &self.job_type
+    }
+
+    /// Returns a reference to the payload.
+    #[inline]
+    pub fn payload(&self) -> &Map {
+        &self.payload
+    }
+
+    /// Returns the priority.
+    #[inline]
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+
+    /// Returns the number of attempts.
+    #[inline]
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Returns the maximum number of attempts.
+    #[inline]
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the base interval of the exponential backoff.
+    #[inline]
+    pub fn backoff(&self) -> Duration {
+        Duration::from_millis(self.backoff_ms)
+    }
+
+    /// Returns the time when the job can be executed.
+    #[inline]
+    pub fn run_at(&self) -> DateTime {
+        self.run_at
+    }
+
+    /// Returns the worker which holds the lease of the job.
+    #[inline]
+    pub fn locked_by(&self) -> Option<&str> {
+        self.locked_by.as_deref()
+    }
+
+    /// Returns the time until which the job is locked by a worker.
+    #[inline]
+    pub fn locked_until(&self) -> Option<DateTime> {
+        self.locked_until
+    }
+
+    /// Returns the status.
+    #[inline]
+    pub fn status(&self) -> QueuedJobStatus {
+        self.status
+    }
+
+    /// Returns the error message of the last attempt.
+    #[inline]
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Returns the creation time.
+    #[inline]
+    pub fn created_at(&self) -> DateTime {
+        self.created_at
+    }
+
+    /// Returns `true` if the job can be claimed by a worker at the time.
+    pub fn is_claimable(&self, now: DateTime) -> bool {
+        match self.status {
+            QueuedJobStatus::Pending => self.run_at <= now,
+            QueuedJobStatus::Running => self.locked_until.is_some_and(|dt| dt < now),
+            _ => false,
+        }
+    }
+
+    /// Returns the time of the next retry with an exponential backoff,
+    /// or `None` if the job has exhausted its attempts.
+    pub fn next_retry_at(&self) -> Option<DateTime> {
+        if self.attempts >= self.max_attempts {
+            return None;
+        }
+
+        let exponent = self.attempts.saturating_sub(1).min(16);
+        let backoff = self.backoff().saturating_mul(1 << exponent);
+        Some(DateTime::now() + backoff)
+    }
+}
+
+/// A backend for storing the queued jobs.
+///
+/// The updates of a claimed job are fenced by the job ID, the lease holder and the number of
+/// attempts, so that a worker whose lease has expired and been reclaimed by another worker
+/// can not overwrite the job. They return `false` if the worker no longer holds the lease.
+pub trait QueueBackend: Send + Sync {
+    /// Pushes a job into the queue.
+    fn push<'a>(&'a self, job: &'a QueuedJob) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Claims a due job with the highest priority among the job types,
+    /// and locks it for the worker in the lease duration.
+    fn claim<'a>(
+        &'a self,
+        job_types: &'a [&'a str],
+        worker_id: &'a str,
+        lease_duration: Duration,
+    ) -> BoxFuture<'a, Result<Option<QueuedJob>, Error>>;
+
+    /// Extends the lease of the claimed job.
+    fn renew<'a>(
+        &'a self,
+        job: &'a QueuedJob,
+        lease_duration: Duration,
+    ) -> BoxFuture<'a, Result<bool, Error>>;
+
+    /// Marks the claimed job as succeeded.
+    fn complete<'a>(&'a self, job: &'a QueuedJob) -> BoxFuture<'a, Result<bool, Error>>;
+
+    /// Schedules the claimed job to be retried at the time.
+    fn retry<'a>(
+        &'a self,
+        job: &'a QueuedJob,
+        run_at: DateTime,
+        error: &'a str,
+    ) -> BoxFuture<'a, Result<bool, Error>>;
+
+    /// Moves the claimed job to the dead letters.
+    fn bury<'a>(&'a self, job: &'a QueuedJob, error: &'a str)
+        -> BoxFuture<'a, Result<bool, Error>>;
+
+    /// Returns the dead letters.
+    fn dead_letters(&self, limit: usize) -> BoxFuture<'_, Result<Vec<QueuedJob>, Error>>;
+}
+
+/// In-memory backend for the queued jobs.
+#[derive(Debug, Default)]
+pub struct MemoryQueueBackend {
+    /// Queued jobs.
+    jobs: Mutex<Vec<QueuedJob>>,
+}
+
+impl MemoryQueueBackend {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the job if it is still leased by the worker which has claimed it.
+    fn update(&self, claimed: &QueuedJob, f: impl FnOnce(&mut QueuedJob)) -> bool {
+        let mut jobs = self.jobs.lock();
+        let job = jobs.iter_mut().find(|job| {
+            job.id == claimed.id
+                && job.status == QueuedJobStatus::Running
+                && job.locked_by == claimed.locked_by
+                && job.attempts == claimed.attempts
+        });
+        if let Some(job) = job {
+            f(job);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl QueueBackend for MemoryQueueBackend {
+    fn push<'a>(&'a self, job: &'a QueuedJob) -> BoxFuture<'a, Result<(), Error>> {
+        self.jobs.lock().push(job.clone());
+        Box::pin(async { Ok(()) })
+    }
+
+    fn claim<'a>(
+        &'a self,
+        job_types: &'a [&'a str],
+        worker_id: &'a str,
+        lease_duration: Duration,
+    ) -> BoxFuture<'a, Result<Option<QueuedJob>, Error>> {
+        let now = DateTime::now();
+        let mut jobs = self.jobs.lock();
+        let job = jobs
+            .iter_mut()
+            .filter(|job| job.is_claimable(now) && job_types.contains(&job.job_type.as_str()))
+            .max_by(|a, b| {
+                a.priority
+                    .cmp(&b.priority)
+                    .then_with(|| b.run_at.cmp(&a.run_at))
+            })
+            .map(|job| {
+                job.status = QueuedJobStatus::Running;
+                job.attempts += 1;
+                job.locked_by = Some(worker_id.to_owned());
+                job.locked_until = Some(now + lease_duration);
+                job.clone()
+            });
+        Box::pin(async move { Ok(job) })
+    }
+
+    fn renew<'a>(
+        &'a self,
+        job: &'a QueuedJob,
+        lease_duration: Duration,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        let renewed = self.update(job, |job| {
+            job.locked_until = Some(DateTime::now() + lease_duration);
+        });
+        Box::pin(async move { Ok(renewed) })
+    }
+
+    fn complete<'a>(&'a self, job: &'a QueuedJob) -> BoxFuture<'a, Result<bool, Error>> {
+        let completed = self.update(job, |job| {
+            job.status = QueuedJobStatus::Succeeded;
+            job.locked_by = None;
+            job.locked_until = None;
+        });
+        Box::pin(async move { Ok(completed) })
+    }
+
+    fn retry<'a>(
+        &'a self,
+        job: &'a QueuedJob,
+        run_at: DateTime,
+        error: &'a str,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        let retried = self.update(job, |job| {
+            job.status = QueuedJobStatus::Pending;
+            job.run_at = run_at;
+            job.locked_by = None;
+            job.locked_until = None;
+            job.last_error = Some(error.to_owned());
+        });
+        Box::pin(async move { Ok(retried) })
+    }
+
+    fn bury<'a>(
+        &'a self,
+        job: &'a QueuedJob,
+        error: &'a str,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        let buried = self.update(job, |job| {
+            job.status = QueuedJobStatus::Dead;
+            job.locked_by = None;
+            job.locked_until = None;
+            job.last_error = Some(error.to_owned());
+        });
+        Box::pin(async move { Ok(buried) })
+    }
+
+    fn dead_letters(&self, limit: usize) -> BoxFuture<'_, Result<Vec<QueuedJob>, Error>> {
+        let jobs = self
+            .jobs
+            .lock()
+            .iter()
+            .filter(|job| job.status == QueuedJobStatus::Dead)
+            .take(limit)
+            .cloned()
+            .collect();
+        Box::pin(async move { Ok(jobs) })
+    }
+}
+
+/// Global job queue.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JobQueue;
+
+impl JobQueue {
+    /// Sets the backend for the job queue.
+    #[inline]
+    pub fn set_backend(backend: impl QueueBackend + 'static) {
+        *QUEUE_BACKEND.write() = Arc::new(backend);
+    }
+
+    /// Returns the backend for the job queue.
+    #[inline]
+    pub fn backend() -> Arc<dyn QueueBackend> {
+        QUEUE_BACKEND.read().clone()
+    }
+
+    /// Registers a handler for the job type.
+    /// It returns an error if a handler for the job type has been registered.
+    pub fn register(job_type: &'static str, handler: QueuedJobHandler) -> Result<(), Error> {
+        let mut handlers = QUEUE_HANDLERS.write();
+        if handlers.iter().any(|&(name, _)| name == job_type) {
+            bail!(
+                "handler for the job type `{}` has already been registered",
+                job_type
+            );
+        }
+        handlers.push((job_type, handler));
+        Ok(())
+    }
+
+    /// Enqueues a job with the options and returns the job ID.
+    pub async fn enqueue(
+        job_type: impl Into<String>,
+        payload: Map,
+        options: JobOptions,
+    ) -> Result<Uuid, Error> {
+        let job = QueuedJob::new(job_type, payload, options);
+        Self::backend().push(&job).await?;
+        Ok(job.id)
+    }
+
+    /// Claims and processes the next due job.
+    /// It returns `true` if a job has been processed.
+    pub async fn process_next() -> Result<bool, Error> {
+        let handlers = QUEUE_HANDLERS.read().clone();
+        if handlers.is_empty() {
+            return Ok(false);
+        }
+
+        let job_types = handlers
+            .iter()
+            .map(|&(job_type, _)| job_type)
+            .collect::<Vec<_>>();
+        let backend = Self::backend();
+        let lease_duration = Self::lease_duration();
+        let Some(job) = backend
+            .claim(&job_types, WORKER_ID.as_str(), lease_duration)
+            .await?
+        else {
+            return Ok(false);
+        };
+        let Some(&(_, handler)) = handlers
+            .iter()
+            .find(|(job_type, _)| *job_type == job.job_type)
+        else {
+            return Err(warn!(
+                "handler for the job type `{}` does not exist",
+                job.job_type
+            ));
+        };
+
+        let job_id = job.id;
+        let handling = AssertUnwindSafe(handler(&job)).catch_unwind();
+        let renewal = pin!(Self::renew_lease(backend.as_ref(), &job, lease_duration));
+        let result = match future::select(handling, renewal).await {
+            Either::Left((result, _)) => result,
+            Either::Right(((), handling)) => handling.await,
+        };
+        let result = result.unwrap_or_else(|_| Err(warn!("job `{}` panicked", job_id)));
+        let updated = match result {
+            Ok(()) => backend.complete(&job).await?,
+            Err(err) => {
+                let message = err.to_string();
+                if let Some(run_at) = job.next_retry_at() {
+                    tracing::warn!(
+                        job_id = %job_id,
+                        job_type = job.job_type.as_str(),
+                        attempts = job.attempts,
+                        "job failed and will be retried: {message}"
+                    );
+                    backend.retry(&job, run_at, &message).await?
+                } else {
+                    tracing::error!(
+                        job_id = %job_id,
+                        job_type = job.job_type.as_str(),
+                        attempts = job.attempts,
+                        "job failed and is moved to the dead letters: {message}"
+                    );
+                    backend.bury(&job, &message).await?
+                }
+            }
+        };
+        if !updated {
+            tracing::warn!(
+                job_id = %job_id,
+                job_type = job.job_type.as_str(),
+                attempts = job.attempts,
+                "the lease of the job has been lost and the result is discarded"
+            );
+        }
+        Ok(true)
+    }
+
+    /// Renews the lease of the job periodically until the lease is lost.
+    async fn renew_lease(backend: &dyn QueueBackend, job: &QueuedJob, lease_duration: Duration) {
+        let interval = (lease_duration / 2).max(Duration::from_secs(1));
+        loop {
+            Delay::new(interval).await;
+            match backend.renew(job, lease_duration).await {
+                Ok(true) => (),
+                Ok(false) => {
+                    tracing::warn!(
+                        job_id = %job.id,
+                        job_type = job.job_type.as_str(),
+                        "the lease of the job has been lost"
+                    );
+                    break;
+                }
+                Err(err) => tracing::warn!(
+                    job_id = %job.id,
+                    job_type = job.job_type.as_str(),
+                    "fail to renew the lease of the job: {err}"
+                ),
+            }
+        }
+    }
+
+    /// Returns the dead letters.
+    #[inline]
+    pub async fn dead_letters(limit: usize) -> Result<Vec<QueuedJob>, Error> {
+        Self::backend().dead_letters(limit).await
+    }
+
+    /// Returns the number of workers.
+    #[inline]
+    pub fn workers() -> usize {
+        QUEUE_CONFIG.0
+    }
+
+    /// Returns the poll interval when the queue is idle.
+    #[inline]
+    pub fn poll_interval() -> Duration {
+        QUEUE_CONFIG.1
+    }
+
+    /// Returns the lease duration of a claimed job.
+    #[inline]
+    pub fn lease_duration() -> Duration {
+        QUEUE_CONFIG.2
+    }
+}
+
+/// Backend for the job queue.
+static QUEUE_BACKEND: LazyLock<RwLock<Arc<dyn QueueBackend>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(MemoryQueueBackend::new())));
+
+/// ID of the workers in the process which is used as the holder of the job leases.
+static WORKER_ID: LazyLock<String> = LazyLock::new(|| Uuid::now_v7().to_string());
+
+/// Handlers for the job types.
+static QUEUE_HANDLERS: LazyLock<RwLock<Vec<(&'static str, QueuedJobHandler)>>> =
+    LazyLock::new(|| RwLock::new(Vec::new()));
+
+/// Number of workers, poll interval and lease duration of the job queue.
+static QUEUE_CONFIG: LazyLock<(usize, Duration, Duration)> = LazyLock::new(|| {
+    let mut workers = 0;
+    let mut poll_interval = Duration::from_secs(1);
+    let mut lease_duration = Duration::from_secs(300);
+    if let Some(config) = State::shared().get_config("job-queue") {
+        if let Some(value) = config.get_usize("workers") {
+            workers = value;
+        }
+        if let Some(value) = config.get_duration("poll-interval") {
+            poll_interval = value;
+        }
+        if let Some(value) = config.get_duration("lease-duration") {
+            lease_duration = value;
+        }
+    }
+    (workers, poll_interval, lease_duration)
+});
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        JobOptions, JobQueue, MemoryQueueBackend, QueueBackend, QueuedJob, QueuedJobStatus,
+    };
+    use crate::{datetime::DateTime, error::Error, BoxFuture, Map};
+    use futures::executor::block_on;
+    use std::time::Duration;
+
+    #[test]
+    fn it_computes_retry_backoff() {
+        let options = JobOptions::new()
+            .max_attempts(3)
+            .backoff(Duration::from_secs(10));
+        let mut job = QueuedJob::new("email", Map::new(), options);
+
+        job.attempts = 1;
+        let run_at = job.next_retry_at().unwrap();
+        assert!(run_at <= DateTime::now() + Duration::from_secs(10));
+        assert!(run_at > DateTime::now() + Duration::from_secs(9));
+
+        job.attempts = 2;
+        let run_at = job.next_retry_at().unwrap();
+        assert!(run_at > DateTime::now() + Duration::from_secs(19));
+
+        job.attempts = 3;
+        assert!(job.next_retry_at().is_none());
+    }
+
+    #[test]
+    fn it_claims_jobs_from_memory() {
+        let backend = MemoryQueueBackend::new();
+        let lease = Duration::from_secs(60);
+        let low = QueuedJob::new("email", Map::new(), JobOptions::new().priority(1));
+        let high = QueuedJob::new("email", Map::new(), JobOptions::new().priority(5));
+        let other = QueuedJob::new("report", Map::new(), JobOptions::new().priority(10));
+        for job in [&low, &high, &other] {
+            block_on(backend.push(job)).unwrap();
+        }
+
+        let claimed = block_on(backend.claim(&["email"], "worker", lease))
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.id(), high.id());
+        assert_eq!(claimed.status(), QueuedJobStatus::Running);
+        assert_eq!(claimed.attempts(), 1);
+        assert_eq!(claimed.locked_by(), Some("worker"));
+
+        let job = block_on(backend.claim(&["email"], "worker", lease))
+            .unwrap()
+            .unwrap();
+        assert_eq!(job.id(), low.id());
+        assert!(block_on(backend.claim(&["email"], "worker", lease))
+            .unwrap()
+            .is_none());
+
+        assert!(block_on(backend.renew(&claimed, lease)).unwrap());
+        assert!(block_on(backend.complete(&claimed)).unwrap());
+        assert!(!block_on(backend.complete(&claimed)).unwrap());
+
+        let jobs = backend.jobs.lock();
+        let job = jobs.iter().find(|job| job.id() == high.id()).unwrap();
+        assert_eq!(job.status(), QueuedJobStatus::Succeeded);
+        assert!(job.locked_by().is_none());
+        assert!(job.locked_until().is_none());
+    }
+
+    #[test]
+    fn it_retries_and_buries_jobs_in_memory() {
+        let backend = MemoryQueueBackend::new();
+        let job = QueuedJob::new("email", Map::new(), JobOptions::new());
+        block_on(backend.push(&job)).unwrap();
+
+        let lease = Duration::from_secs(60);
+        let claimed = block_on(backend.claim(&["email"], "worker", lease))
+            .unwrap()
+            .unwrap();
+        let run_at = DateTime::now() + Duration::from_secs(60);
+        assert!(block_on(backend.retry(&claimed, run_at, "timeout")).unwrap());
+        assert!(block_on(backend.claim(&["email"], "worker", lease))
+            .unwrap()
+            .is_none());
+
+        backend.jobs.lock()[0].run_at = DateTime::now();
+        let claimed = block_on(backend.claim(&["email"], "worker", lease))
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.attempts(), 2);
+        assert_eq!(claimed.last_error(), Some("timeout"));
+
+        assert!(block_on(backend.bury(&claimed, "failed")).unwrap());
+        let dead_letters = block_on(backend.dead_letters(10)).unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].last_error(), Some("failed"));
+    }
+
+    #[test]
+    fn it_fences_reclaimed_jobs() {
+        let backend = MemoryQueueBackend::new();
+        let job = QueuedJob::new("email", Map::new(), JobOptions::new());
+        block_on(backend.push(&job)).unwrap();
+
+        let stale = block_on(backend.claim(&["email"], "worker-a", Duration::ZERO))
+            .unwrap()
+            .unwrap();
+        assert_eq!(stale.attempts(), 1);
+        std::thread::sleep(Duration::from_millis(5));
+
+        let claimed = block_on(backend.claim(&["email"], "worker-b", Duration::from_secs(60)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.attempts(), 2);
+        assert!(!block_on(backend.renew(&stale, Duration::from_secs(60))).unwrap());
+        assert!(!block_on(backend.complete(&stale)).unwrap());
+        assert!(!block_on(backend.bury(&stale, "failed")).unwrap());
+        assert!(block_on(backend.complete(&claimed)).unwrap());
+    }
+
+    #[test]
+    fn it_rejects_duplicate_handlers() {
+        fn handle(_job: &QueuedJob) -> BoxFuture<'_, Result<(), Error>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        assert!(JobQueue::register("duplicate", handle).is_ok());
+        assert!(JobQueue::register("duplicate", handle).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{datetime::DateTime, error::Error, BoxFuture, Map, Uuid};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Persistent state of a job.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+pub struct JobState {
+    /// Cron expression of the schedule.
+    pub(super) schedule: String,
+    /// Job data.
+    pub(super) data: Map,
+    /// Last time when running the job.
+    pub(super) last_tick: Option<DateTime>,
+    /// Remaining ticks.
+    pub(super) remaining_ticks: Option<usize>,
+}
+
+impl JobState {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(
+        schedule: String,
+        data: Map,
+        last_tick: Option<DateTime>,
+        remaining_ticks: Option<usize>,
+    ) -> Self {
+        Self {
+            schedule,
+            data,
+            last_tick,
+            remaining_ticks,
+        }
+    }
+
+    /// Returns the cron expression of the schedule.
+    #[inline]
+    pub fn schedule(&self) -> &str {
+        &self.schedule
+    }
+
+    /// Returns a reference to the job data.
+    #[inline]
+    pub fn data(&self) -> &Map {
+        &self.data
+    }
+
+    /// Returns the last time when running the job.
+    #[inline]
+    pub fn last_tick(&self) -> Option<DateTime> {
+        self.last_tick
+    }
+
+    /// Returns the remaining ticks.
+    #[inline]
+    pub fn remaining_ticks(&self) -> Option<usize> {
+        self.remaining_ticks
+    }
+}
+
+/// A record of the job run.
+#[derive(Debug, Clone)]
+pub struct JobRun {
+    /// Run ID.
+    id: Uuid,
+    /// Job ID.
+    job_id: Uuid,
+    /// Start time.
+    started_at: DateTime,
+    /// Duration.
+    duration: Duration,
+    /// Error message.
+    error: Option<String>,
+}
+
+impl JobRun {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(job_id: Uuid, started_at: DateTime, duration: Duration) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            job_id,
+            started_at,
+            duration,
+            error: None,
+        }
+    }
+
+    /// Sets the error message.
+    #[inline]
+    pub fn set_error(&mut self, error: impl ToString) {
+        self.error = Some(error.to_string());
+    }
+
+    /// Returns the run ID.
+    #[inline]
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Returns the job ID.
+    #[inline]
+    pub fn job_id(&self) -> Uuid {
+        self.job_id
+    }
+
+    /// Returns the start time.
+    #[inline]
+    pub fn started_at(&self) -> DateTime {
+        self.started_at
+    }
+
+    /// Returns the duration.
+    #[inline]
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Returns the error message.
+    #[inline]
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Returns `true` if the run is successful.
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Persistent storage for the job state, leases and run history.
+pub trait JobStore: Send + Sync {
+    /// Attempts to acquire the lease of the job for the holder.
+    /// It returns `true` if the lease has been acquired.
+    fn try_lock<'a>(
+        &'a self,
+        name: &'a str,
+        holder: &'a str,
+        lease_duration: Duration,
+    ) -> BoxFuture<'a, Result<bool, Error>>;
+
+    /// Releases the lease of the job held by the holder.
+    fn unlock<'a>(&'a self, name: &'a str, holder: &'a str) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Loads the state of the job.
+    fn load_state<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Option<JobState>, Error>>;
+
+    /// Saves the state of the job.
+    fn save_state<'a>(
+        &'a self,
+        name: &'a str,
+        state: &'a JobState,
+    ) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Records a run of the job executed by the instance.
+    fn record_run<'a>(
+        &'a self,
+        name: &'a str,
+        instance_id: &'a str,
+        run: &'a JobRun,
+    ) -> BoxFuture<'a, Result<(), Error>>;
+}
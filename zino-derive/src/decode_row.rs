@@ -11,6 +11,16 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
     // Model name
     let name = input.ident;
 
+    // Parsing struct attributes
+    let mut cacheable = false;
+    for attr in input.attrs.iter() {
+        for (key, _value) in parser::parse_schema_attr(attr).into_iter() {
+            if matches!(key.as_str(), "cache_ttl" | "cacheable") {
+                cacheable = true;
+            }
+        }
+    }
+
     // Parsing field attributes
     let mut decode_model_fields = Vec::new();
     for field in parser::parse_struct_fields(input.data) {
@@ -21,7 +31,7 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
             'inner: for attr in field.attrs.iter() {
                 let arguments = parser::parse_schema_attr(attr);
                 for (key, _value) in arguments.iter() {
-                    if key == "ignore" || key == "write_only" {
+                    if matches!(key.as_str(), "ignore" | "write_only" | "embedding") {
                         ignore = true;
                         break 'inner;
                     }
@@ -65,6 +75,21 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
             }
         }
     }
+    let cache_methods = if cacheable {
+        quote! {
+            #[inline]
+            fn encode_cached(&self) -> Option<zino_core::JsonValue> {
+                zino_core::orm::encode_cached(self)
+            }
+
+            #[inline]
+            fn decode_cached(value: zino_core::JsonValue) -> Option<Self> {
+                zino_core::orm::decode_cached(value)
+            }
+        }
+    } else {
+        quote! {}
+    };
     quote! {
         impl zino_core::model::DecodeRow<zino_core::orm::DatabaseRow> for #name {
             type Error = zino_core::error::Error;
@@ -76,6 +101,8 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
                 #(#decode_model_fields)*
                 Ok(model)
             }
+
+            #cache_methods
         }
     }
 }
@@ -1,3 +1,4 @@
+use super::parser;
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::DeriveInput;
@@ -7,12 +8,46 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
     // Model name
     let name = input.ident;
 
+    // Parsing field attributes
+    let mut has_embeddings = false;
+    for field in parser::parse_struct_fields(input.data) {
+        for attr in field.attrs.iter() {
+            let arguments = parser::parse_schema_attr(attr);
+            if arguments.iter().any(|(key, _)| key == "embedding") {
+                has_embeddings = true;
+            }
+        }
+    }
+
+    let embedding_hooks = if has_embeddings {
+        quote! {
+            type Data = zino_core::Map;
+
+            #[inline]
+            async fn before_save(&mut self) -> Result<Self::Data, zino_core::error::Error> {
+                <Self as zino_core::orm::Schema>::embedding_sources(self).await
+            }
+
+            #[inline]
+            async fn after_save(
+                ctx: &zino_core::model::QueryContext,
+                data: Self::Data,
+            ) -> Result<(), zino_core::error::Error> {
+                <Self as zino_core::orm::Schema>::sync_embeddings(ctx, &data).await
+            }
+        }
+    } else {
+        quote! {
+            type Data = ();
+        }
+    };
     quote! {
         use zino_core::model::ModelHooks;
 
         impl ModelHooks for #name {
-            type Data = ();
             type Extension = ();
+
+            #embedding_hooks
         }
     }
 }
@@ -35,6 +35,7 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
     let mut reader_name = String::from("main");
     let mut writer_name = String::from("main");
     let mut table_name = None;
+    let mut cache_ttl = None;
     let mut model_comment = None;
     for attr in input.attrs.iter() {
         for (key, value) in parser::parse_schema_attr(attr).into_iter() {
@@ -52,6 +53,9 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
                     "table_name" => {
                         table_name = Some(value);
                     }
+                    "cache_ttl" => {
+                        cache_ttl = Some(value);
+                    }
                     "comment" => {
                         model_comment = Some(value);
                     }
@@ -168,6 +172,14 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
                                 "write_only" => {
                                     write_only_fields.push(quote! { #name });
                                 }
+                                "embedding" => {
+                                    // The embeddings are maintained by the `after_save` hook
+                                    // and can only be used in the `$near` filter.
+                                    write_only_fields.push(quote! { #name });
+                                    extra_attributes.push(quote! {
+                                        column.set_extra_attribute("write_only", true);
+                                    });
+                                }
                                 "constructor" | "validator" => {
                                     extra_attributes.push(quote! {
                                         column.set_extra_attribute(#key, true);
@@ -274,6 +286,7 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
     let num_read_only_fields = read_only_fields.len();
     let num_write_only_fields = write_only_fields.len();
     let quote_table_name = parser::quote_option_string(table_name);
+    let quote_cache_ttl = parser::quote_option_string(cache_ttl);
     let quote_model_comment = parser::quote_option_string(model_comment);
     quote! {
         use zino_core::{
@@ -323,6 +336,7 @@ pub(super) fn parse_token_stream(input: DeriveInput) -> TokenStream {
             const READER_NAME: &'static str = #reader_name;
             const WRITER_NAME: &'static str = #writer_name;
             const TABLE_NAME: Option<&'static str> = #quote_table_name;
+            const CACHE_TTL: Option<&'static str> = #quote_cache_ttl;
 
             #[inline]
             fn primary_key(&self) -> &Self::PrimaryKey {
@@ -0,0 +1,108 @@
+use std::time::Duration;
+use zino_core::{error::Error, BoxFuture, JsonValue};
+
+#[cfg(feature = "cache-accessor")]
+use opendal::{ErrorKind, Operator};
+#[cfg(feature = "cache-accessor")]
+use zino_core::{accessor::GlobalAccessor, datetime::DateTime, extension::JsonObjectExt, Map};
+
+/// A second-tier backend of the cache.
+pub trait CacheBackend: Send + Sync {
+    /// Gets the value of the key together with the remaining TTL.
+    fn get<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<(JsonValue, Option<Duration>)>, Error>>;
+
+    /// Puts a key-value pair with an optional TTL.
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        value: &'a JsonValue,
+        ttl: Option<Duration>,
+    ) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Removes the value of the key.
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>>;
+}
+
+/// A cache backend built on the top of a storage accessor.
+///
+/// The value is stored together with the expiration time,
+/// so that it works for any service supported by [`GlobalAccessor`].
+#[cfg(feature = "cache-accessor")]
+#[derive(Debug, Clone)]
+pub struct AccessorBackend {
+    /// Storage operator.
+    operator: Operator,
+}
+
+#[cfg(feature = "cache-accessor")]
+impl AccessorBackend {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new(operator: Operator) -> Self {
+        Self { operator }
+    }
+
+    /// Creates a new instance with the shared operator of the accessor.
+    #[inline]
+    pub fn with_accessor(name: &str) -> Option<Self> {
+        GlobalAccessor::get(name).map(|operator| Self::new(operator.clone()))
+    }
+}
+
+#[cfg(feature = "cache-accessor")]
+impl CacheBackend for AccessorBackend {
+    fn get<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<(JsonValue, Option<Duration>)>, Error>> {
+        Box::pin(async move {
+            let bytes = match self.operator.read(key).await {
+                Ok(bytes) => bytes,
+                Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+            let mut entry = serde_json::from_slice::<Map>(&bytes)?;
+            let expires_at = entry
+                .get_str("expires_at")
+                .and_then(|s| s.parse::<DateTime>().ok());
+            let ttl = match expires_at {
+                Some(expires_at) if expires_at > DateTime::now() => expires_at.span_after_now(),
+                Some(_) => {
+                    self.operator.delete(key).await?;
+                    return Ok(None);
+                }
+                None => None,
+            };
+            Ok(entry.remove("value").map(|value| (value, ttl)))
+        })
+    }
+
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        value: &'a JsonValue,
+        ttl: Option<Duration>,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let mut entry = Map::new();
+            entry.upsert("value", value.clone());
+            if let Some(ttl) = ttl {
+                entry.upsert("expires_at", DateTime::now() + ttl);
+            }
+            self.operator
+                .write(key, serde_json::to_vec(&entry)?)
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            self.operator.delete(key).await?;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,159 @@
+use lru::LruCache;
+use parking_lot::Mutex;
+use std::{
+    collections::hash_map::RandomState,
+    hash::BuildHasher,
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
+use zino_core::JsonValue;
+
+/// An entry in the memory cache.
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    /// Cached value.
+    value: JsonValue,
+    /// Expiration time.
+    expires_at: Option<Instant>,
+}
+
+impl CacheEntry {
+    /// Returns `true` if the entry has been expired.
+    #[inline]
+    fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|t| t <= Instant::now())
+    }
+}
+
+/// A sharded in-memory LRU cache with per-entry TTL.
+///
+/// Keys are distributed across the shards by hash, so that the lock contention
+/// is reduced for concurrent reads and writes.
+#[derive(Debug)]
+pub struct MemoryCache {
+    /// Shards of the cache.
+    shards: Box<[Mutex<LruCache<String, CacheEntry>>]>,
+    /// Hasher to select a shard.
+    hasher: RandomState,
+}
+
+impl MemoryCache {
+    /// Creates a new instance with the total capacity and the number of shards.
+    pub fn new(capacity: usize, num_shards: usize) -> Self {
+        let num_shards = num_shards.max(1);
+        let shard_capacity =
+            NonZeroUsize::new(capacity.div_ceil(num_shards)).unwrap_or(NonZeroUsize::MIN);
+        let shards = (0..num_shards)
+            .map(|_| Mutex::new(LruCache::new(shard_capacity)))
+            .collect();
+        Self {
+            shards,
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Returns the shard for the key.
+    #[inline]
+    fn shard(&self, key: &str) -> &Mutex<LruCache<String, CacheEntry>> {
+        let hash = self.hasher.hash_one(key);
+        let index = (hash % self.shards.len() as u64) as usize;
+        &self.shards[index]
+    }
+
+    /// Returns a cloned value of the key or `None` if it is not present or has been expired.
+    pub fn get(&self, key: &str) -> Option<JsonValue> {
+        let mut shard = self.shard(key).lock();
+        if shard.peek(key)?.is_expired() {
+            shard.pop(key);
+            None
+        } else {
+            shard.get(key).map(|entry| entry.value.clone())
+        }
+    }
+
+    /// Inserts a key-value pair with an optional TTL.
+    pub fn insert(&self, key: impl Into<String>, value: JsonValue, ttl: Option<Duration>) {
+        let key = key.into();
+        let entry = CacheEntry {
+            value,
+            expires_at: ttl.map(|ttl| Instant::now() + ttl),
+        };
+        self.shard(&key).lock().put(key, entry);
+    }
+
+    /// Removes and returns the value of the key.
+    #[inline]
+    pub fn remove(&self, key: &str) -> Option<JsonValue> {
+        self.shard(key)
+            .lock()
+            .pop(key)
+            .filter(|entry| !entry.is_expired())
+            .map(|entry| entry.value)
+    }
+
+    /// Returns `true` if the key is present and has not been expired.
+    #[inline]
+    pub fn contains(&self, key: &str) -> bool {
+        self.shard(key)
+            .lock()
+            .peek(key)
+            .is_some_and(|entry| !entry.is_expired())
+    }
+
+    /// Removes all the expired entries.
+    pub fn purge_expired(&self) {
+        for shard in self.shards.iter() {
+            let mut shard = shard.lock();
+            let expired_keys = shard
+                .iter()
+                .filter(|(_, entry)| entry.is_expired())
+                .map(|(key, _)| key.clone())
+                .collect::<Vec<_>>();
+            for key in expired_keys {
+                shard.pop(&key);
+            }
+        }
+    }
+
+    /// Returns the number of entries including the expired ones which have not been purged.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().len()).sum()
+    }
+
+    /// Returns `true` if the cache is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| shard.lock().is_empty())
+    }
+
+    /// Clears the contents of the cache.
+    #[inline]
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            shard.lock().clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryCache;
+    use std::time::Duration;
+
+    #[test]
+    fn it_expires_entries() {
+        let cache = MemoryCache::new(16, 2);
+        cache.insert("a", 1.into(), None);
+        cache.insert("b", 2.into(), Some(Duration::ZERO));
+        assert_eq!(cache.get("a"), Some(1.into()));
+        assert_eq!(cache.get("b"), None);
+        assert!(!cache.contains("b"));
+
+        cache.insert("c", 3.into(), Some(Duration::ZERO));
+        cache.purge_expired();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.remove("a"), Some(1.into()));
+        assert!(cache.is_empty());
+    }
+}
@@ -1,9 +1,29 @@
-//! Global cache for the application.
+//! Global cache and tiered caches for the application.
+//!
+//! Named caches can be configured in the `[[cache]]` tables, each of which
+//! has an in-memory tier with per-entry TTL and an optional backend tier
+//! using a storage accessor.
 
 use lru::LruCache;
 use parking_lot::RwLock;
-use std::num::NonZeroUsize;
-use zino_core::{state::State, JsonValue, LazyLock};
+use std::{collections::HashMap, num::NonZeroUsize};
+use zino_core::{
+    error::Error,
+    response::{CachedResponse, ResponseCacheStore},
+    state::State,
+    BoxFuture, JsonValue, LazyLock,
+};
+
+mod backend;
+mod memory;
+mod tiered;
+
+pub use backend::CacheBackend;
+pub use memory::MemoryCache;
+pub use tiered::{CacheStats, TieredCache};
+
+#[cfg(feature = "cache-accessor")]
+pub use backend::AccessorBackend;
 
 /// Global cache built on the top of [`LruCache`].
 #[derive(Debug, Clone, Copy, Default)]
@@ -130,6 +150,65 @@ impl GlobalCache {
     }
 }
 
+impl ResponseCacheStore for GlobalCache {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<CachedResponse>, Error>> {
+        Box::pin(async move {
+            let Some(value) = Self::get(key) else {
+                return Ok(None);
+            };
+            let response = serde_json::from_value::<CachedResponse>(value)?;
+            if response.is_expired() {
+                Self::pop(key);
+                Ok(None)
+            } else {
+                Ok(Some(response))
+            }
+        })
+    }
+
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        response: &'a CachedResponse,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            Self::put(key, serde_json::to_value(response)?);
+            Ok(())
+        })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            Self::pop(key);
+            Ok(())
+        })
+    }
+
+    fn get_version<'a>(
+        &'a self,
+        model_name: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, Error>> {
+        let version = RESPONSE_CACHE_VERSIONS.read().get(model_name).cloned();
+        Box::pin(async move { Ok(version) })
+    }
+
+    fn set_version<'a>(
+        &'a self,
+        model_name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        RESPONSE_CACHE_VERSIONS
+            .write()
+            .insert(model_name.to_owned(), version.to_owned());
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Versions of the models for the cached responses. They are not stored in the LRU cache
+/// since the stale responses would be served again if a version was evicted.
+static RESPONSE_CACHE_VERSIONS: LazyLock<RwLock<HashMap<String, String>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
 /// Global cache.
 static GLOBAL_CACHE: LazyLock<RwLock<LruCache<String, JsonValue>>> = LazyLock::new(|| {
     let capacity = if let Some(cache) = State::shared().get_config("cache") {
@@ -0,0 +1,453 @@
+use super::{CacheBackend, MemoryCache};
+use futures::future::{BoxFuture, FutureExt, Shared};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering::Relaxed},
+        Arc,
+    },
+    time::Duration,
+};
+use toml::Table;
+use zino_core::{error::Error, extension::TomlTableExt, state::State, JsonValue, LazyLock};
+
+#[cfg(feature = "cache-accessor")]
+use super::AccessorBackend;
+
+/// A shared future of the loader.
+type SharedLoader = Shared<BoxFuture<'static, Result<JsonValue, Arc<Error>>>>;
+
+/// Statistics of a cache.
+#[derive(Debug, Default)]
+pub struct CacheStats {
+    /// Number of the hits in the memory tier.
+    memory_hits: AtomicU64,
+    /// Number of the hits in the backend tier.
+    backend_hits: AtomicU64,
+    /// Number of the misses.
+    misses: AtomicU64,
+}
+
+impl CacheStats {
+    /// Returns the number of the hits in the memory tier.
+    #[inline]
+    pub fn memory_hits(&self) -> u64 {
+        self.memory_hits.load(Relaxed)
+    }
+
+    /// Returns the number of the hits in the backend tier.
+    #[inline]
+    pub fn backend_hits(&self) -> u64 {
+        self.backend_hits.load(Relaxed)
+    }
+
+    /// Returns the number of the misses.
+    #[inline]
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Relaxed)
+    }
+
+    /// Returns the ratio of the hits to the total lookups.
+    pub fn hit_ratio(&self) -> f64 {
+        let hits = self.memory_hits() + self.backend_hits();
+        let total = hits + self.misses();
+        if total == 0 {
+            0.0
+        } else {
+            hits as f64 / total as f64
+        }
+    }
+}
+
+/// A two-tier cache with a sharded in-memory tier and an optional backend tier.
+///
+/// Concurrent loads of the same key are coalesced into a single call of the loader
+/// to prevent the cache stampedes.
+#[derive(Clone)]
+pub struct TieredCache {
+    /// Cache name.
+    name: &'static str,
+    /// Namespace as the key prefix in the backend tier.
+    namespace: &'static str,
+    /// Default TTL of the entries.
+    ttl: Option<Duration>,
+    /// In-memory tier.
+    memory: Arc<MemoryCache>,
+    /// Backend tier.
+    backend: Option<Arc<dyn CacheBackend>>,
+    /// Loaders in progress.
+    loaders: Arc<Mutex<HashMap<String, SharedLoader>>>,
+    /// Statistics.
+    stats: Arc<CacheStats>,
+}
+
+impl TieredCache {
+    /// Creates a new instance with the name and the capacity of the memory tier.
+    pub fn new(name: &'static str, capacity: usize) -> Self {
+        Self {
+            name,
+            namespace: name,
+            ttl: None,
+            memory: Arc::new(MemoryCache::new(capacity, 16)),
+            backend: None,
+            loaders: Arc::default(),
+            stats: Arc::default(),
+        }
+    }
+
+    /// Creates a new instance with the config.
+    ///
+    /// The config contains the fields `name`, `capacity`, `shards`, `ttl`,
+    /// `namespace` and `accessor`.
+    pub fn with_config(config: &Table) -> Self {
+        let name = config.get_str("name").unwrap_or("default");
+        let capacity = config.get_usize("capacity").unwrap_or(10000);
+        let num_shards = config.get_usize("shards").unwrap_or(16);
+        let mut cache = Self::new(name.to_owned().leak(), capacity);
+        cache.memory = Arc::new(MemoryCache::new(capacity, num_shards));
+        cache.ttl = config.get_duration("ttl");
+        if let Some(namespace) = config.get_str("namespace") {
+            cache.namespace = namespace.to_owned().leak();
+        }
+        #[cfg(feature = "cache-accessor")]
+        if let Some(accessor) = config.get_str("accessor") {
+            if let Some(backend) = AccessorBackend::with_accessor(accessor) {
+                cache.backend = Some(Arc::new(backend));
+            } else {
+                tracing::warn!(
+                    cache_name = name,
+                    "the accessor `{accessor}` does not exist"
+                );
+            }
+        }
+        cache
+    }
+
+    /// Returns a reference to the named cache in the `[[cache]]` config.
+    #[inline]
+    pub fn named(name: &str) -> Option<&'static TieredCache> {
+        NAMED_CACHES.iter().find(|cache| cache.name == name)
+    }
+
+    /// Sets the namespace as the key prefix in the backend tier.
+    #[inline]
+    pub fn namespace(mut self, namespace: &'static str) -> Self {
+        self.namespace = namespace;
+        self
+    }
+
+    /// Sets the default TTL of the entries.
+    #[inline]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the backend tier.
+    #[inline]
+    pub fn backend(mut self, backend: impl CacheBackend + 'static) -> Self {
+        self.backend = Some(Arc::new(backend));
+        self
+    }
+
+    /// Returns the cache name.
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns a reference to the in-memory tier.
+    #[inline]
+    pub fn memory(&self) -> &MemoryCache {
+        &self.memory
+    }
+
+    /// Returns a reference to the statistics.
+    #[inline]
+    pub fn stats(&self) -> &CacheStats {
+        &self.stats
+    }
+
+    /// Gets the value of the key. The memory tier is populated on a backend hit
+    /// with the remaining TTL of the entry in the backend.
+    pub async fn get(&self, key: &str) -> Result<Option<JsonValue>, Error> {
+        if let Some(value) = self.memory.get(key) {
+            self.record_hit("memory");
+            return Ok(Some(value));
+        }
+        if let Some(backend) = &self.backend {
+            if let Some((value, ttl)) = backend.get(&self.backend_key(key)).await? {
+                self.memory.insert(key, value.clone(), ttl.or(self.ttl));
+                self.record_hit("backend");
+                return Ok(Some(value));
+            }
+        }
+        self.record_miss();
+        Ok(None)
+    }
+
+    /// Inserts a key-value pair with the default TTL.
+    #[inline]
+    pub async fn insert(&self, key: &str, value: impl Into<JsonValue>) -> Result<(), Error> {
+        self.insert_with_ttl(key, value, self.ttl).await
+    }
+
+    /// Inserts a key-value pair with an optional TTL into both tiers.
+    pub async fn insert_with_ttl(
+        &self,
+        key: &str,
+        value: impl Into<JsonValue>,
+        ttl: Option<Duration>,
+    ) -> Result<(), Error> {
+        let value = value.into();
+        if let Some(backend) = &self.backend {
+            backend.put(&self.backend_key(key), &value, ttl).await?;
+        }
+        self.memory.insert(key, value, ttl);
+        Ok(())
+    }
+
+    /// Removes the value of the key from both tiers.
+    pub async fn remove(&self, key: &str) -> Result<(), Error> {
+        self.memory.remove(key);
+        if let Some(backend) = &self.backend {
+            backend.remove(&self.backend_key(key)).await?;
+        }
+        Ok(())
+    }
+
+    /// Gets the value of the key, or loads and inserts it if it is not present.
+    /// Concurrent calls for the same key share a single invocation of the loader,
+    /// and the error of the loader is returned to each of them with the sources.
+    pub async fn get_or_insert_with<F, Fut>(&self, key: &str, loader: F) -> Result<JsonValue, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<JsonValue, Error>> + Send + 'static,
+    {
+        if let Some(value) = self.get(key).await? {
+            return Ok(value);
+        }
+
+        let shared_loader = {
+            let mut loaders = self.loaders.lock();
+            if let Some(shared_loader) = loaders.get(key) {
+                shared_loader.clone()
+            } else {
+                let cache = self.clone();
+                let loader_key = key.to_owned();
+                let fut = loader();
+                let shared_loader = async move {
+                    let result = fut.await;
+                    if let Ok(value) = &result {
+                        if let Err(err) = cache.insert(&loader_key, value.clone()).await {
+                            tracing::warn!(
+                                cache_name = cache.name,
+                                "fail to insert the loaded value: {err}"
+                            );
+                        }
+                    }
+                    cache.loaders.lock().remove(&loader_key);
+                    result.map_err(Arc::new)
+                }
+                .boxed()
+                .shared();
+                loaders.insert(key.to_owned(), shared_loader.clone());
+                shared_loader
+            }
+        };
+        shared_loader
+            .await
+            .map_err(|err| Arc::try_unwrap(err).unwrap_or_else(|err| copy_error(&err)))
+    }
+
+    /// Returns the key in the backend tier.
+    #[inline]
+    fn backend_key(&self, key: &str) -> String {
+        format!("{}:{}", self.namespace, key)
+    }
+
+    /// Records a hit in the tier.
+    fn record_hit(&self, tier: &'static str) {
+        if tier == "memory" {
+            self.stats.memory_hits.fetch_add(1, Relaxed);
+        } else {
+            self.stats.backend_hits.fetch_add(1, Relaxed);
+        }
+        #[cfg(feature = "metrics")]
+        metrics::counter!("zino_cache_hits_total", "cache" => self.name, "tier" => tier)
+            .increment(1);
+    }
+
+    /// Records a miss.
+    fn record_miss(&self) {
+        self.stats.misses.fetch_add(1, Relaxed);
+        #[cfg(feature = "metrics")]
+        metrics::counter!("zino_cache_misses_total", "cache" => self.name).increment(1);
+    }
+}
+
+/// Copies the error with the message and the sources.
+fn copy_error(err: &Error) -> Error {
+    let message = err.message().to_owned();
+    match err.source() {
+        Some(source) => Error::with_source(message, copy_error(source)),
+        None => Error::new(message),
+    }
+}
+
+/// Named caches in the `[[cache]]` config.
+static NAMED_CACHES: LazyLock<Vec<TieredCache>> = LazyLock::new(|| {
+    let mut caches = Vec::new();
+    if let Some(configs) = State::shared().config().get_array("cache") {
+        for config in configs.iter().filter_map(|v| v.as_table()) {
+            let cache = TieredCache::with_config(config);
+            tracing::info!(
+                cache_name = cache.name,
+                "named cache `{}` is loaded",
+                cache.name
+            );
+            caches.push(cache);
+        }
+    }
+    caches
+});
+
+#[cfg(test)]
+mod tests {
+    use super::{CacheBackend, TieredCache};
+    use futures::{channel::oneshot, executor::block_on, future};
+    use parking_lot::Mutex;
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering::Relaxed},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+    use zino_core::{error::Error, BoxFuture, JsonValue};
+
+    #[derive(Default)]
+    struct TestBackend {
+        entries: Mutex<HashMap<String, (JsonValue, Option<Instant>)>>,
+    }
+
+    impl CacheBackend for TestBackend {
+        fn get<'a>(
+            &'a self,
+            key: &'a str,
+        ) -> BoxFuture<'a, Result<Option<(JsonValue, Option<Duration>)>, Error>> {
+            let entry = self.entries.lock().get(key).cloned();
+            let entry = entry.and_then(|(value, expires_at)| match expires_at {
+                Some(expires_at) => expires_at
+                    .checked_duration_since(Instant::now())
+                    .map(|ttl| (value, Some(ttl))),
+                None => Some((value, None)),
+            });
+            Box::pin(async move { Ok(entry) })
+        }
+
+        fn put<'a>(
+            &'a self,
+            key: &'a str,
+            value: &'a JsonValue,
+            ttl: Option<Duration>,
+        ) -> BoxFuture<'a, Result<(), Error>> {
+            let expires_at = ttl.map(|ttl| Instant::now() + ttl);
+            self.entries
+                .lock()
+                .insert(key.to_owned(), (value.clone(), expires_at));
+            Box::pin(async { Ok(()) })
+        }
+
+        fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+            self.entries.lock().remove(key);
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[test]
+    fn it_uses_remaining_ttl_on_backend_hits() {
+        let cache = TieredCache::new("test", 16)
+            .ttl(Duration::from_secs(3600))
+            .backend(TestBackend::default());
+        block_on(cache.insert_with_ttl("a", 1, Some(Duration::from_millis(100)))).unwrap();
+        block_on(cache.insert("b", 2)).unwrap();
+        cache.memory().clear();
+
+        assert_eq!(block_on(cache.get("a")).unwrap(), Some(1.into()));
+        assert_eq!(block_on(cache.get("b")).unwrap(), Some(2.into()));
+        assert_eq!(cache.stats().backend_hits(), 2);
+
+        std::thread::sleep(Duration::from_millis(150));
+        assert!(!cache.memory().contains("a"));
+        assert!(cache.memory().contains("b"));
+        assert_eq!(block_on(cache.get("a")).unwrap(), None);
+        assert_eq!(cache.stats().misses(), 1);
+    }
+
+    #[test]
+    fn it_coalesces_loaders() {
+        let cache = TieredCache::new("test", 16);
+        let counter = Arc::new(AtomicUsize::new(0));
+        let (sender, receiver) = oneshot::channel::<JsonValue>();
+        let receiver = Arc::new(Mutex::new(Some(receiver)));
+        let load = || {
+            let counter = counter.clone();
+            let receiver = receiver.lock().take();
+            async move {
+                counter.fetch_add(1, Relaxed);
+                match receiver {
+                    Some(receiver) => receiver.await.map_err(Error::from),
+                    None => Ok(JsonValue::Null),
+                }
+            }
+        };
+        let send = async move {
+            sender.send("value".into()).ok();
+        };
+
+        let (a, b, _) = block_on(future::join3(
+            cache.get_or_insert_with("key", load),
+            cache.get_or_insert_with("key", load),
+            send,
+        ));
+        assert_eq!(a.unwrap(), "value");
+        assert_eq!(b.unwrap(), "value");
+        assert_eq!(counter.load(Relaxed), 1);
+        assert_eq!(block_on(cache.get("key")).unwrap(), Some("value".into()));
+    }
+
+    #[test]
+    fn it_returns_loader_errors_with_sources() {
+        let cache = TieredCache::new("test", 16);
+        let (sender, receiver) = oneshot::channel::<()>();
+        let receiver = Arc::new(Mutex::new(Some(receiver)));
+        let load = || {
+            let receiver = receiver.lock().take();
+            async move {
+                if let Some(receiver) = receiver {
+                    receiver.await.ok();
+                }
+                let source = Error::new("connection refused");
+                Err(Error::with_source("fail to load the value", source))
+            }
+        };
+        let send = async move {
+            sender.send(()).ok();
+        };
+
+        let (a, b, _) = block_on(future::join3(
+            cache.get_or_insert_with("key", load),
+            cache.get_or_insert_with("key", load),
+            send,
+        ));
+        for err in [a.unwrap_err(), b.unwrap_err()] {
+            assert_eq!(err.message(), "fail to load the value");
+            assert_eq!(err.source().unwrap().message(), "connection refused");
+        }
+        assert_eq!(block_on(cache.get("key")).unwrap(), None);
+    }
+}
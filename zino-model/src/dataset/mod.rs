@@ -8,7 +8,7 @@ use zino_core::{
     extension::JsonObjectExt,
     model::{Model, ModelHooks},
     validation::Validation,
-    Map, Uuid,
+    JsonValue, Map, Uuid,
 };
 use zino_derive::{DecodeRow, ModelAccessor, Schema};
 
@@ -120,6 +120,29 @@ impl Model for Dataset {
     }
 }
 
+impl Dataset {
+    /// Records an output of the dataset, such as the result of exporting a query.
+    /// The outputs are stored in the `extra` field in the order of their creation.
+    pub fn add_output(&mut self, output: Map) {
+        let outputs = self
+            .extra
+            .entry("outputs")
+            .or_insert_with(|| Vec::<JsonValue>::new().into());
+        if let Some(outputs) = outputs.as_array_mut() {
+            outputs.push(output.into());
+        } else {
+            *outputs = vec![JsonValue::from(output)].into();
+        }
+        self.valid_from = DateTime::now();
+    }
+
+    /// Returns the outputs of the dataset.
+    #[inline]
+    pub fn outputs(&self) -> Option<&Vec<JsonValue>> {
+        self.extra.get_array("outputs")
+    }
+}
+
 impl ModelHooks for Dataset {
     type Data = ();
     #[cfg(feature = "maintainer-id")]
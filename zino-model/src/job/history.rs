@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use zino_core::{
+    datetime::DateTime,
+    error::Error,
+    extension::JsonObjectExt,
+    model::{Model, ModelHooks},
+    schedule::JobRun,
+    validation::Validation,
+    Map, Uuid,
+};
+use zino_derive::{DecodeRow, Schema};
+
+/// The `job_history` model.
+///
+/// It records a run of the named job executed by a scheduler instance.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, DecodeRow, Schema)]
+#[serde(default)]
+pub struct JobHistory {
+    // Basic fields.
+    #[schema(read_only)]
+    id: Uuid,
+    #[schema(not_null, index_type = "hash")]
+    name: String,
+    #[schema(index_type = "hash")]
+    status: String,
+
+    // Info fields.
+    #[schema(read_only)]
+    job_id: Uuid,
+    #[schema(read_only)]
+    instance_id: String,
+    #[schema(read_only, index_type = "btree")]
+    started_at: DateTime,
+    #[schema(read_only)]
+    duration: u64, // milliseconds
+    #[schema(read_only)]
+    error: String,
+
+    // Revisions.
+    #[schema(read_only, default_value = "now", index_type = "btree")]
+    created_at: DateTime,
+}
+
+impl Model for JobHistory {
+    const MODEL_NAME: &'static str = "job_history";
+
+    #[inline]
+    fn new() -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            ..Self::default()
+        }
+    }
+
+    fn read_map(&mut self, data: &Map) -> Validation {
+        let mut validation = Validation::new();
+        if let Some(result) = data.parse_uuid("id") {
+            match result {
+                Ok(id) => self.id = id,
+                Err(err) => validation.record_fail("id", err),
+            }
+        }
+        if let Some(name) = data.parse_string("name") {
+            self.name = name.into_owned();
+        }
+        validation
+    }
+}
+
+impl ModelHooks for JobHistory {
+    type Data = ();
+    type Extension = ();
+}
+
+impl JobHistory {
+    /// Creates a new instance for the run of the job executed by the instance.
+    pub fn with_run(name: &str, instance_id: &str, run: &JobRun) -> Self {
+        let status = if run.is_success() {
+            "Succeeded"
+        } else {
+            "Failed"
+        };
+        Self {
+            id: run.id(),
+            name: name.to_owned(),
+            status: status.to_owned(),
+            job_id: run.job_id(),
+            instance_id: instance_id.to_owned(),
+            started_at: run.started_at(),
+            duration: run.duration().as_millis().try_into().unwrap_or(u64::MAX),
+            error: run.error().unwrap_or_default().to_owned(),
+            created_at: DateTime::now(),
+        }
+    }
+
+    /// Returns the job name.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the status.
+    #[inline]
+    pub fn status(&self) -> &str {
+        &self.status
+    }
+
+    /// Returns the start time.
+    #[inline]
+    pub fn started_at(&self) -> DateTime {
+        self.started_at
+    }
+
+    /// Returns the duration in milliseconds.
+    #[inline]
+    pub fn duration(&self) -> u64 {
+        self.duration
+    }
+
+    /// Returns the error message.
+    #[inline]
+    pub fn error(&self) -> &str {
+        &self.error
+    }
+}
@@ -0,0 +1,97 @@
+//! The `job` model and related services.
+
+use serde::{Deserialize, Serialize};
+use zino_core::{
+    datetime::DateTime,
+    error::Error,
+    extension::JsonObjectExt,
+    model::{Model, ModelHooks},
+    validation::Validation,
+    Map,
+};
+use zino_derive::{DecodeRow, Schema};
+
+mod history;
+mod store;
+
+pub use history::JobHistory;
+pub use store::OrmJobStore;
+
+/// The `job` model.
+///
+/// It stores the state and the lease of a named job which is shared by the scheduler instances.
+/// The status is `Pending` until the state of the job has been saved.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, DecodeRow, Schema)]
+#[serde(default)]
+pub struct Job {
+    // Basic fields.
+    #[schema(primary_key, read_only)]
+    name: String,
+    #[schema(default_value = "Pending", index_type = "hash")]
+    status: String,
+
+    // Info fields.
+    schedule: String,
+    data: Map,
+    last_tick: DateTime,
+    remaining_ticks: Option<i64>,
+    lock_holder: String,
+    lock_expires_at: DateTime,
+
+    // Revisions.
+    #[schema(read_only, default_value = "now", index_type = "btree")]
+    created_at: DateTime,
+    #[schema(default_value = "now", index_type = "btree")]
+    updated_at: DateTime,
+}
+
+impl Model for Job {
+    const MODEL_NAME: &'static str = "job";
+
+    #[inline]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn read_map(&mut self, data: &Map) -> Validation {
+        let mut validation = Validation::new();
+        if let Some(name) = data.parse_string("name") {
+            self.name = name.into_owned();
+        }
+        if let Some(schedule) = data.parse_string("schedule") {
+            self.schedule = schedule.into_owned();
+        }
+        if let Some(result) = data.parse_i64("remaining_ticks") {
+            match result {
+                Ok(remaining_ticks) => self.remaining_ticks = Some(remaining_ticks),
+                Err(err) => validation.record_fail("remaining_ticks", err),
+            }
+        }
+        validation
+    }
+}
+
+impl ModelHooks for Job {
+    type Data = ();
+    type Extension = ();
+}
+
+impl Job {
+    /// Returns the cron expression of the schedule.
+    #[inline]
+    pub fn schedule(&self) -> &str {
+        &self.schedule
+    }
+
+    /// Returns the holder of the lease.
+    #[inline]
+    pub fn lock_holder(&self) -> &str {
+        &self.lock_holder
+    }
+
+    /// Returns the expiration time of the lease.
+    #[inline]
+    pub fn lock_expires_at(&self) -> DateTime {
+        self.lock_expires_at
+    }
+}
@@ -0,0 +1,151 @@
+use super::{Job, JobHistory};
+use std::time::Duration;
+use zino_core::{
+    datetime::DateTime,
+    error::Error,
+    extension::JsonObjectExt,
+    model::{Model, Mutation, Query},
+    orm::Schema,
+    schedule::{JobRun, JobState, JobStore},
+    BoxFuture, Map,
+};
+
+/// Persistent storage for the jobs backed by the [`Job`] and [`JobHistory`] models.
+///
+/// A lease is acquired by a conditional `UPDATE` which checks that the lease is held by
+/// the holder or has been expired, so that it can not be taken over by the others
+/// before it expires.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrmJobStore;
+
+impl OrmJobStore {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Creates the tables if they do not exist.
+    pub async fn create_tables(&self) -> Result<(), Error> {
+        Job::create_table().await?;
+        JobHistory::create_table().await?;
+        Ok(())
+    }
+}
+
+impl JobStore for OrmJobStore {
+    fn try_lock<'a>(
+        &'a self,
+        name: &'a str,
+        holder: &'a str,
+        lease_duration: Duration,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        Box::pin(async move {
+            let now = DateTime::now();
+            let expires_at = now + lease_duration;
+            let mut query = Query::from_entry("name", name);
+            let conditions = vec![
+                Map::from_entry("lock_holder", holder),
+                Map::from_entry("lock_expires_at", Map::from_entry("$lt", now)),
+            ];
+            query.add_filter("$or", conditions);
+
+            let mut updates = Map::new();
+            updates.upsert("lock_holder", holder);
+            updates.upsert("lock_expires_at", expires_at);
+            updates.upsert("updated_at", now);
+            let mut mutation = Mutation::new(updates);
+            let ctx = Job::update_many(&query, &mut mutation).await?;
+            if ctx.rows_affected() == Some(1) {
+                return Ok(true);
+            }
+
+            // The job is inserted with the lease if it does not exist. The insertion fails
+            // by the primary key if another instance has inserted it concurrently.
+            let query = Query::from_entry("name", name);
+            if Job::exists(&query).await? {
+                return Ok(false);
+            }
+
+            let mut job = Job::new();
+            job.name = name.to_owned();
+            job.lock_holder = holder.to_owned();
+            job.lock_expires_at = expires_at;
+            match job.insert().await {
+                Ok(_) => Ok(true),
+                Err(_) if Job::exists(&query).await? => Ok(false),
+                Err(err) => Err(err),
+            }
+        })
+    }
+
+    fn unlock<'a>(&'a self, name: &'a str, holder: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let mut query = Query::from_entry("name", name);
+            query.add_filter("lock_holder", holder);
+
+            let now = DateTime::now();
+            let mut updates = Map::new();
+            updates.upsert("lock_holder", "");
+            updates.upsert("lock_expires_at", now);
+            updates.upsert("updated_at", now);
+            let mut mutation = Mutation::new(updates);
+            Job::update_many(&query, &mut mutation).await?;
+            Ok(())
+        })
+    }
+
+    fn load_state<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Option<JobState>, Error>> {
+        Box::pin(async move {
+            let query = Query::from_entry("name", name);
+            let state = Job::find_one::<Job>(&query)
+                .await?
+                .filter(|job| job.status != "Pending")
+                .map(|job| {
+                    let remaining_ticks = job
+                        .remaining_ticks
+                        .map(|ticks| usize::try_from(ticks).unwrap_or_default());
+                    JobState::new(job.schedule, job.data, Some(job.last_tick), remaining_ticks)
+                });
+            Ok(state)
+        })
+    }
+
+    fn save_state<'a>(
+        &'a self,
+        name: &'a str,
+        state: &'a JobState,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let query = Query::from_entry("name", name);
+            let now = DateTime::now();
+            let remaining_ticks = state
+                .remaining_ticks()
+                .map(|ticks| i64::try_from(ticks).unwrap_or(i64::MAX));
+            let mut updates = Map::new();
+            updates.upsert("status", "Active");
+            updates.upsert("schedule", state.schedule());
+            updates.upsert("data", state.data().clone());
+            updates.upsert("last_tick", state.last_tick().unwrap_or(now));
+            updates.upsert("remaining_ticks", remaining_ticks);
+            updates.upsert("updated_at", now);
+            let mut mutation = Mutation::new(updates);
+            Job::update_many(&query, &mut mutation).await?;
+            Ok(())
+        })
+    }
+
+    fn record_run<'a>(
+        &'a self,
+        name: &'a str,
+        instance_id: &'a str,
+        run: &'a JobRun,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            JobHistory::with_run(name, instance_id, run)
+                .insert()
+                .await?;
+            Ok(())
+        })
+    }
+}
@@ -17,6 +17,7 @@ pub mod order;
 
 pub mod collection;
 pub mod dataset;
+pub mod job;
 pub mod project;
 pub mod source;
 pub mod task;
@@ -24,6 +25,9 @@ pub mod task;
 pub mod log;
 pub mod record;
 
+#[cfg(feature = "rag")]
+pub mod passage;
+
 pub use group::Group;
 pub use policy::Policy;
 pub use resource::Resource;
@@ -36,9 +40,13 @@ pub use order::Order;
 
 pub use collection::Collection;
 pub use dataset::Dataset;
+pub use job::Job;
 pub use project::Project;
 pub use source::Source;
 pub use task::Task;
 
 pub use log::Log;
 pub use record::Record;
+
+#[cfg(feature = "rag")]
+pub use passage::Passage;
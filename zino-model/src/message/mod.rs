@@ -18,6 +18,9 @@ use crate::tag::Tag;
 #[cfg(feature = "maintainer-id")]
 use zino_core::auth::UserSession;
 
+#[cfg(feature = "embedding")]
+use zino_core::model::QueryContext;
+
 /// The `message` model.
 #[derive(Debug, Clone, Default, Serialize, Deserialize, DecodeRow, Schema, ModelAccessor)]
 #[serde(default)]
@@ -46,6 +49,9 @@ pub struct Message {
     consumer_id: Option<Uuid>, // group.id
 
     message: String,
+    #[cfg(feature = "embedding")]
+    #[schema(embedding = "message")]
+    embedding: Vec<f32>,
     #[cfg(feature = "tags")]
     #[schema(reference = "Tag", index_type = "gin")]
     tags: Vec<Uuid>, // tag.id, tag.namespace = "*:message"
@@ -120,6 +126,9 @@ impl Model for Message {
 }
 
 impl ModelHooks for Message {
+    #[cfg(feature = "embedding")]
+    type Data = Map;
+    #[cfg(not(feature = "embedding"))]
     type Data = ();
     #[cfg(feature = "maintainer-id")]
     type Extension = UserSession<Uuid, String>;
@@ -144,4 +153,16 @@ impl ModelHooks for Message {
         }
         Ok(())
     }
+
+    #[cfg(feature = "embedding")]
+    #[inline]
+    async fn before_save(&mut self) -> Result<Self::Data, Error> {
+        self.embedding_sources().await
+    }
+
+    #[cfg(feature = "embedding")]
+    #[inline]
+    async fn after_save(ctx: &QueryContext, data: Self::Data) -> Result<(), Error> {
+        Self::sync_embeddings(ctx, &data).await
+    }
 }
@@ -0,0 +1,542 @@
+//! The `passage` model and related services for the retrieval-augmented answering.
+//!
+//! The documents are split into passages with the embeddings of their contents.
+//! The passages most similar to a question are retrieved with the row-level visibility
+//! of the user session, and cited by the answer of a chatbot.
+//!
+//! ```toml
+//! [rag]
+//! chatbot = "gpt"
+//! accessor = "local"
+//! chunk-size = 1000
+//! chunk-overlap = 200
+//! top-k = 5
+//! max-top-k = 20
+//! max-file-size = 10485760
+//! min-similarity = 0.2
+//! temperature = 0.2
+//! max-tokens = 1024
+//! ```
+
+use crate::{group::Group, resource::Resource};
+use serde::{Deserialize, Serialize};
+use zino_core::{
+    accessor::GlobalAccessor,
+    auth::UserSession,
+    bail,
+    chatbot::{
+        build_rag_conversation, parse_citations, ChatbotService, EmbeddingService, GlobalChatbot,
+        RetrievedPassage, TextSplitter,
+    },
+    datetime::DateTime,
+    error::Error,
+    extension::{JsonObjectExt, TomlTableExt},
+    model::{Model, ModelHooks, QueryContext},
+    orm::Transaction,
+    state::State,
+    validation::Validation,
+    warn, Map, Uuid,
+};
+use zino_derive::{DecodeRow, ModelAccessor, Schema};
+
+#[cfg(feature = "owner-id")]
+use crate::user::User;
+
+/// The `passage` model.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, DecodeRow, Schema, ModelAccessor)]
+#[serde(default)]
+pub struct Passage {
+    // Basic fields.
+    #[schema(read_only)]
+    id: Uuid,
+    #[schema(not_null)]
+    name: String,
+    #[cfg(feature = "namespace")]
+    #[schema(default_value = "Passage::model_namespace", index_type = "hash")]
+    namespace: String,
+    #[cfg(feature = "visibility")]
+    #[schema(default_value = "Internal")]
+    visibility: String,
+    #[schema(default_value = "Active", index_type = "hash")]
+    status: String,
+    description: String,
+
+    // Info fields.
+    #[schema(reference = "Group")]
+    tenant_id: Option<Uuid>, // group.id
+    #[schema(reference = "Resource")]
+    resource_id: Option<Uuid>, // resource.id
+    #[schema(not_null, index_type = "hash")]
+    source: String,
+    chunk_index: u32,
+    #[schema(not_null)]
+    content: String,
+    #[schema(embedding = "content", index_type = "hnsw")]
+    embedding: Vec<f32>,
+
+    // Extensions.
+    extra: Map,
+
+    // Revisions.
+    #[cfg(feature = "owner-id")]
+    #[schema(reference = "User")]
+    owner_id: Option<Uuid>, // user.id
+    #[schema(read_only, default_value = "now", index_type = "btree")]
+    created_at: DateTime,
+    #[schema(default_value = "now", index_type = "btree")]
+    updated_at: DateTime,
+    version: u64,
+    #[cfg(feature = "edition")]
+    edition: u32,
+}
+
+impl Model for Passage {
+    const MODEL_NAME: &'static str = "passage";
+
+    #[inline]
+    fn new() -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            ..Self::default()
+        }
+    }
+
+    fn read_map(&mut self, data: &Map) -> Validation {
+        let mut validation = Validation::new();
+        if let Some(result) = data.parse_uuid("id") {
+            match result {
+                Ok(id) => self.id = id,
+                Err(err) => validation.record_fail("id", err),
+            }
+        }
+        if let Some(name) = data.parse_string("name") {
+            self.name = name.into_owned();
+        }
+        if let Some(description) = data.parse_string("description") {
+            self.description = description.into_owned();
+        }
+        if let Some(result) = data.parse_uuid("tenant_id") {
+            match result {
+                Ok(tenant_id) => self.tenant_id = Some(tenant_id),
+                Err(err) => validation.record_fail("tenant_id", err),
+            }
+        }
+        if let Some(result) = data.parse_uuid("resource_id") {
+            match result {
+                Ok(resource_id) => self.resource_id = Some(resource_id),
+                Err(err) => validation.record_fail("resource_id", err),
+            }
+        }
+        if let Some(source) = data.parse_string("source") {
+            self.source = source.into_owned();
+        }
+        if let Some(content) = data.parse_string("content") {
+            self.content = content.into_owned();
+        }
+        #[cfg(feature = "owner-id")]
+        if let Some(result) = data.parse_uuid("owner_id") {
+            match result {
+                Ok(owner_id) => self.owner_id = Some(owner_id),
+                Err(err) => validation.record_fail("owner_id", err),
+            }
+        }
+        validation
+    }
+}
+
+impl Passage {
+    /// Creates a template for the passages of the resource,
+    /// which inherit the `namespace`, `visibility` and `owner_id`.
+    pub fn with_resource(resource: &Resource) -> Self {
+        let mut passage = Self::new();
+        passage.name = resource.name().to_owned();
+        #[cfg(feature = "namespace")]
+        {
+            passage.namespace = resource.namespace().to_owned();
+        }
+        #[cfg(feature = "visibility")]
+        {
+            passage.visibility = resource.visibility().to_owned();
+        }
+        #[cfg(feature = "owner-id")]
+        {
+            passage.owner_id = resource.owner_id().copied();
+        }
+        passage.resource_id = Some(*resource.id());
+        passage.source = format!("resource:{}", resource.id());
+        passage
+    }
+
+    /// Sets the `tenant_id`.
+    #[inline]
+    pub fn set_tenant_id(&mut self, tenant_id: Uuid) {
+        self.tenant_id = Some(tenant_id);
+    }
+
+    /// Sets the `visibility`.
+    #[cfg(feature = "visibility")]
+    #[inline]
+    pub fn set_visibility(&mut self, visibility: impl Into<String>) {
+        self.visibility = visibility.into();
+    }
+
+    /// Sets the `owner_id`.
+    #[cfg(feature = "owner-id")]
+    #[inline]
+    pub fn set_owner_id(&mut self, owner_id: Uuid) {
+        self.owner_id = Some(owner_id);
+    }
+
+    /// Splits the text into passages with the template, replacing the existing passages
+    /// of the same source inside of a transaction. The embeddings of the passages
+    /// are generated in a batch. It returns the number of the passages.
+    pub async fn index_text(&self, text: &str) -> Result<usize, Error> {
+        if self.source.is_empty() {
+            return Err(warn!("the `source` of the passages should be specified"));
+        }
+
+        let splitter = State::shared()
+            .get_config("rag")
+            .map(TextSplitter::with_config)
+            .unwrap_or_default();
+        let chunks = splitter.split(text);
+        let num_chunks = chunks.len();
+        let mut passages = Vec::with_capacity(num_chunks);
+        for (index, content) in chunks.into_iter().enumerate() {
+            let mut passage = self.clone();
+            passage.id = Uuid::now_v7();
+            passage.chunk_index = index.try_into()?;
+            passage.content = content;
+            passages.push(passage);
+        }
+
+        let query = Query::from_entry("source", self.source.as_str());
+        Self::transactional_replace(&query, passages).await?;
+        Ok(num_chunks)
+    }
+
+    /// Indexes the `name` and `description` of the resource for the tenant of the user session.
+    /// The content of the file is also indexed if it is a text file in the storage
+    /// of `[rag] accessor` whose size does not exceed `[rag] max-file-size`.
+    ///
+    /// A superuser can index any resource. Other users should belong to a tenant
+    /// and own the resource, since the resources are not scoped by tenants.
+    pub async fn index_resource(
+        resource: &Resource,
+        session: &UserSession<Uuid, String>,
+    ) -> Result<usize, Error> {
+        let tenant_id = session.tenant_id().copied();
+        if !session.is_superuser() {
+            if tenant_id.is_none() {
+                bail!("403 Forbidden: the tenant of the user session should be specified");
+            }
+            if resource.owner_id() != Some(session.user_id()) {
+                bail!(
+                    "403 Forbidden: only the owner can index the resource `{}`",
+                    resource.id()
+                );
+            }
+        }
+
+        let mut texts = vec![resource.name().to_owned()];
+        if !resource.description().is_empty() {
+            texts.push(resource.description().to_owned());
+        }
+
+        let location = resource.location();
+        let mime_type = resource.mime_type();
+        let is_text = mime_type.starts_with("text/")
+            || mime_type.ends_with("json")
+            || mime_type.ends_with("xml");
+        if let Some(accessor) = State::shared()
+            .get_config("rag")
+            .and_then(|config| config.get_str("accessor"))
+            .filter(|_| is_text && !location.is_empty())
+        {
+            if let Some(text) = Self::read_text_file(accessor, location).await? {
+                texts.push(text);
+            }
+        }
+        let mut passage = Self::with_resource(resource);
+        passage.tenant_id = tenant_id;
+        passage.index_text(&texts.join("\n\n")).await
+    }
+
+    /// Indexes the UTF-8 text files in the directory of the storage accessor recursively
+    /// with the template. It returns the number of the passages.
+    /// The files larger than `[rag] max-file-size` are skipped.
+    pub async fn index_files(&self, accessor: &str, dir: &str) -> Result<usize, Error> {
+        let operator = GlobalAccessor::get(accessor)
+            .ok_or_else(|| warn!("the accessor `{}` does not exist", accessor))?;
+        let entries = operator.list_with(dir).recursive(true).await?;
+        let mut num_passages = 0;
+        for entry in entries {
+            if !entry.metadata().is_file() {
+                continue;
+            }
+
+            let path = entry.path();
+            if let Some(text) = Self::read_text_file(accessor, path).await? {
+                let mut passage = self.clone();
+                passage.name = entry.name().to_owned();
+                passage.resource_id = None;
+                passage.source = format!("{accessor}:{path}");
+                num_passages += passage.index_text(&text).await?;
+            }
+        }
+        Ok(num_passages)
+    }
+
+    /// Reads a UTF-8 text file in the storage of the accessor. It returns `None`
+    /// if the file is not a text file or its size exceeds `[rag] max-file-size`,
+    /// so that no more than the limit is loaded into memory.
+    async fn read_text_file(accessor: &str, path: &str) -> Result<Option<String>, Error> {
+        let operator = GlobalAccessor::get(accessor)
+            .ok_or_else(|| warn!("the accessor `{}` does not exist", accessor))?;
+        let max_file_size = State::shared()
+            .get_config("rag")
+            .and_then(|config| config.get_u64("max-file-size"))
+            .unwrap_or(10 * 1024 * 1024);
+        let file_size = operator.stat(path).await?.content_length();
+        if file_size > max_file_size {
+            tracing::warn!(accessor, path, file_size, "skip indexing the large file");
+            return Ok(None);
+        } else if file_size == 0 {
+            return Ok(Some(String::new()));
+        }
+
+        // The range bounds the bytes read in case the file grows after the `stat`.
+        let bytes = operator.read_with(path).range(0..file_size).await?;
+        match String::from_utf8(bytes) {
+            Ok(text) => Ok(Some(text)),
+            Err(_) => {
+                tracing::warn!(accessor, path, "skip indexing the non-text file");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns the filters of the passages visible to the user session.
+    ///
+    /// A superuser can see all the passages, and an admin can see the passages of its tenant.
+    /// An admin without a tenant is denied. Other users can see the `Public` passages,
+    /// the `Internal` and `Protected` passages of its tenant and the passages owned by itself.
+    /// Anonymous users can only see the `Public` passages.
+    pub fn access_filters(session: Option<&UserSession<Uuid, String>>) -> Result<Map, Error> {
+        let mut filters = Map::new();
+        match session {
+            Some(session) if session.is_superuser() => (),
+            Some(session) if session.has_admin_role() => {
+                let Some(tenant_id) = session.tenant_id() else {
+                    bail!("403 Forbidden: the tenant of the admin should be specified");
+                };
+                filters.upsert("tenant_id", tenant_id.to_string());
+            }
+            Some(session) => {
+                let mut visible_filters = vec![
+                    Map::from_entry("visibility", "Public"),
+                    Map::from_entry("owner_id", session.user_id().to_string()),
+                ];
+                if let Some(tenant_id) = session.tenant_id() {
+                    let visibility = Map::from_entry("$in", vec!["Internal", "Protected"]);
+                    let shared_filters = vec![
+                        Map::from_entry("visibility", visibility),
+                        Map::from_entry("tenant_id", tenant_id.to_string()),
+                    ];
+                    visible_filters.push(Map::from_entry("$and", shared_filters));
+                }
+                filters.upsert("$or", visible_filters);
+            }
+            None => {
+                filters.upsert("visibility", "Public");
+            }
+        }
+        Ok(filters)
+    }
+
+    /// Retrieves the top-k passages most similar to the question, which are visible
+    /// to the user session.
+    ///
+    /// The options can contain the filters of `namespace`, `visibility`, `tenant_id`,
+    /// `resource_id` and `source`, and the `top_k` and `min_similarity`
+    /// to override the `[rag]` config. The `top_k` is clamped to `[rag] max-top-k`.
+    pub async fn retrieve(
+        question: &str,
+        options: &Map,
+        session: Option<&UserSession<Uuid, String>>,
+    ) -> Result<Vec<Map>, Error> {
+        let config = State::shared().get_config("rag");
+        let embedder = GlobalChatbot::embedder()
+            .ok_or_else(|| warn!("no chatbot is configured for the text embeddings"))?;
+        let embedding = embedder.try_embed_one(question.to_owned()).await?;
+        if embedding.iter().all(|&x| x == 0.0) {
+            bail!("the embedding of the question should be a nonzero vector");
+        }
+
+        let mut similarity_filter = Map::new();
+        similarity_filter.upsert("$field", "embedding");
+        similarity_filter.upsert("$vector", embedding);
+        if let Some(min_similarity) = options
+            .get_f32("min_similarity")
+            .or_else(|| config.and_then(|config| config.get_f32("min-similarity")))
+        {
+            similarity_filter.upsert("$min_similarity", min_similarity);
+        }
+
+        let max_top_k = config
+            .and_then(|config| config.get_usize("max-top-k"))
+            .unwrap_or(20);
+        let top_k = options
+            .get_usize("top_k")
+            .or_else(|| config.and_then(|config| config.get_usize("top-k")))
+            .unwrap_or(5)
+            .clamp(1, max_top_k.max(1));
+        let filter_fields = [
+            "namespace",
+            "visibility",
+            "tenant_id",
+            "resource_id",
+            "source",
+        ];
+        let mut query = Self::default_list_query();
+        for key in filter_fields {
+            if let Some(value) = options.get(key) {
+                query.add_filter(key, value.clone());
+            }
+        }
+        query.append_filters(&mut Self::access_filters(session)?);
+        query.add_filter("$near", similarity_filter);
+        query.set_limit(top_k);
+        Self::find(&query).await
+    }
+
+    /// Answers the question with the retrieved passages, and returns the `answer`,
+    /// the `citations` of the passages and the token `usage`.
+    ///
+    /// The chatbot is specified by `[rag] chatbot`, and defaults to the chatbot
+    /// for the text embeddings. The `temperature` and `max-tokens` for the model
+    /// are also read from the `[rag]` config, which can not be overridden by the options.
+    pub async fn answer(
+        question: &str,
+        options: &Map,
+        session: Option<&UserSession<Uuid, String>>,
+    ) -> Result<Map, Error> {
+        let config = State::shared().get_config("rag");
+        let chatbot = match config.and_then(|config| config.get_str("chatbot")) {
+            Some(name) => GlobalChatbot::get(name)
+                .ok_or_else(|| warn!("404 Not Found: the chatbot `{}` does not exist", name))?,
+            None => GlobalChatbot::embedder()
+                .ok_or_else(|| warn!("no chatbot is configured for the answering"))?,
+        };
+
+        let passages = Self::retrieve(question, options, session).await?;
+        let retrieved_passages = passages
+            .iter()
+            .map(|passage| {
+                let id = passage.get_str("id").unwrap_or_default();
+                let content = passage.get_str("content").unwrap_or_default();
+                let mut retrieved_passage = RetrievedPassage::new(id, content);
+                retrieved_passage.set_title(passage.get_str("name").unwrap_or_default());
+                retrieved_passage.set_source(passage.get_str("source").unwrap_or_default());
+                retrieved_passage
+            })
+            .collect::<Vec<_>>();
+        let system_prompt = config.and_then(|config| config.get_str("system-prompt"));
+        let conversation = build_rag_conversation(question, &retrieved_passages, system_prompt);
+        let mut chat_options = Map::new();
+        if let Some(temperature) = config.and_then(|config| config.get_f32("temperature")) {
+            chat_options.upsert("temperature", temperature);
+        }
+        if let Some(max_tokens) = config.and_then(|config| config.get_u64("max-tokens")) {
+            chat_options.upsert("max-tokens", max_tokens);
+        }
+        let chat_options = Some(chat_options).filter(|options| !options.is_empty());
+        let completion = chatbot.try_complete(&conversation, chat_options).await?;
+
+        let answer = completion.content().unwrap_or_default().to_owned();
+        let citations = parse_citations(&answer, passages.len())
+            .into_iter()
+            .map(|number| {
+                let mut citation = passages[number - 1].clone();
+                citation.upsert("number", number);
+                citation
+            })
+            .collect::<Vec<_>>();
+        let mut data = Map::new();
+        data.upsert("answer", answer);
+        data.upsert("citations", citations);
+        data.upsert("model", completion.model());
+        if let Some(usage) = completion.usage() {
+            let mut map = Map::new();
+            map.upsert("prompt_tokens", usage.prompt_tokens());
+            map.upsert("completion_tokens", usage.completion_tokens());
+            map.upsert("total_tokens", usage.total_tokens());
+            data.upsert("usage", map);
+        }
+        Ok(data)
+    }
+}
+
+impl ModelHooks for Passage {
+    type Data = Map;
+    type Extension = ();
+
+    #[inline]
+    async fn before_save(&mut self) -> Result<Self::Data, Error> {
+        self.embedding_sources().await
+    }
+
+    #[inline]
+    async fn after_save(ctx: &QueryContext, data: Self::Data) -> Result<(), Error> {
+        Self::sync_embeddings(ctx, &data).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Passage;
+    use zino_core::{auth::UserSession, extension::JsonObjectExt, JsonValue, Map, Uuid};
+
+    #[test]
+    fn it_builds_access_filters() {
+        let filters = Passage::access_filters(None).unwrap();
+        assert_eq!(filters, Map::from_entry("visibility", "Public"));
+
+        let user_id = Uuid::now_v7();
+        let tenant_id = Uuid::now_v7();
+        let mut session = UserSession::<Uuid, String>::new(user_id, None);
+        session.set_roles(vec!["superuser".to_owned()]);
+        assert!(Passage::access_filters(Some(&session)).unwrap().is_empty());
+
+        session.set_roles(vec!["admin".to_owned()]);
+        assert!(Passage::access_filters(Some(&session)).is_err());
+
+        session.set_tenant_id(tenant_id);
+        let filters = Passage::access_filters(Some(&session)).unwrap();
+        assert_eq!(filters, Map::from_entry("tenant_id", tenant_id.to_string()));
+
+        session.set_roles(vec!["user".to_owned()]);
+        let filters = Passage::access_filters(Some(&session)).unwrap();
+        let visible_filters = filters.get_array("$or").unwrap();
+        assert_eq!(visible_filters.len(), 3);
+        assert_eq!(
+            visible_filters[1],
+            JsonValue::from(Map::from_entry("owner_id", user_id.to_string()))
+        );
+
+        let shared_filters = visible_filters[2]
+            .as_object()
+            .and_then(|filters| filters.get_array("$and"))
+            .unwrap();
+        assert_eq!(
+            shared_filters[1],
+            JsonValue::from(Map::from_entry("tenant_id", tenant_id.to_string()))
+        );
+
+        let session = UserSession::<Uuid, String>::new(user_id, None);
+        let filters = Passage::access_filters(Some(&session)).unwrap();
+        let visible_filters = filters.get_array("$or").unwrap();
+        assert_eq!(visible_filters.len(), 2);
+        assert!(!JsonValue::from(filters).to_string().contains("Internal"));
+    }
+}
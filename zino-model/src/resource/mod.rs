@@ -5,6 +5,7 @@ use zino_core::{
     datetime::DateTime,
     error::Error,
     extension::JsonObjectExt,
+    file::{UploadSession, UploadedFile},
     model::{Model, ModelHooks},
     validation::Validation,
     Map, Uuid,
@@ -17,9 +18,15 @@ use crate::tag::Tag;
 #[cfg(any(feature = "owner-id", feature = "maintainer-id"))]
 use crate::user::User;
 
-#[cfg(feature = "maintainer-id")]
+#[cfg(any(feature = "maintainer-id", feature = "upload"))]
 use zino_core::auth::UserSession;
 
+#[cfg(feature = "embedding")]
+use zino_core::model::QueryContext;
+
+#[cfg(feature = "upload")]
+use zino_core::{file::UploadOptions, request::RequestContext, response::Rejection};
+
 /// The `resource` model.
 #[derive(Debug, Clone, Default, Serialize, Deserialize, DecodeRow, Schema, ModelAccessor)]
 #[serde(default)]
@@ -46,6 +53,9 @@ pub struct Resource {
     mime_type: String,
     #[schema(not_null)]
     location: String,
+    #[cfg(feature = "embedding")]
+    #[schema(embedding = "description")]
+    embedding: Vec<f32>,
     #[cfg(feature = "tags")]
     #[schema(reference = "Tag", index_type = "gin")]
     tags: Vec<Uuid>, // tag.id, tag.namespace = "*:resource"
@@ -119,7 +129,152 @@ impl Model for Resource {
     }
 }
 
+impl Resource {
+    /// Returns the `id`.
+    #[inline]
+    pub fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    /// Returns the `name`.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the `namespace`.
+    #[cfg(feature = "namespace")]
+    #[inline]
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Returns the `visibility`.
+    #[cfg(feature = "visibility")]
+    #[inline]
+    pub fn visibility(&self) -> &str {
+        &self.visibility
+    }
+
+    /// Returns the `description`.
+    #[inline]
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns the `mime_type`.
+    #[inline]
+    pub fn mime_type(&self) -> &str {
+        &self.mime_type
+    }
+
+    /// Returns the `location`.
+    #[inline]
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    /// Returns the `owner_id`.
+    #[cfg(feature = "owner-id")]
+    #[inline]
+    pub fn owner_id(&self) -> Option<&Uuid> {
+        self.owner_id.as_ref()
+    }
+}
+
+#[cfg(feature = "upload")]
+impl Resource {
+    /// Streams the files in the multipart body to the storage of an accessor,
+    /// and inserts a resource for each uploaded file. The `owner_id` and `namespace`
+    /// of the resources are set by the user session if it exists.
+    pub async fn upload_files<Ctx: RequestContext + ?Sized>(
+        ctx: &mut Ctx,
+        options: &UploadOptions,
+    ) -> Result<Vec<Self>, Rejection> {
+        use zino_core::orm::Schema;
+
+        let session = ctx.get_data::<UserSession<Uuid, String>>();
+        let files = ctx.upload_files(options).await?;
+        let mut resources = Vec::with_capacity(files.len());
+        for file in files {
+            let mut resource = Self::from(file);
+            if let Some(session) = &session {
+                resource.set_user_session(session);
+            }
+            if let Err(err) = resource.clone().insert().await {
+                return Err(Rejection::from_error(err).context(ctx));
+            }
+            resources.push(resource);
+        }
+        Ok(resources)
+    }
+
+    /// Sets the `owner_id`, `maintainer_id` and `namespace` by the user session.
+    /// The namespace is scoped by the tenant of the session if it exists.
+    #[allow(unused_variables)]
+    fn set_user_session(&mut self, session: &UserSession<Uuid, String>) {
+        #[cfg(feature = "owner-id")]
+        {
+            self.owner_id = Some(*session.user_id());
+        }
+        #[cfg(feature = "maintainer-id")]
+        {
+            self.maintainer_id = Some(*session.user_id());
+        }
+        #[cfg(feature = "namespace")]
+        if let Some(tenant_id) = session.tenant_id() {
+            self.namespace = format!("{tenant_id}:{}", Self::MODEL_NAME);
+        }
+    }
+}
+
+impl From<UploadedFile> for Resource {
+    fn from(file: UploadedFile) -> Self {
+        let mut extra = Map::new();
+        extra.upsert("file_size", file.file_size());
+        extra.upsert("checksum", format!("{:x}", file.checksum()));
+        extra.upsert("content_md5", file.content_md5());
+        if let Some(field_name) = file.field_name() {
+            extra.upsert("field_name", field_name);
+        }
+
+        let mut resource = Self::new();
+        resource.name = file.file_name().unwrap_or(file.location()).to_owned();
+        resource.category = file.category().to_owned();
+        resource.mime_type = file.mime_type().to_owned();
+        resource.location = file.location().to_owned();
+        resource.extra = extra;
+        resource
+    }
+}
+
+impl From<&UploadSession> for Resource {
+    fn from(session: &UploadSession) -> Self {
+        let mime_type = session.content_type().unwrap_or("application/octet-stream");
+        let mut extra = Map::new();
+        extra.upsert("upload_id", session.id().to_string());
+        extra.upsert("total_size", session.total_size());
+        extra.upsert("offset", session.offset());
+        extra.upsert("expires_at", session.expires_at());
+
+        let mut resource = Self::new();
+        resource.name = session.file_name().unwrap_or(session.location()).to_owned();
+        resource.status = "Pending".to_owned();
+        resource.category = mime_type
+            .split_once('/')
+            .map_or(mime_type, |(category, _)| category)
+            .to_owned();
+        resource.mime_type = mime_type.to_owned();
+        resource.location = session.location().to_owned();
+        resource.extra = extra;
+        resource
+    }
+}
+
 impl ModelHooks for Resource {
+    #[cfg(feature = "embedding")]
+    type Data = Map;
+    #[cfg(not(feature = "embedding"))]
     type Data = ();
     #[cfg(feature = "maintainer-id")]
     type Extension = UserSession<Uuid, String>;
@@ -144,4 +299,16 @@ impl ModelHooks for Resource {
         }
         Ok(())
     }
+
+    #[cfg(feature = "embedding")]
+    #[inline]
+    async fn before_save(&mut self) -> Result<Self::Data, Error> {
+        self.embedding_sources().await
+    }
+
+    #[cfg(feature = "embedding")]
+    #[inline]
+    async fn after_save(ctx: &QueryContext, data: Self::Data) -> Result<(), Error> {
+        Self::sync_embeddings(ctx, &data).await
+    }
 }
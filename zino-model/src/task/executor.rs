@@ -0,0 +1,490 @@
+use super::{Task, TaskStatus};
+use chrono::Local;
+use futures::future;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    time::Duration,
+};
+use zino_core::{
+    bail,
+    datetime::DateTime,
+    error::Error,
+    extension::JsonObjectExt,
+    model::Query,
+    orm::{ModelAccessor, Schema},
+    schedule::{JobRun, JobSchedule},
+    BoxFuture, Map, Uuid,
+};
+
+/// A function pointer of the task handler.
+pub type TaskHandler = for<'a> fn(task: &'a Task) -> BoxFuture<'a, Result<(), Error>>;
+
+/// A function pointer to claim the task before it is executed.
+type TaskClaim = for<'a> fn(task: &'a mut Task) -> BoxFuture<'a, Result<bool, Error>>;
+
+/// A workflow runner for the tasks with dependencies.
+///
+/// The tasks are executed in the topological order of the dependency DAG,
+/// and a task with higher priority is executed first among the ready ones.
+#[derive(Debug, Default)]
+pub struct TaskExecutor {
+    /// Handlers for the task names.
+    handlers: HashMap<String, TaskHandler>,
+    /// Handlers for the task namespaces.
+    #[cfg(feature = "namespace")]
+    namespace_handlers: HashMap<String, TaskHandler>,
+}
+
+impl TaskExecutor {
+    /// Maximum number of the due tasks loaded at a time.
+    pub const MAX_DUE_TASKS: usize = 100;
+
+    /// Duration of the lease on a claimed task.
+    pub const LEASE_DURATION: Duration = Duration::from_secs(30 * 60);
+
+    /// Creates a new instance.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a handler for the task name.
+    #[inline]
+    pub fn register(&mut self, name: impl Into<String>, handler: TaskHandler) {
+        self.handlers.insert(name.into(), handler);
+    }
+
+    /// Registers a handler for the task namespace.
+    /// It is used when there is no handler for the task name.
+    #[cfg(feature = "namespace")]
+    #[inline]
+    pub fn register_namespace(&mut self, namespace: impl Into<String>, handler: TaskHandler) {
+        self.namespace_handlers.insert(namespace.into(), handler);
+    }
+
+    /// Returns the handler for the task.
+    fn handler(&self, task: &Task) -> Option<TaskHandler> {
+        let handler = self.handlers.get(&task.name);
+        #[cfg(feature = "namespace")]
+        let handler = handler.or_else(|| self.namespace_handlers.get(&task.namespace));
+        handler.copied()
+    }
+
+    /// Resolves the execution order of the tasks by their dependencies,
+    /// and returns the indices of the tasks.
+    /// Dependencies which are not in the tasks do not affect the order,
+    /// although [`execute`](Self::execute) blocks the tasks depending on them.
+    pub fn resolve_order(tasks: &[Task]) -> Result<Vec<usize>, Error> {
+        let (order, unresolved_tasks) = Self::sort_tasks(tasks);
+        if !unresolved_tasks.is_empty() {
+            let names = unresolved_tasks
+                .into_iter()
+                .map(|index| tasks[index].name.as_str())
+                .collect::<Vec<_>>();
+            bail!(
+                "there is a dependency cycle among the tasks: {}",
+                names.join(", ")
+            );
+        }
+        Ok(order)
+    }
+
+    /// Sorts the tasks topologically, and returns the indices of the sorted tasks
+    /// together with the indices of the tasks in or behind a dependency cycle.
+    fn sort_tasks(tasks: &[Task]) -> (Vec<usize>, Vec<usize>) {
+        let indices = tasks
+            .iter()
+            .enumerate()
+            .map(|(index, task)| (task.id, index))
+            .collect::<HashMap<_, _>>();
+        let mut in_degrees = vec![0; tasks.len()];
+        let mut dependents = vec![Vec::new(); tasks.len()];
+        for (index, task) in tasks.iter().enumerate() {
+            for dependency in task.dependencies.iter() {
+                if let Some(&dependency_index) = indices.get(dependency) {
+                    in_degrees[index] += 1;
+                    dependents[dependency_index].push(index);
+                }
+            }
+        }
+
+        let mut ready_tasks = in_degrees
+            .iter()
+            .enumerate()
+            .filter(|(_, &in_degree)| in_degree == 0)
+            .map(|(index, _)| (tasks[index].priority, Reverse(index)))
+            .collect::<BinaryHeap<_>>();
+        let mut order = Vec::with_capacity(tasks.len());
+        while let Some((_, Reverse(index))) = ready_tasks.pop() {
+            order.push(index);
+            for &dependent in dependents[index].iter() {
+                in_degrees[dependent] -= 1;
+                if in_degrees[dependent] == 0 {
+                    ready_tasks.push((tasks[dependent].priority, Reverse(dependent)));
+                }
+            }
+        }
+
+        let unresolved_tasks = in_degrees
+            .into_iter()
+            .enumerate()
+            .filter(|(_, in_degree)| *in_degree > 0)
+            .map(|(index, _)| index)
+            .collect();
+        (order, unresolved_tasks)
+    }
+
+    /// Groups the sorted tasks into levels, so that the dependencies of a task
+    /// are always in the previous levels.
+    fn group_levels(
+        tasks: &[Task],
+        indices: &HashMap<Uuid, usize>,
+        order: &[usize],
+    ) -> Vec<Vec<usize>> {
+        let mut task_levels = vec![0; tasks.len()];
+        let mut levels: Vec<Vec<usize>> = Vec::new();
+        for &index in order {
+            let level = tasks[index]
+                .dependencies
+                .iter()
+                .filter_map(|dependency| indices.get(dependency))
+                .map(|&dependency_index| task_levels[dependency_index] + 1)
+                .max()
+                .unwrap_or_default();
+            task_levels[index] = level;
+            if levels.len() <= level {
+                levels.resize_with(level + 1, Vec::new);
+            }
+            levels[level].push(index);
+        }
+        levels
+    }
+
+    /// Parses the cron schedule of the task. It is evaluated in the time zone
+    /// specified by the `time_zone` field of the extensions, or in the local time zone.
+    fn parse_schedule(task: &Task) -> Result<Option<JobSchedule>, Error> {
+        if task.schedule.is_empty() {
+            return Ok(None);
+        }
+
+        let mut schedule = JobSchedule::new(&task.schedule)?;
+        if let Some(time_zone) = task.extra.get_str("time_zone") {
+            schedule = schedule.with_time_zone(time_zone)?;
+        }
+        Ok(Some(schedule))
+    }
+
+    /// Executes the due tasks in the order of their dependencies,
+    /// and returns the runs together with the indices of the updated tasks.
+    ///
+    /// A task whose dependencies are missing or not healthy is marked as blocked,
+    /// so the failure is propagated to all the dependents. A due task waits for
+    /// its dependencies until they have run successfully since it became due,
+    /// unless they are one-off tasks which have been completed. The tasks in or
+    /// behind a dependency cycle are marked as blocked without aborting the others,
+    /// and a due task without a handler is marked as failed. The tasks whose
+    /// dependencies have been resolved are executed concurrently.
+    pub async fn execute(&self, tasks: &mut [Task]) -> Result<(Vec<JobRun>, Vec<usize>), Error> {
+        self.execute_with(tasks, |_| Box::pin(async { Ok(true) }))
+            .await
+    }
+
+    /// Executes the due tasks which have been claimed.
+    async fn execute_with(
+        &self,
+        tasks: &mut [Task],
+        claim: TaskClaim,
+    ) -> Result<(Vec<JobRun>, Vec<usize>), Error> {
+        let (order, unresolved_tasks) = Self::sort_tasks(tasks);
+        let mut runs = Vec::new();
+        let mut updated_tasks = Vec::new();
+        for index in unresolved_tasks {
+            let task = &mut tasks[index];
+            let Ok(status) = task.status.parse::<TaskStatus>() else {
+                continue;
+            };
+            if status.is_schedulable() && status != TaskStatus::Blocked {
+                tracing::warn!(
+                    task_id = task.id.to_string(),
+                    task_name = task.name.as_str(),
+                    "task is blocked by a dependency cycle"
+                );
+                task.status = TaskStatus::Blocked.to_string();
+                updated_tasks.push(index);
+            }
+        }
+
+        let indices = tasks
+            .iter()
+            .enumerate()
+            .map(|(index, task)| (task.id, index))
+            .collect::<HashMap<_, _>>();
+        let mut succeeded_tasks = HashSet::new();
+        for level in Self::group_levels(tasks, &indices, &order) {
+            let mut claimed_tasks = Vec::new();
+            for index in level {
+                let task = &tasks[index];
+                let Ok(status) = task.status.parse::<TaskStatus>() else {
+                    continue;
+                };
+                if !status.is_schedulable() {
+                    continue;
+                }
+
+                let blocked_by = task.dependencies.iter().find(|dependency| {
+                    !indices.get(dependency).is_some_and(|&i| {
+                        tasks[i]
+                            .status
+                            .parse::<TaskStatus>()
+                            .is_ok_and(|s| s.is_healthy())
+                    })
+                });
+                if let Some(dependency) = blocked_by {
+                    if status != TaskStatus::Blocked {
+                        tracing::warn!(
+                            task_id = task.id.to_string(),
+                            task_name = task.name.as_str(),
+                            "task is blocked by the dependency `{dependency}`"
+                        );
+                        tasks[index].status = TaskStatus::Blocked.to_string();
+                        updated_tasks.push(index);
+                    }
+                    continue;
+                }
+
+                let now = DateTime::now();
+                if !task.is_due(now) {
+                    continue;
+                }
+
+                let waiting_for = task.dependencies.iter().find(|dependency| {
+                    let dependency_task = &tasks[indices[dependency]];
+                    let completed = dependency_task.status == TaskStatus::Completed.as_ref();
+                    !(completed
+                        || succeeded_tasks.contains(*dependency)
+                        || dependency_task.last_time > task.next_time)
+                });
+                if waiting_for.is_some() {
+                    continue;
+                }
+
+                let Some(handler) = self.handler(task) else {
+                    if status != TaskStatus::Failed {
+                        tracing::error!(
+                            task_id = task.id.to_string(),
+                            task_name = task.name.as_str(),
+                            "handler for the task does not exist"
+                        );
+                        let mut run = JobRun::new(task.id, now, Default::default());
+                        run.set_error("handler for the task does not exist");
+                        runs.push(run);
+                        tasks[index].status = TaskStatus::Failed.to_string();
+                        updated_tasks.push(index);
+                    }
+                    continue;
+                };
+
+                let schedule = match Self::parse_schedule(task) {
+                    Ok(schedule) => schedule,
+                    Err(err) => {
+                        let mut run = JobRun::new(task.id, now, Default::default());
+                        run.set_error(err);
+                        runs.push(run);
+                        tasks[index].status = TaskStatus::Failed.to_string();
+                        updated_tasks.push(index);
+                        continue;
+                    }
+                };
+
+                let task = &mut tasks[index];
+                match claim(task).await {
+                    Ok(true) => claimed_tasks.push((index, handler, schedule)),
+                    Ok(false) => (),
+                    Err(err) => tracing::error!(
+                        task_id = task.id.to_string(),
+                        task_name = task.name.as_str(),
+                        "fail to claim the task: {err}"
+                    ),
+                }
+            }
+
+            let tasks_ref = &*tasks;
+            let results = future::join_all(claimed_tasks.iter().map(|&(index, handler, _)| {
+                let task = &tasks_ref[index];
+                async move {
+                    let start_time = DateTime::now();
+                    let result = handler(task).await;
+                    (start_time, result)
+                }
+            }))
+            .await;
+            for ((index, _, schedule), (start_time, result)) in
+                claimed_tasks.into_iter().zip(results)
+            {
+                let task = &mut tasks[index];
+                let mut run = JobRun::new(task.id, start_time, start_time.span_between_now());
+                task.last_time = start_time;
+                if let Some(schedule) = schedule.as_ref() {
+                    let start_time = chrono::DateTime::<Local>::from(start_time);
+                    if let Some(next_time) = schedule.after(&start_time).next() {
+                        task.next_time = next_time.into();
+                    }
+                }
+                match result {
+                    Ok(()) => {
+                        task.status = if schedule.is_some() {
+                            TaskStatus::Active.to_string()
+                        } else {
+                            TaskStatus::Completed.to_string()
+                        };
+                        task.extra.remove("failures");
+                        succeeded_tasks.insert(task.id);
+                    }
+                    Err(err) => {
+                        tracing::error!(
+                            task_id = task.id.to_string(),
+                            task_name = task.name.as_str(),
+                            "fail to execute the task: {err}"
+                        );
+                        task.status = TaskStatus::Failed.to_string();
+                        if schedule.is_none() {
+                            let failures = task.extra.get_u32("failures").unwrap_or_default() + 1;
+                            task.extra.upsert("failures", failures);
+                            task.next_time = start_time + Self::retry_delay(failures);
+                        }
+                        run.set_error(err);
+                    }
+                }
+                runs.push(run);
+                updated_tasks.push(index);
+            }
+        }
+        Ok((runs, updated_tasks))
+    }
+
+    /// Returns the exponential backoff delay for retrying a failed one-off task.
+    pub(super) fn retry_delay(failures: u32) -> Duration {
+        const RETRY_DELAY: Duration = Duration::from_secs(60);
+        const MAX_RETRY_DELAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+        let factor = 1 << failures.saturating_sub(1).min(16);
+        RETRY_DELAY.saturating_mul(factor).min(MAX_RETRY_DELAY)
+    }
+
+    /// Claims the task by bumping its version and leasing it to the executor,
+    /// so that the task is never executed by the other replicas at the same time.
+    fn claim(task: &mut Task) -> BoxFuture<'_, Result<bool, Error>> {
+        Box::pin(async move {
+            let now = DateTime::now();
+            let mut query = task.current_version_query();
+            query.add_filter("$or", Self::unlocked_filters(now));
+
+            let locked_by = Uuid::now_v7().to_string();
+            let locked_until = now + Self::LEASE_DURATION;
+            let mut updates = Map::new();
+            updates.upsert("locked_by", locked_by.as_str());
+            updates.upsert("locked_until", locked_until);
+            let mut mutation = task.next_version_mutation(&mut updates);
+            let ctx = Task::update_many(&query, &mut mutation).await?;
+            if ctx.rows_affected() != Some(1) {
+                return Ok(false);
+            }
+
+            task.version = task.next_version();
+            task.locked_by = locked_by;
+            task.locked_until = locked_until;
+            Ok(true)
+        })
+    }
+
+    /// Returns the filters for the tasks which are not leased to an executor.
+    fn unlocked_filters(now: DateTime) -> Vec<Map> {
+        vec![
+            Map::from_entry("locked_by", ""),
+            Map::from_entry("locked_until", Map::from_entry("$lt", now)),
+        ]
+    }
+
+    /// Loads the due tasks together with their dependencies from the database,
+    /// executes the ones claimed by the executor, and saves the updated tasks.
+    ///
+    /// At most [`MAX_DUE_TASKS`](Self::MAX_DUE_TASKS) due tasks are loaded at a time.
+    /// A task is claimed with an optimistic version check before it is executed,
+    /// and leased for [`LEASE_DURATION`](Self::LEASE_DURATION) until it has been saved.
+    pub async fn run_pending(&self) -> Result<Vec<JobRun>, Error> {
+        let now = DateTime::now();
+        let statuses = [TaskStatus::Active, TaskStatus::Failed, TaskStatus::Blocked]
+            .map(<&str>::from)
+            .to_vec();
+        let mut query = Query::from_entry("status", Map::from_entry("$in", statuses));
+        query.add_filter("next_time", Map::from_entry("$le", now));
+        query.add_filter("$or", Self::unlocked_filters(now));
+        query.order_desc("priority");
+        query.set_limit(Self::MAX_DUE_TASKS);
+        let mut tasks = Task::find::<Task>(&query).await?;
+
+        let task_ids = tasks.iter().map(|task| task.id).collect::<HashSet<_>>();
+        let dependencies = tasks
+            .iter()
+            .flat_map(|task| task.dependencies.iter())
+            .filter(|dependency| !task_ids.contains(dependency))
+            .map(|dependency| dependency.to_string())
+            .collect::<HashSet<_>>();
+        if !dependencies.is_empty() {
+            let dependencies = dependencies.into_iter().collect::<Vec<_>>();
+            let query = Query::from_entry("id", Map::from_entry("$in", dependencies));
+            tasks.extend(Task::find::<Task>(&query).await?);
+        }
+
+        let (runs, updated_tasks) = self.execute_with(&mut tasks, Self::claim).await?;
+        for index in updated_tasks {
+            let task = &tasks[index];
+            let query = task.current_version_query();
+            let mut updates = Map::new();
+            updates.upsert("status", task.status.as_str());
+            updates.upsert("last_time", task.last_time);
+            updates.upsert("next_time", task.next_time);
+            updates.upsert("locked_by", "");
+            updates.upsert("extra", task.extra.clone());
+            let mut mutation = task.next_version_mutation(&mut updates);
+            match Task::update_one(&query, &mut mutation).await {
+                Ok(ctx) if ctx.rows_affected() != Some(1) => tracing::warn!(
+                    task_id = task.id.to_string(),
+                    task_name = task.name.as_str(),
+                    "task has been updated by others"
+                ),
+                Ok(_) => (),
+                Err(err) => tracing::error!(
+                    task_id = task.id.to_string(),
+                    task_name = task.name.as_str(),
+                    "fail to update the task: {err}"
+                ),
+            }
+        }
+        Ok(runs)
+    }
+}
+
+impl Task {
+    /// Returns `true` if the task is due at the time.
+    ///
+    /// The validity period is unbounded if `expires_at` is not later than `valid_from`.
+    pub fn is_due(&self, now: DateTime) -> bool {
+        let valid_from = self.valid_from;
+        let expires_at = self.expires_at;
+        valid_from <= now && (expires_at <= valid_from || now < expires_at) && self.next_time <= now
+    }
+
+    /// Returns the IDs of the dependencies.
+    #[inline]
+    pub fn dependencies(&self) -> &[Uuid] {
+        &self.dependencies
+    }
+
+    /// Returns the task priority.
+    #[inline]
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+}
@@ -15,6 +15,14 @@ use zino_derive::{DecodeRow, ModelAccessor, Schema};
 #[cfg(feature = "tags")]
 use crate::tag::Tag;
 
+mod executor;
+mod queue;
+mod status;
+
+pub use executor::{TaskExecutor, TaskHandler};
+pub use queue::OrmQueueBackend;
+pub use status::TaskStatus;
+
 #[cfg(any(feature = "owner-id", feature = "maintainer-id"))]
 use crate::user::User;
 
@@ -55,6 +63,12 @@ pub struct Task {
     last_time: DateTime,
     next_time: DateTime,
     priority: u16,
+    attempts: u32,
+    max_attempts: u32,
+    backoff: u64, // milliseconds
+    locked_by: String,
+    locked_until: DateTime,
+    last_error: String,
     #[cfg(feature = "tags")]
     #[schema(reference = "Tag", index_type = "gin")]
     tags: Vec<Uuid>, // tag.id, tag.namespace = "*:task"
@@ -154,3 +168,151 @@ impl ModelHooks for Task {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Task, TaskExecutor, TaskStatus};
+    use chrono::{Local, Timelike, Utc};
+    use futures::executor::block_on;
+    use std::time::Duration;
+    use zino_core::{
+        bail, datetime::DateTime, error::Error, extension::JsonObjectExt, model::Model, BoxFuture,
+    };
+
+    fn new_task(name: &str) -> Task {
+        let mut task = Task::new();
+        task.name = name.to_owned();
+        task.status = TaskStatus::Active.to_string();
+        task.expires_at = task.valid_from;
+        task
+    }
+
+    fn succeed(_task: &Task) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn fail(_task: &Task) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async { bail!("fail to extract the data") })
+    }
+
+    #[test]
+    fn it_resolves_task_order() {
+        let mut extract = Task::new();
+        extract.name = "extract".to_owned();
+        let mut transform = Task::new();
+        transform.name = "transform".to_owned();
+        transform.dependencies = vec![extract.id];
+        let mut audit = Task::new();
+        audit.name = "audit".to_owned();
+        audit.priority = 10;
+        let mut load = Task::new();
+        load.name = "load".to_owned();
+        load.dependencies = vec![transform.id, audit.id];
+
+        let tasks = vec![load, transform, extract, audit];
+        let order = TaskExecutor::resolve_order(&tasks).unwrap();
+        assert_eq!(order, vec![3, 2, 1, 0]);
+
+        let mut tasks = tasks;
+        tasks[2].dependencies = vec![tasks[0].id];
+        assert!(TaskExecutor::resolve_order(&tasks).is_err());
+    }
+
+    #[test]
+    fn it_executes_tasks() {
+        let mut executor = TaskExecutor::new();
+        executor.register("extract", fail);
+        for name in ["transform", "report", "cycle", "export"] {
+            executor.register(name, succeed);
+        }
+
+        let extract = new_task("extract");
+        let mut transform = new_task("transform");
+        transform.dependencies = vec![extract.id];
+        let mut cycle_a = new_task("cycle");
+        let mut cycle_b = new_task("cycle");
+        cycle_a.dependencies = vec![cycle_b.id];
+        cycle_b.dependencies = vec![cycle_a.id];
+        let mut report = new_task("report");
+        report.next_time = DateTime::now() + Duration::from_secs(3600);
+        let mut export = new_task("export");
+        export.dependencies = vec![report.id];
+
+        let mut tasks = vec![extract, transform, cycle_a, cycle_b, report, export];
+        let (runs, updated_tasks) = block_on(executor.execute(&mut tasks)).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(updated_tasks.len(), 4);
+
+        let extract = &tasks[0];
+        assert_eq!(extract.status, TaskStatus::Failed.as_ref());
+        assert_eq!(extract.extra.get("failures"), Some(&1.into()));
+        assert!(extract.next_time > DateTime::now() + Duration::from_secs(59));
+        assert_eq!(tasks[1].status, TaskStatus::Blocked.as_ref());
+        assert_eq!(tasks[2].status, TaskStatus::Blocked.as_ref());
+        assert_eq!(tasks[3].status, TaskStatus::Blocked.as_ref());
+        assert_eq!(tasks[5].status, TaskStatus::Active.as_ref());
+
+        tasks[4].next_time = DateTime::now();
+        let (runs, _) = block_on(executor.execute(&mut tasks)).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(tasks[4].status, TaskStatus::Completed.as_ref());
+        assert_eq!(tasks[5].status, TaskStatus::Completed.as_ref());
+    }
+
+    #[test]
+    fn it_fails_tasks_without_handlers() {
+        let mut executor = TaskExecutor::new();
+        executor.register("load", succeed);
+
+        let extract = new_task("extract");
+        let mut load = new_task("load");
+        load.dependencies = vec![extract.id];
+
+        let mut tasks = vec![extract, load];
+        let (runs, updated_tasks) = block_on(executor.execute(&mut tasks)).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert!(runs[0].error().is_some());
+        assert_eq!(updated_tasks, vec![0, 1]);
+        assert_eq!(tasks[0].status, TaskStatus::Failed.as_ref());
+        assert_eq!(tasks[1].status, TaskStatus::Blocked.as_ref());
+
+        let (runs, updated_tasks) = block_on(executor.execute(&mut tasks)).unwrap();
+        assert!(runs.is_empty());
+        assert!(updated_tasks.is_empty());
+    }
+
+    #[test]
+    fn it_evaluates_schedules_in_time_zones() {
+        let mut executor = TaskExecutor::new();
+        executor.register("report", succeed);
+
+        let mut report = new_task("report");
+        report.schedule = "0 0 9 * * *".to_owned();
+        report.extra.upsert("time_zone", "Asia/Tokyo");
+
+        let mut tasks = vec![report];
+        let (runs, _) = block_on(executor.execute(&mut tasks)).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(tasks[0].status, TaskStatus::Active.as_ref());
+
+        let next_time = chrono::DateTime::<Local>::from(tasks[0].next_time).with_timezone(&Utc);
+        assert_eq!(next_time.hour(), 0);
+        assert_eq!(next_time.minute(), 0);
+
+        tasks[0].extra.upsert("time_zone", "Mars/Olympus_Mons");
+        tasks[0].next_time = DateTime::now();
+        let (runs, _) = block_on(executor.execute(&mut tasks)).unwrap();
+        assert!(runs[0].error().is_some());
+        assert_eq!(tasks[0].status, TaskStatus::Failed.as_ref());
+    }
+
+    #[test]
+    fn it_computes_retry_delays() {
+        assert_eq!(TaskExecutor::retry_delay(1), Duration::from_secs(60));
+        assert_eq!(TaskExecutor::retry_delay(3), Duration::from_secs(240));
+        assert_eq!(
+            TaskExecutor::retry_delay(100),
+            Duration::from_secs(24 * 60 * 60)
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use super::Task;
+use serde::Deserialize;
+use std::time::Duration;
+use zino_core::{
+    datetime::DateTime,
+    error::Error,
+    extension::JsonObjectExt,
+    model::{Model, Mutation, Query},
+    orm::Schema,
+    schedule::{QueueBackend, QueuedJob, QueuedJobStatus},
+    BoxFuture, JsonValue, Map,
+};
+
+/// A backend for the queued jobs backed by the [`Task`] model.
+///
+/// The job type is stored as the task name, the payload as the `extra` field
+/// and the time when the job can be executed as the `next_time` field.
+/// A job is claimed with a row lock by `FOR UPDATE SKIP LOCKED`,
+/// so that the concurrent workers never claim the same job.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrmQueueBackend;
+
+impl OrmQueueBackend {
+    /// Creates a new instance.
+    #[inline]
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Creates the table if it does not exist.
+    #[inline]
+    pub async fn create_table(&self) -> Result<(), Error> {
+        Task::create_table().await
+    }
+
+    /// Encodes the queued job as a task.
+    fn encode_job(job: &QueuedJob) -> Task {
+        let mut task = Task::new();
+        task.id = job.id();
+        task.name = job.job_type().to_owned();
+        task.status = job.status().as_str().to_owned();
+        task.next_time = job.run_at();
+        task.priority = job.priority();
+        task.attempts = job.attempts();
+        task.max_attempts = job.max_attempts();
+        task.backoff = job.backoff().as_millis().try_into().unwrap_or(u64::MAX);
+        task.last_error = job.last_error().unwrap_or_default().to_owned();
+        task.extra = job.payload().clone();
+        task.created_at = job.created_at();
+        task
+    }
+
+    /// Decodes the task as a queued job.
+    fn decode_task(task: Task) -> Result<QueuedJob, Error> {
+        let mut map = Map::new();
+        map.upsert("id", task.id.to_string());
+        map.upsert("job_type", task.name);
+        map.upsert("payload", task.extra);
+        map.upsert("priority", task.priority);
+        map.upsert("attempts", task.attempts);
+        map.upsert("max_attempts", task.max_attempts);
+        map.upsert("backoff_ms", task.backoff);
+        map.upsert("run_at", task.next_time);
+        if !task.locked_by.is_empty() {
+            map.upsert("locked_by", task.locked_by);
+            map.upsert("locked_until", task.locked_until);
+        }
+        map.upsert("status", task.status);
+        if !task.last_error.is_empty() {
+            map.upsert("last_error", task.last_error);
+        }
+        map.upsert("created_at", task.created_at);
+        Ok(QueuedJob::deserialize(JsonValue::Object(map))?)
+    }
+
+    /// Updates the claimed job if it is still leased by the worker which has claimed it.
+    async fn update_claimed(job: &QueuedJob, mut updates: Map) -> Result<bool, Error> {
+        let mut query = Query::from_entry("id", job.id().to_string());
+        query.add_filter("status", QueuedJobStatus::Running.as_str());
+        query.add_filter("locked_by", job.locked_by().unwrap_or_default());
+        query.add_filter("attempts", job.attempts());
+
+        updates.upsert("updated_at", DateTime::now());
+        let mut mutation = Mutation::new(updates);
+        let ctx = Task::update_many(&query, &mut mutation).await?;
+        Ok(ctx.rows_affected() == Some(1))
+    }
+
+    /// Returns the updates for releasing the lease of the job.
+    fn release_lease(status: QueuedJobStatus) -> Map {
+        let mut updates = Map::new();
+        updates.upsert("status", status.as_str());
+        updates.upsert("locked_by", "");
+        updates.upsert("locked_until", DateTime::now());
+        updates
+    }
+}
+
+impl QueueBackend for OrmQueueBackend {
+    fn push<'a>(&'a self, job: &'a QueuedJob) -> BoxFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            Self::encode_job(job).insert().await?;
+            Ok(())
+        })
+    }
+
+    fn claim<'a>(
+        &'a self,
+        job_types: &'a [&'a str],
+        worker_id: &'a str,
+        lease_duration: Duration,
+    ) -> BoxFuture<'a, Result<Option<QueuedJob>, Error>> {
+        Box::pin(async move {
+            if job_types.is_empty() {
+                return Ok(None);
+            }
+
+            let now = DateTime::now();
+            let pending = vec![
+                Map::from_entry("status", QueuedJobStatus::Pending.as_str()),
+                Map::from_entry("next_time", Map::from_entry("$le", now)),
+            ];
+            let expired = vec![
+                Map::from_entry("status", QueuedJobStatus::Running.as_str()),
+                Map::from_entry("locked_until", Map::from_entry("$lt", now)),
+            ];
+            let conditions = vec![
+                Map::from_entry("$and", pending),
+                Map::from_entry("$and", expired),
+            ];
+            let mut query = Query::from_entry("name", Map::from_entry("$in", job_types.to_vec()));
+            query.add_filter("$or", conditions);
+            query.order_desc("priority");
+            query.order_asc("next_time");
+
+            let mut updates = Map::new();
+            updates.upsert("status", QueuedJobStatus::Running.as_str());
+            updates.upsert("$inc", Map::from_entry("attempts", 1));
+            updates.upsert("locked_by", worker_id);
+            updates.upsert("locked_until", now + lease_duration);
+            updates.upsert("last_time", now);
+            updates.upsert("updated_at", now);
+            let mut mutation = Mutation::new(updates);
+            Task::claim_one::<Task>(&query, &mut mutation)
+                .await?
+                .map(Self::decode_task)
+                .transpose()
+        })
+    }
+
+    fn renew<'a>(
+        &'a self,
+        job: &'a QueuedJob,
+        lease_duration: Duration,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        let updates = Map::from_entry("locked_until", DateTime::now() + lease_duration);
+        Box::pin(Self::update_claimed(job, updates))
+    }
+
+    fn complete<'a>(&'a self, job: &'a QueuedJob) -> BoxFuture<'a, Result<bool, Error>> {
+        let updates = Self::release_lease(QueuedJobStatus::Succeeded);
+        Box::pin(Self::update_claimed(job, updates))
+    }
+
+    fn retry<'a>(
+        &'a self,
+        job: &'a QueuedJob,
+        run_at: DateTime,
+        error: &'a str,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        let mut updates = Self::release_lease(QueuedJobStatus::Pending);
+        updates.upsert("next_time", run_at);
+        updates.upsert("last_error", error);
+        Box::pin(Self::update_claimed(job, updates))
+    }
+
+    fn bury<'a>(
+        &'a self,
+        job: &'a QueuedJob,
+        error: &'a str,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        let mut updates = Self::release_lease(QueuedJobStatus::Dead);
+        updates.upsert("last_error", error);
+        Box::pin(Self::update_claimed(job, updates))
+    }
+
+    fn dead_letters(&self, limit: usize) -> BoxFuture<'_, Result<Vec<QueuedJob>, Error>> {
+        Box::pin(async move {
+            let mut query = Query::from_entry("status", QueuedJobStatus::Dead.as_str());
+            query.order_desc("created_at");
+            query.set_limit(limit);
+            Task::find::<Task>(&query)
+                .await?
+                .into_iter()
+                .map(Self::decode_task)
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OrmQueueBackend;
+    use std::time::Duration;
+    use zino_core::{
+        extension::JsonObjectExt,
+        schedule::{JobOptions, QueuedJob, QueuedJobStatus},
+        Map,
+    };
+
+    #[test]
+    fn it_converts_queued_jobs() {
+        let payload = Map::from_entry("to", "alice@example.com");
+        let options = JobOptions::new()
+            .priority(5)
+            .max_attempts(4)
+            .backoff(Duration::from_secs(10));
+        let job = QueuedJob::new("email", payload.clone(), options);
+        let mut task = OrmQueueBackend::encode_job(&job);
+        assert_eq!(task.name, "email");
+        assert_eq!(task.status, "Pending");
+
+        task.status = QueuedJobStatus::Running.as_str().to_owned();
+        task.attempts = 1;
+        task.locked_by = "worker".to_owned();
+        let decoded = OrmQueueBackend::decode_task(task).unwrap();
+        assert_eq!(decoded.id(), job.id());
+        assert_eq!(decoded.job_type(), "email");
+        assert_eq!(decoded.payload(), &payload);
+        assert_eq!(decoded.priority(), 5);
+        assert_eq!(decoded.attempts(), 1);
+        assert_eq!(decoded.max_attempts(), 4);
+        assert_eq!(decoded.backoff(), Duration::from_secs(10));
+        assert_eq!(decoded.status(), QueuedJobStatus::Running);
+        assert_eq!(decoded.locked_by(), Some("worker"));
+        assert!(decoded.locked_until().is_some());
+        assert!(decoded.last_error().is_none());
+    }
+}
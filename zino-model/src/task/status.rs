@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, Display, EnumString, IntoStaticStr};
+use zino_core::JsonValue;
+
+/// Task status.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Default,
+    Serialize,
+    Deserialize,
+    AsRefStr,
+    Display,
+    EnumString,
+    IntoStaticStr,
+)]
+#[non_exhaustive]
+pub enum TaskStatus {
+    /// It indicates that the task is waiting to be scheduled.
+    /// This is the default value.
+    #[default]
+    Active,
+    /// It indicates that the task has been disabled.
+    Inactive,
+    /// It indicates that the one-off task has been completed.
+    Completed,
+    /// It indicates that the last run of the task has failed.
+    Failed,
+    /// It indicates that the task is blocked by a failed dependency.
+    Blocked,
+}
+
+impl TaskStatus {
+    /// Returns `true` if the task can be scheduled.
+    #[inline]
+    pub fn is_schedulable(self) -> bool {
+        matches!(self, Self::Active | Self::Failed | Self::Blocked)
+    }
+
+    /// Returns `true` if the dependents of the task can be executed.
+    #[inline]
+    pub fn is_healthy(self) -> bool {
+        matches!(self, Self::Active | Self::Completed)
+    }
+}
+
+impl From<TaskStatus> for JsonValue {
+    #[inline]
+    fn from(value: TaskStatus) -> Self {
+        value.as_ref().into()
+    }
+}
@@ -1,20 +1,21 @@
-use crate::{middleware, ActixResponse, Request, RouterConfigure};
+use crate::{channel, middleware, ActixResponse, Request, RouterConfigure};
 use actix_files::{Files, NamedFile};
 use actix_web::{
     dev::{fn_service, ServiceRequest, ServiceResponse},
     http::StatusCode,
     middleware::Compress,
-    rt::{self, Runtime},
+    rt::{self, signal, Runtime},
     web::{self, FormConfig, JsonConfig, PayloadConfig},
     App, HttpServer, Responder,
 };
-use std::{fs, path::PathBuf, time::Duration};
+use std::{fs, path::PathBuf, sync::Arc, time::Duration};
+use tokio::sync::watch;
 use utoipa_rapidoc::RapiDoc;
 use zino_core::{
     application::{Application, Plugin, ServerTag},
     extension::TomlTableExt,
     response::Response,
-    schedule::AsyncScheduler,
+    schedule::{AsyncScheduler, JobQueue, SchedulerHandle},
 };
 
 /// An HTTP server cluster for `actix-web`.
@@ -53,16 +54,34 @@ impl Application for ActixCluster {
             Self::load().await;
             super::load_plugins(self.custom_plugins, app_env).await;
         });
-        if scheduler.is_ready() {
+        let (shutdown_sender, mut shutdown_receiver) = watch::channel(false);
+        let worker_handles = (0..JobQueue::workers())
+            .map(|worker_id| {
+                let shutdown_receiver = shutdown_receiver.clone();
+                runtime.spawn(super::run_queue_worker(worker_id, shutdown_receiver))
+            })
+            .collect::<Vec<_>>();
+        scheduler.set_runtime(Arc::new(super::TokioJobRuntime));
+        if let Some(handle) = scheduler.handle() {
+            SchedulerHandle::set_shared(handle);
+        }
+        let scheduler_handle = scheduler.is_ready().then(|| {
             runtime.spawn(async move {
+                let handle = scheduler.handle().unwrap_or_default();
                 loop {
                     scheduler.tick().await;
 
                     // Cannot use `std::thread::sleep` because it blocks the Tokio runtime.
-                    rt::time::sleep(scheduler.time_till_next_job()).await;
+                    // The scheduler wakes up at the next fire time or when a command arrives.
+                    tokio::select! {
+                        _ = rt::time::sleep(scheduler.time_till_next_job()) => {},
+                        _ = handle.command_received() => {},
+                        _ = shutdown_receiver.changed() => break,
+                    }
                 }
-            });
-        }
+                tracing::warn!("async job scheduler has been stopped");
+            })
+        });
 
         runtime.block_on(async {
             let default_routes = self.default_routes.leak() as &'static [_];
@@ -73,6 +92,16 @@ impl Application for ActixCluster {
             let app_domain = Self::domain();
             let listeners = app_state.listeners();
             let has_debug_server = listeners.iter().any(|listener| listener.0.is_debug());
+            let mut shutdown_timeout = Duration::from_secs(30); // 30 seconds
+            let mut shutdown_delay = Duration::ZERO;
+            if let Some(config) = app_state.get_config("server") {
+                if let Some(timeout) = config.get_duration("shutdown-timeout") {
+                    shutdown_timeout = timeout;
+                }
+                if let Some(delay) = config.get_duration("shutdown-delay") {
+                    shutdown_delay = delay;
+                }
+            }
             let servers = listeners.into_iter().map(|listener| {
                 let server_tag = listener.0;
                 let addr = listener.1;
@@ -173,12 +202,79 @@ impl Application for ActixCluster {
                         }
                     }
 
-                    // Render OpenAPI docs.
+                    // Server-sent events and WebSocket channels.
+                    if let Some(config) = app_state.get_config("channel") {
+                        let sse_route = config.get_str("sse-route").unwrap_or("/sse");
+                        let websocket_route =
+                            config.get_str("websocket-route").unwrap_or("/websocket");
+                        app = app
+                            .route(sse_route, web::get().to(channel::sse_handler))
+                            .route(websocket_route, web::get().to(channel::websocket_handler));
+                        tracing::info!(
+                            "Channel routers `{sse_route}` and `{websocket_route}` are registered for `{addr}`"
+                        );
+                    }
+
+                    // Streaming chat completions.
+                    #[cfg(feature = "chatbot")]
+                    if let Some(config) = app_state.get_config("chat") {
+                        let chat_route = config.get_str("route").unwrap_or("/chat");
+                        app = app.route(chat_route, web::post().to(channel::chat_handler));
+                        tracing::info!("Chat router `{chat_route}` is registered for `{addr}`");
+                    }
+
+                    // Health, readiness and liveness probes.
                     let is_docs_server = if has_debug_server {
                         server_tag.is_debug()
                     } else {
                         server_tag.is_main()
                     };
+                    let health_config = app_state.get_config("health");
+                    let is_health_server = health_config
+                        .and_then(|config| config.get_str("server"))
+                        .map(|tag| server_tag == ServerTag::from(tag))
+                        .unwrap_or(is_docs_server);
+                    if is_health_server {
+                        let (liveness_route, readiness_route) = health_config
+                            .map(|config| {
+                                (
+                                    config.get_str("liveness-route").unwrap_or("/healthz"),
+                                    config.get_str("readiness-route").unwrap_or("/readyz"),
+                                )
+                            })
+                            .unwrap_or(("/healthz", "/readyz"));
+                        app = app
+                            .route(liveness_route, web::get().to(super::health_check::liveness))
+                            .route(readiness_route, web::get().to(super::health_check::readiness));
+                        tracing::info!(
+                            "Health routers `{liveness_route}` and `{readiness_route}` are registered for `{addr}`"
+                        );
+                    }
+
+                    // Admin endpoints for the scheduled jobs.
+                    #[cfg(feature = "jwt")]
+                    if server_tag.is_debug() {
+                        let jobs_route = app_state
+                            .get_config("scheduler")
+                            .and_then(|config| config.get_str("admin-route"))
+                            .unwrap_or("/jobs");
+                        app = app
+                            .route(jobs_route, web::get().to(super::job_admin::list))
+                            .route(jobs_route, web::post().to(super::job_admin::add))
+                            .route(
+                                &format!("{jobs_route}/{{id}}"),
+                                web::get().to(super::job_admin::view),
+                            )
+                            .route(
+                                &format!("{jobs_route}/{{id}}/{{action}}"),
+                                web::post().to(super::job_admin::manage),
+                            );
+                        tracing::info!(
+                            "Job admin routers `{jobs_route}/**` are registered for `{addr}`"
+                        );
+                    }
+
+                    // Render OpenAPI docs.
                     if is_docs_server {
                         if let Some(config) = app_state.get_config("openapi") {
                             if config.get_bool("show-docs") != Some(false) {
@@ -213,6 +309,8 @@ impl Application for ActixCluster {
                     app.app_data(FormConfig::default().limit(body_limit))
                         .app_data(JsonConfig::default().limit(body_limit))
                         .app_data(PayloadConfig::default().limit(body_limit))
+                        .wrap(middleware::IdempotencyChecker)
+                        .wrap(middleware::ResponseCache)
                         .wrap(Compress::default())
                         .wrap(middleware::RequestContextInitializer)
                         .wrap(middleware::tracing_middleware())
@@ -223,15 +321,76 @@ impl Application for ActixCluster {
                 .backlog(backlog)
                 .max_connections(max_connections)
                 .client_request_timeout(request_timeout)
+                .shutdown_timeout(shutdown_timeout.as_secs())
+                .disable_signals()
                 .bind(addr)
                 .unwrap_or_else(|err| panic!("fail to create an HTTP server: {err}"))
                 .run()
             });
-            for result in futures::future::join_all(servers).await {
+            let servers = servers.collect::<Vec<_>>();
+            let server_handles = servers
+                .iter()
+                .map(|server| server.handle())
+                .collect::<Vec<_>>();
+            let servers = futures::future::join_all(servers);
+            tokio::pin!(servers);
+
+            // Flips the readiness and stops accepting new connections after the signal.
+            // The in-flight requests are drained with the deadline of `shutdown_timeout`.
+            let signal = async {
+                shutdown_signal().await;
+                Self::start_shutdown();
+                if !shutdown_delay.is_zero() {
+                    rt::time::sleep(shutdown_delay).await;
+                }
+                let stops = server_handles.iter().map(|handle| handle.stop(true));
+                futures::future::join_all(stops).await;
+            };
+            let results = tokio::select! {
+                results = &mut servers => results,
+                _ = signal => servers.await,
+            };
+            for result in results {
                 if let Err(err) = result {
                     tracing::error!("actix server error: {err}");
                 }
             }
+
+            // Stops the scheduler and queue workers before running the shutdown hooks and closing the pools.
+            shutdown_sender.send_replace(true);
+            if let Some(handle) = scheduler_handle {
+                if rt::time::timeout(shutdown_timeout, handle).await.is_err() {
+                    tracing::warn!("fail to stop the async job scheduler within {shutdown_timeout:?}");
+                }
+            }
+            let workers = futures::future::join_all(worker_handles);
+            if rt::time::timeout(shutdown_timeout, workers).await.is_err() {
+                tracing::warn!("fail to stop the job queue workers within {shutdown_timeout:?}");
+            }
+            Self::shutdown().await;
         });
     }
 }
+
+/// Waits for the `Ctrl+C` or terminate signal.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = signal::ctrl_c().await {
+            tracing::error!("fail to install the `Ctrl+C` handler: {err}");
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("fail to install the terminate signal handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    };
+    tracing::warn!("signal received, starting graceful shutdown");
+}
@@ -1,15 +1,17 @@
-use crate::{middleware, AxumExtractor, AxumResponse};
+use crate::{channel, middleware, AxumExtractor, AxumResponse};
 use axum::{
     error_handling::HandleErrorLayer,
     extract::{rejection::LengthLimitError, DefaultBodyLimit},
     http::StatusCode,
     middleware::from_fn,
+    routing::get,
     BoxError, Router, Server,
 };
 use std::{
-    any::Any, borrow::Cow, convert::Infallible, fs, net::SocketAddr, path::PathBuf, time::Duration,
+    any::Any, borrow::Cow, convert::Infallible, fs, net::SocketAddr, path::PathBuf, sync::Arc,
+    time::Duration,
 };
-use tokio::{runtime::Builder, signal};
+use tokio::{runtime::Builder, signal, sync::watch};
 use tower::{
     timeout::{error::Elapsed, TimeoutLayer},
     ServiceBuilder,
@@ -25,7 +27,7 @@ use zino_core::{
     application::{Application, Plugin, ServerTag},
     extension::TomlTableExt,
     response::Response,
-    schedule::AsyncScheduler,
+    schedule::{AsyncScheduler, JobQueue, SchedulerHandle},
     LazyLock,
 };
 
@@ -71,16 +73,35 @@ impl Application for AxumCluster {
             Self::load().await;
             super::load_plugins(self.custom_plugins, app_env).await;
         });
-        if scheduler.is_ready() {
+        let (shutdown_sender, shutdown_receiver) = watch::channel(false);
+        scheduler.set_runtime(Arc::new(super::TokioJobRuntime));
+        if let Some(handle) = scheduler.handle() {
+            SchedulerHandle::set_shared(handle);
+        }
+        let scheduler_handle = scheduler.is_ready().then(|| {
+            let mut shutdown_receiver = shutdown_receiver.clone();
             runtime.spawn(async move {
+                let handle = scheduler.handle().unwrap_or_default();
                 loop {
                     scheduler.tick().await;
 
                     // Cannot use `std::thread::sleep` because it blocks the Tokio runtime.
-                    tokio::time::sleep(scheduler.time_till_next_job()).await;
+                    // The scheduler wakes up at the next fire time or when a command arrives.
+                    tokio::select! {
+                        _ = tokio::time::sleep(scheduler.time_till_next_job()) => {},
+                        _ = handle.command_received() => {},
+                        _ = shutdown_receiver.changed() => break,
+                    }
                 }
-            });
-        }
+                tracing::warn!("async job scheduler has been stopped");
+            })
+        });
+        let worker_handles = (0..JobQueue::workers())
+            .map(|worker_id| {
+                let shutdown_receiver = shutdown_receiver.clone();
+                runtime.spawn(super::run_queue_worker(worker_id, shutdown_receiver))
+            })
+            .collect::<Vec<_>>();
 
         runtime.block_on(async {
             let default_routes = self.default_routes;
@@ -90,6 +111,16 @@ impl Application for AxumCluster {
             let app_version = Self::version();
             let listeners = app_state.listeners();
             let has_debug_server = listeners.iter().any(|listener| listener.0.is_debug());
+            let mut shutdown_timeout = Duration::from_secs(30); // 30 seconds
+            let mut shutdown_delay = Duration::ZERO;
+            if let Some(config) = app_state.get_config("server") {
+                if let Some(timeout) = config.get_duration("shutdown-timeout") {
+                    shutdown_timeout = timeout;
+                }
+                if let Some(delay) = config.get_duration("shutdown-delay") {
+                    shutdown_delay = delay;
+                }
+            }
             let servers = listeners.into_iter().map(|listener| {
                 let server_tag = listener.0;
                 let addr = listener.1;
@@ -169,12 +200,75 @@ impl Application for AxumCluster {
                     }
                 }
 
-                // Render OpenAPI docs.
+                // Server-sent events and WebSocket channels.
+                if let Some(config) = app_state.get_config("channel") {
+                    let sse_route = config.get_str("sse-route").unwrap_or("/sse");
+                    let websocket_route = config.get_str("websocket-route").unwrap_or("/websocket");
+                    app = app
+                        .route(sse_route, get(channel::sse_handler))
+                        .route(websocket_route, get(channel::websocket_handler));
+                    tracing::info!(
+                        "Channel routers `{sse_route}` and `{websocket_route}` are registered for `{addr}`"
+                    );
+                }
+
+                // Streaming chat completions.
+                #[cfg(feature = "chatbot")]
+                if let Some(config) = app_state.get_config("chat") {
+                    let chat_route = config.get_str("route").unwrap_or("/chat");
+                    app = app.route(chat_route, axum::routing::post(channel::chat_handler));
+                    tracing::info!("Chat router `{chat_route}` is registered for `{addr}`");
+                }
+
+                // Health, readiness and liveness probes.
                 let is_docs_server = if has_debug_server {
                     server_tag.is_debug()
                 } else {
                     server_tag.is_main()
                 };
+                let health_config = app_state.get_config("health");
+                let is_health_server = health_config
+                    .and_then(|config| config.get_str("server"))
+                    .map(|tag| server_tag == ServerTag::from(tag))
+                    .unwrap_or(is_docs_server);
+                if is_health_server {
+                    let (liveness_route, readiness_route) = health_config
+                        .map(|config| {
+                            (
+                                config.get_str("liveness-route").unwrap_or("/healthz"),
+                                config.get_str("readiness-route").unwrap_or("/readyz"),
+                            )
+                        })
+                        .unwrap_or(("/healthz", "/readyz"));
+                    app = app
+                        .route(liveness_route, get(super::health_check::liveness))
+                        .route(readiness_route, get(super::health_check::readiness));
+                    tracing::info!(
+                        "Health routers `{liveness_route}` and `{readiness_route}` are registered for `{addr}`"
+                    );
+                }
+
+                // Admin endpoints for the scheduled jobs.
+                #[cfg(feature = "jwt")]
+                if server_tag.is_debug() {
+                    let jobs_route = app_state
+                        .get_config("scheduler")
+                        .and_then(|config| config.get_str("admin-route"))
+                        .unwrap_or("/jobs");
+                    app = app
+                        .route(
+                            jobs_route,
+                            get(super::job_admin::list).post(super::job_admin::add),
+                        )
+                        .route(&format!("{jobs_route}/:id"), get(super::job_admin::view))
+                        .route(
+                            &format!("{jobs_route}/:id/:action"),
+                            axum::routing::post(super::job_admin::manage),
+                        );
+                    tracing::info!("Job admin routers `{jobs_route}/**` are registered for `{addr}`");
+                }
+
+                // Render OpenAPI docs.
                 if is_docs_server {
                     if let Some(config) = app_state.get_config("openapi") {
                         if config.get_bool("show-docs") != Some(false) {
@@ -226,6 +320,8 @@ impl Application for AxumCluster {
                             .layer(LazyLock::force(&middleware::CORS_MIDDLEWARE))
                             .layer(from_fn(middleware::request_context))
                             .layer(from_fn(middleware::extract_etag))
+                            .layer(from_fn(middleware::check_idempotency))
+                            .layer(from_fn(middleware::cache_response))
                             .layer(HandleErrorLayer::new(|err: BoxError| async move {
                                 let status_code = if err.is::<Elapsed>() {
                                     StatusCode::REQUEST_TIMEOUT
@@ -253,41 +349,81 @@ impl Application for AxumCluster {
                             ))
                             .layer(TimeoutLayer::new(request_timeout)),
                     );
+                let mut shutdown_receiver = shutdown_receiver.clone();
                 Server::bind(&addr)
                     .serve(app.into_make_service_with_connect_info::<SocketAddr>())
-                    .with_graceful_shutdown(Self::shutdown())
+                    .with_graceful_shutdown(async move {
+                        shutdown_receiver.changed().await.ok();
+                    })
             });
-            for result in futures::future::join_all(servers).await {
+            let servers = futures::future::join_all(servers);
+            tokio::pin!(servers);
+
+            // Flips the readiness and stops accepting new connections after the signal.
+            let signal = async {
+                shutdown_signal().await;
+                Self::start_shutdown();
+                if !shutdown_delay.is_zero() {
+                    tokio::time::sleep(shutdown_delay).await;
+                }
+                shutdown_sender.send_replace(true);
+            };
+            let results = tokio::select! {
+                results = &mut servers => results,
+                _ = signal => {
+                    // Drains the in-flight requests with a deadline.
+                    match tokio::time::timeout(shutdown_timeout, &mut servers).await {
+                        Ok(results) => results,
+                        Err(_) => {
+                            tracing::warn!(
+                                "fail to drain in-flight requests within {shutdown_timeout:?}"
+                            );
+                            Vec::new()
+                        }
+                    }
+                }
+            };
+            for result in results {
                 if let Err(err) = result {
                     tracing::error!("axum server error: {err}");
                 }
             }
-        });
-    }
 
-    async fn shutdown() {
-        let ctrl_c = async {
-            if let Err(err) = signal::ctrl_c().await {
-                tracing::error!("fail to install the `Ctrl+C` handler: {err}");
+            // Stops the scheduler and queue workers before running the shutdown hooks and closing the pools.
+            shutdown_sender.send_replace(true);
+            if let Some(handle) = scheduler_handle {
+                if tokio::time::timeout(shutdown_timeout, handle).await.is_err() {
+                    tracing::warn!("fail to stop the async job scheduler within {shutdown_timeout:?}");
+                }
+            }
+            let workers = futures::future::join_all(worker_handles);
+            if tokio::time::timeout(shutdown_timeout, workers).await.is_err() {
+                tracing::warn!("fail to stop the job queue workers within {shutdown_timeout:?}");
             }
-            #[cfg(feature = "orm")]
-            zino_core::orm::GlobalPool::close_all().await;
-        };
-        #[cfg(unix)]
-        let terminate = async {
-            signal::unix::signal(signal::unix::SignalKind::terminate())
-                .expect("fail to install the terminate signal handler")
-                .recv()
-                .await;
-            #[cfg(feature = "orm")]
-            zino_core::orm::GlobalPool::close_all().await;
-        };
-        #[cfg(not(unix))]
-        let terminate = std::future::pending::<()>();
-        tokio::select! {
-            _ = ctrl_c => {},
-            _ = terminate => {},
-        };
-        tracing::warn!("signal received, starting graceful shutdown");
+            Self::shutdown().await;
+        });
     }
 }
+
+/// Waits for the `Ctrl+C` or terminate signal.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = signal::ctrl_c().await {
+            tracing::error!("fail to install the `Ctrl+C` handler: {err}");
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("fail to install the terminate signal handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    };
+    tracing::warn!("signal received, starting graceful shutdown");
+}
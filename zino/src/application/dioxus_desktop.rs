@@ -64,11 +64,16 @@ where
         });
         if scheduler.is_ready() {
             runtime.spawn(async move {
+                let handle = scheduler.handle().unwrap_or_default();
                 loop {
                     scheduler.tick().await;
 
                     // Cannot use `std::thread::sleep` because it blocks the Tokio runtime.
-                    tokio::time::sleep(scheduler.time_till_next_job()).await;
+                    // The scheduler wakes up at the next fire time or when a command arrives.
+                    tokio::select! {
+                        _ = tokio::time::sleep(scheduler.time_till_next_job()) => {},
+                        _ = handle.command_received() => {},
+                    }
                 }
             });
         }
@@ -0,0 +1,22 @@
+use crate::{Request, Response, Result};
+use zino_core::{application::HealthReport, response::StatusCode};
+
+/// Reports the liveness of the application.
+pub(crate) async fn liveness(req: Request) -> Result {
+    let mut res = Response::default().context(&req);
+    res.set_json_response(HealthReport::liveness().into_map());
+    Ok(res.into())
+}
+
+/// Reports the readiness of the application with the status of components.
+pub(crate) async fn readiness(req: Request) -> Result {
+    let report = HealthReport::readiness().await;
+    let status_code = if report.is_up() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let mut res = Response::new(status_code).context(&req);
+    res.set_json_response(report.into_map());
+    Ok(res.into())
+}
@@ -0,0 +1,87 @@
+use crate::{Request, Response, Result};
+use zino_core::{
+    auth::{JwtClaims, UserSession},
+    extension::JsonObjectExt,
+    request::RequestContext,
+    response::{ExtractRejection, Rejection, StatusCode},
+    schedule::SchedulerHandle,
+    warn, Map,
+};
+
+/// Lists the scheduled jobs with the upcoming fire times.
+pub(crate) async fn list(req: Request) -> Result {
+    let handle = authorize(&req)?;
+    let jobs = handle.jobs();
+    let mut res = Response::default().context(&req);
+    res.set_data(&jobs);
+    Ok(res.into())
+}
+
+/// Inspects a scheduled job with the data.
+pub(crate) async fn view(req: Request) -> Result {
+    let handle = authorize(&req)?;
+    let job_id = req.parse_param::<String>("id")?;
+    let job = handle.get(&job_id).extract(&req)?;
+    let mut res = Response::default().context(&req);
+    res.set_data(&job);
+    Ok(res.into())
+}
+
+/// Adds a job from the config with a registered handler.
+pub(crate) async fn add(mut req: Request) -> Result {
+    let handle = authorize(&req)?;
+    let config: Map = req.parse_body().await?;
+    let job_id = handle.add_from_config(&config).extract(&req)?;
+    let mut res = Response::new(StatusCode::ACCEPTED).context(&req);
+    res.set_data(&Map::from_entry("id", job_id.to_string()));
+    Ok(res.into())
+}
+
+/// Pauses, resumes or triggers a scheduled job.
+pub(crate) async fn manage(req: Request) -> Result {
+    let handle = authorize(&req)?;
+    let job_id = req.parse_param::<String>("id")?;
+    let action = req.parse_param::<String>("action")?;
+    let result = match action.as_str() {
+        "pause" => handle.pause(&job_id),
+        "resume" => handle.resume(&job_id),
+        "trigger" => handle.trigger(&job_id),
+        "remove" => handle.remove(&job_id),
+        _ => {
+            let err = warn!("404 Not Found: the job action `{}` is unsupported", action);
+            return Err(Rejection::not_found(err).context(&req).into());
+        }
+    };
+    let job_id = result.extract(&req)?;
+    let mut data = Map::new();
+    data.upsert("id", job_id.to_string());
+    data.upsert("action", action);
+    let mut res = Response::new(StatusCode::ACCEPTED).context(&req);
+    res.set_data(&data);
+    Ok(res.into())
+}
+
+/// Authorizes the request by a JWT token with the admin role,
+/// and returns the shared scheduler handle.
+fn authorize(req: &Request) -> Result<&'static SchedulerHandle, Rejection> {
+    let claims = req.parse_jwt_claims(JwtClaims::shared_key())?;
+    let user_session = UserSession::<String>::try_from_jwt_claims(claims)
+        .map_err(|err| Rejection::unauthorized(err).context(req))?;
+    if !user_session.has_admin_role() {
+        let err = warn!("403 Forbidden: the admin role is required to manage the jobs");
+        return Err(Rejection::forbidden(err).context(req));
+    }
+    tracing::info!(
+        user_id = user_session.user_id().as_str(),
+        "authorized to manage the scheduled jobs"
+    );
+    shared_handle(req)
+}
+
+/// Returns the shared scheduler handle.
+fn shared_handle(req: &Request) -> Result<&'static SchedulerHandle, Rejection> {
+    SchedulerHandle::shared().ok_or_else(|| {
+        let err = warn!("503 Service Unavailable: the async job scheduler is not running");
+        Rejection::service_unavailable(err).context(req)
+    })
+}
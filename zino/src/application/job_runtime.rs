@@ -0,0 +1,18 @@
+use std::time::Duration;
+use zino_core::{schedule::JobRuntime, BoxFuture};
+
+/// Tokio runtime for executing the scheduled jobs in the background.
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct TokioJobRuntime;
+
+impl JobRuntime for TokioJobRuntime {
+    #[inline]
+    fn spawn(&self, future: BoxFuture<'static>) {
+        tokio::spawn(future);
+    }
+
+    #[inline]
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
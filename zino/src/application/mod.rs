@@ -1,14 +1,28 @@
 cfg_if::cfg_if! {
     if #[cfg(feature = "actix")] {
+        mod health_check;
+        #[cfg(feature = "jwt")]
+        mod job_admin;
+        mod job_runtime;
         mod plugin_loader;
+        mod queue_worker;
         pub(crate) mod actix_cluster;
 
+        use job_runtime::TokioJobRuntime;
         use plugin_loader::load_plugins;
+        use queue_worker::run_queue_worker;
     } else if #[cfg(feature = "axum")] {
+        mod health_check;
+        #[cfg(feature = "jwt")]
+        mod job_admin;
+        mod job_runtime;
         mod plugin_loader;
+        mod queue_worker;
         pub(crate) mod axum_cluster;
 
+        use job_runtime::TokioJobRuntime;
         use plugin_loader::load_plugins;
+        use queue_worker::run_queue_worker;
     } else if #[cfg(feature = "dioxus-desktop")] {
         mod plugin_loader;
         pub(crate) mod dioxus_desktop;
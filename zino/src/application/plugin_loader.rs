@@ -1,4 +1,4 @@
-use zino_core::{application::Plugin, state::Env};
+use zino_core::{application::Plugin, error::Error, state::Env};
 
 /// Loads the plugins for the application.
 pub(super) async fn load_plugins(plugins: Vec<Plugin>, app_env: &Env) {
@@ -14,11 +14,11 @@ pub(super) async fn load_plugins(plugins: Vec<Plugin>, app_env: &Env) {
                 .iter()
                 .find(|dep| !plugin_names.contains(dep))
             {
-                tracing::error!(
-                    app_env = app_env.as_str(),
-                    plugin_name,
-                    "fail to find the dependency `{dependency}` for the plugin `{plugin_name}`",
+                let message = format!(
+                    "fail to find the dependency `{dependency}` for the plugin `{plugin_name}`"
                 );
+                tracing::error!(app_env = app_env.as_str(), plugin_name, message);
+                plugin.record_load_error(&Error::new(message));
             } else {
                 if let Err(err) = plugin.load().await {
                     tracing::error!(
@@ -0,0 +1,28 @@
+use tokio::sync::watch;
+use zino_core::schedule::JobQueue;
+
+/// Runs a worker for the job queue until the shutdown signal is received.
+pub(super) async fn run_queue_worker(
+    worker_id: usize,
+    mut shutdown_receiver: watch::Receiver<bool>,
+) {
+    loop {
+        let idle = match JobQueue::process_next().await {
+            Ok(processed) => !processed,
+            Err(err) => {
+                tracing::error!(worker_id, "fail to process the queued job: {err}");
+                true
+            }
+        };
+        if idle {
+            // Cannot use `std::thread::sleep` because it blocks the Tokio runtime.
+            tokio::select! {
+                _ = tokio::time::sleep(JobQueue::poll_interval()) => {},
+                _ = shutdown_receiver.changed() => break,
+            }
+        } else if *shutdown_receiver.borrow() {
+            break;
+        }
+    }
+    tracing::warn!(worker_id, "job queue worker has been stopped");
+}
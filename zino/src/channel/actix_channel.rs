@@ -0,0 +1,119 @@
+use crate::{response::actix_response::ActixRejection, Request};
+use actix_web::{
+    http::header::{self, ContentEncoding},
+    rt,
+    web::{Bytes, Payload},
+    Error, HttpRequest, HttpResponse,
+};
+use actix_ws::{Message, ProtocolError};
+use futures::{stream, StreamExt};
+use std::convert::Infallible;
+use zino_core::{
+    channel::{CloudEvent, GlobalEventHub},
+    request::RequestContext,
+};
+
+/// Streams the subscribed cloud events as server-sent events.
+pub(crate) async fn sse_handler(req: HttpRequest) -> crate::Result<HttpResponse> {
+    let req = Request::from(req);
+    let subscription = super::subscription::parse_subscription(&req)?;
+    let last_event_id = req.get_header("last-event-id");
+    let events = GlobalEventHub::subscribe(subscription, last_event_id).map(|event| {
+        let id = event.id();
+        let event_type = event.event_type();
+        let data = stringify_event(&event);
+        let message = format!("id: {id}\nevent: {event_type}\ndata: {data}\n\n");
+        Ok::<_, Infallible>(Bytes::from(message))
+    });
+    let heartbeats = heartbeat_stream().map(|_| Ok(Bytes::from_static(b": heartbeat\n\n")));
+    let res = HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .insert_header(ContentEncoding::Identity)
+        .streaming(stream::select(events, heartbeats));
+    Ok(res)
+}
+
+/// Streams the chat completion chunks as server-sent events.
+#[cfg(feature = "chatbot")]
+pub(crate) async fn chat_handler(mut req: Request) -> crate::Result<HttpResponse> {
+    let chunks = super::chat_completion::stream_chat_completion(&mut req).await?;
+    let events = chunks
+        .map(|result| {
+            let (event_type, data) = super::chat_completion::format_chat_event(result);
+            let message = format!("event: {event_type}\ndata: {data}\n\n");
+            Ok::<_, Infallible>(Bytes::from(message))
+        })
+        .chain(stream::once(async {
+            Ok(Bytes::from_static(b"event: done\ndata: [DONE]\n\n"))
+        }));
+    let res = HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .insert_header(ContentEncoding::Identity)
+        .streaming(events);
+    Ok(res)
+}
+
+/// Upgrades the connection to a WebSocket pushing the subscribed cloud events.
+pub(crate) async fn websocket_handler(
+    req: HttpRequest,
+    body: Payload,
+) -> Result<HttpResponse, Error> {
+    let request = Request::from(req.clone());
+    let subscription =
+        super::subscription::parse_subscription(&request).map_err(ActixRejection::from)?;
+    let (res, mut session, messages) = actix_ws::handle(&req, body)?;
+    let req = request;
+    let last_event_id = req
+        .get_query("last_event_id")
+        .or_else(|| req.get_header("last-event-id"));
+    let events = GlobalEventHub::subscribe(subscription, last_event_id).map(Signal::Event);
+    let messages = messages.map(Signal::Message);
+    let heartbeats = heartbeat_stream().map(|_| Signal::Heartbeat);
+    let mut signals = stream::select(events, stream::select(messages, heartbeats));
+    rt::spawn(async move {
+        while let Some(signal) = signals.next().await {
+            let result = match signal {
+                Signal::Event(event) => session.text(stringify_event(&event)).await,
+                Signal::Message(Ok(Message::Ping(data))) => session.pong(&data).await,
+                Signal::Message(Ok(Message::Close(_)) | Err(_)) => break,
+                Signal::Message(_) => Ok(()),
+                Signal::Heartbeat => session.ping(b"").await,
+            };
+            if result.is_err() {
+                return;
+            }
+        }
+        session.close(None).await.ok();
+    });
+    Ok(res)
+}
+
+/// Signals for a WebSocket session.
+enum Signal {
+    /// A cloud event to be pushed.
+    Event(CloudEvent),
+    /// A message received from the client.
+    Message(Result<Message, ProtocolError>),
+    /// A heartbeat tick.
+    Heartbeat,
+}
+
+/// Returns a stream of heartbeat ticks.
+fn heartbeat_stream() -> impl futures::Stream<Item = ()> {
+    let interval = rt::time::interval(GlobalEventHub::heartbeat_interval());
+    stream::unfold(interval, |mut interval| async move {
+        interval.tick().await;
+        Some(((), interval))
+    })
+    .skip(1)
+}
+
+/// Stringifies the cloud event as JSON.
+fn stringify_event(event: &CloudEvent) -> String {
+    serde_json::to_string(event).unwrap_or_else(|err| {
+        tracing::error!("fail to serialize the cloud event: {err}");
+        String::new()
+    })
+}
@@ -0,0 +1,109 @@
+use crate::AxumExtractor;
+use axum::{
+    body::Body,
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    http::Request,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures::{SinkExt, Stream, StreamExt};
+use std::convert::Infallible;
+use zino_core::{
+    channel::{CloudEvent, GlobalEventHub, Subscription},
+    request::RequestContext,
+};
+
+/// Streams the subscribed cloud events as server-sent events.
+pub(crate) async fn sse_handler(
+    req: AxumExtractor<Request<Body>>,
+) -> crate::Result<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    let subscription = super::subscription::parse_subscription(&req)?;
+    let last_event_id = req.get_header("last-event-id");
+    let stream = GlobalEventHub::subscribe(subscription, last_event_id).map(|event| {
+        let event = Event::default()
+            .id(event.id())
+            .event(event.event_type())
+            .data(stringify_event(&event));
+        Ok(event)
+    });
+    let keep_alive = KeepAlive::new().interval(GlobalEventHub::heartbeat_interval());
+    Ok(Sse::new(stream).keep_alive(keep_alive))
+}
+
+/// Streams the chat completion chunks as server-sent events.
+#[cfg(feature = "chatbot")]
+pub(crate) async fn chat_handler(
+    mut req: AxumExtractor<Request<Body>>,
+) -> crate::Result<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    let chunks = super::chat_completion::stream_chat_completion(&mut req).await?;
+    let events = chunks
+        .map(|result| {
+            let (event_type, data) = super::chat_completion::format_chat_event(result);
+            Ok(Event::default().event(event_type).data(data))
+        })
+        .chain(futures::stream::once(async {
+            Ok(Event::default().event("done").data("[DONE]"))
+        }));
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// Upgrades the connection to a WebSocket pushing the subscribed cloud events.
+pub(crate) async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    req: AxumExtractor<Request<Body>>,
+) -> crate::Result<Response> {
+    let subscription = super::subscription::parse_subscription(&req)?;
+    let last_event_id = req
+        .get_query("last_event_id")
+        .or_else(|| req.get_header("last-event-id"))
+        .map(|s| s.to_owned());
+    let res = ws
+        .on_upgrade(move |socket| push_events(socket, subscription, last_event_id))
+        .into_response();
+    Ok(res)
+}
+
+/// Pushes the subscribed cloud events to the WebSocket.
+async fn push_events(socket: WebSocket, subscription: Subscription, last_event_id: Option<String>) {
+    let (mut sender, mut receiver) = socket.split();
+    let mut subscriber = GlobalEventHub::subscribe(subscription, last_event_id.as_deref());
+    let mut heartbeat = tokio::time::interval(GlobalEventHub::heartbeat_interval());
+    loop {
+        tokio::select! {
+            event = subscriber.next() => {
+                let Some(event) = event else {
+                    break;
+                };
+                if sender.send(Message::Text(stringify_event(&event))).await.is_err() {
+                    break;
+                }
+            }
+            message = receiver.next() => {
+                match message {
+                    Some(Ok(Message::Ping(data))) => {
+                        if sender.send(Message::Pong(data)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    _ => {}
+                }
+            }
+            _ = heartbeat.tick() => {
+                if sender.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Stringifies the cloud event as JSON.
+fn stringify_event(event: &CloudEvent) -> String {
+    serde_json::to_string(event).unwrap_or_else(|err| {
+        tracing::error!("fail to serialize the cloud event: {err}");
+        String::new()
+    })
+}
@@ -0,0 +1,98 @@
+use crate::Request;
+use zino_core::{
+    auth::{JwtClaims, UserSession},
+    chatbot::{ChatChunk, ChatStream, ChatbotService, Conversation, GlobalChatbot},
+    error::Error,
+    extension::{JsonObjectExt, JsonValueExt, TomlTableExt},
+    request::RequestContext,
+    response::{ExtractRejection, Rejection},
+    state::State,
+    warn, Map,
+};
+
+/// Default maximum number of tokens to generate for a chat completion.
+const DEFAULT_MAX_TOKENS: u64 = 1024;
+
+/// Parses the conversation in the request body and streams the chat completion chunks.
+///
+/// The request should be authenticated by a JWT token. The body contains the `messages`,
+/// the optional `tools`, the `options` for the model and the `chatbot` name,
+/// which defaults to the `chatbot` in the `[chat]` config. Only the chatbots listed in
+/// the `chatbots` of the config (including the default one) can be selected,
+/// and the `temperature` and `max-tokens` options are clamped to the safe ranges.
+pub(crate) async fn stream_chat_completion(req: &mut Request) -> Result<ChatStream, Rejection> {
+    let claims = req.parse_jwt_claims(JwtClaims::shared_key())?;
+    let user_session = UserSession::<String>::try_from_jwt_claims(claims)
+        .map_err(|err| Rejection::unauthorized(err).context(&*req))?;
+    let mut body: Map = req.parse_body().await?;
+    let req = &*req;
+
+    let config = State::shared().get_config("chat");
+    let default_chatbot = config.and_then(|config| config.get_str("chatbot"));
+    let name = body
+        .get_str("chatbot")
+        .or(default_chatbot)
+        .map(|s| s.to_owned())
+        .ok_or_else(|| {
+            let err = warn!("the `chatbot` should be specified");
+            Rejection::from_validation_entry("chatbot", err).context(req)
+        })?;
+    let allowed = default_chatbot == Some(name.as_str())
+        || config
+            .and_then(|config| config.get_str_array("chatbots"))
+            .is_some_and(|chatbots| chatbots.contains(&name.as_str()));
+    if !allowed {
+        let err = warn!("403 Forbidden: the chatbot `{}` is not allowed", name);
+        return Err(Rejection::forbidden(err).context(req));
+    }
+
+    let chatbot = GlobalChatbot::get(&name).ok_or_else(|| {
+        let err = warn!("404 Not Found: the chatbot `{}` does not exist", name);
+        Rejection::not_found(err).context(req)
+    })?;
+    let options = body
+        .remove("options")
+        .and_then(|v| v.into_map_opt())
+        .unwrap_or_default();
+    let max_tokens = config.and_then(|config| config.get_u64("max-tokens"));
+    let options = clamp_options(&options, max_tokens);
+    let conversation = serde_json::from_value::<Conversation>(body.into())
+        .map_err(|err| Rejection::from_validation_entry("messages", err).context(req))?;
+    if conversation.messages().is_empty() {
+        let err = warn!("the `messages` should be nonempty");
+        return Err(Rejection::from_validation_entry("messages", err).context(req));
+    }
+    tracing::info!(
+        user_id = user_session.user_id().as_str(),
+        chatbot = name.as_str(),
+        "streaming the chat completion"
+    );
+    chatbot
+        .try_stream(&conversation, Some(options))
+        .await
+        .extract(req)
+}
+
+/// Formats the result of a chat completion chunk as the event type and data.
+pub(crate) fn format_chat_event(result: Result<ChatChunk, Error>) -> (&'static str, String) {
+    match result {
+        Ok(chunk) => match serde_json::to_string(&chunk) {
+            Ok(data) => ("chunk", data),
+            Err(err) => ("error", err.to_string()),
+        },
+        Err(err) => ("error", err.to_string()),
+    }
+}
+
+/// Retains the `temperature` and `max-tokens` options, which are clamped to the safe ranges.
+fn clamp_options(options: &Map, max_tokens: Option<u64>) -> Map {
+    let mut clamped_options = Map::new();
+    if let Some(temperature) = options.get_f32("temperature") {
+        clamped_options.upsert("temperature", temperature.clamp(0.0, 2.0));
+    }
+
+    let max_tokens = max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    let tokens = options.get_u64("max-tokens").unwrap_or(max_tokens);
+    clamped_options.upsert("max-tokens", tokens.clamp(1, max_tokens));
+    clamped_options
+}
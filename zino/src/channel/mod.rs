@@ -0,0 +1,23 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "actix")] {
+        mod actix_channel;
+        mod subscription;
+
+        pub(crate) use self::actix_channel::{sse_handler, websocket_handler};
+
+        #[cfg(feature = "chatbot")]
+        mod chat_completion;
+        #[cfg(feature = "chatbot")]
+        pub(crate) use self::actix_channel::chat_handler;
+    } else if #[cfg(feature = "axum")] {
+        mod axum_channel;
+        mod subscription;
+
+        pub(crate) use self::axum_channel::{sse_handler, websocket_handler};
+
+        #[cfg(feature = "chatbot")]
+        mod chat_completion;
+        #[cfg(feature = "chatbot")]
+        pub(crate) use self::axum_channel::chat_handler;
+    }
+}
@@ -0,0 +1,44 @@
+use crate::Request;
+use zino_core::{
+    channel::Subscription, extension::TomlTableExt, request::RequestContext, response::Rejection,
+    state::State, warn,
+};
+
+#[cfg(feature = "jwt")]
+use zino_core::{auth::JwtClaims, extension::JsonObjectExt};
+
+/// Parses the subscription of an event channel and authenticates the subscriber.
+///
+/// The session-scoped events are only delivered to an authenticated subscriber,
+/// whose session is derived from the `sid` claim (or the subject) of a JWT token
+/// in the `authorization` header or the `access_token` query parameter.
+/// A request without a token only receives the events without a session,
+/// unless `require-auth` is enabled in the `[channel]` config.
+pub(crate) fn parse_subscription(req: &Request) -> Result<Subscription, Rejection> {
+    let subscription = req.subscription();
+    #[cfg(feature = "jwt")]
+    if req.get_header("authorization").is_some() || req.get_query("access_token").is_some() {
+        let claims = req.parse_jwt_claims(JwtClaims::shared_key())?;
+        let session_id = claims
+            .data()
+            .get_str("sid")
+            .or_else(|| claims.subject())
+            .ok_or_else(|| {
+                let err = warn!("401 Unauthorized: the session of a JWT token is missing");
+                Rejection::unauthorized(err).context(req)
+            })?;
+        let mut subscription = subscription;
+        subscription.set_session_id(Some(session_id.to_owned()));
+        return Ok(subscription);
+    }
+
+    let require_auth = State::shared()
+        .get_config("channel")
+        .and_then(|config| config.get_bool("require-auth"))
+        .unwrap_or_default();
+    if require_auth {
+        let err = warn!("401 Unauthorized: the event channel requires a JWT token");
+        return Err(Rejection::unauthorized(err).context(req));
+    }
+    Ok(subscription)
+}
@@ -6,6 +6,7 @@
 #![forbid(unsafe_code)]
 
 mod application;
+mod channel;
 mod controller;
 mod middleware;
 mod request;
@@ -0,0 +1,203 @@
+use crate::response::actix_response::{build_stored_response, collect_stored_headers};
+use actix_web::{
+    body::{self, BodySize, BoxBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{
+            HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CACHE_CONTROL, ETAG, IF_NONE_MATCH,
+        },
+        Method, StatusCode,
+    },
+    Error, HttpResponse,
+};
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+};
+use zino_core::response::{CacheControl, CachedResponse, GlobalResponseCache};
+
+#[derive(Default)]
+pub struct ResponseCache;
+
+impl<S, B> Transform<S, ServiceRequest> for ResponseCache
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ResponseCacheMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ResponseCacheMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct ResponseCacheMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for ResponseCacheMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let route = (req.method() == Method::GET)
+            .then(|| GlobalResponseCache::route(req.path()))
+            .flatten()
+            .filter(|_| !parse_cache_control(req.headers()).is_no_store());
+        let scope = route.and_then(|route| {
+            let authorization = req
+                .headers()
+                .get(AUTHORIZATION)
+                .and_then(|value| value.to_str().ok());
+            GlobalResponseCache::authorize(route, authorization)
+        });
+        let (Some(route), Some(scope)) = (route, scope) else {
+            let fut = self.service.call(req);
+            return Box::pin(async move {
+                let res = fut.await?;
+                Ok(res.map_into_boxed_body())
+            });
+        };
+
+        let service = self.service.clone();
+        Box::pin(async move {
+            let uri = req.uri().to_string();
+            let key = match GlobalResponseCache::cache_key(route, "GET", &uri, Some(&scope)).await {
+                Ok(key) => key,
+                Err(err) => {
+                    tracing::warn!("fail to generate the cache key for `{uri}`: {err}");
+                    let res = service.call(req).await?;
+                    return Ok(res.map_into_boxed_body());
+                }
+            };
+            let store = GlobalResponseCache::store();
+            if !parse_cache_control(req.headers()).is_no_cache() {
+                match store.get(&key).await {
+                    Ok(Some(cached_response)) => {
+                        let if_none_match = req
+                            .headers()
+                            .get(IF_NONE_MATCH)
+                            .and_then(|value| value.to_str().ok());
+                        if if_none_match.is_some_and(|value| cached_response.matches_etag(value)) {
+                            let res = build_not_modified_response(&cached_response);
+                            return Ok(req.into_response(res));
+                        }
+                        match build_stored_response(cached_response.response()) {
+                            Ok(mut res) => {
+                                res.headers_mut().insert(
+                                    HeaderName::from_static("x-cache"),
+                                    HeaderValue::from_static("HIT"),
+                                );
+                                return Ok(req.into_response(res));
+                            }
+                            Err(err) => tracing::warn!(
+                                "fail to replay the cached response for `{uri}`: {err}"
+                            ),
+                        }
+                    }
+                    Ok(None) => (),
+                    Err(err) => {
+                        tracing::warn!("fail to get the cached response for `{uri}`: {err}")
+                    }
+                }
+            }
+
+            let res = service.call(req).await?;
+            let cache_control = parse_cache_control(res.headers());
+            let max_body_size = GlobalResponseCache::max_body_size();
+            let cacheable = res.status() == StatusCode::OK
+                && !cache_control.is_no_store()
+                && (route.is_user_scoped() || !cache_control.is_private())
+                && matches!(
+                    res.response().body().size(),
+                    BodySize::Sized(size) if size <= max_body_size as u64
+                );
+            if !cacheable {
+                return Ok(res.map_into_boxed_body());
+            }
+
+            let (http_req, http_res) = res.into_parts();
+            let (mut http_res, body) = http_res.into_parts();
+            let body = match body::to_bytes_limited(body, max_body_size).await {
+                Ok(Ok(bytes)) => bytes,
+                Ok(Err(err)) => {
+                    let err: Box<dyn std::error::Error> = err.into();
+                    tracing::error!("fail to read the response body for `{uri}`: {err}");
+                    let res = HttpResponse::new(StatusCode::INTERNAL_SERVER_ERROR);
+                    return Ok(ServiceResponse::new(http_req, res));
+                }
+                Err(err) => {
+                    tracing::error!("fail to read the response body for `{uri}`: {err}");
+                    let res = HttpResponse::new(StatusCode::INTERNAL_SERVER_ERROR);
+                    return Ok(ServiceResponse::new(http_req, res));
+                }
+            };
+            let headers =
+                collect_stored_headers(http_res.headers(), GlobalResponseCache::is_cached_header);
+            let ttl = cache_control
+                .max_age()
+                .map(|max_age| max_age.min(route.ttl()))
+                .unwrap_or_else(|| route.ttl());
+            let cached_response =
+                CachedResponse::new(http_res.status().as_u16(), headers, &body, ttl);
+            if let Err(err) = store.put(&key, &cached_response).await {
+                tracing::error!("fail to cache the response for `{uri}`: {err}");
+            }
+
+            let headers = http_res.headers_mut();
+            if !headers.contains_key("x-etag") && !headers.contains_key(ETAG) {
+                if let Ok(etag) = HeaderValue::try_from(cached_response.etag()) {
+                    headers.insert(HeaderName::from_static("x-etag"), etag);
+                }
+            }
+            headers.insert(
+                HeaderName::from_static("x-cache"),
+                HeaderValue::from_static("MISS"),
+            );
+            Ok(ServiceResponse::new(
+                http_req,
+                http_res.set_body(BoxBody::new(body)),
+            ))
+        })
+    }
+}
+
+/// Parses the `cache-control` header.
+fn parse_cache_control(headers: &HeaderMap) -> CacheControl {
+    headers
+        .get(CACHE_CONTROL)
+        .and_then(|value| value.to_str().ok())
+        .map(CacheControl::parse)
+        .unwrap_or_default()
+}
+
+/// Builds a `304 Not Modified` response for the cached response.
+/// The `x-etag` header will be converted to the `etag` header by the outer middleware.
+fn build_not_modified_response(cached_response: &CachedResponse) -> HttpResponse<BoxBody> {
+    let mut res = HttpResponse::new(StatusCode::NOT_MODIFIED);
+    let headers = res.headers_mut();
+    if let Ok(etag) = HeaderValue::try_from(cached_response.etag()) {
+        headers.insert(HeaderName::from_static("x-etag"), etag);
+    }
+    headers.insert(
+        HeaderName::from_static("x-cache"),
+        HeaderValue::from_static("HIT"),
+    );
+    res
+}
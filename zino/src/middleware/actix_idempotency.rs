@@ -0,0 +1,237 @@
+use crate::response::actix_response::{build_stored_response, collect_stored_headers};
+use actix_web::{
+    body::{self, BodySize, BoxBody, MessageBody},
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION},
+        Method, StatusCode,
+    },
+    web::Bytes,
+    Error, FromRequest, HttpResponse,
+};
+use std::{
+    future::{ready, Future, Ready},
+    net::IpAddr,
+    pin::Pin,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use zino_core::{
+    request::{GlobalIdempotency, IdempotencyRecord, IdempotencyStatus},
+    response::StoredResponse,
+    SharedString,
+};
+
+#[derive(Default)]
+pub struct IdempotencyChecker;
+
+impl<S, B> Transform<S, ServiceRequest> for IdempotencyChecker
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = IdempotencyMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(IdempotencyMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct IdempotencyMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for IdempotencyMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let key = matches!(
+            *req.method(),
+            Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+        )
+        .then(|| {
+            let client_ip = req
+                .connection_info()
+                .realip_remote_addr()
+                .and_then(|s| s.parse().ok());
+            req.headers()
+                .get(GlobalIdempotency::header_name())
+                .and_then(|value| value.to_str().ok())
+                .map(|key| scoped_key(key, req.headers(), client_ip))
+        })
+        .flatten();
+        let Some(key) = key else {
+            let fut = self.service.call(req);
+            return Box::pin(async move {
+                let res = fut.await?;
+                Ok(res.map_into_boxed_body())
+            });
+        };
+
+        let service = self.service.clone();
+        Box::pin(async move {
+            // The size of the request body is limited by the `PayloadConfig` of the app.
+            let (http_req, mut payload) = req.into_parts();
+            let body = match Bytes::from_request(&http_req, &mut payload).await {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    let res = build_error_response(StatusCode::BAD_REQUEST, err.to_string());
+                    return Ok(ServiceResponse::new(http_req, res));
+                }
+            };
+            let method = http_req.method().as_str();
+            let uri = http_req.uri().to_string();
+            let fingerprint = GlobalIdempotency::fingerprint(method, &uri, &body);
+
+            let wait_timeout = GlobalIdempotency::wait_timeout();
+            let start_time = Instant::now();
+            let status = loop {
+                match GlobalIdempotency::check(&key, &fingerprint).await {
+                    Ok(IdempotencyStatus::InProgress) if start_time.elapsed() < wait_timeout => {
+                        actix_web::rt::time::sleep(Duration::from_millis(100)).await;
+                    }
+                    Ok(status) => break status,
+                    Err(err) => {
+                        let status_code = StatusCode::INTERNAL_SERVER_ERROR;
+                        let res = build_error_response(status_code, err.to_string());
+                        return Ok(ServiceResponse::new(http_req, res));
+                    }
+                }
+            };
+            let res = match status {
+                IdempotencyStatus::Acquired => {
+                    let req = ServiceRequest::from_parts(http_req, Payload::from(body));
+                    match service.call(req).await {
+                        Ok(res) => return store_response(&key, fingerprint, res).await,
+                        Err(err) => {
+                            release_key(&key).await;
+                            return Err(err);
+                        }
+                    }
+                }
+                IdempotencyStatus::Completed(record) => replay_response(record),
+                IdempotencyStatus::InProgress => build_error_response(
+                    StatusCode::CONFLICT,
+                    "a request with the same idempotency key is in progress",
+                ),
+                IdempotencyStatus::Mismatched => build_error_response(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "the idempotency key has been used by a different request",
+                ),
+            };
+            Ok(ServiceResponse::new(http_req, res))
+        })
+    }
+}
+
+/// Returns the key in the store namespaced by the authenticated identity or the client IP.
+fn scoped_key(key: &str, headers: &HeaderMap, client_ip: Option<IpAddr>) -> String {
+    let authorization = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    GlobalIdempotency::scoped_key(key, authorization, client_ip)
+}
+
+/// Stores the response for the idempotency key. The key will be released
+/// if the response is a server error so that the request can be retried,
+/// or if the response body is too large to be stored.
+async fn store_response<B: MessageBody + 'static>(
+    key: &str,
+    fingerprint: String,
+    res: ServiceResponse<B>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let store = GlobalIdempotency::store();
+    let status_code = res.status();
+    let max_body_size = GlobalIdempotency::max_body_size();
+    let storable = match res.response().body().size() {
+        BodySize::None => true,
+        BodySize::Sized(size) => size <= max_body_size as u64,
+        BodySize::Stream => false,
+    };
+    if status_code.is_server_error() || !storable {
+        release_key(key).await;
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let (http_req, http_res) = res.into_parts();
+    let (http_res, body) = http_res.into_parts();
+    let body = match body::to_bytes_limited(body, max_body_size).await {
+        Ok(Ok(bytes)) => bytes,
+        Ok(Err(err)) => {
+            release_key(key).await;
+            let err: Box<dyn std::error::Error> = err.into();
+            let status_code = StatusCode::INTERNAL_SERVER_ERROR;
+            let res = build_error_response(status_code, err.to_string());
+            return Ok(ServiceResponse::new(http_req, res));
+        }
+        Err(err) => {
+            release_key(key).await;
+            let status_code = StatusCode::INTERNAL_SERVER_ERROR;
+            let res = build_error_response(status_code, err.to_string());
+            return Ok(ServiceResponse::new(http_req, res));
+        }
+    };
+    let headers = collect_stored_headers(http_res.headers(), GlobalIdempotency::is_replayed_header);
+    let mut record = IdempotencyRecord::new(fingerprint, GlobalIdempotency::ttl());
+    record.set_response(StoredResponse::new(status_code.as_u16(), headers, &body));
+    if let Err(err) = store.store(key, &record).await {
+        tracing::error!("fail to store the idempotency record for `{key}`: {err}");
+    }
+    Ok(ServiceResponse::new(
+        http_req,
+        http_res.set_body(BoxBody::new(body)),
+    ))
+}
+
+/// Releases the idempotency key so that the request can be retried.
+async fn release_key(key: &str) {
+    if let Err(err) = GlobalIdempotency::store().release(key).await {
+        tracing::error!("fail to release the idempotency key `{key}`: {err}");
+    }
+}
+
+/// Replays the stored response of the idempotency record.
+fn replay_response(record: IdempotencyRecord) -> HttpResponse<BoxBody> {
+    let Some(response) = record.response() else {
+        return build_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "the idempotency record has no stored response",
+        );
+    };
+    match build_stored_response(response) {
+        Ok(mut res) => {
+            res.headers_mut().insert(
+                HeaderName::from_static("idempotent-replayed"),
+                HeaderValue::from_static("true"),
+            );
+            res
+        }
+        Err(err) => build_error_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+/// Builds an error response with the message.
+fn build_error_response(
+    status_code: StatusCode,
+    message: impl Into<SharedString>,
+) -> HttpResponse<BoxBody> {
+    let mut res = crate::Response::new(status_code);
+    res.set_message(message);
+    crate::response::actix_response::build_http_response(&mut res)
+}
@@ -0,0 +1,141 @@
+use crate::{
+    request::axum_request::to_bytes_limited,
+    response::axum_response::{build_stored_response, collect_stored_headers},
+};
+use axum::{
+    body::{boxed, Body, Full, HttpBody},
+    http::{
+        header::{AUTHORIZATION, CACHE_CONTROL, ETAG, IF_NONE_MATCH},
+        HeaderMap, HeaderValue, Method, Request, StatusCode,
+    },
+    middleware::Next,
+    response::Response,
+};
+use zino_core::response::{CacheControl, CachedResponse, GlobalResponseCache};
+
+pub(crate) async fn cache_response(req: Request<Body>, next: Next<Body>) -> Response {
+    if req.method() != Method::GET {
+        return next.run(req).await;
+    }
+    let Some(route) = GlobalResponseCache::route(req.uri().path()) else {
+        return next.run(req).await;
+    };
+    let cache_control = parse_cache_control(req.headers());
+    if cache_control.is_no_store() {
+        return next.run(req).await;
+    }
+
+    let authorization = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    let Some(scope) = GlobalResponseCache::authorize(route, authorization) else {
+        return next.run(req).await;
+    };
+
+    let uri = req.uri().to_string();
+    let key = match GlobalResponseCache::cache_key(route, "GET", &uri, Some(&scope)).await {
+        Ok(key) => key,
+        Err(err) => {
+            tracing::warn!("fail to generate the cache key for `{uri}`: {err}");
+            return next.run(req).await;
+        }
+    };
+    let store = GlobalResponseCache::store();
+    if !cache_control.is_no_cache() {
+        match store.get(&key).await {
+            Ok(Some(cached_response)) => {
+                let if_none_match = req
+                    .headers()
+                    .get(IF_NONE_MATCH)
+                    .and_then(|value| value.to_str().ok());
+                if if_none_match.is_some_and(|value| cached_response.matches_etag(value)) {
+                    return build_not_modified_response(&cached_response);
+                }
+                match build_stored_response(cached_response.response()) {
+                    Ok(mut res) => {
+                        res.headers_mut()
+                            .insert("x-cache", HeaderValue::from_static("HIT"));
+                        return res;
+                    }
+                    Err(err) => {
+                        tracing::warn!("fail to replay the cached response for `{uri}`: {err}")
+                    }
+                }
+            }
+            Ok(None) => (),
+            Err(err) => tracing::warn!("fail to get the cached response for `{uri}`: {err}"),
+        }
+    }
+
+    let res = next.run(req).await;
+    let cache_control = parse_cache_control(res.headers());
+    let max_body_size = GlobalResponseCache::max_body_size();
+    let cacheable = res.status() == StatusCode::OK
+        && !cache_control.is_no_store()
+        && (route.is_user_scoped() || !cache_control.is_private())
+        && res
+            .body()
+            .size_hint()
+            .upper()
+            .is_some_and(|size| size <= max_body_size as u64);
+    if !cacheable {
+        return res;
+    }
+
+    let (mut parts, body) = res.into_parts();
+    let body = match to_bytes_limited(body, max_body_size).await {
+        Ok(Some(bytes)) => bytes,
+        Ok(None) => {
+            tracing::error!("the response body for `{uri}` is larger than its size hint");
+            parts.status = StatusCode::INTERNAL_SERVER_ERROR;
+            return Response::from_parts(parts, boxed(Full::default()));
+        }
+        Err(err) => {
+            tracing::error!("fail to read the response body for `{uri}`: {err}");
+            parts.status = StatusCode::INTERNAL_SERVER_ERROR;
+            return Response::from_parts(parts, boxed(Full::default()));
+        }
+    };
+    let headers = collect_stored_headers(&parts.headers, GlobalResponseCache::is_cached_header);
+    let ttl = cache_control
+        .max_age()
+        .map(|max_age| max_age.min(route.ttl()))
+        .unwrap_or_else(|| route.ttl());
+    let cached_response = CachedResponse::new(parts.status.as_u16(), headers, &body, ttl);
+    if let Err(err) = store.put(&key, &cached_response).await {
+        tracing::error!("fail to cache the response for `{uri}`: {err}");
+    }
+    if !parts.headers.contains_key("x-etag") && !parts.headers.contains_key(ETAG) {
+        if let Ok(etag) = HeaderValue::try_from(cached_response.etag()) {
+            parts.headers.insert("x-etag", etag);
+        }
+    }
+    parts
+        .headers
+        .insert("x-cache", HeaderValue::from_static("MISS"));
+    Response::from_parts(parts, boxed(Full::from(body)))
+}
+
+/// Parses the `cache-control` header.
+fn parse_cache_control(headers: &HeaderMap) -> CacheControl {
+    headers
+        .get(CACHE_CONTROL)
+        .and_then(|value| value.to_str().ok())
+        .map(CacheControl::parse)
+        .unwrap_or_default()
+}
+
+/// Builds a `304 Not Modified` response for the cached response.
+/// The `x-etag` header will be converted to the `etag` header by the outer middleware.
+fn build_not_modified_response(cached_response: &CachedResponse) -> Response {
+    let mut res = Response::new(boxed(Full::default()));
+    *res.status_mut() = StatusCode::NOT_MODIFIED;
+
+    let headers = res.headers_mut();
+    if let Ok(etag) = HeaderValue::try_from(cached_response.etag()) {
+        headers.insert("x-etag", etag);
+    }
+    headers.insert("x-cache", HeaderValue::from_static("HIT"));
+    res
+}
@@ -0,0 +1,172 @@
+use crate::{
+    request::axum_request::to_bytes_limited,
+    response::axum_response::{build_stored_response, collect_stored_headers},
+};
+use axum::{
+    body::{boxed, Body, Full, HttpBody},
+    extract::ConnectInfo,
+    http::{header::AUTHORIZATION, HeaderMap, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::{Duration, Instant},
+};
+use zino_core::{
+    extension::{HeaderMapExt, TomlTableExt},
+    request::{GlobalIdempotency, IdempotencyRecord, IdempotencyStatus},
+    response::StoredResponse,
+    state::State,
+    LazyLock, SharedString,
+};
+
+pub(crate) async fn check_idempotency(req: Request<Body>, next: Next<Body>) -> Response {
+    let method = req.method();
+    if !matches!(
+        *method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    ) {
+        return next.run(req).await;
+    }
+    let Some(key) = req
+        .headers()
+        .get(GlobalIdempotency::header_name())
+        .and_then(|value| value.to_str().ok())
+    else {
+        return next.run(req).await;
+    };
+    let client_ip = req.headers().get_client_ip().or_else(|| {
+        req.extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|socket| socket.ip())
+    });
+    let key = scoped_key(key, req.headers(), client_ip);
+
+    let (parts, body) = req.into_parts();
+    let body = match to_bytes_limited(body, *BODY_LIMIT).await {
+        Ok(Some(bytes)) => bytes,
+        Ok(None) => {
+            return build_error_response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "the request body is too large",
+            )
+        }
+        Err(err) => return build_error_response(StatusCode::BAD_REQUEST, err.to_string()),
+    };
+    let method = parts.method.as_str();
+    let uri = parts.uri.to_string();
+    let fingerprint = GlobalIdempotency::fingerprint(method, &uri, &body);
+
+    let wait_timeout = GlobalIdempotency::wait_timeout();
+    let start_time = Instant::now();
+    let status = loop {
+        match GlobalIdempotency::check(&key, &fingerprint).await {
+            Ok(IdempotencyStatus::InProgress) if start_time.elapsed() < wait_timeout => {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            Ok(status) => break status,
+            Err(err) => {
+                return build_error_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+            }
+        }
+    };
+    match status {
+        IdempotencyStatus::Acquired => {
+            let req = Request::from_parts(parts, Body::from(body));
+            let res = next.run(req).await;
+            store_response(&key, fingerprint, res).await
+        }
+        IdempotencyStatus::Completed(record) => replay_response(record),
+        IdempotencyStatus::InProgress => build_error_response(
+            StatusCode::CONFLICT,
+            "a request with the same idempotency key is in progress",
+        ),
+        IdempotencyStatus::Mismatched => build_error_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "the idempotency key has been used by a different request",
+        ),
+    }
+}
+
+/// Returns the key in the store namespaced by the authenticated identity or the client IP.
+fn scoped_key(key: &str, headers: &HeaderMap, client_ip: Option<IpAddr>) -> String {
+    let authorization = headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok());
+    GlobalIdempotency::scoped_key(key, authorization, client_ip)
+}
+
+/// Stores the response for the idempotency key. The key will be released
+/// if the response is a server error so that the request can be retried,
+/// or if the response body is too large to be stored.
+async fn store_response(key: &str, fingerprint: String, res: Response) -> Response {
+    let store = GlobalIdempotency::store();
+    let status_code = res.status();
+    let max_body_size = GlobalIdempotency::max_body_size();
+    let body_size = res.body().size_hint().upper();
+    if status_code.is_server_error() || body_size.map_or(true, |size| size > max_body_size as u64) {
+        if let Err(err) = store.release(key).await {
+            tracing::error!("fail to release the idempotency key `{key}`: {err}");
+        }
+        return res;
+    }
+
+    let (parts, body) = res.into_parts();
+    let body = match to_bytes_limited(body, max_body_size).await {
+        Ok(Some(bytes)) => bytes,
+        Ok(None) => {
+            if let Err(err) = store.release(key).await {
+                tracing::error!("fail to release the idempotency key `{key}`: {err}");
+            }
+            return build_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "the response body is larger than its size hint",
+            );
+        }
+        Err(err) => {
+            if let Err(release_err) = store.release(key).await {
+                tracing::error!("fail to release the idempotency key `{key}`: {release_err}");
+            }
+            return build_error_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string());
+        }
+    };
+    let headers = collect_stored_headers(&parts.headers, GlobalIdempotency::is_replayed_header);
+    let mut record = IdempotencyRecord::new(fingerprint, GlobalIdempotency::ttl());
+    record.set_response(StoredResponse::new(status_code.as_u16(), headers, &body));
+    if let Err(err) = store.store(key, &record).await {
+        tracing::error!("fail to store the idempotency record for `{key}`: {err}");
+    }
+    Response::from_parts(parts, boxed(Full::from(body)))
+}
+
+/// Replays the stored response of the idempotency record.
+fn replay_response(record: IdempotencyRecord) -> Response {
+    let Some(response) = record.response() else {
+        return build_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "the idempotency record has no stored response",
+        );
+    };
+    match build_stored_response(response) {
+        Ok(mut res) => {
+            res.headers_mut()
+                .insert("idempotent-replayed", HeaderValue::from_static("true"));
+            res
+        }
+        Err(err) => build_error_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+/// Builds an error response with the message.
+fn build_error_response(status_code: StatusCode, message: impl Into<SharedString>) -> Response {
+    let mut res = crate::Response::new(status_code);
+    res.set_message(message);
+    crate::response::axum_response::build_http_response(res).into_response()
+}
+
+/// Maximum size of the request body.
+static BODY_LIMIT: LazyLock<usize> = LazyLock::new(|| {
+    State::shared()
+        .get_config("server")
+        .and_then(|config| config.get_usize("body-limit"))
+        .unwrap_or(128 * 1024 * 1024)
+});
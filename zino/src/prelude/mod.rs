@@ -16,7 +16,7 @@ pub use zino_core::{
     reject,
     request::RequestContext,
     response::{ExtractRejection, Rejection, StatusCode, WebHook},
-    schedule::{AsyncCronJob, AsyncJob, AsyncJobScheduler, CronJob, Job, JobScheduler},
+    schedule::{AsyncCronJob, AsyncJob, AsyncJobScheduler, CronJob, Job, JobContext, JobScheduler},
     state::State,
     validation::Validation,
     warn, BoxFuture, Decimal, LazyLock, Map, Record, Uuid,
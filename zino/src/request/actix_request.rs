@@ -4,6 +4,7 @@ use actix_web::{
     web::Bytes,
     FromRequest, HttpMessage, HttpRequest,
 };
+use futures::{channel::mpsc, stream::BoxStream, SinkExt, StreamExt};
 use std::{
     borrow::Cow,
     convert::Infallible,
@@ -99,6 +100,22 @@ impl RequestContext for ActixExtractor<HttpRequest> {
         let bytes = Bytes::from_request(&self.0, &mut self.1).await?;
         Ok(bytes.into())
     }
+
+    fn take_body_stream(&mut self) -> BoxStream<'static, Result<Bytes, Error>> {
+        // The payload is not `Send`, so it is forwarded by a local task.
+        let mut payload = std::mem::replace(&mut self.1, Payload::None);
+        let (mut sender, receiver) = mpsc::channel(16);
+        actix_web::rt::spawn(async move {
+            while let Some(result) = payload.next().await {
+                let result = result.map_err(Error::from);
+                let is_err = result.is_err();
+                if sender.send(result).await.is_err() || is_err {
+                    break;
+                }
+            }
+        });
+        receiver.boxed()
+    }
 }
 
 impl From<ServiceRequest> for ActixExtractor<HttpRequest> {
@@ -4,7 +4,8 @@ use axum::{
     extract::{ConnectInfo, FromRequest, MatchedPath, OriginalUri},
     http::{HeaderMap, Method, Request},
 };
-use bytes::{Buf, BufMut};
+use bytes::{Buf, BufMut, Bytes};
+use futures::stream::{self, BoxStream, StreamExt};
 use std::{
     borrow::Cow,
     convert::Infallible,
@@ -123,6 +124,15 @@ impl RequestContext for AxumExtractor<Request<Body>> {
         let bytes = to_bytes(self.body_mut()).await?;
         Ok(bytes)
     }
+
+    fn take_body_stream(&mut self) -> BoxStream<'static, Result<Bytes, Error>> {
+        let body = std::mem::take(self.body_mut());
+        stream::unfold(body, |mut body| async move {
+            let result = body.data().await?;
+            Some((result.map_err(Error::from), body))
+        })
+        .boxed()
+    }
 }
 
 #[async_trait]
@@ -138,7 +148,7 @@ impl FromRequest<(), Body> for AxumExtractor<Request<Body>> {
 /// Concatenates the buffers from a body into a single `Bytes` asynchronously.
 ///
 /// Copy from https://docs.rs/hyper/0.14.27/hyper/body/fn.to_bytes.html
-async fn to_bytes<T: HttpBody + Unpin>(mut body: T) -> Result<Vec<u8>, T::Error> {
+pub(crate) async fn to_bytes<T: HttpBody + Unpin>(mut body: T) -> Result<Vec<u8>, T::Error> {
     let _ = Pin::new(&mut body);
 
     // If there's only 1 chunk, we can just return Buf::to_bytes()
@@ -172,3 +182,24 @@ async fn to_bytes<T: HttpBody + Unpin>(mut body: T) -> Result<Vec<u8>, T::Error>
 
     Ok(vec)
 }
+
+/// Concatenates the buffers from a body into a single `Bytes` asynchronously,
+/// returning `None` if the size of the body exceeds the limit.
+pub(crate) async fn to_bytes_limited<T: HttpBody + Unpin>(
+    mut body: T,
+    limit: usize,
+) -> Result<Option<Vec<u8>>, T::Error> {
+    if body.size_hint().lower() > limit as u64 {
+        return Ok(None);
+    }
+
+    let mut vec = Vec::new();
+    while let Some(buf) = body.data().await {
+        let buf = buf?;
+        if vec.len() + buf.remaining() > limit {
+            return Ok(None);
+        }
+        vec.put(buf);
+    }
+    Ok(Some(vec))
+}
@@ -1,14 +1,15 @@
 use actix_web::{
     body::BoxBody,
     http::{
-        header::{self, HeaderName, HeaderValue},
+        header::{self, HeaderMap, HeaderName, HeaderValue},
         StatusCode,
     },
     HttpRequest, HttpResponse, Responder, ResponseError,
 };
 use std::fmt;
 use zino_core::{
-    response::{Rejection, Response, ResponseCode},
+    error::Error,
+    response::{Rejection, Response, ResponseCode, StoredResponse},
     trace::TimingMetric,
 };
 
@@ -118,7 +119,9 @@ impl ResponseError for ActixRejection {
 }
 
 /// Build http response from `zino_core::response::Response`.
-fn build_http_response<S: ResponseCode>(response: &mut Response<S>) -> HttpResponse<BoxBody> {
+pub(crate) fn build_http_response<S: ResponseCode>(
+    response: &mut Response<S>,
+) -> HttpResponse<BoxBody> {
     match response.read_bytes() {
         Ok(data) => {
             let status_code = response
@@ -144,3 +147,40 @@ fn build_http_response<S: ResponseCode>(response: &mut Response<S>) -> HttpRespo
         }
     }
 }
+
+/// Builds an http response from the stored response to be replayed.
+pub(crate) fn build_stored_response(
+    response: &StoredResponse,
+) -> Result<HttpResponse<BoxBody>, Error> {
+    let body = response.decode_body()?;
+    let status_code = StatusCode::from_u16(response.status_code())?;
+    let mut res = HttpResponse::with_body(status_code, BoxBody::new(body));
+
+    let headers = res.headers_mut();
+    for (key, value) in response.headers() {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::try_from(key.as_str()),
+            HeaderValue::try_from(value.as_str()),
+        ) {
+            headers.append(name, value);
+        }
+    }
+    Ok(res)
+}
+
+/// Collects the headers accepted by the filter to be stored.
+pub(crate) fn collect_stored_headers(
+    headers: &HeaderMap,
+    filter: fn(&str) -> bool,
+) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| filter(name.as_str()))
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.as_str().to_owned(), value.to_owned()))
+        })
+        .collect()
+}
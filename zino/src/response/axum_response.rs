@@ -1,13 +1,16 @@
 use axum::{
-    body::{Bytes, Full},
+    body::{boxed, Bytes, Full},
     http::{
         self,
-        header::{self, HeaderName, HeaderValue},
+        header::{self, HeaderMap, HeaderName, HeaderValue},
         StatusCode,
     },
     response::IntoResponse,
 };
-use zino_core::response::{Rejection, Response, ResponseCode};
+use zino_core::{
+    error::Error,
+    response::{Rejection, Response, ResponseCode, StoredResponse},
+};
 
 /// An HTTP response for `axum`.
 pub struct AxumResponse<S: ResponseCode = StatusCode>(Response<S>);
@@ -70,3 +73,40 @@ pub(crate) fn build_http_response<S: ResponseCode>(
 
     res
 }
+
+/// Builds an http response from the stored response to be replayed.
+pub(crate) fn build_stored_response(
+    response: &StoredResponse,
+) -> Result<axum::response::Response, Error> {
+    let body = response.decode_body()?;
+    let mut res = axum::response::Response::new(boxed(Full::from(body)));
+    *res.status_mut() = StatusCode::from_u16(response.status_code())?;
+
+    let headers = res.headers_mut();
+    for (key, value) in response.headers() {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::try_from(key.as_str()),
+            HeaderValue::try_from(value.as_str()),
+        ) {
+            headers.append(name, value);
+        }
+    }
+    Ok(res)
+}
+
+/// Collects the headers accepted by the filter to be stored.
+pub(crate) fn collect_stored_headers(
+    headers: &HeaderMap,
+    filter: fn(&str) -> bool,
+) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| filter(name.as_str()))
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.as_str().to_owned(), value.to_owned()))
+        })
+        .collect()
+}